@@ -0,0 +1,2 @@
+-- base32 encoded secret, enables TOTP for the user
+alter table users add column totp_secret text;
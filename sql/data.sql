@@ -14,4 +14,10 @@ INSERT INTO users(username, password)
 VALUES ('testuser3', '$argon2id$v=19$m=19456,t=2,p=1$wdd9R3bV4juf5+zBb3qmig$TAMrnpTWqd62b0f0Wp8tSIvpCWSQI2x0OW/8yPd/KGg');
 
 INSERT INTO views(user_id, root, label, permissions)
-VALUES ((SELECT user_id FROM users WHERE username = 'testuser3'), 'ROOT', 'LABEL', 'INVALID');
\ No newline at end of file
+VALUES ((SELECT user_id FROM users WHERE username = 'testuser3'), 'ROOT', 'LABEL', 'INVALID');
+
+INSERT INTO users(username, password, totp_secret)
+VALUES ('testuser4', '$argon2id$v=19$m=19456,t=2,p=1$Zm9xdGVzdHVzZXI0c2FsdA$khTH80ygzxnNbHB7UmgHUpHfA1XSMrhTP4p+AOJcU0g', 'GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ');
+
+INSERT INTO views(user_id, root, label, permissions)
+VALUES ((SELECT user_id FROM users WHERE username = 'testuser4'), '/', 'root', 'r;l') ON CONFLICT DO NOTHING;
\ No newline at end of file
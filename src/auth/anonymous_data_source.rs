@@ -0,0 +1,248 @@
+//! An authentication data source for anonymous access, as described by
+//! [RFC1635](https://datatracker.ietf.org/doc/html/rfc1635).
+//!
+//! Anonymous users log in as 'anonymous' or 'ftp' with their email address as the password. All
+//! of them get the same views, in which they can only read and list.
+
+use std::collections::HashSet;
+use std::path::PathBuf;
+
+use async_trait::async_trait;
+use config::{Config, Value};
+use tracing::{info, warn};
+
+use crate::auth::auth_error::AuthError;
+use crate::auth::data_source::DataSource;
+use crate::auth::login_form::LoginForm;
+use crate::auth::user_data::UserData;
+use crate::auth::user_permission::UserPermission;
+use crate::io::file_system_view::FileSystemView;
+use crate::io::recursive_view::RecursiveView;
+use crate::io::view_dispatch::ViewDispatch;
+
+/// The usernames accepted for anonymous logins, case-insensitive.
+const ANONYMOUS_USERNAMES: [&str; 2] = ["anonymous", "ftp"];
+
+/// The username all anonymous users are logged in as.
+pub(crate) const ANONYMOUS_USERNAME: &str = "anonymous";
+
+/// Permissions anonymous users have in their views.
+const ANONYMOUS_PERMISSIONS: [UserPermission; 2] = [UserPermission::Read, UserPermission::List];
+
+/// A view available to anonymous users.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) struct AnonymousView {
+  pub(crate) root: PathBuf,
+  pub(crate) label: String,
+  /// Whether the view is a [`RecursiveView`] instead of a [`FileSystemView`].
+  pub(crate) recursive: bool,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct AnonymousDataSource {
+  views: Vec<AnonymousView>,
+}
+
+impl AnonymousDataSource {
+  /// Constructs a new [`AnonymousDataSource`] giving anonymous users the `views`.
+  pub(crate) fn new(views: Vec<AnonymousView>) -> Self {
+    AnonymousDataSource { views }
+  }
+
+  /// Loads the data source from config.
+  ///
+  /// Anonymous access is enabled with 'anonymous_login'. The views are read from
+  /// 'anonymous_views', as a list of tables with 'root', 'label' and optionally 'recursive'.
+  /// Invalid views are reported and skipped.
+  ///
+  /// # Returns
+  ///
+  /// The [`AnonymousDataSource`] if anonymous access is enabled, [`None`] otherwise.
+  ///
+  pub(crate) fn from_config(config: &Config) -> Option<Self> {
+    if !config.get_bool("anonymous_login").unwrap_or(false) {
+      return None;
+    }
+    let views = match config.get_array("anonymous_views") {
+      Ok(views) => views.into_iter().filter_map(read_view).collect(),
+      Err(e) => {
+        warn!("No valid anonymous_views, anonymous users won't see anything! {e}");
+        Vec::new()
+      }
+    };
+    Some(AnonymousDataSource::new(views))
+  }
+
+  /// Returns true if the `username` is used for anonymous logins.
+  pub(crate) fn is_anonymous(username: &str) -> bool {
+    ANONYMOUS_USERNAMES.iter().any(|u| u.eq_ignore_ascii_case(username))
+  }
+}
+
+fn read_view(value: Value) -> Option<AnonymousView> {
+  let view = value.clone().into_table().ok().and_then(|mut table| {
+    let root = table.remove("root")?.into_string().ok()?;
+    let label = table.remove("label")?.into_string().ok()?;
+    let recursive = match table.remove("recursive") {
+      Some(recursive) => recursive.into_bool().ok()?,
+      None => false,
+    };
+    Some(AnonymousView {
+      root: PathBuf::from(root),
+      label,
+      recursive,
+    })
+  });
+  if view.is_none() {
+    warn!("Invalid anonymous view, ignoring! {value}");
+  }
+  view
+}
+
+#[async_trait]
+impl DataSource for AnonymousDataSource {
+  /// Attempts to authenticate an anonymous user.
+  ///
+  /// Any password is accepted, it's logged as the email address of the user. The user is logged in
+  /// as [`ANONYMOUS_USERNAME`] with the configured views, limited to reading and listing.
+  ///
+  /// # Errors
+  ///
+  /// This function can return the following [`AuthError`] variants:
+  ///
+  /// - [`AuthError::BackendError`]: If the username or password is missing.
+  /// - [`AuthError::UserNotFoundError`]: If the username isn't used for anonymous logins.
+  ///
+  async fn authenticate(&self, login_form: &LoginForm) -> Result<UserData, AuthError> {
+    let (Some(username), Some(email)) = (&login_form.username, &login_form.password) else {
+      return Err(AuthError::BackendError);
+    };
+    if !AnonymousDataSource::is_anonymous(username) {
+      return Err(AuthError::UserNotFoundError);
+    }
+    info!("Anonymous login as '{username}' with email {email:?}.");
+
+    let mut user_data = UserData::new(ANONYMOUS_USERNAME, "");
+    user_data.anonymous = true;
+    let permissions = HashSet::from(ANONYMOUS_PERMISSIONS);
+    for view in self.views.iter() {
+      let v: Result<ViewDispatch, ()> = if view.recursive {
+        RecursiveView::new_option(view.root.clone(), &view.label, permissions.clone())
+          .map(|v| v.into())
+      } else {
+        FileSystemView::new_option(view.root.clone(), &view.label, permissions.clone())
+          .map(|v| v.into())
+      };
+      match v {
+        Ok(v) => user_data.add_view(v),
+        Err(_) => warn!("Failed to load view, the path may not exist! View: {:?}", view),
+      }
+    }
+    Ok(user_data)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::collections::HashSet;
+  use std::path::PathBuf;
+
+  use config::{Config, File, FileFormat};
+
+  use crate::auth::anonymous_data_source::{AnonymousDataSource, AnonymousView};
+  use crate::auth::auth_error::AuthError;
+  use crate::auth::data_source::DataSource;
+  use crate::auth::login_form::LoginForm;
+  use crate::auth::user_permission::UserPermission;
+  use crate::io::view::View;
+  use crate::utils::test_utils::*;
+
+  fn login_form(username: &str, password: &str) -> LoginForm {
+    LoginForm {
+      username: Some(username.to_string()),
+      password: Some(password.to_string()),
+      certificate: None,
+      one_time_password: None,
+    }
+  }
+
+  #[test]
+  fn from_config_test() {
+    let config = Config::builder()
+      .add_source(File::from_str(
+        r#"
+        anonymous_login = true
+        anonymous_views = [
+          { root = "/srv/public", label = "public" },
+          { root = "/srv/mirror", label = "mirror", recursive = true },
+          { label = "invalid" },
+        ]
+        "#,
+        FileFormat::Toml,
+      ))
+      .build()
+      .unwrap();
+
+    let source = AnonymousDataSource::from_config(&config).expect("Anonymous login is enabled");
+    assert_eq!(
+      vec![
+        AnonymousView {
+          root: PathBuf::from("/srv/public"),
+          label: "public".to_string(),
+          recursive: false,
+        },
+        AnonymousView {
+          root: PathBuf::from("/srv/mirror"),
+          label: "mirror".to_string(),
+          recursive: true,
+        },
+      ],
+      source.views
+    );
+  }
+
+  #[test]
+  fn from_config_disabled_test() {
+    let config = Config::builder().build().unwrap();
+    assert_eq!(None, AnonymousDataSource::from_config(&config));
+  }
+
+  #[tokio::test]
+  async fn authenticate_test() {
+    setup_tracing();
+    let source = AnonymousDataSource::new(vec![
+      AnonymousView {
+        root: std::env::current_dir().unwrap().join("test_files"),
+        label: "public".to_string(),
+        recursive: false,
+      },
+      AnonymousView {
+        root: PathBuf::from("NONEXISTENT"),
+        label: "missing".to_string(),
+        recursive: false,
+      },
+    ]);
+
+    for username in ["anonymous", "FTP"] {
+      let user_data =
+        source.authenticate(&login_form(username, "guest@example.com")).await.unwrap();
+      assert_eq!("anonymous", user_data.username);
+      assert!(user_data.anonymous);
+      assert_eq!(1, user_data.file_system_views.len());
+      let view = &user_data.file_system_views[0];
+      assert_eq!("public", view.get_label());
+      assert_eq!(
+        &HashSet::from([UserPermission::Read, UserPermission::List]),
+        view.get_permissions()
+      );
+    }
+  }
+
+  #[tokio::test]
+  async fn authenticate_not_anonymous_test() {
+    setup_tracing();
+    let source = AnonymousDataSource::default();
+    let result = source.authenticate(&login_form("testuser", "guest@example.com")).await;
+    assert_eq!(AuthError::UserNotFoundError, result.unwrap_err());
+  }
+}
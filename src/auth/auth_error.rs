@@ -3,10 +3,15 @@
 use strum_macros::Display;
 use thiserror::Error;
 
+use crate::session::connection_registry::ConnectionLimitError;
+
 #[derive(Debug, Eq, PartialEq, Display, Error)]
 pub(crate) enum AuthError {
   UserNotFoundError,
   InvalidCredentials,
   PermissionParsingError,
   BackendError,
+  /// The credentials need a one-time password, which wasn't supplied.
+  SecondFactorRequired,
+  LimitReached(ConnectionLimitError),
 }
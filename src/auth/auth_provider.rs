@@ -1,5 +1,6 @@
 //! Authenticates a user using the supplied [`DataSource`]s.
 
+use crate::auth::auth_error::AuthError;
 use crate::auth::data_source::DataSource;
 use crate::auth::login_form::LoginForm;
 use crate::auth::user_data::UserData;
@@ -28,19 +29,24 @@ impl AuthProvider {
   ///
   /// # Returns
   ///
-  /// An [`Option`] that contains the [`UserData`] entity if the authentication was successful,
-  /// [`None`] otherwise.
+  /// A [`Result`] that contains the [`UserData`] entity if the authentication was successful.
+  /// Otherwise [`AuthError::SecondFactorRequired`] if any of the data sources needs a one-time
+  /// password, [`AuthError::InvalidCredentials`] if none do.
   ///
-  pub(crate) async fn authenticate(&self, login_form: LoginForm) -> Option<UserData> {
+  pub(crate) async fn authenticate(&self, login_form: LoginForm) -> Result<UserData, AuthError> {
+    let mut error = AuthError::InvalidCredentials;
     for data_source in self.data_sources.iter() {
       match data_source.authenticate(&login_form).await {
-        Ok(ud) => return Some(ud),
+        Ok(ud) => return Ok(ud),
         Err(e) => {
           info!("Failed to authenticate user: {}", e);
+          if e == AuthError::SecondFactorRequired {
+            error = e;
+          }
         }
       }
     }
-    None
+    Err(error)
   }
 
   pub(crate) fn add_data_source(&mut self, data_source: Box<dyn DataSource>) {
@@ -52,6 +58,7 @@ impl AuthProvider {
 mod tests {
   use sqlx::SqlitePool;
 
+  use crate::auth::auth_error::AuthError;
   use crate::auth::auth_provider::AuthProvider;
   use crate::auth::login_form::LoginForm;
   use crate::auth::sqlite_data_source::SqliteDataSource;
@@ -67,7 +74,7 @@ mod tests {
     let mut form = LoginForm::default();
     let _ = form.username.insert("testuser1".to_string());
     let _ = form.password.insert("user1".to_string());
-    assert!(provider.authenticate(form).await.is_some());
+    assert!(provider.authenticate(form).await.is_ok());
     Ok(())
   }
 
@@ -80,7 +87,22 @@ mod tests {
     let mut form = LoginForm::default();
     let _ = form.username.insert("testuser1".to_string());
     let _ = form.password.insert("INVALID".to_string());
-    assert!(provider.authenticate(form).await.is_none());
+    assert_eq!(Err(AuthError::InvalidCredentials), provider.authenticate(form).await.map(|_| ()));
+    Ok(())
+  }
+
+  #[sqlx::test]
+  async fn authenticate_second_factor_test(pool: SqlitePool) -> sqlx::Result<()> {
+    setup_tracing();
+    setup_test_db(&pool).await?;
+    let mut provider = AuthProvider::new();
+    provider.add_data_source(Box::new(TestDataSource::new()));
+    provider.add_data_source(Box::new(SqliteDataSource::new(pool)));
+    let mut form = LoginForm::default();
+    let _ = form.username.insert("testuser4".to_string());
+    let _ = form.password.insert("user4".to_string());
+    let result = provider.authenticate(form).await.map(|_| ());
+    assert_eq!(Err(AuthError::SecondFactorRequired), result);
     Ok(())
   }
 }
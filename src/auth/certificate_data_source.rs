@@ -0,0 +1,206 @@
+//! An authentication data source for clients authenticated with a TLS client certificate.
+//!
+//! The subject or an alternative name of the certificate is mapped to a user, who is logged in
+//! without a password:
+//!
+//! ```toml
+//! client_certificate_users = [
+//!   { identity = "backup.example.com", username = "backup", views = [
+//!     { root = "/srv/backup", label = "backup", permissions = "r;l;w;c" },
+//!   ] },
+//! ]
+//! ```
+
+use async_trait::async_trait;
+use config::{Config, Value};
+use tracing::{info, warn};
+
+use crate::auth::auth_error::AuthError;
+use crate::auth::data_source::DataSource;
+use crate::auth::login_form::LoginForm;
+use crate::auth::user_data::UserData;
+use crate::auth::view_definition::ViewDefinition;
+
+/// A user mapped from a certificate identity.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct CertificateUser {
+  /// The subject or an alternative name of the certificate.
+  pub(crate) identity: String,
+  pub(crate) username: String,
+  pub(crate) views: Vec<ViewDefinition>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct CertificateDataSource {
+  users: Vec<CertificateUser>,
+}
+
+impl CertificateDataSource {
+  /// Constructs a new [`CertificateDataSource`] mapping certificates to the `users`.
+  pub(crate) fn new(users: Vec<CertificateUser>) -> Self {
+    CertificateDataSource { users }
+  }
+
+  /// Loads the data source from config.
+  ///
+  /// The users are read from 'client_certificate_users', as a list of tables with 'identity',
+  /// 'username' and 'views'. Invalid users are reported and skipped.
+  ///
+  /// # Returns
+  ///
+  /// The [`CertificateDataSource`] if any users are mapped, [`None`] otherwise.
+  ///
+  pub(crate) fn from_config(config: &Config) -> Option<Self> {
+    let users: Vec<_> = match config.get_array("client_certificate_users") {
+      Ok(users) => users.into_iter().filter_map(read_user).collect(),
+      Err(config::ConfigError::NotFound(_)) => return None,
+      Err(e) => {
+        warn!("Invalid client_certificate_users, ignoring! {e}");
+        return None;
+      }
+    };
+    (!users.is_empty()).then(|| CertificateDataSource::new(users))
+  }
+}
+
+fn read_user(value: Value) -> Option<CertificateUser> {
+  let user = value.clone().into_table().ok().and_then(|mut table| {
+    let identity = table.remove("identity")?.into_string().ok()?;
+    let username = table.remove("username")?.into_string().ok()?;
+    let views = match table.remove("views") {
+      Some(views) => ViewDefinition::from_array(views)?,
+      None => Vec::new(),
+    };
+    Some(CertificateUser {
+      identity,
+      username,
+      views,
+    })
+  });
+  if user.is_none() {
+    warn!("Invalid client certificate user, ignoring! {value}");
+  }
+  user
+}
+
+#[async_trait]
+impl DataSource for CertificateDataSource {
+  /// Attempts to authenticate a user with the client certificate.
+  ///
+  /// The user is authenticated if it's mapped from the subject or an alternative name of the
+  /// certificate. No password is needed, the certificate was already verified during the TLS
+  /// handshake.
+  ///
+  /// # Errors
+  ///
+  /// This function can return the following [`AuthError`] variants:
+  ///
+  /// - [`AuthError::BackendError`]: If the username or certificate is missing.
+  /// - [`AuthError::UserNotFoundError`]: If the username isn't mapped from any certificate.
+  /// - [`AuthError::InvalidCredentials`]: If the username isn't mapped from this certificate.
+  ///
+  async fn authenticate(&self, login_form: &LoginForm) -> Result<UserData, AuthError> {
+    let (Some(username), Some(certificate)) = (&login_form.username, &login_form.certificate)
+    else {
+      return Err(AuthError::BackendError);
+    };
+    let mut users = self.users.iter().filter(|u| &u.username == username).peekable();
+    if users.peek().is_none() {
+      return Err(AuthError::UserNotFoundError);
+    }
+    let Some(user) = users.find(|u| certificate.matches(&u.identity)) else {
+      return Err(AuthError::InvalidCredentials);
+    };
+    info!("User '{username}' authenticated by the certificate of '{}'.", certificate.subject);
+
+    let mut user_data = UserData::new(username, "");
+    for view in user.views.iter().filter_map(ViewDefinition::load) {
+      user_data.add_view(view);
+    }
+    Ok(user_data)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use config::{Config, File, FileFormat};
+
+  use crate::auth::auth_error::AuthError;
+  use crate::auth::certificate_data_source::CertificateDataSource;
+  use crate::auth::client_certificate::ClientCertificate;
+  use crate::auth::data_source::DataSource;
+  use crate::auth::login_form::LoginForm;
+  use crate::utils::test_utils::*;
+
+  fn login_form(username: &str, certificate: Option<ClientCertificate>) -> LoginForm {
+    LoginForm {
+      username: Some(username.to_string()),
+      password: None,
+      certificate,
+      one_time_password: None,
+    }
+  }
+
+  fn certificate() -> ClientCertificate {
+    ClientCertificate {
+      subject: "O=foq, CN=test-client".to_string(),
+      alt_names: vec!["client.example.com".to_string()],
+    }
+  }
+
+  fn source() -> CertificateDataSource {
+    let root = std::env::current_dir().unwrap().join("test_files");
+    let config = Config::builder()
+      .add_source(File::from_str(
+        &format!(
+          r#"
+          client_certificate_users = [
+            {{ identity = "client.example.com", username = "backup", views = [
+              {{ root = "{}", label = "files", permissions = "r;l" }},
+            ] }},
+            {{ identity = "O=foq, CN=other", username = "other" }},
+            {{ identity = "invalid" }},
+          ]
+          "#,
+          root.display()
+        ),
+        FileFormat::Toml,
+      ))
+      .build()
+      .unwrap();
+    CertificateDataSource::from_config(&config).expect("Users are mapped")
+  }
+
+  #[test]
+  fn from_config_test() {
+    setup_tracing();
+    let source = source();
+    assert_eq!(2, source.users.len());
+    assert_eq!("backup", source.users[0].username);
+    assert_eq!("files", source.users[0].views[0].label);
+    assert!(source.users[1].views.is_empty());
+
+    assert_eq!(None, CertificateDataSource::from_config(&Config::builder().build().unwrap()));
+  }
+
+  #[tokio::test]
+  async fn authenticate_test() {
+    setup_tracing();
+    let user_data =
+      source().authenticate(&login_form("backup", Some(certificate()))).await.unwrap();
+    assert_eq!("backup", user_data.username);
+    assert_eq!(1, user_data.file_system_views.len());
+  }
+
+  #[tokio::test]
+  async fn authenticate_invalid_test() {
+    setup_tracing();
+    let source = source();
+    let result = source.authenticate(&login_form("other", Some(certificate()))).await;
+    assert_eq!(AuthError::InvalidCredentials, result.unwrap_err());
+    let result = source.authenticate(&login_form("NONEXISTENT", Some(certificate()))).await;
+    assert_eq!(AuthError::UserNotFoundError, result.unwrap_err());
+    let result = source.authenticate(&login_form("backup", None)).await;
+    assert_eq!(AuthError::BackendError, result.unwrap_err());
+  }
+}
@@ -0,0 +1,78 @@
+//! The identity of a client authenticated with a TLS client certificate.
+
+use tracing::warn;
+use x509_parser::extensions::GeneralName;
+use x509_parser::parse_x509_certificate;
+
+/// The names of a verified client certificate, used to map the client to a user.
+#[derive(Clone, Debug, Ord, PartialOrd, Eq, PartialEq, Hash, Default)]
+pub(crate) struct ClientCertificate {
+  /// The subject, e.g. 'O=foq, CN=client'.
+  pub(crate) subject: String,
+  /// The DNS names, email addresses and URIs in the subject alternative name.
+  pub(crate) alt_names: Vec<String>,
+}
+
+impl ClientCertificate {
+  /// Reads the names from the DER encoded certificate, [`None`] if it can't be parsed.
+  pub(crate) fn from_der(der: &[u8]) -> Option<Self> {
+    let (_, certificate) = match parse_x509_certificate(der) {
+      Ok(certificate) => certificate,
+      Err(e) => {
+        warn!("Failed to parse client certificate! {e}");
+        return None;
+      }
+    };
+    let alt_names = match certificate.subject_alternative_name() {
+      Ok(Some(extension)) => extension
+        .value
+        .general_names
+        .iter()
+        .filter_map(|name| match name {
+          GeneralName::DNSName(name) | GeneralName::RFC822Name(name) | GeneralName::URI(name) => {
+            Some(name.to_string())
+          }
+          _ => None,
+        })
+        .collect(),
+      _ => Vec::new(),
+    };
+    Some(ClientCertificate {
+      subject: certificate.subject().to_string(),
+      alt_names,
+    })
+  }
+
+  /// Reads the names from the end-entity certificate of the chain presented by the client.
+  pub(crate) fn from_chain<T: AsRef<[u8]>>(chain: &[T]) -> Option<Self> {
+    chain.first().and_then(|certificate| ClientCertificate::from_der(certificate.as_ref()))
+  }
+
+  /// Returns true if the `identity` is the subject or one of the alternative names. The alternative
+  /// names are compared case-insensitive.
+  pub(crate) fn matches(&self, identity: &str) -> bool {
+    self.subject == identity || self.alt_names.iter().any(|n| n.eq_ignore_ascii_case(identity))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::path::Path;
+
+  use crate::auth::client_certificate::ClientCertificate;
+  use crate::utils::tls_utils::load_certs;
+
+  #[test]
+  fn from_chain_test() {
+    let chain = load_certs(Path::new("certs/test-client-cert.pem")).unwrap();
+    let certificate = ClientCertificate::from_chain(&chain).expect("Certificate should be parsed");
+    assert_eq!("O=foq, CN=test-client", certificate.subject);
+    assert_eq!(vec!["client.example.com", "client@example.com"], certificate.alt_names);
+
+    assert!(certificate.matches("O=foq, CN=test-client"));
+    assert!(certificate.matches("Client.Example.com"));
+    assert!(!certificate.matches("CN=test-client"));
+    assert_eq!(None, ClientCertificate::from_chain::<Vec<u8>>(&[]));
+    assert_eq!(None, ClientCertificate::from_der(b"invalid"));
+  }
+}
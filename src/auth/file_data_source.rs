@@ -0,0 +1,325 @@
+//! An authentication data source backed by a local TOML file.
+//!
+//! The file contains a list of users, each with a password hash and views:
+//!
+//! ```toml
+//! [[users]]
+//! username = "alice"
+//! password = "$argon2id$v=19$m=19456,t=2,p=1$..."
+//! views = [
+//!   { root = "/srv/alice", label = "home", permissions = "r;l;w;c" },
+//!   { root = "/srv/shared", label = "shared", permissions = "r;l", recursive = true },
+//! ]
+//! ```
+//!
+//! The password hashes can be either argon2 PHC strings or bcrypt hashes. The permissions use the
+//! same format as the views in the SQLite database. The file is reloaded when it's modified.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+use argon2::{Argon2, PasswordHash, PasswordVerifier};
+use async_trait::async_trait;
+use config::{Config, File, FileFormat, Value};
+use tokio::task::spawn_blocking;
+use tracing::{error, info, warn};
+use zeroize::Zeroizing;
+
+use crate::auth::auth_error::AuthError;
+use crate::auth::data_source::DataSource;
+use crate::auth::login_form::LoginForm;
+use crate::auth::user_data::UserData;
+use crate::auth::view_definition::ViewDefinition;
+
+/// A user loaded from the file.
+#[derive(Clone, Debug, PartialEq)]
+struct FileUser {
+  password: String,
+  views: Vec<ViewDefinition>,
+}
+
+/// The users loaded from the file, together with the modification time of the file at the time.
+#[derive(Debug, Default)]
+struct LoadedUsers {
+  modified: Option<SystemTime>,
+  users: HashMap<String, FileUser>,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct FileDataSource {
+  path: PathBuf,
+  loaded: Arc<Mutex<LoadedUsers>>,
+}
+
+impl FileDataSource {
+  /// Constructs a new [`FileDataSource`] reading the users from the file at `path`.
+  ///
+  /// The file is loaded on the first authentication.
+  pub(crate) fn new(path: impl Into<PathBuf>) -> Self {
+    FileDataSource {
+      path: path.into(),
+      loaded: Arc::new(Mutex::new(LoadedUsers::default())),
+    }
+  }
+
+  /// Loads the data source from config.
+  ///
+  /// # Returns
+  ///
+  /// The [`FileDataSource`] reading from the file in 'users_file', or [`None`] if it isn't set.
+  ///
+  pub(crate) fn from_config(config: &Config) -> Option<Self> {
+    config.get_string("users_file").ok().map(FileDataSource::new)
+  }
+
+  /// Returns the user with the `username`, reloading the file first if it was modified.
+  ///
+  /// If the file can't be read or parsed, the previously loaded users are kept.
+  fn get_user(&self, username: &str) -> Result<Option<FileUser>, AuthError> {
+    let mut loaded = self.loaded.lock().map_err(|_| AuthError::BackendError)?;
+    match std::fs::metadata(&self.path).and_then(|m| m.modified()) {
+      Ok(modified) if loaded.modified != Some(modified) => match self.load() {
+        Ok(users) => {
+          info!("Loaded {} users from {:?}.", users.len(), self.path);
+          *loaded = LoadedUsers {
+            modified: Some(modified),
+            users,
+          };
+        }
+        Err(e) => {
+          warn!("Failed to load users from {:?}, keeping the previous ones! {e}", self.path)
+        }
+      },
+      Ok(_) => {}
+      Err(e) => warn!("Failed to read users file {:?}! {e}", self.path),
+    }
+    Ok(loaded.users.get(username).cloned())
+  }
+
+  fn load(&self) -> Result<HashMap<String, FileUser>, config::ConfigError> {
+    let file = Config::builder()
+      .add_source(File::from(self.path.as_path()).format(FileFormat::Toml))
+      .build()?;
+    let users = match file.get_array("users") {
+      Ok(users) => users,
+      Err(config::ConfigError::NotFound(_)) => Vec::new(),
+      Err(e) => return Err(e),
+    };
+    Ok(users.into_iter().filter_map(read_user).collect())
+  }
+}
+
+fn read_user(value: Value) -> Option<(String, FileUser)> {
+  let user = value.clone().into_table().ok().and_then(|mut table| {
+    let username = table.remove("username")?.into_string().ok()?;
+    let password = table.remove("password")?.into_string().ok()?;
+    let views = match table.remove("views") {
+      Some(views) => ViewDefinition::from_array(views)?,
+      None => Vec::new(),
+    };
+    Some((username, FileUser { password, views }))
+  });
+  if user.is_none() {
+    warn!("Invalid user in users file, ignoring! {value}");
+  }
+  user
+}
+
+/// Verifies the `password` against the argon2 or bcrypt `hash`.
+fn verify_password(password: &str, hash: &str) -> Result<bool, AuthError> {
+  if hash.starts_with("$2") {
+    return bcrypt::verify(password, hash).map_err(|e| {
+      warn!("Invalid bcrypt hash! {e}");
+      AuthError::BackendError
+    });
+  }
+  let parsed_hash = PasswordHash::new(hash).map_err(|e| {
+    warn!("Invalid argon2 hash! {e}");
+    AuthError::BackendError
+  })?;
+  Ok(Argon2::default().verify_password(password.as_bytes(), &parsed_hash).is_ok())
+}
+
+#[async_trait]
+impl DataSource for FileDataSource {
+  /// Attempts to authenticate a user.
+  ///
+  /// Looks up the user in the file, reloading it if it was modified. If the passwords match, the
+  /// users views are loaded and the [`UserData`] entity is returned. Reading the file and verifying
+  /// the password block, so they run on the blocking thread pool.
+  ///
+  /// # Errors
+  ///
+  /// This function can return the following [`AuthError`] variants:
+  ///
+  /// - [`AuthError::BackendError`]: If the username or password is missing, or the hash is invalid.
+  /// - [`AuthError::UserNotFoundError`]: If the username is not in the file.
+  /// - [`AuthError::InvalidCredentials`]: If the password is incorrect.
+  ///
+  async fn authenticate(&self, login_form: &LoginForm) -> Result<UserData, AuthError> {
+    let (Some(username), Some(password)) = (&login_form.username, &login_form.password) else {
+      return Err(AuthError::BackendError);
+    };
+    let data_source = self.clone();
+    let lookup_username = username.clone();
+    let password = Zeroizing::new(password.clone());
+    let user = spawn_blocking(move || {
+      let Some(user) = data_source.get_user(&lookup_username)? else {
+        return Err(AuthError::UserNotFoundError);
+      };
+      if !verify_password(&password, &user.password)? {
+        return Err(AuthError::InvalidCredentials);
+      }
+      Ok(user)
+    })
+    .await
+    .map_err(|e| {
+      error!("Failed to verify the password of user '{username}'! {e}");
+      AuthError::BackendError
+    })??;
+
+    let mut user_data = UserData::new(username, user.password);
+    for view in user.views.iter().filter_map(ViewDefinition::load) {
+      user_data.add_view(view);
+    }
+    Ok(user_data)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::collections::HashSet;
+  use std::env::temp_dir;
+  use std::fs::{File, write};
+  use std::path::Path;
+  use std::time::{Duration, SystemTime};
+
+  use config::Config;
+  use uuid::Uuid;
+
+  use crate::auth::auth_error::AuthError;
+  use crate::auth::data_source::DataSource;
+  use crate::auth::file_data_source::FileDataSource;
+  use crate::auth::login_form::LoginForm;
+  use crate::auth::user_permission::UserPermission;
+  use crate::io::view::View;
+  use crate::utils::test_utils::*;
+
+  // The hash of 'user1'
+  const ARGON2_HASH: &str = "$argon2id$v=19$m=19456,t=2,p=1$xS9QG9glzsQ9R7Er/L/zQw$kFDa3+IQ+baHI445Vs5RRdFEHf9g4KU09r5HYMfX+ZM";
+
+  fn login_form(username: &str, password: &str) -> LoginForm {
+    LoginForm {
+      username: Some(username.to_string()),
+      password: Some(password.to_string()),
+      certificate: None,
+      one_time_password: None,
+    }
+  }
+
+  // Writes the file and sets its modification time, so consecutive writes are always detected
+  fn write_users(path: &Path, contents: &str, modified: SystemTime) {
+    write(path, contents).unwrap();
+    File::options().write(true).open(path).unwrap().set_modified(modified).unwrap();
+  }
+
+  fn users_file(root: &Path) -> String {
+    let bcrypt_hash = bcrypt::hash("user2", 4).unwrap();
+    format!(
+      r#"
+      [[users]]
+      username = "testuser1"
+      password = "{ARGON2_HASH}"
+      views = [
+        {{ root = "{}", label = "files", permissions = "r;l;w" }},
+        {{ root = "NONEXISTENT", label = "missing", permissions = "r" }},
+      ]
+
+      [[users]]
+      username = "testuser2"
+      password = "{bcrypt_hash}"
+
+      [[users]]
+      username = "invalid"
+      password = "{ARGON2_HASH}"
+      views = [{{ root = "/", label = "root", permissions = "INVALID" }}]
+      "#,
+      root.display()
+    )
+  }
+
+  #[tokio::test]
+  async fn authenticate_test() {
+    setup_tracing();
+    let path = temp_dir().join(Uuid::new_v4().as_hyphenated().to_string());
+    let _cleanup = FileCleanup::new(&path);
+    let root = std::env::current_dir().unwrap().join("test_files");
+    write_users(&path, &users_file(&root), SystemTime::now());
+    let source = FileDataSource::new(&path);
+
+    let user_data = source.authenticate(&login_form("testuser1", "user1")).await.unwrap();
+    assert_eq!("testuser1", user_data.username);
+    assert_eq!(1, user_data.file_system_views.len());
+    let view = &user_data.file_system_views[0];
+    assert_eq!("files", view.get_label());
+    assert_eq!(
+      &HashSet::from([UserPermission::Read, UserPermission::List, UserPermission::Write]),
+      view.get_permissions()
+    );
+
+    let user_data = source.authenticate(&login_form("testuser2", "user2")).await.unwrap();
+    assert!(user_data.file_system_views.is_empty());
+  }
+
+  #[tokio::test]
+  async fn authenticate_invalid_test() {
+    setup_tracing();
+    let path = temp_dir().join(Uuid::new_v4().as_hyphenated().to_string());
+    let _cleanup = FileCleanup::new(&path);
+    write_users(&path, &users_file(Path::new("/")), SystemTime::now());
+    let source = FileDataSource::new(&path);
+
+    let result = source.authenticate(&login_form("testuser1", "wrong")).await;
+    assert_eq!(AuthError::InvalidCredentials, result.unwrap_err());
+    let result = source.authenticate(&login_form("testuser2", "wrong")).await;
+    assert_eq!(AuthError::InvalidCredentials, result.unwrap_err());
+    let result = source.authenticate(&login_form("invalid", "user1")).await;
+    assert_eq!(AuthError::UserNotFoundError, result.unwrap_err());
+    let result = source.authenticate(&login_form("NONEXISTENT", "user1")).await;
+    assert_eq!(AuthError::UserNotFoundError, result.unwrap_err());
+  }
+
+  #[tokio::test]
+  async fn reload_test() {
+    setup_tracing();
+    let path = temp_dir().join(Uuid::new_v4().as_hyphenated().to_string());
+    let _cleanup = FileCleanup::new(&path);
+    let modified = SystemTime::now() - Duration::from_secs(60);
+    write_users(&path, &users_file(Path::new("/")), modified);
+    let source = FileDataSource::new(&path);
+    assert!(source.authenticate(&login_form("testuser1", "user1")).await.is_ok());
+
+    // Invalid files are reported and the previous users are kept
+    write_users(&path, "[[users]", modified + Duration::from_secs(1));
+    assert!(source.authenticate(&login_form("testuser1", "user1")).await.is_ok());
+
+    let users = format!("[[users]]\nusername = \"testuser3\"\npassword = \"{ARGON2_HASH}\"\n");
+    write_users(&path, &users, modified + Duration::from_secs(2));
+    let result = source.authenticate(&login_form("testuser1", "user1")).await;
+    assert_eq!(AuthError::UserNotFoundError, result.unwrap_err());
+    assert!(source.authenticate(&login_form("testuser3", "user1")).await.is_ok());
+  }
+
+  #[test]
+  fn from_config_test() {
+    let config =
+      Config::builder().set_override("users_file", "users.toml").unwrap().build().unwrap();
+    let source = FileDataSource::from_config(&config).expect("users_file is set");
+    assert_eq!(Path::new("users.toml"), source.path);
+
+    let config = Config::builder().build().unwrap();
+    assert!(FileDataSource::from_config(&config).is_none());
+  }
+}
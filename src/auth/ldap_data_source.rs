@@ -0,0 +1,385 @@
+//! An authentication data source backed by an LDAP directory.
+//!
+//! Users are authenticated with a simple bind as themselves, over LDAPS or StartTLS. Their views
+//! are built from templates, each applying to the members of a group or to every user. The roots
+//! of the templates can contain '{username}', which is replaced by the name of the user:
+//!
+//! ```toml
+//! ldap_url = "ldaps://ldap.example.com"
+//! ldap_user_dn = "uid={username},ou=people,dc=example,dc=com"
+//! ldap_views = [
+//!   { root = "/home/{username}", label = "home", permissions = "r;l;w;c" },
+//!   { group = "cn=staff,ou=groups,dc=example,dc=com", root = "/srv/staff", label = "staff", permissions = "r;l" },
+//! ]
+//! ```
+
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use config::{Config, Value};
+use ldap3::{LdapConnAsync, LdapConnSettings, Scope, SearchEntry, dn_escape};
+use tracing::{error, info, warn};
+
+use crate::auth::auth_error::AuthError;
+use crate::auth::data_source::DataSource;
+use crate::auth::login_form::LoginForm;
+use crate::auth::user_data::UserData;
+use crate::auth::user_permission::UserPermission;
+use crate::io::file_system_view::FileSystemView;
+use crate::io::recursive_view::RecursiveView;
+use crate::io::view_dispatch::ViewDispatch;
+
+/// The placeholder replaced by the username in the templates.
+const USERNAME_PLACEHOLDER: &str = "{username}";
+
+/// The LDAP result code of a failed bind, see
+/// [RFC4511](https://datatracker.ietf.org/doc/html/rfc4511#appendix-A.2).
+const INVALID_CREDENTIALS: u32 = 49;
+
+/// A view given to the members of a group.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct LdapViewTemplate {
+  /// The DN of the group, [`None`] if the view is given to every user.
+  pub(crate) group: Option<String>,
+  /// The root of the view, can contain the username placeholder.
+  pub(crate) root: String,
+  pub(crate) label: String,
+  pub(crate) permissions: HashSet<UserPermission>,
+  /// Whether the view is a [`RecursiveView`] instead of a [`FileSystemView`].
+  pub(crate) recursive: bool,
+}
+
+impl LdapViewTemplate {
+  /// Returns true if the view is given to a member of the `groups`. DNs are compared
+  /// case-insensitive.
+  fn applies_to(&self, groups: &[String]) -> bool {
+    match &self.group {
+      Some(group) => groups.iter().any(|g| g.eq_ignore_ascii_case(group)),
+      None => true,
+    }
+  }
+
+  /// Returns the root of the view for the user with the `username`.
+  fn root_for(&self, username: &str) -> PathBuf {
+    PathBuf::from(self.root.replace(USERNAME_PLACEHOLDER, username))
+  }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct LdapDataSource {
+  url: String,
+  starttls: bool,
+  no_tls_verify: bool,
+  timeout: Duration,
+  /// The DN users bind as, containing the username placeholder.
+  user_dn: String,
+  /// The attribute of the user entry listing the groups of the user.
+  group_attribute: String,
+  views: Vec<LdapViewTemplate>,
+}
+
+impl LdapDataSource {
+  /// Loads the data source from config.
+  ///
+  /// The server is set in 'ldap_url' and the DN users bind as in 'ldap_user_dn'. Plain 'ldap://'
+  /// URLs are only allowed with 'ldap_starttls', so passwords are never sent in the clear. The
+  /// groups are read from the 'ldap_group_attribute' of the user entry, 'memberOf' by default,
+  /// and the view templates from 'ldap_views'. Certificate verification can be turned off with
+  /// 'ldap_no_tls_verify' for testing.
+  ///
+  /// # Returns
+  ///
+  /// The [`LdapDataSource`] if 'ldap_url' is set and the settings are valid, [`None`] otherwise.
+  ///
+  pub(crate) fn from_config(config: &Config) -> Option<Self> {
+    let url = config.get_string("ldap_url").ok()?;
+    let starttls = config.get_bool("ldap_starttls").unwrap_or(false);
+    if !starttls && !url.to_lowercase().starts_with("ldaps://") {
+      error!("LDAP requires either an ldaps:// URL or ldap_starttls, LDAP is disabled!");
+      return None;
+    }
+    let Ok(user_dn) = config.get_string("ldap_user_dn") else {
+      error!("No ldap_user_dn in config, LDAP is disabled!");
+      return None;
+    };
+    if !user_dn.contains(USERNAME_PLACEHOLDER) {
+      error!("The ldap_user_dn doesn't contain {USERNAME_PLACEHOLDER}, LDAP is disabled!");
+      return None;
+    }
+    let views = match config.get_array("ldap_views") {
+      Ok(views) => views.into_iter().filter_map(read_view_template).collect(),
+      Err(e) => {
+        warn!("No valid ldap_views, LDAP users won't see anything! {e}");
+        Vec::new()
+      }
+    };
+    Some(LdapDataSource {
+      url,
+      starttls,
+      no_tls_verify: config.get_bool("ldap_no_tls_verify").unwrap_or(false),
+      timeout: Duration::from_secs(config.get_int("ldap_timeout").unwrap_or(10).max(1) as u64),
+      user_dn,
+      group_attribute: config.get_string("ldap_group_attribute").unwrap_or("memberOf".into()),
+      views,
+    })
+  }
+
+  /// Binds as the user and returns the groups the user is a member of.
+  async fn bind(&self, username: &str, password: &str) -> Result<Vec<String>, AuthError> {
+    let settings = LdapConnSettings::new()
+      .set_conn_timeout(self.timeout)
+      .set_starttls(self.starttls)
+      .set_no_tls_verify(self.no_tls_verify);
+    let (connection, mut ldap) =
+      LdapConnAsync::with_settings(settings, &self.url).await.map_err(|e| {
+        warn!("Failed to connect to LDAP server! {e}");
+        AuthError::BackendError
+      })?;
+    ldap3::drive!(connection);
+
+    let user_dn = self.user_dn.replace(USERNAME_PLACEHOLDER, &dn_escape(username));
+    let result = ldap.simple_bind(&user_dn, password).await.map_err(|e| {
+      warn!("LDAP bind failed! {e}");
+      AuthError::BackendError
+    })?;
+    match result.rc {
+      0 => {}
+      INVALID_CREDENTIALS => return Err(AuthError::InvalidCredentials),
+      rc => {
+        warn!("LDAP bind failed with result code {rc}: {}", result.text);
+        return Err(AuthError::BackendError);
+      }
+    }
+
+    let groups = ldap
+      .search(&user_dn, Scope::Base, "(objectClass=*)", vec![self.group_attribute.as_str()])
+      .await
+      .and_then(|r| r.success())
+      .map(|(entries, _)| {
+        entries
+          .into_iter()
+          .flat_map(|e| SearchEntry::construct(e).attrs)
+          .filter(|(attribute, _)| attribute.eq_ignore_ascii_case(&self.group_attribute))
+          .flat_map(|(_, values)| values)
+          .collect()
+      })
+      .unwrap_or_else(|e| {
+        warn!("Failed to read the groups of {user_dn}! {e}");
+        Vec::new()
+      });
+    let _ = ldap.unbind().await;
+    Ok(groups)
+  }
+}
+
+fn read_view_template(value: Value) -> Option<LdapViewTemplate> {
+  let template = value.clone().into_table().ok().and_then(|mut table| {
+    let group = match table.remove("group") {
+      Some(group) => Some(group.into_string().ok()?),
+      None => None,
+    };
+    let root = table.remove("root")?.into_string().ok()?;
+    let label = table.remove("label")?.into_string().ok()?;
+    let permissions = table.remove("permissions")?.into_string().ok()?;
+    let permissions = UserPermission::parse_list(&permissions).ok()?;
+    let recursive = match table.remove("recursive") {
+      Some(recursive) => recursive.into_bool().ok()?,
+      None => false,
+    };
+    Some(LdapViewTemplate {
+      group,
+      root,
+      label,
+      permissions,
+      recursive,
+    })
+  });
+  if template.is_none() {
+    warn!("Invalid LDAP view, ignoring! {value}");
+  }
+  template
+}
+
+#[async_trait]
+impl DataSource for LdapDataSource {
+  /// Attempts to authenticate a user.
+  ///
+  /// Binds to the LDAP server as the user. If the bind succeeds, the views of the templates
+  /// applying to the groups of the user are loaded and the [`UserData`] entity is returned.
+  ///
+  /// # Errors
+  ///
+  /// This function can return the following [`AuthError`] variants:
+  ///
+  /// - [`AuthError::BackendError`]: If the username or password is missing, or the server fails.
+  /// - [`AuthError::UserNotFoundError`]: If the username can't be used in paths.
+  /// - [`AuthError::InvalidCredentials`]: If the password is empty or incorrect.
+  ///
+  async fn authenticate(&self, login_form: &LoginForm) -> Result<UserData, AuthError> {
+    let (Some(username), Some(password)) = (&login_form.username, &login_form.password) else {
+      return Err(AuthError::BackendError);
+    };
+    // The username is interpolated into view roots, so it must stay a single path component
+    if username.is_empty() || username.contains(['/', '\\']) || username == "." || username == ".."
+    {
+      return Err(AuthError::UserNotFoundError);
+    }
+    // An empty password would be an unauthenticated bind, which succeeds for any DN
+    if password.is_empty() {
+      return Err(AuthError::InvalidCredentials);
+    }
+
+    let groups = self.bind(username, password).await?;
+    info!("LDAP user '{username}' is a member of {groups:?}.");
+
+    let mut user_data = UserData::new(username, "");
+    for template in self.views.iter().filter(|t| t.applies_to(&groups)) {
+      let root = template.root_for(username);
+      let permissions = template.permissions.clone();
+      let v: Result<ViewDispatch, ()> = if template.recursive {
+        RecursiveView::new_option(root, &template.label, permissions).map(|v| v.into())
+      } else {
+        FileSystemView::new_option(root, &template.label, permissions).map(|v| v.into())
+      };
+      match v {
+        Ok(v) => user_data.add_view(v),
+        Err(_) => warn!("Failed to load view, the path may not exist! View: {:?}", template),
+      }
+    }
+    Ok(user_data)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::collections::HashSet;
+  use std::path::PathBuf;
+
+  use config::{Config, File, FileFormat};
+
+  use crate::auth::auth_error::AuthError;
+  use crate::auth::data_source::DataSource;
+  use crate::auth::ldap_data_source::{LdapDataSource, LdapViewTemplate};
+  use crate::auth::login_form::LoginForm;
+  use crate::auth::user_permission::UserPermission;
+  use crate::utils::test_utils::*;
+
+  const GROUP: &str = "cn=staff,ou=groups,dc=example,dc=com";
+
+  fn config(extra: &str) -> Config {
+    Config::builder()
+      .add_source(File::from_str(
+        &format!(
+          r#"
+          ldap_user_dn = "uid={{username}},ou=people,dc=example,dc=com"
+          ldap_views = [
+            {{ root = "/home/{{username}}", label = "home", permissions = "r;l;w;c" }},
+            {{ group = "{GROUP}", root = "/srv/staff", label = "staff", permissions = "r;l", recursive = true }},
+            {{ root = "/srv/invalid", label = "invalid", permissions = "INVALID" }},
+          ]
+          {extra}
+          "#
+        ),
+        FileFormat::Toml,
+      ))
+      .build()
+      .unwrap()
+  }
+
+  fn login_form(username: &str, password: &str) -> LoginForm {
+    LoginForm {
+      username: Some(username.to_string()),
+      password: Some(password.to_string()),
+      certificate: None,
+      one_time_password: None,
+    }
+  }
+
+  #[test]
+  fn from_config_test() {
+    let source = LdapDataSource::from_config(&config(r#"ldap_url = "ldaps://localhost""#))
+      .expect("Config is valid");
+    assert_eq!("memberOf", source.group_attribute);
+    assert_eq!(
+      vec![
+        LdapViewTemplate {
+          group: None,
+          root: "/home/{username}".to_string(),
+          label: "home".to_string(),
+          permissions: HashSet::from([
+            UserPermission::Read,
+            UserPermission::List,
+            UserPermission::Write,
+            UserPermission::Create,
+          ]),
+          recursive: false,
+        },
+        LdapViewTemplate {
+          group: Some(GROUP.to_string()),
+          root: "/srv/staff".to_string(),
+          label: "staff".to_string(),
+          permissions: HashSet::from([UserPermission::Read, UserPermission::List]),
+          recursive: true,
+        },
+      ],
+      source.views
+    );
+
+    let config = config("ldap_url = \"ldap://localhost\"\nldap_starttls = true");
+    assert!(LdapDataSource::from_config(&config).is_some());
+  }
+
+  #[test]
+  fn from_config_invalid_test() {
+    setup_tracing();
+    // Plaintext binds aren't allowed
+    assert_eq!(None, LdapDataSource::from_config(&config(r#"ldap_url = "ldap://localhost""#)));
+    // No URL, LDAP isn't used
+    assert_eq!(None, LdapDataSource::from_config(&config("")));
+  }
+
+  #[test]
+  fn template_test() {
+    let source = LdapDataSource::from_config(&config(r#"ldap_url = "ldaps://localhost""#))
+      .expect("Config is valid");
+    let (home, staff) = (&source.views[0], &source.views[1]);
+    assert_eq!(PathBuf::from("/home/alice"), home.root_for("alice"));
+    assert!(home.applies_to(&[]));
+    assert!(!staff.applies_to(&["cn=other,dc=example,dc=com".to_string()]));
+    assert!(staff.applies_to(&[GROUP.to_uppercase()]));
+  }
+
+  #[tokio::test]
+  async fn authenticate_invalid_test() {
+    setup_tracing();
+    // Nothing listens on the port, so reaching the server would be a backend error
+    let source = LdapDataSource::from_config(&config(r#"ldap_url = "ldaps://127.0.0.1:1""#))
+      .expect("Config is valid");
+
+    let result = source.authenticate(&login_form("alice", "")).await;
+    assert_eq!(AuthError::InvalidCredentials, result.unwrap_err());
+    let result = source.authenticate(&login_form("../alice", "password")).await;
+    assert_eq!(AuthError::UserNotFoundError, result.unwrap_err());
+    let result = source.authenticate(&login_form("alice", "password")).await;
+    assert_eq!(AuthError::BackendError, result.unwrap_err());
+  }
+
+  // Requires a local slapd with StartTLS and the user 'uid=testuser,ou=people,dc=example,dc=com'
+  // with the password 'testpassword'
+  #[tokio::test]
+  #[ignore]
+  async fn authenticate_test() {
+    setup_tracing();
+    let config = config(
+      "ldap_url = \"ldap://localhost:389\"\nldap_starttls = true\nldap_no_tls_verify = true",
+    );
+    let source = LdapDataSource::from_config(&config).expect("Config is valid");
+
+    let result = source.authenticate(&login_form("testuser", "testpassword")).await;
+    assert_eq!("testuser", result.expect("Authenticate should succeed!").username);
+    let result = source.authenticate(&login_form("testuser", "wrong")).await;
+    assert_eq!(AuthError::InvalidCredentials, result.unwrap_err());
+  }
+}
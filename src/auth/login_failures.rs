@@ -0,0 +1,273 @@
+//! Tracks failed logins to slow down and ban brute-force attempts.
+//!
+//! Failures are counted by the remote IP address and by the username. Each failure delays the
+//! reply, the delay doubles with each consecutive failure. After too many failures the address
+//! is banned, it can't connect and its logins are refused without checking the password. The
+//! failures of a username only add delay, so others can't lock the user out by guessing its
+//! password. The counts are reset after a successful login, or once the ban time passes since the
+//! last failure.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::net::IpAddr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use config::Config;
+use tracing::{info, warn};
+
+const DEFAULT_FAILURE_DELAY: u64 = 1000;
+const DEFAULT_MAX_FAILURES: u32 = 5;
+const DEFAULT_BAN_TIME: u64 = 900;
+/// The delay doesn't grow over the base delay multiplied by this.
+const MAX_DELAY_FACTOR: u32 = 16;
+
+/// Controls how failed logins are punished.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct LoginFailureSettings {
+  /// The delay before replying to the first failed login.
+  pub(crate) delay: Duration,
+  /// The number of failures after which the address is banned.
+  pub(crate) max_failures: Option<u32>,
+  /// How long bans last.
+  pub(crate) ban_time: Duration,
+}
+
+impl Default for LoginFailureSettings {
+  fn default() -> Self {
+    LoginFailureSettings {
+      delay: Duration::from_millis(DEFAULT_FAILURE_DELAY),
+      max_failures: Some(DEFAULT_MAX_FAILURES),
+      ban_time: Duration::from_secs(DEFAULT_BAN_TIME),
+    }
+  }
+}
+
+impl LoginFailureSettings {
+  /// Loads the settings from config.
+  ///
+  /// The delay is read in milliseconds from 'login_failure_delay', the number of failures from
+  /// 'max_login_failures' and the ban time in seconds from 'login_ban_time'. Setting
+  /// 'max_login_failures' to 0 disables bans. Invalid values are reported and the default is
+  /// used instead.
+  ///
+  pub(crate) fn from_config(config: &Config) -> Self {
+    let defaults = LoginFailureSettings::default();
+    let delay = read_u64(config, "login_failure_delay").map(Duration::from_millis);
+    let max_failures = read_u64(config, "max_login_failures")
+      .map(|max| u32::try_from(max).ok().filter(|max| *max > 0));
+    let ban_time = read_u64(config, "login_ban_time").map(Duration::from_secs);
+    LoginFailureSettings {
+      delay: delay.unwrap_or(defaults.delay),
+      max_failures: max_failures.unwrap_or(defaults.max_failures),
+      ban_time: ban_time.unwrap_or(defaults.ban_time),
+    }
+  }
+}
+
+fn read_u64(config: &Config, key: &str) -> Option<u64> {
+  match config.get_int(key) {
+    Ok(value) => match u64::try_from(value) {
+      Ok(value) => Some(value),
+      Err(_) => {
+        warn!("Invalid {key} {value}, using default!");
+        None
+      }
+    },
+    Err(config::ConfigError::NotFound(_)) => None,
+    Err(e) => {
+      warn!("Invalid {key}, using default! {e}");
+      None
+    }
+  }
+}
+
+#[derive(Debug)]
+struct Failures {
+  count: u32,
+  last: Instant,
+}
+
+#[derive(Debug, Default)]
+struct FailureCounts {
+  by_ip: HashMap<IpAddr, Failures>,
+  by_username: HashMap<String, Failures>,
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct LoginFailureTracker {
+  settings: LoginFailureSettings,
+  counts: Mutex<FailureCounts>,
+}
+
+impl LoginFailureTracker {
+  /// Constructs a new tracker with no failures.
+  pub(crate) fn new(settings: LoginFailureSettings) -> Self {
+    LoginFailureTracker {
+      settings,
+      counts: Mutex::new(FailureCounts::default()),
+    }
+  }
+
+  /// Records a failed login and returns how long the reply should be delayed.
+  ///
+  /// The address is banned once it reaches the maximum failures.
+  pub(crate) fn record_failure(&self, ip: Option<IpAddr>, username: &str) -> Duration {
+    let now = Instant::now();
+    let mut counts = self.counts.lock().unwrap();
+    self.remove_expired(&mut counts, now);
+
+    let mut failures = increment(&mut counts.by_username, username.to_string(), now);
+    if let Some(ip) = ip {
+      let ip_failures = increment(&mut counts.by_ip, ip, now);
+      if self.settings.max_failures == Some(ip_failures) {
+        warn!(
+          "Banning {ip} for {}s after {ip_failures} failed logins!",
+          self.settings.ban_time.as_secs()
+        );
+      }
+      failures = failures.max(ip_failures);
+    }
+
+    let factor = 2u32.saturating_pow(failures - 1).min(MAX_DELAY_FACTOR);
+    self.settings.delay * factor
+  }
+
+  /// Clears the failures of the address and user after a successful login.
+  pub(crate) fn record_success(&self, ip: Option<IpAddr>, username: &str) {
+    let mut counts = self.counts.lock().unwrap();
+    counts.by_username.remove(username);
+    if let Some(ip) = ip {
+      counts.by_ip.remove(&ip);
+    }
+  }
+
+  /// Returns true if the address is banned.
+  pub(crate) fn is_banned(&self, ip: IpAddr) -> bool {
+    let counts = self.counts.lock().unwrap();
+    let banned = self.exceeds_max(counts.by_ip.get(&ip));
+    if banned {
+      info!("Refused banned address {ip}.");
+    }
+    banned
+  }
+
+  /// Returns true if the address of the client is banned, clients without an address never are.
+  pub(crate) fn is_client_banned(&self, ip: Option<IpAddr>) -> bool {
+    ip.is_some_and(|ip| self.is_banned(ip))
+  }
+
+  fn exceeds_max(&self, failures: Option<&Failures>) -> bool {
+    match (failures, self.settings.max_failures) {
+      (Some(failures), Some(max)) => {
+        failures.count >= max && failures.last.elapsed() < self.settings.ban_time
+      }
+      _ => false,
+    }
+  }
+
+  fn remove_expired(&self, counts: &mut FailureCounts, now: Instant) {
+    let ban_time = self.settings.ban_time;
+    counts.by_ip.retain(|_, f| now.duration_since(f.last) < ban_time);
+    counts.by_username.retain(|_, f| now.duration_since(f.last) < ban_time);
+  }
+}
+
+fn increment<K: Hash + Eq>(map: &mut HashMap<K, Failures>, key: K, now: Instant) -> u32 {
+  let failures = map.entry(key).or_insert(Failures {
+    count: 0,
+    last: now,
+  });
+  failures.count = failures.count.saturating_add(1);
+  failures.last = now;
+  failures.count
+}
+
+#[cfg(test)]
+mod tests {
+  use std::net::{IpAddr, Ipv4Addr};
+  use std::time::Duration;
+
+  use config::Config;
+
+  use crate::auth::login_failures::{LoginFailureSettings, LoginFailureTracker};
+
+  const IP: IpAddr = IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1));
+  const OTHER_IP: IpAddr = IpAddr::V4(Ipv4Addr::new(192, 0, 2, 2));
+
+  fn tracker(max_failures: u32, ban_time: Duration) -> LoginFailureTracker {
+    LoginFailureTracker::new(LoginFailureSettings {
+      delay: Duration::from_millis(100),
+      max_failures: Some(max_failures),
+      ban_time,
+    })
+  }
+
+  #[test]
+  fn from_config_test() {
+    let config = Config::builder()
+      .set_override("login_failure_delay", 500)
+      .unwrap()
+      .set_override("max_login_failures", 0)
+      .unwrap()
+      .set_override("login_ban_time", -1)
+      .unwrap()
+      .build()
+      .unwrap();
+
+    let settings = LoginFailureSettings::from_config(&config);
+    assert_eq!(Duration::from_millis(500), settings.delay);
+    assert_eq!(None, settings.max_failures);
+    assert_eq!(LoginFailureSettings::default().ban_time, settings.ban_time);
+  }
+
+  #[test]
+  fn escalating_delay_test() {
+    let tracker = tracker(100, Duration::from_secs(60));
+    assert_eq!(Duration::from_millis(100), tracker.record_failure(Some(IP), "user1"));
+    assert_eq!(Duration::from_millis(200), tracker.record_failure(Some(IP), "user2"));
+    assert_eq!(Duration::from_millis(200), tracker.record_failure(None, "user2"));
+    for _ in 0..10 {
+      tracker.record_failure(Some(IP), "user3");
+    }
+    assert_eq!(Duration::from_millis(1600), tracker.record_failure(Some(IP), "user3"));
+
+    tracker.record_success(Some(IP), "user3");
+    assert_eq!(Duration::from_millis(100), tracker.record_failure(Some(IP), "user3"));
+  }
+
+  #[test]
+  fn ban_test() {
+    let tracker = tracker(2, Duration::from_secs(60));
+    tracker.record_failure(Some(IP), "user1");
+    assert!(!tracker.is_banned(IP));
+
+    tracker.record_failure(Some(IP), "user2");
+    assert!(tracker.is_banned(IP));
+    assert!(tracker.is_client_banned(Some(IP)));
+    assert!(!tracker.is_banned(OTHER_IP));
+    assert!(!tracker.is_client_banned(None));
+  }
+
+  #[test]
+  fn username_failures_only_delay_test() {
+    let tracker = tracker(2, Duration::from_secs(60));
+    for _ in 0..3 {
+      tracker.record_failure(None, "user");
+    }
+    // Failures of the user from other addresses slow down, but don't ban this one
+    assert_eq!(Duration::from_millis(800), tracker.record_failure(Some(OTHER_IP), "user"));
+    assert!(!tracker.is_banned(OTHER_IP));
+  }
+
+  #[test]
+  fn ban_expiry_test() {
+    let tracker = tracker(1, Duration::from_millis(100));
+    tracker.record_failure(Some(IP), "user");
+    assert!(tracker.is_banned(IP));
+
+    std::thread::sleep(Duration::from_millis(150));
+    assert!(!tracker.is_banned(IP));
+    assert_eq!(Duration::from_millis(100), tracker.record_failure(Some(IP), "user"));
+  }
+}
@@ -2,8 +2,15 @@
 
 use zeroize::{Zeroize, ZeroizeOnDrop};
 
+use crate::auth::client_certificate::ClientCertificate;
+
 #[derive(Clone, Debug, Ord, PartialOrd, Eq, PartialEq, Hash, Default, Zeroize, ZeroizeOnDrop)]
 pub(crate) struct LoginForm {
   pub(crate) username: Option<String>,
   pub(crate) password: Option<String>,
+  /// The verified client certificate, used instead of the password.
+  #[zeroize(skip)]
+  pub(crate) certificate: Option<ClientCertificate>,
+  /// The TOTP code sent with ACCT, for users with a second factor.
+  pub(crate) one_time_password: Option<String>,
 }
@@ -1,8 +1,16 @@
 //! Contains implementation of user authentication process.
+pub(crate) mod anonymous_data_source;
 pub(crate) mod auth_error;
 pub(crate) mod auth_provider;
+pub(crate) mod certificate_data_source;
+pub(crate) mod client_certificate;
 pub(crate) mod data_source;
+pub(crate) mod file_data_source;
+pub(crate) mod ldap_data_source;
+pub(crate) mod login_failures;
 pub(crate) mod login_form;
 pub(crate) mod sqlite_data_source;
+pub(crate) mod totp;
 pub(crate) mod user_data;
 pub(crate) mod user_permission;
+pub(crate) mod view_definition;
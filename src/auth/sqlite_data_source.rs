@@ -1,16 +1,19 @@
 //! An authentication data source backed by an SQLite database.
 
+use std::collections::HashMap;
 use std::path::PathBuf;
-use std::str::FromStr;
+use std::sync::{Arc, Mutex};
 
 use argon2::{Argon2, PasswordHash, PasswordVerifier};
 use async_trait::async_trait;
 use sqlx::SqlitePool;
+use sqlx::migrate::MigrateError;
 use tracing::warn;
 
 use crate::auth::auth_error::AuthError;
 use crate::auth::data_source::DataSource;
 use crate::auth::login_form::LoginForm;
+use crate::auth::totp;
 use crate::auth::user_data::UserData;
 use crate::auth::user_permission::UserPermission;
 use crate::io::file_system_view::FileSystemView;
@@ -20,12 +23,42 @@ use crate::io::view_dispatch::ViewDispatch;
 #[derive(Clone)]
 pub(crate) struct SqliteDataSource {
   pool: SqlitePool,
+  /// The time step of the last one-time password accepted for each user id, so it can't be reused.
+  totp_steps: Arc<Mutex<HashMap<i64, u64>>>,
 }
 
 impl SqliteDataSource {
   /// Constructs a new [`SqliteDataSource`] instance.
   pub(crate) fn new(pool: SqlitePool) -> Self {
-    SqliteDataSource { pool }
+    SqliteDataSource {
+      pool,
+      totp_steps: Arc::new(Mutex::new(HashMap::new())),
+    }
+  }
+
+  /// Creates the tables, or updates the ones of older versions, with the migrations in
+  /// 'migrations'. Databases set up before there were migrations are updated too, the first
+  /// migration only creates the missing tables.
+  pub(crate) async fn migrate(pool: &SqlitePool) -> Result<(), MigrateError> {
+    sqlx::migrate!().run(pool).await
+  }
+
+  /// Records the time step of an accepted one-time password.
+  ///
+  /// # Returns
+  ///
+  /// False if a code of the same or a later step was already accepted for the user, e.g. by a
+  /// concurrent login.
+  ///
+  fn use_totp_step(&self, user_id: i64, step: u64) -> bool {
+    let mut steps = self.totp_steps.lock().unwrap();
+    match steps.get(&user_id) {
+      Some(last) if *last >= step => false,
+      _ => {
+        steps.insert(user_id, step);
+        true
+      }
+    }
   }
 }
 
@@ -37,6 +70,11 @@ impl DataSource for SqliteDataSource {
   /// compared. If passwords match, then users [`FileSystemView`]s are loaded and the [`UserData`]
   /// entity is constructed and returned.
   ///
+  /// Users with a TOTP secret need a one-time password too, either appended to the password with
+  /// '+' or in [`LoginForm::one_time_password`]. Both are always checked, so the error doesn't tell
+  /// which one was incorrect. A one-time password is refused if it was already used, or is older
+  /// than the last one used.
+  ///
   /// # Arguments
   ///
   /// - `login_form`: A [`LoginForm`] that contains the username and password.
@@ -52,7 +90,9 @@ impl DataSource for SqliteDataSource {
   ///
   /// - [`AuthError::BackendError`]: If a database errors occurs.
   /// - [`AuthError::UserNotFoundError`]: If the username is not in database.
-  /// - [`AuthError::InvalidCredentials`]: If the password is incorrect.
+  /// - [`AuthError::InvalidCredentials`]: If the password or the one-time password is incorrect.
+  /// - [`AuthError::SecondFactorRequired`]: If the user has a TOTP secret, the password is correct
+  ///   and no one-time password was supplied.
   /// - [`AuthError::PermissionParsingError`]: If permissions have incorrect format.
   ///
   async fn authenticate(&self, login_form: &LoginForm) -> Result<UserData, AuthError> {
@@ -63,7 +103,7 @@ impl DataSource for SqliteDataSource {
     let argon = Argon2::default();
     let username = login_form.username.as_ref().unwrap();
     let user_info = match sqlx::query!(
-      "SELECT user_id, username, password FROM users WHERE username = $1",
+      "SELECT user_id, username, password, totp_secret FROM users WHERE username = $1",
       username
     )
     .fetch_optional(&self.pool)
@@ -73,12 +113,37 @@ impl DataSource for SqliteDataSource {
       Some(r) => r,
       None => return Err(AuthError::UserNotFoundError),
     };
+    let password = login_form.password.as_ref().unwrap();
+    let totp_secret = user_info.totp_secret.as_deref().filter(|s| !s.trim().is_empty());
+    let (password, code) = match (totp_secret, &login_form.one_time_password) {
+      (None, _) => (password.as_str(), None),
+      (Some(_), Some(code)) => (password.as_str(), Some(code.as_str())),
+      (Some(_), None) => totp::split_code(password),
+    };
     let password_hash = user_info.password;
     let parsed_hash = PasswordHash::new(&password_hash).unwrap();
-    if argon
-      .verify_password(login_form.password.as_ref().unwrap().as_bytes(), &parsed_hash)
-      .is_err()
-    {
+    let password_valid = argon.verify_password(password.as_bytes(), &parsed_hash).is_ok();
+    let code_step = match (totp_secret, code) {
+      (None, _) => None,
+      // Only tells that a second factor is needed once the password is known to be correct
+      (Some(_), None) if password_valid => return Err(AuthError::SecondFactorRequired),
+      (Some(_), None) => return Err(AuthError::InvalidCredentials),
+      (Some(secret), Some(code)) => {
+        let last_step = self.totp_steps.lock().unwrap().get(&user_info.user_id).copied();
+        let step = totp::verify(secret, code, last_step).map_err(|e| {
+          warn!("Invalid TOTP secret of user '{username}'! {e}");
+          AuthError::BackendError
+        })?;
+        Some(step)
+      }
+    };
+    // The code is only used up by a login with the correct password
+    let code_valid = match code_step {
+      None => true,
+      Some(None) => false,
+      Some(Some(step)) => password_valid && self.use_totp_step(user_info.user_id, step),
+    };
+    if !(password_valid && code_valid) {
       return Err(AuthError::InvalidCredentials);
     }
 
@@ -93,14 +158,8 @@ impl DataSource for SqliteDataSource {
     let mut user_data = UserData::new(username.to_string(), parsed_hash.to_string());
 
     for view in views.iter() {
-      let permissions = Result::from_iter(
-        view
-          .permissions
-          .trim()
-          .split(';')
-          .filter(|&p| !p.is_empty())
-          .map(|p| UserPermission::from_str(p).map_err(|_| AuthError::PermissionParsingError)),
-      )?;
+      let permissions = UserPermission::parse_list(&view.permissions)
+        .map_err(|_| AuthError::PermissionParsingError)?;
       let v: Result<ViewDispatch, ()> = match &view.r#type {
         0 => FileSystemView::new_option(PathBuf::from(&view.root), &view.label, permissions)
           .map(|v| v.into()),
@@ -126,10 +185,23 @@ pub(crate) mod tests {
   use crate::auth::data_source::DataSource;
   use crate::auth::login_form::LoginForm;
   use crate::auth::sqlite_data_source::SqliteDataSource;
+  use crate::auth::totp;
   use crate::utils::test_utils::*;
 
+  /// The TOTP secret of 'testuser4'.
+  const TOTP_SECRET: &str = "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ";
+
+  fn form(username: &str, password: &str, one_time_password: Option<String>) -> LoginForm {
+    LoginForm {
+      username: Some(username.to_string()),
+      password: Some(password.to_string()),
+      certificate: None,
+      one_time_password,
+    }
+  }
+
   pub(crate) async fn setup_test_db(pool: &SqlitePool) -> sqlx::Result<()> {
-    sqlx::query_file!("sql/schema.sql").execute(pool).await?;
+    SqliteDataSource::migrate(pool).await?;
     sqlx::query_file!("sql/data.sql").execute(pool).await?;
     Ok(())
   }
@@ -151,6 +223,26 @@ pub(crate) mod tests {
     Ok(())
   }
 
+  #[sqlx::test(migrations = false)]
+  async fn migrate_old_database_test(pool: SqlitePool) -> sqlx::Result<()> {
+    setup_tracing();
+    // The schema before TOTP, created without migrations
+    sqlx::raw_sql(include_str!("../../migrations/0001_users_and_views.sql")).execute(&pool).await?;
+    sqlx::raw_sql("insert into users (username, password) values ('old', 'hash')")
+      .execute(&pool)
+      .await?;
+
+    SqliteDataSource::migrate(&pool).await?;
+    SqliteDataSource::migrate(&pool).await?;
+    let secret: Option<String> =
+      sqlx::query_scalar("SELECT totp_secret FROM users WHERE username = 'old'")
+        .fetch_one(&pool)
+        .await?;
+    assert_eq!(None, secret);
+
+    Ok(())
+  }
+
   #[sqlx::test]
   async fn login_invalid_test(pool: SqlitePool) -> sqlx::Result<()> {
     setup_tracing();
@@ -204,4 +296,80 @@ pub(crate) mod tests {
 
     Ok(())
   }
+
+  #[sqlx::test]
+  async fn login_one_time_password_test(pool: SqlitePool) -> sqlx::Result<()> {
+    setup_tracing();
+    setup_test_db(&pool).await?;
+    let data_source = SqliteDataSource::new(pool);
+
+    let code = totp::generate(TOTP_SECRET);
+    let result = data_source.authenticate(&form("testuser4", &format!("user4+{code}"), None)).await;
+    assert!(result.is_ok(), "Appended code should be accepted! Got: {result:?}");
+
+    let data_source = SqliteDataSource::new(data_source.pool);
+    let result = data_source.authenticate(&form("testuser4", "user4", Some(code))).await;
+    assert!(result.is_ok(), "Separate code should be accepted! Got: {result:?}");
+
+    Ok(())
+  }
+
+  #[sqlx::test]
+  async fn login_one_time_password_replay_test(pool: SqlitePool) -> sqlx::Result<()> {
+    setup_tracing();
+    setup_test_db(&pool).await?;
+    let data_source = SqliteDataSource::new(pool);
+
+    let code = totp::generate(TOTP_SECRET);
+    // A code sent with an incorrect password isn't used up
+    let result = data_source.authenticate(&form("testuser4", "INVALID", Some(code.clone()))).await;
+    assert_eq!(AuthError::InvalidCredentials, result.unwrap_err());
+    let result = data_source.authenticate(&form("testuser4", "user4", Some(code.clone()))).await;
+    assert!(result.is_ok(), "Code should be accepted! Got: {result:?}");
+
+    let result = data_source.authenticate(&form("testuser4", "user4", Some(code))).await;
+    assert_eq!(AuthError::InvalidCredentials, result.unwrap_err(), "Code should be used once");
+
+    Ok(())
+  }
+
+  #[sqlx::test]
+  async fn login_missing_one_time_password_test(pool: SqlitePool) -> sqlx::Result<()> {
+    setup_tracing();
+    setup_test_db(&pool).await?;
+    let data_source = SqliteDataSource::new(pool);
+
+    let result = data_source.authenticate(&form("testuser4", "user4", None)).await;
+    assert_eq!(AuthError::SecondFactorRequired, result.unwrap_err());
+
+    // Incorrect passwords don't tell that the user has a second factor
+    for password in ["INVALID", "user4+12345"] {
+      let result = data_source.authenticate(&form("testuser4", password, None)).await;
+      assert_eq!(AuthError::InvalidCredentials, result.unwrap_err());
+    }
+
+    Ok(())
+  }
+
+  #[sqlx::test]
+  async fn login_invalid_one_time_password_test(pool: SqlitePool) -> sqlx::Result<()> {
+    setup_tracing();
+    setup_test_db(&pool).await?;
+    let data_source = SqliteDataSource::new(pool);
+
+    let code = totp::generate(TOTP_SECRET);
+    let invalid_code = format!("{:06}", (code.parse::<u32>().unwrap() + 1) % 1_000_000);
+    let forms = [
+      form("testuser4", &format!("user4+{invalid_code}"), None),
+      form("testuser4", &format!("INVALID+{code}"), None),
+      form("testuser4", "user4", Some(invalid_code)),
+      form("testuser4", "INVALID", Some(code)),
+    ];
+    for form in forms {
+      let result = data_source.authenticate(&form).await;
+      assert_eq!(AuthError::InvalidCredentials, result.unwrap_err());
+    }
+
+    Ok(())
+  }
 }
@@ -0,0 +1,127 @@
+//! Time-based one-time passwords, used as the second factor of a login.
+//!
+//! The codes are the 6 digit SHA-1 codes generated by authenticator apps, as specified by
+//! [RFC6238](https://datatracker.ietf.org/doc/html/rfc6238). A code is either appended to the
+//! password, e.g.: 'PASS password+123456', or sent after the password with ACCT.
+//!
+//! Each code is only accepted once, as recommended by
+//! [RFC6238](https://datatracker.ietf.org/doc/html/rfc6238#section-5.2). The time step of the
+//! accepted code is returned, and codes of that step or earlier ones are refused afterwards.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use totp_rs::{Algorithm, Secret, SecretParseError, TOTP};
+
+const DIGITS: usize = 6;
+const STEP: u64 = 30;
+/// The number of steps before and after the current one accepted, to allow for clock drift.
+const SKEW: u64 = 1;
+
+/// Splits the code appended to the password with '+'.
+///
+/// # Returns
+///
+/// The password and the code, or the unchanged password if it doesn't end with a code.
+///
+pub(crate) fn split_code(password: &str) -> (&str, Option<&str>) {
+  match password.rsplit_once('+') {
+    Some((password, code)) if code.len() == DIGITS && code.bytes().all(|b| b.is_ascii_digit()) => {
+      (password, Some(code))
+    }
+    _ => (password, None),
+  }
+}
+
+/// Checks whether the `code` is currently valid for the base32 encoded `secret`.
+///
+/// # Returns
+///
+/// The time step of the code if it is valid and later than the `last_step` accepted, otherwise
+/// [`None`].
+///
+pub(crate) fn verify(
+  secret: &str,
+  code: &str,
+  last_step: Option<u64>,
+) -> Result<Option<u64>, SecretParseError> {
+  let time = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+  verify_at(secret, code, time, last_step)
+}
+
+/// Checks whether the `code` is valid for the base32 encoded `secret` at the `time`, in seconds
+/// since the Unix epoch. See [`verify`].
+pub(crate) fn verify_at(
+  secret: &str,
+  code: &str,
+  time: u64,
+  last_step: Option<u64>,
+) -> Result<Option<u64>, SecretParseError> {
+  let totp = totp(secret)?;
+  let current = time / STEP;
+  let first = match last_step {
+    Some(last) => current.saturating_sub(SKEW).max(last.saturating_add(1)),
+    None => current.saturating_sub(SKEW),
+  };
+  Ok((first..=current.saturating_add(SKEW)).find(|step| totp.check(code, step * STEP)))
+}
+
+fn totp(secret: &str) -> Result<TOTP, SecretParseError> {
+  // Secrets are often shown grouped or padded, neither is part of the key
+  let secret =
+    secret.chars().filter(|c| !c.is_whitespace() && *c != '=').collect::<String>().to_uppercase();
+  let secret = Secret::Encoded(secret).to_bytes()?;
+  // The skew is handled by verify_at, which needs to know the matching step
+  Ok(TOTP::new_unchecked(Algorithm::SHA1, DIGITS, 0, STEP, secret))
+}
+
+/// Generates the current code for the base32 encoded `secret`.
+#[cfg(test)]
+pub(crate) fn generate(secret: &str) -> String {
+  let time = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+  totp(secret).expect("Secret should be valid").generate(time)
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::auth::totp::{generate, split_code, verify, verify_at};
+  use crate::utils::test_utils::*;
+
+  /// The secret of the RFC6238 test vectors, '12345678901234567890'.
+  const SECRET: &str = "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ";
+
+  #[test]
+  fn split_code_test() {
+    setup_tracing();
+    assert_eq!(("pass+word", Some("123456")), split_code("pass+word+123456"));
+    assert_eq!(("password", None), split_code("password"));
+    assert_eq!(("password+12345", None), split_code("password+12345"));
+    assert_eq!(("password+12345a", None), split_code("password+12345a"));
+  }
+
+  #[test]
+  fn verify_test() {
+    setup_tracing();
+    const STEP: u64 = 1111111109 / 30;
+    assert_eq!(Ok(Some(1)), verify_at(SECRET, "287082", 59, None));
+    assert_eq!(Ok(Some(STEP)), verify_at(SECRET, "081804", 1111111109, None));
+    assert_eq!(
+      Ok(Some(STEP)),
+      verify_at("gezd gnbv gy3t qojq gezd gnbv gy3t qojq", "081804", 1111111109, None)
+    );
+    // Codes of the neighbouring steps are accepted, older ones aren't
+    assert_eq!(Ok(Some(STEP)), verify_at(SECRET, "081804", 1111111109 + 30, None));
+    assert_eq!(Ok(None), verify_at(SECRET, "081804", 1111111109 + 90, None));
+    assert_eq!(Ok(None), verify_at(SECRET, "000000", 59, None));
+    assert!(verify(SECRET, &generate(SECRET), None).unwrap().is_some());
+    assert!(verify("INVALID!", "123456", None).is_err());
+  }
+
+  #[test]
+  fn replay_test() {
+    setup_tracing();
+    const STEP: u64 = 1111111109 / 30;
+    assert_eq!(Ok(None), verify_at(SECRET, "081804", 1111111109, Some(STEP)));
+    assert_eq!(Ok(None), verify_at(SECRET, "081804", 1111111109, Some(STEP + 1)));
+    assert_eq!(Ok(Some(STEP)), verify_at(SECRET, "081804", 1111111109, Some(STEP - 1)));
+  }
+}
@@ -8,6 +8,8 @@ pub(crate) struct UserData {
   #[allow(unused)]
   pub(crate) password: String,
   pub(crate) file_system_views: Vec<ViewDispatch>,
+  /// Whether the user logged in anonymously.
+  pub(crate) anonymous: bool,
 }
 
 impl UserData {
@@ -27,6 +29,7 @@ impl UserData {
       username: username.into(),
       password: password.into(),
       file_system_views: Vec::new(),
+      anonymous: false,
     }
   }
 
@@ -1,5 +1,8 @@
 //! Available permissions a user can have.
 
+use std::collections::HashSet;
+use std::str::FromStr;
+
 use strum_macros::{EnumIter, EnumMessage, EnumString};
 
 use crate::io::entry_data::EntryType;
@@ -25,9 +28,19 @@ pub(crate) enum UserPermission {
   List,
   #[strum(serialize = "d")]
   Delete,
+  /// Allows changing the mode of files and directories with SITE CHMOD.
+  #[strum(serialize = "h")]
+  ChangeMode,
 }
 
 impl UserPermission {
+  /// Parses the permissions of a view, separated by ';', e.g. "r;l;w".
+  pub(crate) fn parse_list(
+    permissions: &str,
+  ) -> Result<HashSet<UserPermission>, strum::ParseError> {
+    permissions.trim().split(';').filter(|&p| !p.is_empty()).map(UserPermission::from_str).collect()
+  }
+
   pub(crate) fn get_applicable_permissions(entry_type: &EntryType) -> Vec<UserPermission> {
     match entry_type {
       EntryType::File => Vec::from([
@@ -51,6 +64,8 @@ impl UserPermission {
 
 #[cfg(test)]
 mod test {
+  use std::collections::HashSet;
+
   use strum::{EnumMessage, IntoEnumIterator};
 
   use crate::auth::user_permission::UserPermission;
@@ -88,5 +103,17 @@ mod test {
 
     let perm = UserPermission::Execute;
     assert_eq!("e", perm.get_serializations()[0]);
+
+    let perm = UserPermission::ChangeMode;
+    assert_eq!("h", perm.get_serializations()[0]);
+  }
+
+  #[test]
+  fn parse_list_test() {
+    setup_tracing();
+    let expected = HashSet::from([UserPermission::Read, UserPermission::List]);
+    assert_eq!(Ok(expected), UserPermission::parse_list(" r;L; "));
+    assert_eq!(Ok(HashSet::new()), UserPermission::parse_list(""));
+    assert!(UserPermission::parse_list("r;INVALID").is_err());
   }
 }
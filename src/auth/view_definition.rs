@@ -0,0 +1,63 @@
+//! A view defined in a config table, used by the data sources that don't keep views in a database.
+
+use std::collections::HashSet;
+use std::path::PathBuf;
+
+use config::Value;
+use tracing::warn;
+
+use crate::auth::user_permission::UserPermission;
+use crate::io::file_system_view::FileSystemView;
+use crate::io::recursive_view::RecursiveView;
+use crate::io::view_dispatch::ViewDispatch;
+
+/// A view defined by its 'root', 'label', 'permissions' and optionally 'recursive'. The
+/// permissions use the same format as the views in the SQLite database.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct ViewDefinition {
+  pub(crate) root: PathBuf,
+  pub(crate) label: String,
+  pub(crate) permissions: HashSet<UserPermission>,
+  /// Whether the view is a [`RecursiveView`] instead of a [`FileSystemView`].
+  pub(crate) recursive: bool,
+}
+
+impl ViewDefinition {
+  /// Reads the definition from a config table, [`None`] if it's invalid.
+  pub(crate) fn from_value(value: Value) -> Option<Self> {
+    let mut table = value.into_table().ok()?;
+    let root = table.remove("root")?.into_string().ok()?;
+    let label = table.remove("label")?.into_string().ok()?;
+    let permissions = table.remove("permissions")?.into_string().ok()?;
+    let permissions = UserPermission::parse_list(&permissions).ok()?;
+    let recursive = match table.remove("recursive") {
+      Some(recursive) => recursive.into_bool().ok()?,
+      None => false,
+    };
+    Some(ViewDefinition {
+      root: PathBuf::from(root),
+      label,
+      permissions,
+      recursive,
+    })
+  }
+
+  /// Reads a list of definitions from a config array, [`None`] if any of them is invalid.
+  pub(crate) fn from_array(value: Value) -> Option<Vec<Self>> {
+    value.into_array().ok()?.into_iter().map(ViewDefinition::from_value).collect()
+  }
+
+  /// Creates the view, reporting it if the root doesn't exist.
+  pub(crate) fn load(&self) -> Option<ViewDispatch> {
+    let permissions = self.permissions.clone();
+    let view: Result<ViewDispatch, ()> = if self.recursive {
+      RecursiveView::new_option(self.root.clone(), &self.label, permissions).map(|v| v.into())
+    } else {
+      FileSystemView::new_option(self.root.clone(), &self.label, permissions).map(|v| v.into())
+    };
+    if view.is_err() {
+      warn!("Failed to load view, the path may not exist! View: {:?}", self);
+    }
+    view.ok()
+  }
+}
@@ -2,53 +2,38 @@
 
 use std::str::FromStr;
 use std::sync::Arc;
-use tracing::{debug, info, trace};
+use tracing::{debug, trace};
 use zeroize::{Zeroize, ZeroizeOnDrop};
 
 use crate::commands::commands::Commands;
-use crate::commands::r#impl::abor::abor;
-use crate::commands::r#impl::cdup::cdup;
-use crate::commands::r#impl::cwd::cwd;
-use crate::commands::r#impl::dele::dele;
-use crate::commands::r#impl::feat::feat;
-use crate::commands::r#impl::list::list;
-#[cfg(windows)]
-use crate::commands::r#impl::mfct::mfct;
-use crate::commands::r#impl::mfmt::mfmt;
-use crate::commands::r#impl::mkd::mkd;
-use crate::commands::r#impl::mlsd::mlsd;
-use crate::commands::r#impl::nlst::nlst;
-use crate::commands::r#impl::noop::noop;
-use crate::commands::r#impl::opts::opts;
-use crate::commands::r#impl::pass::pass;
-use crate::commands::r#impl::pasv::pasv;
-use crate::commands::r#impl::pbsz::pbsz;
-use crate::commands::r#impl::prot::prot;
-use crate::commands::r#impl::pwd::pwd;
-use crate::commands::r#impl::rest::rest;
-use crate::commands::r#impl::retr::retr;
-use crate::commands::r#impl::rmd::rmd;
-use crate::commands::r#impl::rmda::rmda;
-use crate::commands::r#impl::stor::stor;
-use crate::commands::r#impl::syst::syst;
-use crate::commands::r#impl::r#type::r#type;
-use crate::commands::r#impl::user::user;
-use crate::commands::reply::Reply;
-use crate::commands::reply_code::ReplyCode;
+use crate::global_context::command_registry;
 use crate::handlers::reply_sender::ReplySend;
 use crate::session::command_processor::CommandProcessor;
 
 #[derive(Clone, Debug, PartialEq, Zeroize, ZeroizeOnDrop)]
-pub(crate) struct Command {
+pub struct Command {
   #[zeroize(skip)]
   pub(crate) command: Commands,
-  pub(crate) argument: String,
+  /// The uppercase name of the command, the commands are registered by it.
+  #[zeroize(skip)]
+  pub name: String,
+  pub argument: String,
 }
 
 impl Command {
   pub(crate) fn new(command: Commands, argument: impl Into<String>) -> Self {
     Command {
       command,
+      name: command.to_string(),
+      argument: argument.into(),
+    }
+  }
+
+  /// Constructs a command not defined by any RFC, see [`Commands::Other`].
+  pub(crate) fn other(name: &str, argument: impl Into<String>) -> Self {
+    Command {
+      command: Commands::Other,
+      name: name.to_uppercase(),
       argument: argument.into(),
     }
   }
@@ -58,47 +43,10 @@ impl Command {
   pub async fn execute(
     &self,
     command_processor: Arc<CommandProcessor>,
-    reply_sender: Arc<impl ReplySend>,
+    reply_sender: Arc<dyn ReplySend>,
   ) {
-    debug!("Executing command: {:?}", self.command);
-    match self.command {
-      Commands::Abor => abor(self, command_processor, reply_sender).await,
-      Commands::Cdup => cdup(self, command_processor, reply_sender).await,
-      Commands::Cwd => cwd(self, command_processor, reply_sender).await,
-      Commands::Dele => dele(self, command_processor, reply_sender).await,
-      Commands::Feat => feat(self, reply_sender).await,
-      Commands::List => list(self, command_processor, reply_sender).await,
-      #[cfg(windows)]
-      Commands::Mfct => mfct(self, command_processor, reply_sender).await,
-      Commands::Mfmt => mfmt(self, command_processor, reply_sender).await,
-      Commands::Mkd => mkd(self, command_processor, reply_sender).await,
-      Commands::Nlst => nlst(self, command_processor, reply_sender).await,
-      Commands::Mlsd => mlsd(self, command_processor, reply_sender).await,
-      Commands::Noop => noop(self, reply_sender).await,
-      Commands::Opts => opts(self, command_processor, reply_sender).await,
-      Commands::Pass => pass(self, command_processor, reply_sender).await,
-      Commands::Pasv => pasv(self, command_processor, reply_sender).await,
-      Commands::Pbsz => pbsz(self, command_processor, reply_sender).await,
-      Commands::Prot => prot(self, command_processor, reply_sender).await,
-      Commands::Pwd => pwd(self, command_processor, reply_sender).await,
-      Commands::Rest => rest(self, command_processor, reply_sender).await,
-      Commands::Retr => retr(self, command_processor, reply_sender).await,
-      Commands::Rmd => rmd(self, command_processor, reply_sender).await,
-      Commands::Rmda => rmda(self, command_processor, reply_sender).await,
-      Commands::Stor => stor(self, command_processor, reply_sender).await,
-      Commands::Syst => syst(self, reply_sender).await,
-      Commands::Type => r#type(self, command_processor, reply_sender).await,
-      Commands::User => user(self, command_processor, reply_sender).await,
-      _ => {
-        info!("Couldn't execute command, not implemented! Command: {:?}", self.command);
-        reply_sender
-          .send_control_message(Reply::new(
-            ReplyCode::CommandNotImplemented,
-            "Command not implemented!",
-          ))
-          .await
-      }
-    }
+    debug!("Executing command: {}", self.name);
+    command_registry().execute(self, command_processor, reply_sender).await
   }
 }
 
@@ -112,7 +60,13 @@ impl FromStr for Command {
     let split = message_trimmed.split_once(' ').unwrap_or((message_trimmed, ""));
     let command = split.0;
     let argument = split.1;
-    let command = Command::new(command.parse()?, argument);
+    let command = match command.parse() {
+      Ok(command) => Command::new(command, argument),
+      Err(_) if !command.is_empty() && command.chars().all(|c| c.is_ascii_alphanumeric()) => {
+        Command::other(command, argument)
+      }
+      Err(e) => anyhow::bail!("Invalid command name! {e}"),
+    };
     trace!("Command parsed: {:?}", command);
     Ok(command)
   }
@@ -143,6 +97,17 @@ mod tests {
     assert!(parsed.as_ref().unwrap().argument.is_empty());
   }
 
+  #[test]
+  fn other_test() {
+    setup_tracing();
+    let parsed = Command::from_str("xTst some argument").unwrap();
+    assert_eq!(Commands::Other, parsed.command);
+    assert_eq!("XTST", parsed.name);
+    assert_eq!("some argument", parsed.argument);
+    assert!(Command::from_str("x.y argument").is_err());
+    assert!(Command::from_str(" argument").is_err());
+  }
+
   #[test]
   fn user_test() {
     setup_tracing();
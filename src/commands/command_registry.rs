@@ -0,0 +1,561 @@
+//! The registry of implemented commands.
+//!
+//! Each command is registered with its handler, syntax and description. Dispatch, HELP and FEAT
+//! are all derived from the registry, so a command is implemented once it's registered here.
+//!
+//! Commands are registered by name, so commands not defined by any RFC can be added too. Such
+//! commands are parsed as [`Commands::Other`] and implemented by a [`CommandHandler`]. The registry
+//! used by the server is set up at startup, see [`COMMAND_REGISTRY`].
+//!
+//! [`COMMAND_REGISTRY`]: crate::global_context::COMMAND_REGISTRY
+
+use std::collections::BTreeMap;
+use std::fmt::{Debug, Display};
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use futures::future::BoxFuture;
+use tracing::info;
+
+use crate::commands::command::Command;
+use crate::commands::commands::Commands;
+use crate::commands::r#impl::abor::abor;
+use crate::commands::r#impl::acct::acct;
+use crate::commands::r#impl::appe::appe;
+use crate::commands::r#impl::auth::auth;
+use crate::commands::r#impl::cdup::cdup;
+use crate::commands::r#impl::cwd::cwd;
+use crate::commands::r#impl::dele::dele;
+use crate::commands::r#impl::eprt::eprt;
+use crate::commands::r#impl::epsv::epsv;
+use crate::commands::r#impl::feat::feat;
+use crate::commands::r#impl::help::help;
+use crate::commands::r#impl::list::list;
+use crate::commands::r#impl::mdtm::mdtm;
+#[cfg(windows)]
+use crate::commands::r#impl::mfct::mfct;
+use crate::commands::r#impl::mfmt::mfmt;
+use crate::commands::r#impl::mkd::mkd;
+use crate::commands::r#impl::mlsd::mlsd;
+use crate::commands::r#impl::mlst::mlst;
+use crate::commands::r#impl::nlst::nlst;
+use crate::commands::r#impl::noop::noop;
+use crate::commands::r#impl::opts::opts;
+use crate::commands::r#impl::pass::pass;
+use crate::commands::r#impl::pasv::pasv;
+use crate::commands::r#impl::pbsz::pbsz;
+use crate::commands::r#impl::port::port;
+use crate::commands::r#impl::prot::prot;
+use crate::commands::r#impl::pwd::pwd;
+use crate::commands::r#impl::quit::quit;
+use crate::commands::r#impl::rein::rein;
+use crate::commands::r#impl::rest::rest;
+use crate::commands::r#impl::retr::retr;
+use crate::commands::r#impl::rmd::rmd;
+use crate::commands::r#impl::rmda::rmda;
+use crate::commands::r#impl::rnfr::rnfr;
+use crate::commands::r#impl::rnto::rnto;
+use crate::commands::r#impl::site::site;
+use crate::commands::r#impl::size::size;
+use crate::commands::r#impl::stat::stat;
+use crate::commands::r#impl::stor::stor;
+use crate::commands::r#impl::syst::syst;
+use crate::commands::r#impl::r#type::r#type;
+use crate::commands::r#impl::user::user;
+use crate::commands::reply::Reply;
+use crate::commands::reply_code::ReplyCode;
+use crate::handlers::reply_sender::ReplySend;
+use crate::session::command_processor::CommandProcessor;
+
+/// Executes a command.
+///
+/// Implement this to add a command to the [`CommandRegistry`]. The handler is responsible for
+/// replying to the client.
+#[async_trait]
+pub trait CommandHandler: Send + Sync {
+  async fn handle(
+    &self,
+    command: &Command,
+    command_processor: Arc<CommandProcessor>,
+    reply_sender: Arc<dyn ReplySend>,
+  );
+}
+
+/// A function executing a command, used by the built-in commands.
+pub type CommandFn =
+  for<'a> fn(&'a Command, Arc<CommandProcessor>, Arc<dyn ReplySend>) -> BoxFuture<'a, ()>;
+
+#[async_trait]
+impl CommandHandler for CommandFn {
+  async fn handle(
+    &self,
+    command: &Command,
+    command_processor: Arc<CommandProcessor>,
+    reply_sender: Arc<dyn ReplySend>,
+  ) {
+    self(command, command_processor, reply_sender).await
+  }
+}
+
+/// A registered command.
+#[derive(Clone)]
+pub struct CommandEntry {
+  /// The syntax of the command, shown by HELP, e.g.: `RETR <path>`.
+  pub(crate) syntax: &'static str,
+  /// A short description of the command, shown by HELP.
+  pub(crate) description: &'static str,
+  /// The feature line advertised by FEAT, if the command is an extension.
+  pub(crate) feature: Option<&'static str>,
+  handler: Arc<dyn CommandHandler>,
+}
+
+impl Debug for CommandEntry {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.debug_struct("CommandEntry")
+      .field("syntax", &self.syntax)
+      .field("description", &self.description)
+      .field("feature", &self.feature)
+      .finish_non_exhaustive()
+  }
+}
+
+impl CommandEntry {
+  /// Constructs a new entry executed by the function, that isn't advertised by FEAT.
+  pub fn new(syntax: &'static str, description: &'static str, handler: CommandFn) -> Self {
+    CommandEntry::with_handler(syntax, description, handler)
+  }
+
+  /// Constructs a new entry executed by the handler, that isn't advertised by FEAT.
+  pub fn with_handler(
+    syntax: &'static str,
+    description: &'static str,
+    handler: impl CommandHandler + 'static,
+  ) -> Self {
+    CommandEntry {
+      syntax,
+      description,
+      feature: None,
+      handler: Arc::new(handler),
+    }
+  }
+
+  /// Sets the feature line advertised by FEAT.
+  pub fn with_feature(mut self, feature: &'static str) -> Self {
+    self.feature = Some(feature);
+    self
+  }
+
+  /// Executes the command.
+  pub(crate) async fn execute(
+    &self,
+    command: &Command,
+    command_processor: Arc<CommandProcessor>,
+    reply_sender: Arc<dyn ReplySend>,
+  ) {
+    self.handler.handle(command, command_processor, reply_sender).await
+  }
+}
+
+/// The implemented commands, ordered by name.
+#[derive(Debug, Default)]
+pub struct CommandRegistry {
+  commands: BTreeMap<String, CommandEntry>,
+}
+
+impl CommandRegistry {
+  /// Constructs a registry containing all the implemented commands.
+  pub fn with_builtin_commands() -> Self {
+    let mut registry = CommandRegistry::default();
+    registry.register(
+      Commands::Abor,
+      CommandEntry::new("ABOR", "Aborts the running transfer.", |c, p, r| Box::pin(abor(c, p, r))),
+    );
+    registry.register(
+      Commands::Acct,
+      CommandEntry::new("ACCT <code>", "Sends the one-time password.", |c, p, r| {
+        Box::pin(acct(c, p, r))
+      }),
+    );
+    registry.register(
+      Commands::Appe,
+      CommandEntry::new("APPE <path>", "Appends data to a file.", |c, p, r| {
+        Box::pin(appe(c, p, r))
+      }),
+    );
+    // The AUTH TLS feature depends on the connection, so FEAT adds it separately
+    registry.register(
+      Commands::Auth,
+      CommandEntry::new("AUTH <mechanism>", "Secures the control connection.", |c, _, r| {
+        Box::pin(auth(c, r))
+      }),
+    );
+    registry.register(
+      Commands::Cdup,
+      CommandEntry::new("CDUP", "Changes to the parent directory.", |c, p, r| {
+        Box::pin(cdup(c, p, r))
+      }),
+    );
+    registry.register(
+      Commands::Cwd,
+      CommandEntry::new("CWD <path>", "Changes the working directory.", |c, p, r| {
+        Box::pin(cwd(c, p, r))
+      }),
+    );
+    registry.register(
+      Commands::Dele,
+      CommandEntry::new("DELE <path>", "Deletes a file.", |c, p, r| Box::pin(dele(c, p, r))),
+    );
+    registry.register(
+      Commands::Eprt,
+      CommandEntry::new(
+        "EPRT |<protocol>|<address>|<port>|",
+        "Opens an active data channel.",
+        |c, p, r| Box::pin(eprt(c, p, r)),
+      ),
+    );
+    registry.register(
+      Commands::Epsv,
+      CommandEntry::new("EPSV [<protocol>|ALL]", "Opens a passive data channel.", |c, p, r| {
+        Box::pin(epsv(c, p, r))
+      }),
+    );
+    registry.register(
+      Commands::Feat,
+      CommandEntry::new("FEAT", "Lists the supported features.", |c, p, r| Box::pin(feat(c, p, r))),
+    );
+    registry.register(
+      Commands::Help,
+      CommandEntry::new("HELP [<command>]", "Shows help for commands.", |c, p, r| {
+        Box::pin(help(c, p, r))
+      }),
+    );
+    registry.register(
+      Commands::List,
+      CommandEntry::new("LIST [<path>]", "Lists a directory.", |c, p, r| Box::pin(list(c, p, r))),
+    );
+    registry.register(
+      Commands::Mdtm,
+      CommandEntry::new("MDTM <path>", "Returns the modification time of a file.", |c, p, r| {
+        Box::pin(mdtm(c, p, r))
+      })
+      .with_feature("MDTM"),
+    );
+    #[cfg(windows)]
+    registry.register(
+      Commands::Mfct,
+      CommandEntry::new("MFCT <time> <path>", "Sets the creation time of a file.", |c, p, r| {
+        Box::pin(mfct(c, p, r))
+      })
+      .with_feature("MFCT"),
+    );
+    registry.register(
+      Commands::Mfmt,
+      CommandEntry::new(
+        "MFMT <time> <path>",
+        "Sets the modification time of a file.",
+        |c, p, r| Box::pin(mfmt(c, p, r)),
+      )
+      .with_feature("MFMT"),
+    );
+    registry.register(
+      Commands::Mkd,
+      CommandEntry::new("MKD <path>", "Creates a directory.", |c, p, r| Box::pin(mkd(c, p, r))),
+    );
+    registry.register(
+      Commands::Mlsd,
+      CommandEntry::new("MLSD [<path>]", "Lists a directory with facts.", |c, p, r| {
+        Box::pin(mlsd(c, p, r))
+      })
+      .with_feature("MLSD"),
+    );
+    // The MLST feature depends on the session, so FEAT adds it separately
+    registry.register(
+      Commands::Mlst,
+      CommandEntry::new("MLST [<path>]", "Returns the facts of a file or directory.", |c, p, r| {
+        Box::pin(mlst(c, p, r))
+      }),
+    );
+    registry.register(
+      Commands::Nlst,
+      CommandEntry::new("NLST [<path>]", "Lists the names in a directory.", |c, p, r| {
+        Box::pin(nlst(c, p, r))
+      }),
+    );
+    registry.register(
+      Commands::Noop,
+      CommandEntry::new("NOOP", "Does nothing.", |c, _, r| Box::pin(noop(c, r))),
+    );
+    registry.register(
+      Commands::Opts,
+      CommandEntry::new("OPTS <command> [<options>]", "Sets options of a command.", |c, p, r| {
+        Box::pin(opts(c, p, r))
+      })
+      .with_feature("UTF8"),
+    );
+    registry.register(
+      Commands::Pass,
+      CommandEntry::new("PASS <password>", "Sends the password.", |c, p, r| {
+        Box::pin(pass(c, p, r))
+      }),
+    );
+    registry.register(
+      Commands::Pasv,
+      CommandEntry::new("PASV", "Opens a passive data channel.", |c, p, r| Box::pin(pasv(c, p, r))),
+    );
+    registry.register(
+      Commands::Pbsz,
+      CommandEntry::new("PBSZ <size>", "Sets the protection buffer size.", |c, p, r| {
+        Box::pin(pbsz(c, p, r))
+      })
+      .with_feature("PBSZ"),
+    );
+    registry.register(
+      Commands::Port,
+      CommandEntry::new("PORT <h1,h2,h3,h4,p1,p2>", "Opens an active data channel.", |c, p, r| {
+        Box::pin(port(c, p, r))
+      }),
+    );
+    registry.register(
+      Commands::Prot,
+      CommandEntry::new("PROT <level>", "Sets the data channel protection.", |c, p, r| {
+        Box::pin(prot(c, p, r))
+      })
+      .with_feature("PROT"),
+    );
+    registry.register(
+      Commands::Pwd,
+      CommandEntry::new("PWD", "Prints the working directory.", |c, p, r| Box::pin(pwd(c, p, r))),
+    );
+    registry.register(
+      Commands::Quit,
+      CommandEntry::new("QUIT", "Ends the session.", |c, p, r| Box::pin(quit(c, p, r))),
+    );
+    registry.register(
+      Commands::Rein,
+      CommandEntry::new("REIN", "Resets the session.", |c, p, r| Box::pin(rein(c, p, r))),
+    );
+    // REST STREAM (RFC 3659) covers restarting both downloads (RETR) and uploads (STOR)
+    registry.register(
+      Commands::Rest,
+      CommandEntry::new("REST <offset>", "Restarts the next transfer at the offset.", |c, p, r| {
+        Box::pin(rest(c, p, r))
+      })
+      .with_feature("REST STREAM"),
+    );
+    registry.register(
+      Commands::Retr,
+      CommandEntry::new("RETR <path>", "Downloads a file.", |c, p, r| Box::pin(retr(c, p, r))),
+    );
+    registry.register(
+      Commands::Rmd,
+      CommandEntry::new("RMD <path>", "Removes an empty directory.", |c, p, r| {
+        Box::pin(rmd(c, p, r))
+      }),
+    );
+    registry.register(
+      Commands::Rmda,
+      CommandEntry::new("RMDA <path>", "Removes a directory with its contents.", |c, p, r| {
+        Box::pin(rmda(c, p, r))
+      })
+      .with_feature("RMDA <path>"),
+    );
+    registry.register(
+      Commands::Rnfr,
+      CommandEntry::new("RNFR <path>", "Selects a file to rename.", |c, p, r| {
+        Box::pin(rnfr(c, p, r))
+      }),
+    );
+    registry.register(
+      Commands::Rnto,
+      CommandEntry::new("RNTO <path>", "Renames the selected file.", |c, p, r| {
+        Box::pin(rnto(c, p, r))
+      }),
+    );
+    registry.register(
+      Commands::Site,
+      CommandEntry::new("SITE <command> [<arguments>]", "Executes a SITE command.", |c, p, r| {
+        Box::pin(site(c, p, r))
+      }),
+    );
+    registry.register(
+      Commands::Size,
+      CommandEntry::new("SIZE <path>", "Returns the size of a file.", |c, p, r| {
+        Box::pin(size(c, p, r))
+      })
+      .with_feature("SIZE"),
+    );
+    registry.register(
+      Commands::Stat,
+      CommandEntry::new(
+        "STAT [<path>]",
+        "Returns the status of the session or a path.",
+        |c, p, r| Box::pin(stat(c, p, r)),
+      ),
+    );
+    registry.register(
+      Commands::Stor,
+      CommandEntry::new("STOR <path>", "Uploads a file.", |c, p, r| Box::pin(stor(c, p, r))),
+    );
+    registry.register(
+      Commands::Syst,
+      CommandEntry::new("SYST", "Returns the system type.", |c, _, r| Box::pin(syst(c, r))),
+    );
+    registry.register(
+      Commands::Type,
+      CommandEntry::new("TYPE <type>", "Sets the data type of transfers.", |c, p, r| {
+        Box::pin(r#type(c, p, r))
+      }),
+    );
+    registry.register(
+      Commands::User,
+      CommandEntry::new("USER <username>", "Sends the username.", |c, p, r| {
+        Box::pin(user(c, p, r))
+      }),
+    );
+    registry
+  }
+
+  /// Registers the command with the name, replacing the previous entry.
+  ///
+  /// The name is either the name of a standard command, or of a command not defined by any RFC.
+  pub fn register(&mut self, name: impl Display, entry: CommandEntry) {
+    self.commands.insert(name.to_string().to_uppercase(), entry);
+  }
+
+  /// Returns the entry of the command with the name, case-insensitive.
+  pub(crate) fn get(&self, name: &str) -> Option<&CommandEntry> {
+    self.commands.get(&name.to_uppercase())
+  }
+
+  /// Executes the command with its registered handler.
+  ///
+  /// If the command isn't registered, the client is told it's not implemented, or not recognized
+  /// if it isn't defined by any RFC either.
+  pub(crate) async fn execute(
+    &self,
+    command: &Command,
+    command_processor: Arc<CommandProcessor>,
+    reply_sender: Arc<dyn ReplySend>,
+  ) {
+    if let Some(entry) = self.get(&command.name) {
+      return entry.execute(command, command_processor, reply_sender).await;
+    }
+    info!("Couldn't execute command, not implemented! Command: {}", command.name);
+    let reply = if command.command == Commands::Other {
+      Reply::new(ReplyCode::SyntaxErrorCommandUnrecognized, "Command not recognized!")
+    } else {
+      Reply::new(ReplyCode::CommandNotImplemented, "Command not implemented!")
+    };
+    reply_sender.send_control_message(reply).await
+  }
+
+  /// Returns the names of all the commands in alphabetical order.
+  pub(crate) fn names(&self) -> impl Iterator<Item = &str> {
+    self.commands.keys().map(String::as_str)
+  }
+
+  /// Returns the feature lines of the commands advertised by FEAT.
+  pub(crate) fn features(&self) -> impl Iterator<Item = &'static str> {
+    self.commands.values().filter_map(|e| e.feature)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::sync::Arc;
+
+  use async_trait::async_trait;
+  use strum::IntoEnumIterator;
+
+  use crate::commands::command::Command;
+  use crate::commands::command_registry::{CommandEntry, CommandHandler, CommandRegistry};
+  use crate::commands::commands::Commands;
+  use crate::commands::reply::Reply;
+  use crate::commands::reply_code::ReplyCode;
+  use crate::handlers::reply_sender::ReplySend;
+  use crate::session::command_processor::CommandProcessor;
+  use crate::utils::test_utils::*;
+
+  struct EchoHandler;
+
+  #[async_trait]
+  impl CommandHandler for EchoHandler {
+    async fn handle(
+      &self,
+      command: &Command,
+      _command_processor: Arc<CommandProcessor>,
+      reply_sender: Arc<dyn ReplySend>,
+    ) {
+      let reply = Reply::new(ReplyCode::CommandOkay, command.argument.clone());
+      reply_sender.send_control_message(reply).await;
+    }
+  }
+
+  async fn execute_with(registry: &CommandRegistry, command: Command) -> String {
+    let command_processor = Arc::new(setup_test_command_processor().1);
+    receive_reply(|reply_sender| registry.execute(&command, command_processor, reply_sender))
+      .await
+      .1
+  }
+
+  #[test]
+  fn builtin_commands_test() {
+    let registry = CommandRegistry::with_builtin_commands();
+    for name in registry.names() {
+      let entry = registry.get(name).unwrap();
+      assert!(entry.syntax.starts_with(name), "Syntax of {name} doesn't match: {}", entry.syntax);
+      assert!(!entry.description.is_empty(), "Description of {name} is missing!");
+      assert!(name.parse::<Commands>().is_ok(), "{name} isn't a command!");
+    }
+    assert!(registry.get("retr").is_some());
+    assert!(registry.get("STOU").is_none());
+  }
+
+  #[test]
+  fn names_test() {
+    let registry = CommandRegistry::with_builtin_commands();
+    let names = registry.names().collect::<Vec<_>>();
+    let mut sorted = names.clone();
+    sorted.sort();
+    assert_eq!(sorted, names);
+    assert!(names.len() < Commands::iter().count());
+  }
+
+  #[test]
+  fn features_test() {
+    let registry = CommandRegistry::with_builtin_commands();
+    let features = registry.features().collect::<Vec<_>>();
+    assert!(features.contains(&"REST STREAM"));
+    assert!(features.contains(&"UTF8"));
+    assert!(!features.iter().any(|f| f.starts_with("MLST")));
+  }
+
+  #[tokio::test]
+  async fn custom_command_test() {
+    setup_tracing();
+    let mut registry = CommandRegistry::with_builtin_commands();
+    registry.register("xecho", CommandEntry::with_handler("XECHO <text>", "Echoes.", EchoHandler));
+    assert_eq!("XECHO <text>", registry.get("XECHO").unwrap().syntax);
+
+    let command = "xecho hello".parse::<Command>().unwrap();
+    assert_eq!("200 hello\r\n", execute_with(&registry, command).await);
+
+    let command = "xother hello".parse::<Command>().unwrap();
+    assert!(execute_with(&registry, command).await.starts_with("500 "));
+  }
+
+  #[tokio::test]
+  async fn replace_builtin_test() {
+    setup_tracing();
+    let mut registry = CommandRegistry::with_builtin_commands();
+    assert!(
+      execute_with(&registry, Command::new(Commands::Stou, "hello")).await.starts_with("502 ")
+    );
+
+    registry.register(Commands::Noop, CommandEntry::with_handler("NOOP", "Echoes.", EchoHandler));
+    assert_eq!(
+      "200 hello\r\n",
+      execute_with(&registry, Command::new(Commands::Noop, "hello")).await
+    );
+  }
+}
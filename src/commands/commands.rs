@@ -1,9 +1,11 @@
-//! All the recognized commands. **NOTE**: Not all commands listed here are implemented.
+//! All the recognized commands. **NOTE**: Not all commands listed here are implemented, the
+//! implemented ones are registered in the
+//! [`CommandRegistry`](crate::commands::command_registry::CommandRegistry).
 
-use strum_macros::{EnumIter, EnumString};
+use strum_macros::{Display, EnumIter, EnumString};
 
-#[derive(Clone, Copy, Debug, PartialEq, EnumString, EnumIter)]
-#[strum(ascii_case_insensitive)]
+#[derive(Clone, Copy, Debug, PartialEq, Display, EnumString, EnumIter)]
+#[strum(ascii_case_insensitive, serialize_all = "UPPERCASE")]
 #[non_exhaustive]
 pub enum Commands {
   Abor,
@@ -25,6 +27,7 @@ pub enum Commands {
   Mfct,
   Mkd,
   Mlsd,
+  Mlst,
   Mode,
   Nlst,
   Noop,
@@ -52,4 +55,9 @@ pub enum Commands {
   Syst,
   Type,
   User,
+  /// A command not defined by any RFC, e.g.: a site-specific command. Such commands are only
+  /// recognized if they are registered in the
+  /// [`CommandRegistry`](crate::commands::command_registry::CommandRegistry).
+  #[strum(disabled)]
+  Other,
 }
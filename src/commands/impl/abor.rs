@@ -13,7 +13,7 @@ use crate::session::command_processor::CommandProcessor;
 pub(crate) async fn abor(
   command: &Command,
   command_processor: Arc<CommandProcessor>,
-  reply_sender: Arc<impl ReplySend>,
+  reply_sender: Arc<dyn ReplySend>,
 ) {
   debug_assert_eq!(Commands::Abor, command.command);
 
@@ -0,0 +1,198 @@
+use std::sync::Arc;
+
+use tokio::sync::RwLock;
+use tracing::{error, info};
+
+use crate::auth::auth_error::AuthError;
+use crate::auth::auth_provider::AuthProvider;
+use crate::commands::command::Command;
+use crate::commands::commands::Commands;
+use crate::commands::r#impl::pass::login_reply;
+use crate::commands::reply::Reply;
+use crate::commands::reply_code::ReplyCode;
+use crate::global_context::{AUTH_PROVIDER, LOGIN_FAILURES};
+use crate::handlers::reply_sender::ReplySend;
+use crate::session::command_processor::CommandProcessor;
+use crate::session::session_properties::SessionProperties;
+
+/// Completes the login of a user with a second factor, with the one-time password as the account.
+#[tracing::instrument(skip_all)]
+pub(crate) async fn acct(
+  command: &Command,
+  command_processor: Arc<CommandProcessor>,
+  reply_sender: Arc<dyn ReplySend>,
+) {
+  debug_assert_eq!(command.command, Commands::Acct);
+
+  let code = command.argument.trim();
+  if code.is_empty() {
+    return reply_sender
+      .send_control_message(Reply::new(
+        ReplyCode::SyntaxErrorInParametersOrArguments,
+        "No one-time password supplied",
+      ))
+      .await;
+  }
+
+  let session_properties = &command_processor.session_properties;
+  if session_properties.read().await.is_logged_in() {
+    return reply_sender
+      .send_control_message(Reply::new(
+        ReplyCode::CommandNotImplementedSuperfluous,
+        "Already logged in, no account needed.",
+      ))
+      .await;
+  }
+
+  let provider = match AUTH_PROVIDER.get() {
+    Some(provider) => provider,
+    None => {
+      error!("Database connection not setup!");
+      return reply_sender
+        .send_control_message(Reply::new(
+          ReplyCode::ServiceNotAvailableClosingControlConnection,
+          "Unknown error occurred!",
+        ))
+        .await;
+    }
+  };
+
+  let reply = one_time_password_login(session_properties, provider, code).await;
+  reply_sender.send_control_message(reply).await
+}
+
+/// Attempts to log in the user with the password from PASS and the one-time password.
+///
+/// The password is only used for one attempt, a failed login needs to start with PASS again. The
+/// reply is the same whichever of the passwords is incorrect.
+async fn one_time_password_login(
+  session_properties: &RwLock<SessionProperties>,
+  provider: &AuthProvider,
+  code: &str,
+) -> Reply {
+  let mut form = {
+    let mut properties = session_properties.write().await;
+    let form = properties.login_form.clone();
+    properties.login_form.password.take();
+    form
+  };
+  let Some(username) = form.username.clone().filter(|_| form.password.is_some()) else {
+    return Reply::new(ReplyCode::BadSequenceOfCommands, "Supply the username and password first!");
+  };
+
+  let _ = form.one_time_password.insert(code.to_string());
+  let ip = session_properties.read().await.registration.as_ref().map(|r| r.ip());
+  info!("User '{}' attempting login with a one-time password.", &username);
+  let result = if LOGIN_FAILURES.is_client_banned(ip) {
+    Err(AuthError::InvalidCredentials)
+  } else {
+    session_properties.write().await.login(provider, form).await
+  };
+  login_reply(result, &username, ip).await
+}
+
+#[cfg(test)]
+mod tests {
+  use std::sync::Arc;
+  use std::time::Duration;
+
+  use sqlx::SqlitePool;
+  use tokio::sync::RwLock;
+  use tokio::sync::mpsc::channel;
+  use tokio::time::timeout;
+
+  use crate::auth::auth_provider::AuthProvider;
+  use crate::auth::sqlite_data_source::SqliteDataSource;
+  use crate::auth::sqlite_data_source::tests::setup_test_db;
+  use crate::auth::totp;
+  use crate::commands::command::Command;
+  use crate::commands::commands::Commands;
+  use crate::commands::r#impl::acct::one_time_password_login;
+  use crate::commands::reply_code::ReplyCode;
+  use crate::data_channels::standard_data_channel_wrapper::StandardDataChannelWrapper;
+  use crate::session::command_processor::CommandProcessor;
+  use crate::session::session_properties::SessionProperties;
+  use crate::utils::test_utils::*;
+
+  /// The TOTP secret of 'testuser4'.
+  const TOTP_SECRET: &str = "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ";
+
+  async fn provider(pool: SqlitePool) -> sqlx::Result<AuthProvider> {
+    setup_test_db(&pool).await?;
+    let mut provider = AuthProvider::new();
+    provider.add_data_source(Box::new(SqliteDataSource::new(pool)));
+    Ok(provider)
+  }
+
+  fn session(username: &str, password: Option<&str>) -> RwLock<SessionProperties> {
+    let mut session_properties = SessionProperties::new();
+    let _ = session_properties.login_form.username.insert(username.to_string());
+    session_properties.login_form.password = password.map(str::to_string);
+    RwLock::new(session_properties)
+  }
+
+  #[sqlx::test]
+  async fn login_test(pool: SqlitePool) -> sqlx::Result<()> {
+    setup_tracing();
+    let provider = provider(pool).await?;
+    let session_properties = session("testuser4", Some("user4"));
+
+    let code = totp::generate(TOTP_SECRET);
+    let reply = one_time_password_login(&session_properties, &provider, &code).await;
+    assert_eq!(ReplyCode::UserLoggedIn, reply.code);
+    assert!(session_properties.read().await.is_logged_in());
+    Ok(())
+  }
+
+  #[sqlx::test]
+  async fn incorrect_credentials_test(pool: SqlitePool) -> sqlx::Result<()> {
+    setup_tracing();
+    let provider = provider(pool).await?;
+    let code = totp::generate(TOTP_SECRET);
+    let invalid_code = format!("{:06}", (code.parse::<u32>().unwrap() + 1) % 1_000_000);
+
+    let session_properties = session("testuser4", Some("user4"));
+    let invalid_code_reply =
+      one_time_password_login(&session_properties, &provider, &invalid_code).await;
+    assert_eq!(ReplyCode::NotLoggedIn, invalid_code_reply.code);
+    assert!(!session_properties.read().await.is_logged_in());
+    assert_eq!(None, session_properties.read().await.login_form.password, "Password is used once");
+
+    let session_properties = session("testuser4", Some("INVALID"));
+    let invalid_password_reply =
+      one_time_password_login(&session_properties, &provider, &code).await;
+    // The replies don't tell which of the passwords is incorrect
+    assert_eq!(invalid_code_reply.to_string(), invalid_password_reply.to_string());
+    Ok(())
+  }
+
+  #[sqlx::test]
+  async fn no_password_test(pool: SqlitePool) -> sqlx::Result<()> {
+    setup_tracing();
+    let provider = provider(pool).await?;
+    let session_properties = session("testuser4", None);
+    let reply = one_time_password_login(&session_properties, &provider, "123456").await;
+    assert_eq!(ReplyCode::BadSequenceOfCommands, reply.code);
+    Ok(())
+  }
+
+  #[tokio::test]
+  async fn no_code_test() {
+    setup_tracing();
+    let session_properties = Arc::new(session("test", Some("test")));
+    let wrapper = Arc::new(StandardDataChannelWrapper::new(LOCALHOST, LOCALHOST.ip()));
+    let command_processor = CommandProcessor::new(session_properties, wrapper);
+
+    let (tx, mut rx) = channel(1024);
+    let reply_sender = TestReplySender::new(tx);
+    timeout(
+      Duration::from_secs(5),
+      Command::new(Commands::Acct, " ")
+        .execute(Arc::new(command_processor), Arc::new(reply_sender)),
+    )
+    .await
+    .expect("Command timed out!");
+
+    receive_and_verify_reply(2, &mut rx, ReplyCode::SyntaxErrorInParametersOrArguments, None).await;
+  }
+}
@@ -0,0 +1,289 @@
+use std::sync::Arc;
+
+use crate::commands::command::Command;
+use crate::commands::commands::Commands;
+use crate::commands::r#impl::shared::store_file;
+use crate::handlers::reply_sender::ReplySend;
+use crate::io::open_options_flags::OpenOptionsWrapperBuilder;
+use crate::session::command_processor::CommandProcessor;
+
+#[tracing::instrument(skip(command_processor, reply_sender))]
+pub(crate) async fn appe(
+  command: &Command,
+  command_processor: Arc<CommandProcessor>,
+  reply_sender: Arc<dyn ReplySend>,
+) {
+  debug_assert_eq!(command.command, Commands::Appe);
+
+  let options = OpenOptionsWrapperBuilder::default().append(true).create(true).build().unwrap();
+  store_file(command, command_processor, reply_sender, options).await;
+}
+
+#[cfg(test)]
+mod tests {
+  use std::collections::HashSet;
+  use std::env::temp_dir;
+  use std::path::Path;
+  use std::sync::Arc;
+  use std::time::Duration;
+
+  use quinn::VarInt;
+  use s2n_quic::client::Connect;
+  use tokio::io::{AsyncWrite, AsyncWriteExt};
+  use tokio::sync::Mutex;
+  use tokio::sync::mpsc::channel;
+  use tokio::time::timeout;
+  use tokio_util::sync::CancellationToken;
+  use uuid::Uuid;
+
+  use crate::auth::user_permission::UserPermission;
+  use crate::commands::command::Command;
+  use crate::commands::commands::Commands;
+  use crate::commands::reply_code::ReplyCode;
+  use crate::data_channels::quic_only_data_channel_wrapper::QuicOnlyDataChannelWrapper;
+  use crate::data_channels::quic_quinn_data_channel_wrapper::QuicQuinnDataChannelWrapper;
+  use crate::listeners::quic_only_listener::QuicOnlyListener;
+  use crate::listeners::quinn_listener::QuinnListener;
+  use crate::session::command_processor::CommandProcessor;
+  use crate::session::data_type::DataType;
+  use crate::session::protection_mode::ProtMode;
+  use crate::utils::test_utils::*;
+
+  const LOCAL_FILE: &str = "test_files/2KiB.txt";
+  const EXISTING_CONTENT: &[u8] = b"existing content\n";
+
+  fn create_remote_file() -> String {
+    let remote_file = format!("{}.test", Uuid::new_v4().as_hyphenated());
+    std::fs::write(temp_dir().join(&remote_file), EXISTING_CONTENT)
+      .expect("Creating remote file should succeed");
+    remote_file
+  }
+
+  async fn transfer<T: AsyncWrite + Unpin>(
+    remote_file: &str,
+    command_processor: CommandProcessor,
+    mut client_dc: T,
+  ) {
+    let (tx, mut rx) = channel(1024);
+    // The files are compared byte by byte
+    command_processor.session_properties.write().await.data_type = DataType::Binary;
+    let reply_sender = TestReplySender::new(tx);
+    let command = Command::new(Commands::Appe, remote_file);
+
+    let command_fut = tokio::spawn(async move {
+      timeout(
+        Duration::from_secs(10),
+        command.execute(Arc::new(command_processor), Arc::new(reply_sender)),
+      )
+      .await
+      .expect("Command timeout!");
+    });
+
+    receive_and_verify_reply(2, &mut rx, ReplyCode::FileStatusOkay, None).await;
+
+    let local_content = std::fs::read(LOCAL_FILE).expect("Test file must exist!");
+    client_dc.write_all(&local_content).await.expect("Transfer should succeed");
+    client_dc.shutdown().await.expect("Data channel shutdown should succeed");
+
+    receive_and_verify_reply(2, &mut rx, ReplyCode::ClosingDataConnection, None).await;
+    command_fut.await.expect("Command should complete!");
+
+    let remote_content =
+      std::fs::read(temp_dir().join(remote_file)).expect("Remote test file must exist!");
+    let mut expected = EXISTING_CONTENT.to_vec();
+    expected.extend_from_slice(&local_content);
+    assert_eq!(expected, remote_content, "File was not appended correctly!");
+  }
+
+  #[tokio::test]
+  async fn appe_tcp_test() {
+    setup_tracing();
+    let remote_file = create_remote_file();
+    let remote_file_path = temp_dir().join(&remote_file);
+    let _cleanup = FileCleanup::new(&remote_file_path);
+
+    let settings = CommandProcessorSettingsBuilder::default()
+      .label("test_files".to_string())
+      .change_path(Some("test_files".to_string()))
+      .username(Some("testuser".to_string()))
+      .view_root(temp_dir())
+      .build()
+      .expect("Settings should be valid");
+    let mut command_processor = setup_test_command_processor_custom(&settings);
+    let client_dc = open_tcp_data_channel(&mut command_processor).await;
+
+    transfer(&remote_file, command_processor, client_dc).await;
+  }
+
+  #[tokio::test]
+  async fn appe_tls_test() {
+    setup_tracing();
+    let remote_file = create_remote_file();
+    let remote_file_path = temp_dir().join(&remote_file);
+    let _cleanup = FileCleanup::new(&remote_file_path);
+
+    let settings = CommandProcessorSettingsBuilder::default()
+      .label("test_files".to_string())
+      .change_path(Some("test_files".to_string()))
+      .username(Some("testuser".to_string()))
+      .view_root(temp_dir())
+      .build()
+      .expect("Settings should be valid");
+    let mut command_processor = setup_test_command_processor_custom(&settings);
+    let client_dc = open_tls_data_channel(&mut command_processor).await;
+
+    transfer(&remote_file, command_processor, client_dc).await;
+  }
+
+  #[tokio::test]
+  async fn appe_quic_test() {
+    setup_tracing();
+    let remote_file = create_remote_file();
+    let remote_file_path = temp_dir().join(&remote_file);
+    let _cleanup = FileCleanup::new(&remote_file_path);
+
+    let mut listener = QuicOnlyListener::new(LOCALHOST).unwrap();
+    let addr = listener.server.local_addr().unwrap();
+    let token = CancellationToken::new();
+    let test_handle = tokio::spawn(async move {
+      let connection = listener.accept(token.clone()).await.unwrap();
+      let wrapper = QuicOnlyDataChannelWrapper::new(LOCALHOST, Arc::new(Mutex::new(connection)));
+      setup_transfer_command_processor(wrapper, temp_dir())
+    });
+
+    let client = setup_s2n_client();
+    let connect = Connect::new(addr).with_server_name("localhost");
+    let mut client_connection = timeout(Duration::from_secs(2), client.connect(connect))
+      .await
+      .expect("Client should connect in time")
+      .expect("Client should connect");
+    client_connection.keep_alive(true).unwrap();
+
+    let command_processor = timeout(Duration::from_secs(1), test_handle)
+      .await
+      .expect("Connection setup should succeed")
+      .unwrap();
+    let _ = command_processor.data_wrapper.open_data_stream(ProtMode::Clear).await.unwrap();
+
+    let client_dc = client_connection.open_bidirectional_stream().await.unwrap();
+
+    transfer(&remote_file, command_processor, client_dc).await;
+  }
+
+  #[tokio::test]
+  async fn appe_quinn_test() {
+    setup_tracing();
+    let remote_file = create_remote_file();
+    let remote_file_path = temp_dir().join(&remote_file);
+    let _cleanup = FileCleanup::new(&remote_file_path);
+
+    let listener = QuinnListener::new(LOCALHOST).unwrap();
+    let addr = listener.listener.local_addr().unwrap();
+    let token = CancellationToken::new();
+    let test_handle = tokio::spawn(async move {
+      let connection =
+        Arc::new(Mutex::new(listener.accept(token.clone()).await.unwrap().await.unwrap()));
+      let wrapper = QuicQuinnDataChannelWrapper::new(LOCALHOST, connection.clone());
+      (setup_transfer_command_processor(wrapper, temp_dir()), connection)
+    });
+
+    let quinn_client = setup_quinn_client(create_tls_client_config("ftpoq-1"));
+    let connection = quinn_client.connect(addr, "localhost").unwrap().await.unwrap();
+
+    let (command_processor, server_connection) = timeout(Duration::from_secs(1), test_handle)
+      .await
+      .expect("Connection setup should succeed")
+      .unwrap();
+    let _ = command_processor.data_wrapper.open_data_stream(ProtMode::Clear).await.unwrap();
+
+    let (mut send_stream, _) = connection.open_bi().await.unwrap();
+    // Required to actually open the stream
+    send_stream.write("".as_bytes()).await.unwrap();
+
+    transfer(&remote_file, command_processor, send_stream).await;
+    server_connection.lock().await.close(VarInt::from_u32(0), "Test end".as_bytes());
+  }
+
+  #[tokio::test]
+  async fn appe_no_permission_test() {
+    setup_tracing();
+    let remote_file = create_remote_file();
+    let remote_file_path = temp_dir().join(&remote_file);
+    let _cleanup = FileCleanup::new(&remote_file_path);
+
+    let settings = CommandProcessorSettingsBuilder::default()
+      .label("test_files".to_string())
+      .change_path(Some("test_files".to_string()))
+      .username(Some("testuser".to_string()))
+      .view_root(temp_dir())
+      .permissions(HashSet::from([UserPermission::Write, UserPermission::Create]))
+      .build()
+      .expect("Settings should be valid");
+    let mut command_processor = setup_test_command_processor_custom(&settings);
+    let _client_dc = open_tcp_data_channel(&mut command_processor).await;
+
+    let (tx, mut rx) = channel(1024);
+    let reply_sender = TestReplySender::new(tx);
+    let command = Command::new(Commands::Appe, &remote_file);
+    timeout(
+      Duration::from_secs(5),
+      command.execute(Arc::new(command_processor), Arc::new(reply_sender)),
+    )
+    .await
+    .expect("Command timed out!");
+
+    receive_and_verify_reply(2, &mut rx, ReplyCode::FileUnavailable, None).await;
+    assert_eq!(EXISTING_CONTENT, std::fs::read(&remote_file_path).unwrap());
+  }
+
+  #[tokio::test]
+  async fn appe_nonexistent_without_create_permission_test() {
+    setup_tracing();
+    let remote_file = format!("{}.test", Uuid::new_v4().as_hyphenated());
+    let remote_file_path = temp_dir().join(&remote_file);
+    let _cleanup = FileCleanup::new(&remote_file_path);
+
+    let settings = CommandProcessorSettingsBuilder::default()
+      .label("test_files".to_string())
+      .change_path(Some("test_files".to_string()))
+      .username(Some("testuser".to_string()))
+      .view_root(temp_dir())
+      .permissions(HashSet::from([UserPermission::Append]))
+      .build()
+      .expect("Settings should be valid");
+    let mut command_processor = setup_test_command_processor_custom(&settings);
+    let _client_dc = open_tcp_data_channel(&mut command_processor).await;
+
+    let (tx, mut rx) = channel(1024);
+    let reply_sender = TestReplySender::new(tx);
+    let command = Command::new(Commands::Appe, &remote_file);
+    timeout(
+      Duration::from_secs(5),
+      command.execute(Arc::new(command_processor), Arc::new(reply_sender)),
+    )
+    .await
+    .expect("Command timed out!");
+
+    receive_and_verify_reply(2, &mut rx, ReplyCode::FileUnavailable, None).await;
+    assert!(!Path::new(&remote_file_path).exists());
+  }
+
+  #[tokio::test]
+  async fn appe_not_logged_in_test() {
+    setup_tracing();
+    let settings = CommandProcessorSettingsBuilder::default().build().unwrap();
+    let command_processor = setup_test_command_processor_custom(&settings);
+
+    let (tx, mut rx) = channel(1024);
+    let reply_sender = TestReplySender::new(tx);
+    let command = Command::new(Commands::Appe, "NONEXISTENT");
+    timeout(
+      Duration::from_secs(5),
+      command.execute(Arc::new(command_processor), Arc::new(reply_sender)),
+    )
+    .await
+    .expect("Command timed out!");
+
+    receive_and_verify_reply(2, &mut rx, ReplyCode::NotLoggedIn, None).await;
+  }
+}
@@ -0,0 +1,91 @@
+use std::sync::Arc;
+
+use crate::commands::command::Command;
+use crate::commands::commands::Commands;
+use crate::commands::reply::Reply;
+use crate::commands::reply_code::ReplyCode;
+use crate::handlers::reply_sender::ReplySend;
+
+/// Handles AUTH on connections where the control channel is already protected, e.g.: implicit
+/// TLS or QUIC.
+///
+/// The upgrade of a cleartext control channel is done by the
+/// [`StandardConnectionHandler`](crate::handlers::standard_connection_handler::StandardConnectionHandler),
+/// because it owns the underlying connection.
+#[tracing::instrument(skip(reply_sender))]
+pub(crate) async fn auth(command: &Command, reply_sender: Arc<dyn ReplySend>) {
+  debug_assert_eq!(command.command, Commands::Auth);
+
+  let reply = match validate_mechanism(&command.argument) {
+    Ok(()) => {
+      Reply::new(ReplyCode::BadSequenceOfCommands, "Control connection is already secured!")
+    }
+    Err(reply) => reply,
+  };
+  reply_sender.send_control_message(reply).await;
+}
+
+/// Checks that the security mechanism requested by the client is TLS.
+///
+/// 'TLS', 'TLS-C' and 'SSL' are all accepted, as some clients still use the older names.
+/// Returns the reply to send to the client if the mechanism is not supported.
+pub(crate) fn validate_mechanism(argument: &str) -> Result<(), Reply> {
+  match argument.trim().to_ascii_uppercase().as_str() {
+    "TLS" | "TLS-C" | "SSL" => Ok(()),
+    "" => {
+      Err(Reply::new(ReplyCode::SyntaxErrorInParametersOrArguments, "AUTH must have an argument!"))
+    }
+    _ => Err(Reply::new(
+      ReplyCode::CommandNotImplementedForThatParameter,
+      "Security mechanism not supported, use TLS!",
+    )),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::sync::Arc;
+  use std::time::Duration;
+
+  use tokio::sync::mpsc::channel;
+  use tokio::time::timeout;
+
+  use crate::commands::command::Command;
+  use crate::commands::commands::Commands;
+  use crate::commands::r#impl::auth::validate_mechanism;
+  use crate::commands::reply_code::ReplyCode;
+  use crate::utils::test_utils::*;
+
+  #[test]
+  fn validate_mechanism_test() {
+    assert!(validate_mechanism("TLS").is_ok());
+    assert!(validate_mechanism("tls-c").is_ok());
+    assert!(validate_mechanism("SSL").is_ok());
+    assert_eq!(
+      ReplyCode::CommandNotImplementedForThatParameter,
+      validate_mechanism("KERBEROS_V4").unwrap_err().code
+    );
+    assert_eq!(
+      ReplyCode::SyntaxErrorInParametersOrArguments,
+      validate_mechanism("").unwrap_err().code
+    );
+  }
+
+  #[tokio::test]
+  async fn auth_already_secured_test() {
+    setup_tracing();
+    let (_, command_processor) = setup_test_command_processor();
+
+    let (tx, mut rx) = channel(1024);
+    let reply_sender = TestReplySender::new(tx);
+    let command = Command::new(Commands::Auth, "TLS");
+    timeout(
+      Duration::from_secs(2),
+      command.execute(Arc::new(command_processor), Arc::new(reply_sender)),
+    )
+    .await
+    .expect("Command timeout!");
+
+    receive_and_verify_reply(2, &mut rx, ReplyCode::BadSequenceOfCommands, None).await;
+  }
+}
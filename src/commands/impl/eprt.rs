@@ -0,0 +1,171 @@
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+
+use tracing::debug;
+
+use crate::commands::command::Command;
+use crate::commands::commands::Commands;
+use crate::commands::r#impl::shared::connect_data_channel;
+use crate::commands::reply::Reply;
+use crate::commands::reply_code::ReplyCode;
+use crate::handlers::reply_sender::ReplySend;
+use crate::session::command_processor::CommandProcessor;
+
+#[tracing::instrument(skip(command_processor, reply_sender))]
+pub(crate) async fn eprt(
+  command: &Command,
+  command_processor: Arc<CommandProcessor>,
+  reply_sender: Arc<dyn ReplySend>,
+) {
+  debug_assert_eq!(command.command, Commands::Eprt);
+
+  let addr = match parse_eprt_address(&command.argument) {
+    Ok(addr) => addr,
+    Err(reply) => {
+      debug!("Invalid EPRT address: {}", command.argument);
+      reply_sender.send_control_message(reply).await;
+      return;
+    }
+  };
+
+  let reply = connect_data_channel(addr, command_processor).await;
+  reply_sender.send_control_message(reply).await;
+}
+
+/// Parses the address in format `<d><net-prt><d><net-addr><d><tcp-port><d>` as specified by
+/// [RFC2428](https://datatracker.ietf.org/doc/html/rfc2428#section-2).
+fn parse_eprt_address(argument: &str) -> Result<SocketAddr, Reply> {
+  let syntax_error =
+    || Reply::new(ReplyCode::SyntaxErrorInParametersOrArguments, "Invalid EPRT address!");
+
+  let argument = argument.trim();
+  let delimiter =
+    argument.chars().next().filter(|c| ('!'..='~').contains(c)).ok_or_else(syntax_error)?;
+  let parts = argument.split(delimiter).collect::<Vec<&str>>();
+  let [_, protocol, address, port, ""] = parts.as_slice() else {
+    return Err(syntax_error());
+  };
+
+  let address = match *protocol {
+    "1" => address.parse().map(IpAddr::V4),
+    "2" => address.parse().map(IpAddr::V6),
+    _ => {
+      return Err(Reply::new(
+        ReplyCode::NetworkProtocolNotSupported,
+        "Network protocol not supported, use (1,2)",
+      ));
+    }
+  }
+  .map_err(|_| syntax_error())?;
+  let port = port.parse::<u16>().map_err(|_| syntax_error())?;
+
+  Ok(SocketAddr::new(address, port))
+}
+
+#[cfg(test)]
+mod tests {
+  use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+  use std::sync::Arc;
+  use std::time::Duration;
+
+  use tokio::io::{AsyncReadExt, AsyncWriteExt};
+  use tokio::net::TcpListener;
+  use tokio::sync::mpsc::channel;
+  use tokio::time::timeout;
+
+  use crate::commands::command::Command;
+  use crate::commands::commands::Commands;
+  use crate::commands::r#impl::eprt::parse_eprt_address;
+  use crate::commands::reply_code::ReplyCode;
+  use crate::utils::test_utils::*;
+
+  #[test]
+  fn parse_test() {
+    assert_eq!(
+      SocketAddr::new(IpAddr::V4(Ipv4Addr::new(132, 235, 1, 2)), 6275),
+      parse_eprt_address("|1|132.235.1.2|6275|").unwrap()
+    );
+    assert_eq!(
+      SocketAddr::new(IpAddr::V6("1080::8:800:200C:417A".parse::<Ipv6Addr>().unwrap()), 5282),
+      parse_eprt_address("|2|1080::8:800:200C:417A|5282|").unwrap()
+    );
+    assert_eq!(
+      SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 21),
+      parse_eprt_address("!1!127.0.0.1!21!").unwrap()
+    );
+  }
+
+  #[test]
+  fn parse_invalid_test() {
+    let invalid = ["", "|1|127.0.0.1|21", "|1|::1|21|", "|2|127.0.0.1|21|", "|1|127.0.0.1|a|"];
+    for argument in invalid {
+      let Err(reply) = parse_eprt_address(argument) else {
+        panic!("'{argument}' should be invalid");
+      };
+      assert_eq!(ReplyCode::SyntaxErrorInParametersOrArguments, reply.code);
+    }
+
+    let Err(reply) = parse_eprt_address("|3|127.0.0.1|21|") else {
+      panic!("Unknown protocol should be invalid");
+    };
+    assert_eq!(ReplyCode::NetworkProtocolNotSupported, reply.code);
+  }
+
+  #[tokio::test]
+  async fn eprt_test() {
+    setup_tracing();
+    let (_, command_processor) = setup_test_command_processor();
+    let command_processor = Arc::new(command_processor);
+    let client_listener = TcpListener::bind(LOCALHOST).await.unwrap();
+    let client_addr = client_listener.local_addr().unwrap();
+
+    let (tx, mut rx) = channel(1024);
+    let reply_sender = TestReplySender::new(tx);
+    let command =
+      Command::new(Commands::Eprt, format!("|1|{}|{}|", client_addr.ip(), client_addr.port()));
+    timeout(
+      Duration::from_secs(2),
+      command.execute(command_processor.clone(), Arc::new(reply_sender)),
+    )
+    .await
+    .expect("Command timeout!");
+    receive_and_verify_reply(2, &mut rx, ReplyCode::CommandOkay, None).await;
+
+    let (mut client_dc, _) = timeout(Duration::from_secs(2), client_listener.accept())
+      .await
+      .expect("Server should connect in time")
+      .expect("Server should connect");
+    let (mut server_dc, _) =
+      timeout(Duration::from_secs(2), command_processor.data_wrapper.acquire())
+        .await
+        .expect("Data channel should be available in time")
+        .expect("Data channel should be available");
+
+    server_dc.write_all(b"active").await.unwrap();
+    server_dc.shutdown().await.unwrap();
+    let mut received = String::new();
+    client_dc.read_to_string(&mut received).await.unwrap();
+    assert_eq!("active", received);
+  }
+
+  #[tokio::test]
+  async fn eprt_bounce_test() {
+    setup_tracing();
+    let (_, command_processor) = setup_test_command_processor();
+    let command_processor = Arc::new(command_processor);
+
+    let (tx, mut rx) = channel(1024);
+    let reply_sender = TestReplySender::new(tx);
+    let command = Command::new(Commands::Eprt, "|2|::1|21|");
+    timeout(
+      Duration::from_secs(2),
+      command.execute(command_processor.clone(), Arc::new(reply_sender)),
+    )
+    .await
+    .expect("Command timeout!");
+
+    receive_and_verify_reply(2, &mut rx, ReplyCode::CommandNotImplementedForThatParameter, None)
+      .await;
+    assert!(command_processor.data_wrapper.try_acquire().is_err());
+  }
+}
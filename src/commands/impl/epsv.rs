@@ -0,0 +1,211 @@
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use tracing::{debug, error};
+
+use crate::commands::command::Command;
+use crate::commands::commands::Commands;
+use crate::commands::reply::Reply;
+use crate::commands::reply_code::ReplyCode;
+use crate::handlers::reply_sender::ReplySend;
+use crate::session::command_processor::CommandProcessor;
+
+#[tracing::instrument(skip(command_processor, reply_sender))]
+pub(crate) async fn epsv(
+  command: &Command,
+  command_processor: Arc<CommandProcessor>,
+  reply_sender: Arc<dyn ReplySend>,
+) {
+  debug_assert_eq!(command.command, Commands::Epsv);
+
+  let argument = command.argument.trim();
+  if argument.eq_ignore_ascii_case("ALL") {
+    debug!("EPSV ALL, refusing other data connection commands");
+    command_processor.session_properties.write().await.epsv_all = true;
+    reply_sender
+      .send_control_message(Reply::new(ReplyCode::CommandOkay, "EPSV ALL command successful."))
+      .await;
+    return;
+  }
+
+  let protocol = match argument {
+    "" => None,
+    "1" | "2" => Some(argument),
+    _ if argument.parse::<u8>().is_ok() => {
+      reply_sender
+        .send_control_message(Reply::new(
+          ReplyCode::NetworkProtocolNotSupported,
+          "Network protocol not supported, use (1,2)",
+        ))
+        .await;
+      return;
+    }
+    _ => {
+      reply_sender
+        .send_control_message(Reply::new(
+          ReplyCode::SyntaxErrorInParametersOrArguments,
+          "Invalid EPSV argument!",
+        ))
+        .await;
+      return;
+    }
+  };
+
+  let wrapper = command_processor.data_wrapper.clone();
+  // Checked before listening, so no listener is left open for a refused protocol
+  let supported = if wrapper.local_addr().ip().to_canonical().is_ipv4() { "1" } else { "2" };
+  if protocol.is_some_and(|protocol| protocol != supported) {
+    return reply_sender
+      .send_control_message(Reply::new(
+        ReplyCode::NetworkProtocolNotSupported,
+        format!("Network protocol not supported, use ({supported})"),
+      ))
+      .await;
+  }
+
+  let prot_mode = command_processor.session_properties.read().await.prot_mode;
+  let reply = match wrapper.open_data_stream(prot_mode).await {
+    Ok(addr) => Reply::new(ReplyCode::EnteringExtendedPassiveMode, create_epsv_response(&addr)),
+    Err(e) => {
+      error!("Failed to open data stream: {}", e);
+      Reply::new(ReplyCode::CantOpenDataConnection, "Failed to listen for data stream")
+    }
+  };
+  reply_sender.send_control_message(reply).await;
+}
+
+/// Creates the reply as specified by
+/// [RFC2428](https://datatracker.ietf.org/doc/html/rfc2428#section-3). Only the port is sent,
+/// the client connects to the same address as the control connection.
+fn create_epsv_response(addr: &SocketAddr) -> String {
+  format!("Entering Extended Passive Mode (|||{}|)", addr.port())
+}
+
+#[cfg(test)]
+mod tests {
+  use std::net::{IpAddr, Ipv6Addr, SocketAddr};
+  use std::sync::Arc;
+
+  use tokio::net::{TcpListener, TcpStream};
+  use tokio::sync::RwLock;
+
+  use crate::commands::command::Command;
+  use crate::commands::commands::Commands;
+  use crate::commands::r#impl::epsv::create_epsv_response;
+  use crate::commands::reply_code::ReplyCode;
+  use crate::data_channels::passive_settings::PassiveSettings;
+  use crate::data_channels::standard_data_channel_wrapper::StandardDataChannelWrapper;
+  use crate::session::command_processor::CommandProcessor;
+  use crate::session::session_properties::SessionProperties;
+  use crate::utils::test_utils::*;
+
+  fn parse_port(message: &str) -> u16 {
+    let start = message.find("(|||").expect("Port should start with '(|||'");
+    let end = message.find("|)").expect("Port should end with '|)'");
+    message[start + 4..end].parse().expect("Port should be valid")
+  }
+
+  #[test]
+  fn response_test() {
+    let addr = SocketAddr::new(IpAddr::V6(Ipv6Addr::LOCALHOST), 55692);
+    assert_eq!(create_epsv_response(&addr), "Entering Extended Passive Mode (|||55692|)");
+  }
+
+  #[tokio::test]
+  async fn epsv_test() {
+    setup_tracing();
+    let (_, command_processor) = setup_test_command_processor();
+
+    let (code, reply) =
+      execute(Command::new(Commands::Epsv, ""), Arc::new(command_processor)).await;
+    assert_eq!(ReplyCode::EnteringExtendedPassiveMode, code);
+
+    let addr = SocketAddr::new(LOCALHOST.ip(), parse_port(&reply));
+    TcpStream::connect(addr).await.expect("Client passive connection should succeed");
+  }
+
+  #[tokio::test]
+  async fn epsv_ipv6_test() {
+    setup_tracing();
+    let localhost = SocketAddr::new(IpAddr::V6(Ipv6Addr::LOCALHOST), 0);
+    let wrapper = Arc::new(StandardDataChannelWrapper::new(localhost, localhost.ip()));
+    let session_properties = Arc::new(RwLock::new(SessionProperties::new()));
+    let command_processor = Arc::new(CommandProcessor::new(session_properties, wrapper));
+
+    let (code, reply) = execute(Command::new(Commands::Epsv, "2"), command_processor.clone()).await;
+    assert_eq!(ReplyCode::EnteringExtendedPassiveMode, code);
+
+    let addr = SocketAddr::new(localhost.ip(), parse_port(&reply));
+    TcpStream::connect(addr).await.expect("Client passive connection should succeed");
+
+    let (code, _reply) = execute(Command::new(Commands::Pasv, ""), command_processor).await;
+    assert_eq!(ReplyCode::CommandNotImplementedForThatParameter, code);
+  }
+
+  #[tokio::test]
+  async fn epsv_protocol_test() {
+    setup_tracing();
+    let (_, command_processor) = setup_test_command_processor();
+    let command_processor = Arc::new(command_processor);
+
+    let (code, _reply) =
+      execute(Command::new(Commands::Epsv, "1"), command_processor.clone()).await;
+    assert_eq!(ReplyCode::EnteringExtendedPassiveMode, code);
+
+    let (code, _reply) =
+      execute(Command::new(Commands::Epsv, "2"), command_processor.clone()).await;
+    assert_eq!(ReplyCode::NetworkProtocolNotSupported, code);
+
+    let (code, _reply) =
+      execute(Command::new(Commands::Epsv, "3"), command_processor.clone()).await;
+    assert_eq!(ReplyCode::NetworkProtocolNotSupported, code);
+
+    let (code, _reply) = execute(Command::new(Commands::Epsv, "invalid"), command_processor).await;
+    assert_eq!(ReplyCode::SyntaxErrorInParametersOrArguments, code);
+  }
+
+  #[tokio::test]
+  async fn epsv_protocol_no_listener_test() {
+    setup_tracing();
+    let port = TcpListener::bind(LOCALHOST).await.unwrap().local_addr().unwrap().port();
+    let wrapper = StandardDataChannelWrapper::new(LOCALHOST, LOCALHOST.ip()).with_passive_settings(
+      PassiveSettings {
+        port_range: Some(port..=port),
+        public_address: None,
+      },
+    );
+    let session_properties = Arc::new(RwLock::new(SessionProperties::new()));
+    let command_processor = Arc::new(CommandProcessor::new(session_properties, Arc::new(wrapper)));
+
+    let (code, _reply) =
+      execute(Command::new(Commands::Epsv, "2"), command_processor.clone()).await;
+    assert_eq!(ReplyCode::NetworkProtocolNotSupported, code);
+
+    // The only passive port is still free
+    let (code, reply) = execute(Command::new(Commands::Epsv, "1"), command_processor).await;
+    assert_eq!(ReplyCode::EnteringExtendedPassiveMode, code);
+    assert_eq!(port, parse_port(&reply));
+  }
+
+  #[tokio::test]
+  async fn epsv_all_test() {
+    setup_tracing();
+    let (_, command_processor) = setup_test_command_processor();
+    let command_processor = Arc::new(command_processor);
+
+    let (code, _reply) =
+      execute(Command::new(Commands::Epsv, "ALL"), command_processor.clone()).await;
+    assert_eq!(ReplyCode::CommandOkay, code);
+    assert!(command_processor.session_properties.read().await.epsv_all);
+
+    let (code, _reply) = execute(Command::new(Commands::Pasv, ""), command_processor.clone()).await;
+    assert_eq!(ReplyCode::BadSequenceOfCommands, code);
+
+    let (code, _reply) =
+      execute(Command::new(Commands::Eprt, "|1|127.0.0.1|2121|"), command_processor.clone()).await;
+    assert_eq!(ReplyCode::BadSequenceOfCommands, code);
+
+    let (code, _reply) = execute(Command::new(Commands::Epsv, ""), command_processor).await;
+    assert_eq!(ReplyCode::EnteringExtendedPassiveMode, code);
+  }
+}
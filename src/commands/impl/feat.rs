@@ -1,30 +1,41 @@
-use once_cell::sync::Lazy;
-use std::iter::Iterator;
 use std::sync::Arc;
 
 use crate::commands::command::Command;
 use crate::commands::commands::Commands;
 use crate::commands::reply::Reply;
 use crate::commands::reply_code::ReplyCode;
+use crate::global_context::command_registry;
 use crate::handlers::reply_sender::ReplySend;
+use crate::io::entry_data::MlstFacts;
+use crate::session::command_processor::CommandProcessor;
+use crate::session::connection_security::ConnectionSecurity;
 
-static LINES: Lazy<Vec<String>> = Lazy::new(|| {
+/// Creates the lines of the FEAT reply from the features of the registered commands. The MLST
+/// line depends on the facts selected by the session. AUTH TLS is only advertised on plain
+/// connections, the others are already secured.
+fn create_lines(facts: &MlstFacts, connection_security: ConnectionSecurity) -> Vec<String> {
   let mut lines: Vec<String> = vec!["Supported features:".to_string()];
-  #[cfg(not(windows))]
-  let features = ["MLSD", "MFMT", "REST STREAM", "UTF8", "RMDA <path>"];
-  #[cfg(windows)]
-  let features = ["MLSD", "MFMT", "MFCT", "REST STREAM", "UTF8", "RMDA <path>"];
-  lines.extend(features.iter().map(|f| format!(" {}", f)));
+  if connection_security == ConnectionSecurity::Plain {
+    lines.push(" AUTH TLS".to_string());
+  }
+  lines.extend(command_registry().features().map(|f| format!(" {}", f)));
+  lines.push(format!(" {}", facts.to_feature_string()));
   lines.push("END".to_string());
   lines
-});
+}
 
-#[tracing::instrument(skip(reply_sender))]
-pub(crate) async fn feat(command: &Command, reply_sender: Arc<impl ReplySend>) {
+#[tracing::instrument(skip(command_processor, reply_sender))]
+pub(crate) async fn feat(
+  command: &Command,
+  command_processor: Arc<CommandProcessor>,
+  reply_sender: Arc<dyn ReplySend>,
+) {
   debug_assert_eq!(command.command, Commands::Feat);
-  reply_sender
-    .send_control_message(Reply::new_multiline(ReplyCode::SystemStatus, LINES.clone()))
-    .await;
+  let lines = {
+    let session_properties = command_processor.session_properties.read().await;
+    create_lines(&session_properties.mlst_facts, session_properties.connection_security)
+  };
+  reply_sender.send_control_message(Reply::new_multiline(ReplyCode::SystemStatus, lines)).await;
 }
 
 #[cfg(test)]
@@ -34,30 +45,58 @@ mod tests {
 
   use crate::commands::command::Command;
   use crate::commands::commands::Commands;
-  use crate::commands::r#impl::feat::{LINES, feat};
+  use crate::commands::r#impl::feat::{create_lines, feat};
+  use crate::io::entry_data::MlstFacts;
+  use crate::session::connection_security::ConnectionSecurity;
   use crate::utils::test_utils::*;
 
   #[tokio::test]
   async fn format_test() {
     setup_tracing();
-    assert_eq!(LINES.first().unwrap(), "Supported features:");
-    assert_eq!(LINES.last().unwrap(), "END");
+    let lines = create_lines(&MlstFacts::default(), ConnectionSecurity::Plain);
+    assert_eq!(lines.first().unwrap(), "Supported features:");
+    assert_eq!(lines.last().unwrap(), "END");
   }
 
   #[tokio::test]
   async fn full_reply_test() {
     setup_tracing();
     #[cfg(not(windows))]
-    const EXPECTED: &str = "211-Supported features:\r\n MLSD\r\n MFMT\r\n REST STREAM\r\n UTF8\r\n RMDA <path>\r\n211 END\r\n";
+    const EXPECTED: &str = "211-Supported features:\r\n AUTH TLS\r\n MDTM\r\n MFMT\r\n MLSD\r\n UTF8\r\n PBSZ\r\n PROT\r\n REST STREAM\r\n RMDA <path>\r\n SIZE\r\n MLST size*;type*;modify*;perm*;\r\n211 END\r\n";
     #[cfg(windows)]
-    const EXPECTED: &str = "211-Supported features:\r\n MLSD\r\n MFMT\r\n MFCT\r\n REST STREAM\r\n UTF8\r\n RMDA <path>\r\n211 END\r\n";
+    const EXPECTED: &str = "211-Supported features:\r\n AUTH TLS\r\n MDTM\r\n MFCT\r\n MFMT\r\n MLSD\r\n UTF8\r\n PBSZ\r\n PROT\r\n REST STREAM\r\n RMDA <path>\r\n SIZE\r\n MLST size*;type*;modify*;perm*;\r\n211 END\r\n";
+    let (_, command_processor) = setup_test_command_processor();
     let (tx, mut rx) = channel(1024);
     let reply_sender = TestReplySender::new(tx);
     let command = Command::new(Commands::Feat, "");
-    feat(&command, Arc::new(reply_sender)).await;
+    feat(&command, Arc::new(command_processor), Arc::new(reply_sender)).await;
     match rx.recv().await {
       Some(reply) => assert_eq!(EXPECTED, reply.to_string()),
       None => panic!("Rx closed without reading reply!"),
     }
   }
+
+  #[tokio::test]
+  async fn selected_facts_test() {
+    setup_tracing();
+    let (_, command_processor) = setup_test_command_processor();
+    command_processor.session_properties.write().await.mlst_facts = "type;perm;".parse().unwrap();
+    let (tx, mut rx) = channel(1024);
+    let reply_sender = TestReplySender::new(tx);
+    let command = Command::new(Commands::Feat, "");
+    feat(&command, Arc::new(command_processor), Arc::new(reply_sender)).await;
+    let reply = rx.recv().await.expect("Reply should be received").to_string();
+    assert!(reply.contains("\r\n MLST size;type*;modify;perm*;\r\n"));
+  }
+
+  #[tokio::test]
+  async fn secured_connection_test() {
+    setup_tracing();
+    for connection_security in [ConnectionSecurity::Tls, ConnectionSecurity::Quic] {
+      let lines = create_lines(&MlstFacts::default(), connection_security);
+      assert!(!lines.contains(&" AUTH TLS".to_string()));
+      assert!(lines.contains(&" PBSZ".to_string()));
+      assert!(lines.contains(&" PROT".to_string()));
+    }
+  }
 }
@@ -0,0 +1,97 @@
+use std::sync::Arc;
+
+use crate::commands::command::Command;
+use crate::commands::commands::Commands;
+use crate::commands::reply::Reply;
+use crate::commands::reply_code::ReplyCode;
+use crate::global_context::command_registry;
+use crate::handlers::reply_sender::ReplySend;
+use crate::session::command_processor::CommandProcessor;
+
+/// The number of command names on a single line of the HELP reply.
+const NAMES_PER_LINE: usize = 8;
+
+/// Sends help over the control connection, as specified by
+/// [RFC959](https://datatracker.ietf.org/doc/html/rfc959#section-4.1.3).
+///
+/// Without an argument, all the implemented commands are listed. With a command name, the syntax
+/// and description of the command is sent. Both are taken from the command registry.
+#[tracing::instrument(skip(_command_processor, reply_sender))]
+pub(crate) async fn help(
+  command: &Command,
+  _command_processor: Arc<CommandProcessor>,
+  reply_sender: Arc<dyn ReplySend>,
+) {
+  debug_assert_eq!(Commands::Help, command.command);
+
+  let reply = if command.argument.is_empty() {
+    Reply::new_multiline(ReplyCode::Help, create_lines())
+  } else {
+    match command_registry().get(command.argument.trim()) {
+      Some(entry) => {
+        Reply::new(ReplyCode::Help, format!("Syntax: {} - {}", entry.syntax, entry.description))
+      }
+      None => Reply::new(
+        ReplyCode::SyntaxErrorInParametersOrArguments,
+        format!("Unknown command {}.", command.argument.trim()),
+      ),
+    }
+  };
+  reply_sender.send_control_message(reply).await;
+}
+
+fn create_lines() -> Vec<String> {
+  let names = command_registry().names().collect::<Vec<_>>();
+  let mut lines = vec!["The following commands are recognized:".to_string()];
+  lines.extend(names.chunks(NAMES_PER_LINE).map(|chunk| format!(" {}", chunk.join(" "))));
+  lines.push("Help OK.".to_string());
+  lines
+}
+
+#[cfg(test)]
+mod tests {
+  use std::sync::Arc;
+
+  use crate::commands::command::Command;
+  use crate::commands::commands::Commands;
+  use crate::commands::reply_code::ReplyCode;
+  use crate::utils::test_utils::*;
+
+  #[tokio::test]
+  async fn help_test() {
+    setup_tracing();
+    let (code, reply) =
+      execute(Command::new(Commands::Help, ""), Arc::new(setup_test_command_processor().1)).await;
+    assert_eq!(ReplyCode::Help, code);
+    let lines = reply.split("\r\n").collect::<Vec<_>>();
+    assert_eq!("214-The following commands are recognized:", lines[0]);
+    assert_eq!(" ABOR ACCT APPE AUTH CDUP CWD DELE EPRT", lines[1]);
+    assert!(reply.contains(" RETR "));
+    assert!(reply.contains(" SITE "));
+    assert!(!reply.contains(" STOU"));
+    assert!(reply.ends_with("214 Help OK.\r\n"));
+  }
+
+  #[tokio::test]
+  async fn command_help_test() {
+    setup_tracing();
+    let (code, reply) =
+      execute(Command::new(Commands::Help, "retr"), Arc::new(setup_test_command_processor().1))
+        .await;
+    assert_eq!(ReplyCode::Help, code);
+    assert_eq!("214 Syntax: RETR <path> - Downloads a file.\r\n", reply);
+  }
+
+  #[tokio::test]
+  async fn unknown_command_test() {
+    setup_tracing();
+    let (code, _) =
+      execute(Command::new(Commands::Help, "XYZ"), Arc::new(setup_test_command_processor().1))
+        .await;
+    assert_eq!(ReplyCode::SyntaxErrorInParametersOrArguments, code);
+    let (code, _) =
+      execute(Command::new(Commands::Help, "STOU"), Arc::new(setup_test_command_processor().1))
+        .await;
+    assert_eq!(ReplyCode::SyntaxErrorInParametersOrArguments, code);
+  }
+}
@@ -20,7 +20,7 @@ use crate::session::command_processor::CommandProcessor;
 pub(crate) async fn list(
   command: &Command,
   command_processor: Arc<CommandProcessor>,
-  reply_sender: Arc<impl ReplySend>,
+  reply_sender: Arc<dyn ReplySend>,
 ) {
   debug_assert_eq!(command.command, Commands::List);
 
@@ -50,7 +50,7 @@ pub(crate) async fn list(
     Err(r) => return reply_sender.send_control_message(r).await,
   };
 
-  let data_channel_pair = acquire_data_channel(command_processor.data_wrapper.clone()).await;
+  let data_channel_pair = acquire_data_channel(&command_processor, &session_properties).await;
   let (mut data_channel, token) = match data_channel_pair {
     Ok((dc, token)) => (dc, token),
     Err(e) => {
@@ -73,7 +73,9 @@ pub(crate) async fn list(
   trace!("Sending listing to client:\n{}", mem.replace("\r\n", "\\r\\n"));
 
   let mut buf = BufReader::new(mem.as_bytes());
-  let transfer = copy_data(&mut buf, &mut data_channel);
+  let transfer_guard =
+    command_processor.transfer_status.start(format!("{} {}", command.command, command.argument));
+  let transfer = copy_data(&mut buf, &mut data_channel, transfer_guard.transferred());
   let result = select! {
     result = transfer => result,
     _ = token.cancelled() => Err(std::io::Error::new(ErrorKind::ConnectionAborted, "Connection aborted!"))
@@ -112,6 +114,7 @@ mod tests {
   use crate::commands::commands::Commands;
   use crate::commands::r#impl::shared::ACQUIRE_TIMEOUT;
   use crate::commands::reply_code::ReplyCode;
+  use crate::session::security_policy::SecurityPolicy;
   use crate::tracing_print;
   use crate::utils::test_utils::*;
 
@@ -377,4 +380,44 @@ mod tests {
 
     receive_and_verify_reply(2, &mut rx, ReplyCode::BadSequenceOfCommands, None).await;
   }
+
+  #[tokio::test]
+  async fn unprotected_data_refused_test() {
+    setup_tracing();
+    let command = Command::new(Commands::List, String::new());
+
+    let label = "test_files".to_string();
+
+    let settings = CommandProcessorSettingsBuilder::default()
+      .label(label.clone())
+      .change_path(Some(label.clone()))
+      .username(Some("testuser".to_string()))
+      .view_root(current_dir().unwrap().join("test_files"))
+      .build()
+      .expect("Settings should be valid");
+
+    let mut command_processor = setup_test_command_processor_custom(&settings);
+    command_processor.security_policy = Arc::new(SecurityPolicy {
+      protected_data: true,
+      ..Default::default()
+    });
+    let _client_dc = open_tcp_data_channel(&mut command_processor).await;
+
+    let (tx, mut rx) = channel(1024);
+    let reply_sender = TestReplySender::new(tx);
+    timeout(
+      Duration::from_secs(3),
+      command.execute(Arc::new(command_processor), Arc::new(reply_sender)),
+    )
+    .await
+    .expect("Command timeout!");
+
+    receive_and_verify_reply(
+      2,
+      &mut rx,
+      ReplyCode::DataConnectionCannotBeOpenedWithThisProtSetting,
+      None,
+    )
+    .await;
+  }
 }
@@ -0,0 +1,116 @@
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+
+use crate::commands::command::Command;
+use crate::commands::commands::Commands;
+use crate::commands::r#impl::shared::get_file_entry_or_error_reply;
+use crate::commands::reply::Reply;
+use crate::commands::reply_code::ReplyCode;
+use crate::handlers::reply_sender::ReplySend;
+use crate::io::timeval::format_timeval;
+use crate::session::command_processor::CommandProcessor;
+
+/// Replies with the last modification time of a file as specified by
+/// [RFC3659](https://datatracker.ietf.org/doc/html/rfc3659#section-3). The time is in UTC, in the
+/// same format as used by MFMT.
+#[tracing::instrument(skip(command_processor, reply_sender))]
+pub(crate) async fn mdtm(
+  command: &Command,
+  command_processor: Arc<CommandProcessor>,
+  reply_sender: Arc<dyn ReplySend>,
+) {
+  debug_assert_eq!(command.command, Commands::Mdtm);
+
+  let session_properties = command_processor.session_properties.read().await;
+
+  if !session_properties.is_logged_in() {
+    reply_sender
+      .send_control_message(Reply::new(ReplyCode::NotLoggedIn, "User not logged in!"))
+      .await;
+    return;
+  }
+
+  if command.argument.is_empty() {
+    reply_sender
+      .send_control_message(Reply::new(
+        ReplyCode::SyntaxErrorInParametersOrArguments,
+        "No file specified!",
+      ))
+      .await;
+    return;
+  }
+
+  let entry = session_properties.file_system_view_root.metadata(&command.argument);
+  let reply = match get_file_entry_or_error_reply(entry) {
+    Ok(entry) => {
+      Reply::new(ReplyCode::FileStatus, format_timeval(&DateTime::<Utc>::from(*entry.modify())))
+    }
+    Err(reply) => reply,
+  };
+  reply_sender.send_control_message(reply).await;
+}
+
+#[cfg(test)]
+mod tests {
+  use std::env::temp_dir;
+  use std::fs::{File, FileTimes};
+  use std::sync::Arc;
+
+  use chrono::{Timelike, Utc};
+  use uuid::Uuid;
+
+  use crate::commands::command::Command;
+  use crate::commands::commands::Commands;
+  use crate::commands::reply_code::ReplyCode;
+  use crate::io::timeval::format_timeval;
+  use crate::utils::test_utils::*;
+
+  fn setup_settings() -> CommandProcessorSettings {
+    CommandProcessorSettingsBuilder::default()
+      .label("test".to_string())
+      .view_root(temp_dir())
+      .change_path(Some("test".to_string()))
+      .username(Some("testuser".to_string()))
+      .build()
+      .unwrap()
+  }
+
+  #[tokio::test]
+  async fn mdtm_test() {
+    setup_tracing();
+    let file_name = format!("{}.test", Uuid::new_v4().as_hyphenated());
+    let file_path = temp_dir().join(&file_name);
+    let file = File::create(&file_path).unwrap();
+    let _cleanup = FileCleanup::new(&file_path);
+    let modified = Utc::now().with_nanosecond(0).unwrap() - chrono::Duration::days(3);
+    file.set_times(FileTimes::new().set_modified(modified.into())).unwrap();
+
+    let command_processor = setup_test_command_processor_custom(&setup_settings());
+    let (code, message) =
+      execute(Command::new(Commands::Mdtm, &file_name), Arc::new(command_processor)).await;
+    assert_eq!(ReplyCode::FileStatus, code);
+    assert_eq!(format!("213 {}\r\n", format_timeval(&modified)), message);
+  }
+
+  #[tokio::test]
+  async fn mdtm_directory_test() {
+    setup_tracing();
+    let command_processor = setup_test_command_processor_custom(&setup_settings());
+
+    let (code, _) =
+      execute(Command::new(Commands::Mdtm, "/test"), Arc::new(command_processor)).await;
+    assert_eq!(ReplyCode::FileUnavailable, code);
+  }
+
+  #[tokio::test]
+  async fn mdtm_not_logged_in_test() {
+    setup_tracing();
+    let settings = CommandProcessorSettingsBuilder::default().build().unwrap();
+    let command_processor = setup_test_command_processor_custom(&settings);
+
+    let (code, _) =
+      execute(Command::new(Commands::Mdtm, "file"), Arc::new(command_processor)).await;
+    assert_eq!(ReplyCode::NotLoggedIn, code);
+  }
+}
@@ -13,7 +13,7 @@ use std::sync::Arc;
 pub(crate) async fn mfct(
   command: &Command,
   command_processor: Arc<CommandProcessor>,
-  reply_sender: Arc<impl ReplySend>,
+  reply_sender: Arc<dyn ReplySend>,
 ) {
   debug_assert_eq!(command.command, Commands::Mfct);
 
@@ -43,7 +43,7 @@ pub(crate) async fn mfct(
 mod tests {
   use crate::io::timeval::format_timeval;
   use crate::utils::test_utils::*;
-  use chrono::{DateTime, Local, TimeDelta, Timelike};
+  use chrono::{DateTime, TimeDelta, Timelike, Utc};
   use std::env::temp_dir;
   use std::fs::File;
   use std::ops::Sub;
@@ -100,7 +100,7 @@ mod tests {
     let file_path = root.join(&file_name);
     touch(&file_path).expect("Test file must exist");
     let _cleanup = FileCleanup::new(&file_path);
-    let timeval = Local::now().sub(TimeDelta::hours(4)).with_nanosecond(0u32).unwrap();
+    let timeval = Utc::now().sub(TimeDelta::hours(4)).with_nanosecond(0u32).unwrap();
     let command = Command::new(
       Commands::Mfct,
       format!("{} /{}/{}", format_timeval(&timeval), label, file_name),
@@ -125,7 +125,7 @@ mod tests {
     .expect("Command timeout!");
 
     receive_and_verify_reply(2, &mut rx, ReplyCode::FileStatus, None).await;
-    let modification_time: DateTime<Local> =
+    let modification_time: DateTime<Utc> =
       File::open(&file_path).unwrap().metadata().unwrap().created().unwrap().into();
     assert_eq!(timeval, modification_time);
   }
@@ -139,7 +139,7 @@ mod tests {
     let file_path = root.join(&file_name);
     touch(&file_path).expect("Test file must exist");
     let _cleanup = FileCleanup::new(&file_path);
-    let timeval = Local::now().sub(TimeDelta::hours(4)).with_nanosecond(0u32).unwrap();
+    let timeval = Utc::now().sub(TimeDelta::hours(4)).with_nanosecond(0u32).unwrap();
     let command =
       Command::new(Commands::Mfct, format!("{} {}/{}", format_timeval(&timeval), label, file_name));
 
@@ -162,7 +162,7 @@ mod tests {
     .expect("Command timeout!");
 
     receive_and_verify_reply(2, &mut rx, ReplyCode::FileStatus, None).await;
-    let modification_time: DateTime<Local> =
+    let modification_time: DateTime<Utc> =
       File::open(&file_path).unwrap().metadata().unwrap().created().unwrap().into();
     assert_eq!(timeval, modification_time);
   }
@@ -176,7 +176,7 @@ mod tests {
     let file_path = root.join(&file_name);
     touch(&file_path).expect("Test file must exist");
     let _cleanup = FileCleanup::new(&file_path);
-    let timeval = Local::now().sub(TimeDelta::hours(4)).with_nanosecond(0u32).unwrap();
+    let timeval = Utc::now().sub(TimeDelta::hours(4)).with_nanosecond(0u32).unwrap();
     let command =
       Command::new(Commands::Mfct, format!("{} {}", format_timeval(&timeval), &file_name));
 
@@ -200,7 +200,7 @@ mod tests {
     .expect("Command timeout!");
 
     receive_and_verify_reply(2, &mut rx, ReplyCode::FileStatus, None).await;
-    let modification_time: DateTime<Local> =
+    let modification_time: DateTime<Utc> =
       File::open(&file_path).unwrap().metadata().unwrap().created().unwrap().into();
     assert_eq!(timeval, modification_time);
   }
@@ -13,7 +13,7 @@ use crate::session::command_processor::CommandProcessor;
 pub(crate) async fn mkd(
   command: &Command,
   command_processor: Arc<CommandProcessor>,
-  reply_sender: Arc<impl ReplySend>,
+  reply_sender: Arc<dyn ReplySend>,
 ) {
   trace!("Executing MKD command");
   debug_assert_eq!(command.command, Commands::Mkd);
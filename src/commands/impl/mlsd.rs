@@ -18,7 +18,7 @@ use crate::session::command_processor::CommandProcessor;
 pub(crate) async fn mlsd(
   command: &Command,
   command_processor: Arc<CommandProcessor>,
-  reply_sender: Arc<impl ReplySend>,
+  reply_sender: Arc<dyn ReplySend>,
 ) {
   debug_assert_eq!(command.command, Commands::Mlsd);
 
@@ -38,7 +38,7 @@ pub(crate) async fn mlsd(
     Err(r) => return reply_sender.send_control_message(r).await,
   };
 
-  let data_channel_pair = acquire_data_channel(command_processor.data_wrapper.clone()).await;
+  let data_channel_pair = acquire_data_channel(&command_processor, &session_properties).await;
   let (mut data_channel, token) = match data_channel_pair {
     Ok((dc, token)) => (dc, token),
     Err(e) => {
@@ -53,11 +53,14 @@ pub(crate) async fn mlsd(
     ))
     .await;
 
-  let mem = listing.iter().map(|l| l.to_string()).collect::<String>();
+  let facts = &session_properties.mlst_facts;
+  let mem = listing.iter().map(|l| format!("{}\r\n", l.facts(facts))).collect::<String>();
   trace!("Sending listing to client:\n{}", mem.replace("\r\n", "\\r\\n"));
 
   let mut buf = BufReader::new(mem.as_bytes());
-  let transfer = copy_data(&mut buf, &mut data_channel);
+  let transfer_guard =
+    command_processor.transfer_status.start(format!("{} {}", command.command, command.argument));
+  let transfer = copy_data(&mut buf, &mut data_channel, transfer_guard.transferred());
   let result = select! {
     result = transfer => result,
     _ = token.cancelled() => Err(std::io::Error::new(ErrorKind::ConnectionAborted, "Connection aborted!"))
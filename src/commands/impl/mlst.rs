@@ -0,0 +1,130 @@
+use std::sync::Arc;
+
+use crate::commands::command::Command;
+use crate::commands::commands::Commands;
+use crate::commands::r#impl::shared::get_entry_or_error_reply;
+use crate::commands::reply::Reply;
+use crate::commands::reply_code::ReplyCode;
+use crate::handlers::reply_sender::ReplySend;
+use crate::session::command_processor::CommandProcessor;
+
+/// Sends the facts about a single object over the control connection as specified by
+/// [RFC3659](https://datatracker.ietf.org/doc/html/rfc3659#section-7.2). If no path is
+/// specified, the facts of the current working directory are sent.
+///
+/// Only the facts selected with 'OPTS MLST' are included.
+#[tracing::instrument(skip(command_processor, reply_sender))]
+pub(crate) async fn mlst(
+  command: &Command,
+  command_processor: Arc<CommandProcessor>,
+  reply_sender: Arc<dyn ReplySend>,
+) {
+  debug_assert_eq!(command.command, Commands::Mlst);
+
+  let session_properties = command_processor.session_properties.read().await;
+
+  if !session_properties.is_logged_in() {
+    reply_sender
+      .send_control_message(Reply::new(ReplyCode::NotLoggedIn, "User not logged in!"))
+      .await;
+    return;
+  }
+
+  let root = &session_properties.file_system_view_root;
+  let (path, name) = if command.argument.is_empty() {
+    (".", root.get_current_working_directory())
+  } else {
+    (command.argument.as_str(), command.argument.clone())
+  };
+
+  let mut entry = match get_entry_or_error_reply(root.metadata(path)) {
+    Ok(entry) => entry,
+    Err(reply) => return reply_sender.send_control_message(reply).await,
+  };
+  entry.change_name(name);
+
+  let lines = vec![
+    format!("Listing {}", entry.name()),
+    format!(" {}", entry.facts(&session_properties.mlst_facts)),
+    "End".to_string(),
+  ];
+  reply_sender
+    .send_control_message(Reply::new_multiline(ReplyCode::RequestedFileActionOkay, lines))
+    .await;
+}
+
+#[cfg(test)]
+mod tests {
+  use std::env::temp_dir;
+  use std::sync::Arc;
+
+  use uuid::Uuid;
+
+  use crate::commands::command::Command;
+  use crate::commands::commands::Commands;
+  use crate::commands::reply_code::ReplyCode;
+  use crate::session::command_processor::CommandProcessor;
+  use crate::utils::test_utils::*;
+
+  fn setup() -> Arc<CommandProcessor> {
+    let settings = CommandProcessorSettingsBuilder::default()
+      .label("test".to_string())
+      .view_root(temp_dir())
+      .change_path(Some("test".to_string()))
+      .username(Some("testuser".to_string()))
+      .build()
+      .unwrap();
+    Arc::new(setup_test_command_processor_custom(&settings))
+  }
+
+  #[tokio::test]
+  async fn mlst_file_test() {
+    setup_tracing();
+    let file_name = format!("{}.test", Uuid::new_v4().as_hyphenated());
+    let file_path = temp_dir().join(&file_name);
+    std::fs::write(&file_path, b"content").unwrap();
+    let _cleanup = FileCleanup::new(&file_path);
+    let command_processor = setup();
+
+    let (code, message) =
+      execute(Command::new(Commands::Mlst, &file_name), command_processor.clone()).await;
+    assert_eq!(ReplyCode::RequestedFileActionOkay, code);
+    let lines = message.split("\r\n").collect::<Vec<_>>();
+    assert_eq!(format!("250-Listing {file_name}"), lines[0]);
+    assert!(lines[1].starts_with(" size=7;type=file;modify="));
+    assert!(lines[1].ends_with(&format!("; {file_name}")));
+    assert_eq!("250 End", lines[2]);
+  }
+
+  #[tokio::test]
+  async fn mlst_selected_facts_test() {
+    setup_tracing();
+    let command_processor = setup();
+    command_processor.session_properties.write().await.mlst_facts = "type;".parse().unwrap();
+
+    let (code, message) =
+      execute(Command::new(Commands::Mlst, ""), command_processor.clone()).await;
+    assert_eq!(ReplyCode::RequestedFileActionOkay, code);
+    assert_eq!("250-Listing /test\r\n type=dir; /test\r\n250 End\r\n", message);
+  }
+
+  #[tokio::test]
+  async fn mlst_nonexistent_test() {
+    setup_tracing();
+    let command_processor = setup();
+
+    let (code, _) =
+      execute(Command::new(Commands::Mlst, "NONEXISTENT"), command_processor.clone()).await;
+    assert_eq!(ReplyCode::FileUnavailable, code);
+  }
+
+  #[tokio::test]
+  async fn mlst_not_logged_in_test() {
+    setup_tracing();
+    let settings = CommandProcessorSettingsBuilder::default().build().unwrap();
+    let command_processor = Arc::new(setup_test_command_processor_custom(&settings));
+
+    let (code, _) = execute(Command::new(Commands::Mlst, "file"), command_processor.clone()).await;
+    assert_eq!(ReplyCode::NotLoggedIn, code);
+  }
+}
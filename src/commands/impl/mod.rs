@@ -1,29 +1,45 @@
 //! Contains actual implementations of commands.
 
 pub(crate) mod abor;
+pub(crate) mod acct;
+pub(crate) mod appe;
+pub(crate) mod auth;
 pub(crate) mod cdup;
 pub(crate) mod cwd;
 pub(crate) mod dele;
+pub(crate) mod eprt;
+pub(crate) mod epsv;
 pub(crate) mod feat;
+pub(crate) mod help;
 pub(crate) mod list;
+pub(crate) mod mdtm;
 #[cfg(windows)]
 pub(crate) mod mfct;
 pub(crate) mod mfmt;
 pub(crate) mod mkd;
 pub(crate) mod mlsd;
+pub(crate) mod mlst;
 pub(crate) mod nlst;
 pub(crate) mod noop;
 pub(crate) mod opts;
 pub(crate) mod pass;
 pub(crate) mod pasv;
 pub(crate) mod pbsz;
+pub(crate) mod port;
 pub(crate) mod prot;
 pub(crate) mod pwd;
+pub(crate) mod quit;
+pub(crate) mod rein;
 pub(crate) mod rest;
 pub(crate) mod retr;
 pub(crate) mod rmd;
 pub(crate) mod rmda;
+pub(crate) mod rnfr;
+pub(crate) mod rnto;
 pub(crate) mod shared;
+pub(crate) mod site;
+pub(crate) mod size;
+pub(crate) mod stat;
 pub(crate) mod stor;
 pub(crate) mod syst;
 pub(crate) mod r#type;
@@ -19,7 +19,7 @@ use crate::session::command_processor::CommandProcessor;
 pub(crate) async fn nlst(
   command: &Command,
   command_processor: Arc<CommandProcessor>,
-  reply_sender: Arc<impl ReplySend>,
+  reply_sender: Arc<dyn ReplySend>,
 ) {
   debug_assert_eq!(Commands::Nlst, command.command);
 
@@ -39,7 +39,7 @@ pub(crate) async fn nlst(
     Err(r) => return reply_sender.send_control_message(r).await,
   };
 
-  let data_channel_pair = acquire_data_channel(command_processor.data_wrapper.clone()).await;
+  let data_channel_pair = acquire_data_channel(&command_processor, &session_properties).await;
   let (mut data_channel, token) = match data_channel_pair {
     Ok((dc, token)) => (dc, token),
     Err(e) => {
@@ -63,7 +63,9 @@ pub(crate) async fn nlst(
   trace!("Sending listing to client:\n{}", mem);
 
   let mut buf = BufReader::new(mem.as_bytes());
-  let transfer = copy_data(&mut buf, &mut data_channel);
+  let transfer_guard =
+    command_processor.transfer_status.start(format!("{} {}", command.command, command.argument));
+  let transfer = copy_data(&mut buf, &mut data_channel, transfer_guard.transferred());
   let result = select! {
     result = transfer => result,
     _ = token.cancelled() => Err(std::io::Error::new(ErrorKind::ConnectionAborted, "Connection aborted!"))
@@ -6,7 +6,7 @@ use crate::handlers::reply_sender::ReplySend;
 use std::sync::Arc;
 
 #[tracing::instrument(skip(reply_sender))]
-pub(crate) async fn noop(command: &Command, reply_sender: Arc<impl ReplySend>) {
+pub(crate) async fn noop(command: &Command, reply_sender: Arc<dyn ReplySend>) {
   debug_assert_eq!(Commands::Noop, command.command);
   reply_sender.send_control_message(Reply::new(ReplyCode::CommandOkay, "OK")).await;
 }
@@ -35,7 +35,7 @@ mod tests {
 
     let session_properties = Arc::new(RwLock::new(SessionProperties::new()));
 
-    let wrapper = Arc::new(StandardDataChannelWrapper::new(LOCALHOST));
+    let wrapper = Arc::new(StandardDataChannelWrapper::new(LOCALHOST, LOCALHOST.ip()));
     let command_processor = CommandProcessor::new(session_properties.clone(), wrapper);
 
     let (tx, mut rx) = channel(1024);
@@ -5,13 +5,14 @@ use crate::commands::commands::Commands;
 use crate::commands::reply::Reply;
 use crate::commands::reply_code::ReplyCode;
 use crate::handlers::reply_sender::ReplySend;
+use crate::io::entry_data::MlstFacts;
 use crate::session::command_processor::CommandProcessor;
 
 #[tracing::instrument(skip(command_processor, reply_sender))]
 pub(crate) async fn opts(
   command: &Command,
   command_processor: Arc<CommandProcessor>,
-  reply_sender: Arc<impl ReplySend>,
+  reply_sender: Arc<dyn ReplySend>,
 ) {
   debug_assert_eq!(Commands::Opts, command.command);
   let mut session_properties = command_processor.session_properties.write().await;
@@ -32,6 +33,15 @@ pub(crate) async fn opts(
       .await;
   }
 
+  let (option, value) = command.argument.split_once(' ').unwrap_or((&command.argument, ""));
+  if option.eq_ignore_ascii_case("MLST") {
+    // Parsing never fails, unsupported facts are ignored
+    let facts: MlstFacts = value.parse().unwrap();
+    let reply = format!("MLST OPTS {facts}").trim_end().to_string();
+    session_properties.mlst_facts = facts;
+    return reply_sender.send_control_message(Reply::new(ReplyCode::CommandOkay, reply)).await;
+  }
+
   match command.argument.to_uppercase().as_str() {
     "UTF8 ON" => {
       session_properties.utf8 = true;
@@ -80,4 +90,33 @@ mod tests {
 
     receive_and_verify_reply(2, &mut rx, ReplyCode::CommandOkay, None).await;
   }
+
+  #[tokio::test]
+  async fn mlst_facts_test() {
+    setup_tracing();
+    let (_, command_processor) = setup_test_command_processor();
+    let command_processor = Arc::new(command_processor);
+
+    let arguments = [
+      ("MLST type;size;", "200 MLST OPTS size;type;\r\n"),
+      ("mlst Modify;unknown;", "200 MLST OPTS modify;\r\n"),
+      ("MLST", "200 MLST OPTS\r\n"),
+    ];
+    for (argument, expected) in arguments {
+      let (tx, mut rx) = channel(1024);
+      let reply_sender = TestReplySender::new(tx);
+      let command = Command::new(Commands::Opts, argument);
+      timeout(
+        Duration::from_secs(3),
+        command.execute(command_processor.clone(), Arc::new(reply_sender)),
+      )
+      .await
+      .expect("Command timeout!");
+      let reply = rx.recv().await.expect("Reply should be received");
+      assert_eq!(expected, reply.to_string());
+    }
+
+    let facts = &command_processor.session_properties.read().await.mlst_facts;
+    assert_eq!("", facts.to_string());
+  }
 }
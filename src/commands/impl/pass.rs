@@ -1,11 +1,15 @@
+use std::net::IpAddr;
 use std::sync::Arc;
+use tokio::time::sleep;
 use tracing::{error, info};
 
+use crate::auth::auth_error::AuthError;
 use crate::commands::command::Command;
 use crate::commands::commands::Commands;
+use crate::commands::r#impl::user::tls_required_reply;
 use crate::commands::reply::Reply;
 use crate::commands::reply_code::ReplyCode;
-use crate::global_context::AUTH_PROVIDER;
+use crate::global_context::{AUTH_PROVIDER, LOGIN_FAILURES};
 use crate::handlers::reply_sender::ReplySend;
 use crate::session::command_processor::CommandProcessor;
 
@@ -13,7 +17,7 @@ use crate::session::command_processor::CommandProcessor;
 pub(crate) async fn pass(
   command: &Command,
   command_processor: Arc<CommandProcessor>,
-  reply_sender: Arc<impl ReplySend>,
+  reply_sender: Arc<dyn ReplySend>,
 ) {
   debug_assert_eq!(command.command, Commands::Pass);
 
@@ -38,6 +42,16 @@ pub(crate) async fn pass(
       .await;
   }
 
+  let login_allowed = {
+    let properties = session_properties.read().await;
+    let username = properties.login_form.username.as_deref();
+    command_processor.security_policy.is_login_allowed(username, properties.connection_security)
+  };
+  if !login_allowed {
+    info!("Refusing login over an unprotected connection!");
+    return reply_sender.send_control_message(tls_required_reply()).await;
+  }
+
   let provider = match AUTH_PROVIDER.get() {
     Some(provider) => provider,
     None => {
@@ -51,23 +65,61 @@ pub(crate) async fn pass(
     }
   };
 
-  let mut form = session_properties.read().await.login_form.clone();
+  let mut form = {
+    let mut properties = session_properties.write().await;
+    // A password still waiting for its one-time password is replaced
+    properties.login_form.password.take();
+    properties.login_form.clone()
+  };
   let _ = form.password.insert(password.to_string());
   let username = form.username.as_ref().unwrap().clone();
+  let ip = session_properties.read().await.registration.as_ref().map(|r| r.ip());
   info!("User '{}' attempting login.", &username);
-  let result = session_properties.write().await.login(provider, form).await;
-
-  return if result {
-    info!("User '{}' logged in successfully", &username);
-    reply_sender
-      .send_control_message(Reply::new(ReplyCode::UserLoggedIn, "Log in successful"))
-      .await
+  // Banned addresses are refused without checking the password, the ban can start after connecting
+  let result = if LOGIN_FAILURES.is_client_banned(ip) {
+    Err(AuthError::InvalidCredentials)
   } else {
-    info!("User '{}' failed to login!", &username);
-    reply_sender
-      .send_control_message(Reply::new(ReplyCode::NotLoggedIn, "Incorrect credentials!"))
-      .await
+    session_properties.write().await.login(provider, form).await
   };
+
+  if result == Err(AuthError::SecondFactorRequired) {
+    info!("User '{}' needs a one-time password.", &username);
+    // Kept for ACCT, which completes the login
+    let _ = session_properties.write().await.login_form.password.insert(password.to_string());
+    return reply_sender
+      .send_control_message(Reply::new(
+        ReplyCode::NeedAccountForLogin,
+        "Need one-time password, send it with ACCT.",
+      ))
+      .await;
+  }
+  reply_sender.send_control_message(login_reply(result, &username, ip).await).await
+}
+
+/// Creates the reply to a login attempt of the user, recording the result. Failed logins are
+/// replied to after the delay set by [`LOGIN_FAILURES`].
+pub(crate) async fn login_reply(
+  result: Result<(), AuthError>,
+  username: &str,
+  ip: Option<IpAddr>,
+) -> Reply {
+  match result {
+    Ok(()) => {
+      info!("User '{}' logged in successfully", username);
+      LOGIN_FAILURES.record_success(ip, username);
+      Reply::new(ReplyCode::UserLoggedIn, "Log in successful")
+    }
+    Err(AuthError::LimitReached(e)) => {
+      info!("User '{}' failed to login! {e}", username);
+      Reply::new(ReplyCode::NotLoggedIn, e.to_string())
+    }
+    Err(_) => {
+      let delay = LOGIN_FAILURES.record_failure(ip, username);
+      info!("User '{}' failed to login! Replying in {}ms", username, delay.as_millis());
+      sleep(delay).await;
+      Reply::new(ReplyCode::NotLoggedIn, "Incorrect credentials!")
+    }
+  }
 }
 
 #[cfg(test)]
@@ -86,6 +138,8 @@ mod tests {
   use crate::data_channels::standard_data_channel_wrapper::StandardDataChannelWrapper;
   use crate::global_context::AUTH_PROVIDER;
   use crate::session::command_processor::CommandProcessor;
+  use crate::session::connection_registry::{ConnectionLimits, ConnectionRegistry};
+  use crate::session::security_policy::SecurityPolicy;
   use crate::session::session_properties::SessionProperties;
   use crate::utils::test_utils::*;
 
@@ -96,7 +150,7 @@ mod tests {
     let _ = session_properties.login_form.username.insert("test".to_string());
 
     let session_properties = Arc::new(RwLock::new(session_properties));
-    let wrapper = Arc::new(StandardDataChannelWrapper::new(LOCALHOST));
+    let wrapper = Arc::new(StandardDataChannelWrapper::new(LOCALHOST, LOCALHOST.ip()));
     let command_processor = CommandProcessor::new(session_properties, wrapper);
 
     let command = Command::new(Commands::Pass, "test");
@@ -116,6 +170,42 @@ mod tests {
     receive_and_verify_reply(2, &mut rx, ReplyCode::UserLoggedIn, None).await;
   }
 
+  #[tokio::test]
+  async fn too_many_logins_test() {
+    setup_tracing();
+    let registry = Arc::new(ConnectionRegistry::new(ConnectionLimits {
+      max_logins_per_user: Some(1),
+      ..Default::default()
+    }));
+    let mut other_session = registry.register(LOCALHOST.ip()).unwrap();
+    other_session.login("test").unwrap();
+
+    let mut session_properties = SessionProperties::new();
+    let _ = session_properties.login_form.username.insert("test".to_string());
+    session_properties.registration = Some(registry.register(LOCALHOST.ip()).unwrap());
+
+    let session_properties = Arc::new(RwLock::new(session_properties));
+    let wrapper = Arc::new(StandardDataChannelWrapper::new(LOCALHOST, LOCALHOST.ip()));
+    let command_processor = Arc::new(CommandProcessor::new(session_properties, wrapper));
+
+    let command = Command::new(Commands::Pass, "test");
+
+    let users = vec![UserData::new("test", "test")];
+    AUTH_PROVIDER.get_or_init(|| async { create_test_auth_provider(users) }).await;
+
+    let (tx, mut rx) = channel(1024);
+    let reply_sender = TestReplySender::new(tx);
+    timeout(
+      Duration::from_secs(5),
+      command.execute(command_processor.clone(), Arc::new(reply_sender)),
+    )
+    .await
+    .expect("Command timed out!");
+
+    receive_and_verify_reply(2, &mut rx, ReplyCode::NotLoggedIn, Some("Too many")).await;
+    assert!(!command_processor.session_properties.read().await.is_logged_in());
+  }
+
   #[tokio::test]
   async fn incorrect_password_test() {
     setup_tracing();
@@ -123,7 +213,7 @@ mod tests {
     let _ = session_properties.login_form.username.insert("test".to_string());
 
     let session_properties = Arc::new(RwLock::new(session_properties));
-    let wrapper = Arc::new(StandardDataChannelWrapper::new(LOCALHOST));
+    let wrapper = Arc::new(StandardDataChannelWrapper::new(LOCALHOST, LOCALHOST.ip()));
     let command_processor = CommandProcessor::new(session_properties, wrapper);
 
     let command = Command::new(Commands::Pass, "INVALID");
@@ -143,11 +233,41 @@ mod tests {
     receive_and_verify_reply(2, &mut rx, ReplyCode::NotLoggedIn, None).await;
   }
 
+  #[tokio::test]
+  async fn tls_required_test() {
+    setup_tracing();
+    let mut session_properties = SessionProperties::new();
+    let _ = session_properties.login_form.username.insert("test".to_string());
+
+    let session_properties = Arc::new(RwLock::new(session_properties));
+    let wrapper = Arc::new(StandardDataChannelWrapper::new(LOCALHOST, LOCALHOST.ip()));
+    let mut command_processor = CommandProcessor::new(session_properties, wrapper);
+    command_processor.security_policy = Arc::new(SecurityPolicy {
+      tls_login_users: ["test".to_string()].into(),
+      ..Default::default()
+    });
+
+    let command = Command::new(Commands::Pass, "test");
+
+    let (tx, mut rx) = channel(1024);
+    let reply_sender = TestReplySender::new(tx);
+    let command_processor = Arc::new(command_processor);
+    timeout(
+      Duration::from_secs(5),
+      command.execute(command_processor.clone(), Arc::new(reply_sender)),
+    )
+    .await
+    .expect("Command timed out!");
+
+    receive_and_verify_reply(2, &mut rx, ReplyCode::RequestDeniedForPolicyReasons, None).await;
+    assert!(!command_processor.session_properties.read().await.is_logged_in());
+  }
+
   #[tokio::test]
   async fn no_username_test() {
     setup_tracing();
     let session_properties = Arc::new(RwLock::new(SessionProperties::new()));
-    let wrapper = Arc::new(StandardDataChannelWrapper::new(LOCALHOST));
+    let wrapper = Arc::new(StandardDataChannelWrapper::new(LOCALHOST, LOCALHOST.ip()));
     let command_processor = CommandProcessor::new(session_properties, wrapper);
 
     let command = Command::new(Commands::Pass, "test");
@@ -174,7 +294,7 @@ mod tests {
     let _ = session_properties.login_form.username.insert("test".to_string());
 
     let session_properties = Arc::new(RwLock::new(session_properties));
-    let wrapper = Arc::new(StandardDataChannelWrapper::new(LOCALHOST));
+    let wrapper = Arc::new(StandardDataChannelWrapper::new(LOCALHOST, LOCALHOST.ip()));
     let command_processor = CommandProcessor::new(session_properties, wrapper);
 
     let command = Command::new(Commands::Pass, "");
@@ -201,7 +321,7 @@ mod tests {
     let _ = session_properties.login_form.username.insert("test".to_string());
 
     let session_properties = Arc::new(RwLock::new(session_properties));
-    let wrapper = Arc::new(StandardDataChannelWrapper::new(LOCALHOST));
+    let wrapper = Arc::new(StandardDataChannelWrapper::new(LOCALHOST, LOCALHOST.ip()));
     let command_processor = CommandProcessor::new(session_properties, wrapper);
 
     let command = Command::new(Commands::Pass, "test");
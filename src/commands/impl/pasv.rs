@@ -1,7 +1,7 @@
-use std::net::{SocketAddr, SocketAddrV4};
+use std::net::{IpAddr, SocketAddrV4};
 use std::sync::Arc;
 
-use tracing::error;
+use tracing::{debug, error};
 
 use crate::commands::command::Command;
 use crate::commands::commands::Commands;
@@ -14,25 +14,39 @@ use crate::session::command_processor::CommandProcessor;
 pub(crate) async fn pasv(
   command: &Command,
   command_processor: Arc<CommandProcessor>,
-  reply_sender: Arc<impl ReplySend>,
+  reply_sender: Arc<dyn ReplySend>,
 ) {
   debug_assert_eq!(command.command, Commands::Pasv);
 
   let wrapper = command_processor.data_wrapper.clone();
   let properties = command_processor.session_properties.read().await;
+  if properties.epsv_all {
+    reply_sender
+      .send_control_message(Reply::new(
+        ReplyCode::BadSequenceOfCommands,
+        "Only EPSV is allowed after EPSV ALL!",
+      ))
+      .await;
+    return;
+  }
+
   let reply = match wrapper.open_data_stream(properties.prot_mode).await {
-    Ok(addr) => match addr {
-      SocketAddr::V4(addr) => {
-        Reply::new(ReplyCode::EnteringPassiveMode, create_pasv_response(&addr))
-      }
-      SocketAddr::V6(_) => {
-        error!("PASV: IPv6 is not supported!");
-        Reply::new(ReplyCode::CommandNotImplementedForThatParameter, "Server only supports IPv6!")
+    Ok(addr) => match addr.ip().to_canonical() {
+      IpAddr::V4(ip) => Reply::new(
+        ReplyCode::EnteringPassiveMode,
+        create_pasv_response(&SocketAddrV4::new(ip, addr.port())),
+      ),
+      IpAddr::V6(_) => {
+        debug!("PASV: IPv6 address can't be represented, EPSV must be used!");
+        Reply::new(
+          ReplyCode::CommandNotImplementedForThatParameter,
+          "PASV is not supported over IPv6, use EPSV!",
+        )
       }
     },
     Err(e) => {
       error!("Failed to open data stream: {}", e);
-      Reply::new(ReplyCode::SyntaxErrorInParametersOrArguments, "Failed to listen for data stream")
+      Reply::new(ReplyCode::CantOpenDataConnection, "Failed to listen for data stream")
     }
   };
   reply_sender.send_control_message(reply).await;
@@ -56,15 +70,19 @@ mod tests {
   use std::sync::Arc;
   use std::time::Duration;
 
-  use tokio::net::TcpStream;
+  use tokio::net::{TcpListener, TcpStream};
+  use tokio::sync::RwLock;
   use tokio::sync::mpsc::channel;
   use tokio::time::timeout;
 
   use crate::commands::command::Command;
   use crate::commands::commands::Commands;
   use crate::commands::r#impl::pasv::create_pasv_response;
-  use crate::commands::reply::Reply;
   use crate::commands::reply_code::ReplyCode;
+  use crate::data_channels::passive_settings::PassiveSettings;
+  use crate::data_channels::standard_data_channel_wrapper::StandardDataChannelWrapper;
+  use crate::session::command_processor::CommandProcessor;
+  use crate::session::session_properties::SessionProperties;
   use crate::tracing_print;
   use crate::utils::test_utils::*;
 
@@ -118,7 +136,7 @@ mod tests {
       }
     };
 
-    let addr = parse_socketaddr(reply);
+    let addr = parse_socketaddr(&reply.to_string());
 
     tracing_print!("Connecting to passive listener");
     if let Err(e) = TcpStream::connect(addr).await {
@@ -127,8 +145,7 @@ mod tests {
     tracing_print!("Client passive connection successful!");
   }
 
-  fn parse_socketaddr(reply: Reply) -> SocketAddr {
-    let message = reply.to_string();
+  fn parse_socketaddr(message: &str) -> SocketAddr {
     let start = message.find('(').expect("Address should start with '(' (non-standard)");
     let end = message.find(')').expect("Address should end with ')' (non-standard)");
     let addr = message[start + 1..end].split(',').collect::<Vec<&str>>();
@@ -157,4 +174,66 @@ mod tests {
       IpAddr::V4(Ipv4Addr::from_str(&ip).expect("Message should contain valid IPv4 octets"));
     SocketAddr::new(addr, p1 * 256 + p2)
   }
+
+  fn setup_command_processor(passive_settings: PassiveSettings) -> Arc<CommandProcessor> {
+    let wrapper = StandardDataChannelWrapper::new(LOCALHOST, LOCALHOST.ip())
+      .with_passive_settings(passive_settings);
+    let session_properties = Arc::new(RwLock::new(SessionProperties::new()));
+    Arc::new(CommandProcessor::new(session_properties, Arc::new(wrapper)))
+  }
+
+  #[tokio::test]
+  async fn port_range_test() {
+    setup_tracing();
+    let occupied = TcpListener::bind(LOCALHOST).await.unwrap();
+    let occupied_port = occupied.local_addr().unwrap().port();
+    let free_port = TcpListener::bind(LOCALHOST).await.unwrap().local_addr().unwrap().port();
+    let port_range = occupied_port.min(free_port)..=occupied_port.max(free_port);
+
+    let command_processor = setup_command_processor(PassiveSettings {
+      port_range: Some(port_range.clone()),
+      public_address: None,
+    });
+    for _ in 0..2 {
+      let (code, reply) =
+        execute(Command::new(Commands::Pasv, ""), command_processor.clone()).await;
+      assert_eq!(ReplyCode::EnteringPassiveMode, code);
+      let addr = parse_socketaddr(&reply);
+      assert!(port_range.contains(&addr.port()));
+      assert_ne!(occupied_port, addr.port());
+      TcpStream::connect(addr).await.expect("Client passive connection should succeed");
+    }
+  }
+
+  #[tokio::test]
+  async fn port_range_exhausted_test() {
+    setup_tracing();
+    let occupied = TcpListener::bind(LOCALHOST).await.unwrap();
+    let occupied_port = occupied.local_addr().unwrap().port();
+
+    let command_processor = setup_command_processor(PassiveSettings {
+      port_range: Some(occupied_port..=occupied_port),
+      public_address: None,
+    });
+    let (code, _reply) = execute(Command::new(Commands::Pasv, ""), command_processor).await;
+    assert_eq!(ReplyCode::CantOpenDataConnection, code);
+  }
+
+  #[tokio::test]
+  async fn public_address_test() {
+    setup_tracing();
+    let public_address = Ipv4Addr::new(203, 0, 113, 1);
+    let command_processor = setup_command_processor(PassiveSettings {
+      port_range: None,
+      public_address: Some(IpAddr::V4(public_address)),
+    });
+
+    let (code, reply) = execute(Command::new(Commands::Pasv, ""), command_processor).await;
+    assert_eq!(ReplyCode::EnteringPassiveMode, code);
+    let addr = parse_socketaddr(&reply);
+    assert_eq!(IpAddr::V4(public_address), addr.ip());
+
+    let local_addr = SocketAddr::new(LOCALHOST.ip(), addr.port());
+    TcpStream::connect(local_addr).await.expect("Client passive connection should succeed");
+  }
 }
@@ -10,13 +10,23 @@ use std::sync::Arc;
 pub(crate) async fn pbsz(
   command: &Command,
   command_processor: Arc<CommandProcessor>,
-  reply_sender: Arc<impl ReplySend>,
+  reply_sender: Arc<dyn ReplySend>,
 ) {
   debug_assert_eq!(command.command, Commands::Pbsz);
 
   let buffer_size = &command.argument.parse::<u32>();
   let mut properties = command_processor.session_properties.write().await;
 
+  // PBSZ is only allowed after the security data exchange, see RFC 4217 section 8
+  if !properties.connection_security.is_control_protected() {
+    return reply_sender
+      .send_control_message(Reply::new(
+        ReplyCode::BadSequenceOfCommands,
+        "Secure the control connection with AUTH first!",
+      ))
+      .await;
+  }
+
   match buffer_size {
     Ok(0) => {
       properties.pbsz = Some(0);
@@ -50,6 +60,7 @@ mod tests {
   use crate::commands::command::Command;
   use crate::commands::commands::Commands;
   use crate::commands::reply_code::ReplyCode;
+  use crate::session::connection_security::ConnectionSecurity;
   use crate::utils::test_utils::*;
   use std::sync::Arc;
   use std::time::Duration;
@@ -61,8 +72,10 @@ mod tests {
     setup_tracing();
     let command = Command::new(Commands::Pbsz, "0");
 
-    let settings =
-      CommandProcessorSettingsBuilder::default().build().expect("Settings should be valid");
+    let settings = CommandProcessorSettingsBuilder::default()
+      .connection_security(ConnectionSecurity::Tls)
+      .build()
+      .expect("Settings should be valid");
 
     let command_processor = setup_test_command_processor_custom(&settings);
     let (tx, mut rx) = mpsc::channel(1024);
@@ -85,8 +98,10 @@ mod tests {
     setup_tracing();
     let command = Command::new(Commands::Pbsz, "10");
 
-    let settings =
-      CommandProcessorSettingsBuilder::default().build().expect("Settings should be valid");
+    let settings = CommandProcessorSettingsBuilder::default()
+      .connection_security(ConnectionSecurity::Tls)
+      .build()
+      .expect("Settings should be valid");
 
     let command_processor = setup_test_command_processor_custom(&settings);
     let (tx, mut rx) = mpsc::channel(1024);
@@ -109,8 +124,10 @@ mod tests {
     setup_tracing();
     let command = Command::new(Commands::Pbsz, "value");
 
-    let settings =
-      CommandProcessorSettingsBuilder::default().build().expect("Settings should be valid");
+    let settings = CommandProcessorSettingsBuilder::default()
+      .connection_security(ConnectionSecurity::Tls)
+      .build()
+      .expect("Settings should be valid");
 
     let command_processor = setup_test_command_processor_custom(&settings);
     let (tx, mut rx) = mpsc::channel(1024);
@@ -133,8 +150,10 @@ mod tests {
     setup_tracing();
     let command = Command::new(Commands::Pbsz, "");
 
-    let settings =
-      CommandProcessorSettingsBuilder::default().build().expect("Settings should be valid");
+    let settings = CommandProcessorSettingsBuilder::default()
+      .connection_security(ConnectionSecurity::Tls)
+      .build()
+      .expect("Settings should be valid");
 
     let command_processor = setup_test_command_processor_custom(&settings);
     let (tx, mut rx) = mpsc::channel(1024);
@@ -151,4 +170,16 @@ mod tests {
 
     receive_and_verify_reply(2, &mut rx, ReplyCode::SyntaxErrorInParametersOrArguments, None).await;
   }
+
+  #[tokio::test]
+  async fn plain_connection_test() {
+    setup_tracing();
+    let settings =
+      CommandProcessorSettingsBuilder::default().build().expect("Settings should be valid");
+    let command_processor = Arc::new(setup_test_command_processor_custom(&settings));
+
+    let (code, _) = execute(Command::new(Commands::Pbsz, "0"), command_processor.clone()).await;
+    assert_eq!(ReplyCode::BadSequenceOfCommands, code);
+    assert_eq!(None, command_processor.session_properties.read().await.pbsz);
+  }
 }
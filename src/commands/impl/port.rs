@@ -0,0 +1,219 @@
+use std::net::{Ipv4Addr, SocketAddrV4};
+use std::sync::Arc;
+
+use tracing::debug;
+
+use crate::commands::command::Command;
+use crate::commands::commands::Commands;
+use crate::commands::r#impl::shared::connect_data_channel;
+use crate::commands::reply::Reply;
+use crate::commands::reply_code::ReplyCode;
+use crate::handlers::reply_sender::ReplySend;
+use crate::session::command_processor::CommandProcessor;
+
+#[tracing::instrument(skip(command_processor, reply_sender))]
+pub(crate) async fn port(
+  command: &Command,
+  command_processor: Arc<CommandProcessor>,
+  reply_sender: Arc<dyn ReplySend>,
+) {
+  debug_assert_eq!(command.command, Commands::Port);
+
+  let Some(addr) = parse_port_address(&command.argument) else {
+    debug!("Invalid PORT address: {}", command.argument);
+    reply_sender
+      .send_control_message(Reply::new(
+        ReplyCode::SyntaxErrorInParametersOrArguments,
+        "Invalid PORT address!",
+      ))
+      .await;
+    return;
+  };
+
+  let reply = connect_data_channel(addr.into(), command_processor).await;
+  reply_sender.send_control_message(reply).await;
+}
+
+/// Parses the address in format 'h1,h2,h3,h4,p1,p2' as specified by
+/// [RFC959](https://datatracker.ietf.org/doc/html/rfc959#section-4).
+fn parse_port_address(argument: &str) -> Option<SocketAddrV4> {
+  let numbers =
+    argument.trim().split(',').map(|n| n.trim().parse::<u8>()).collect::<Result<Vec<u8>, _>>();
+  match numbers.ok()?.as_slice() {
+    [h1, h2, h3, h4, p1, p2] => Some(SocketAddrV4::new(
+      Ipv4Addr::new(*h1, *h2, *h3, *h4),
+      u16::from(*p1) * 256 + u16::from(*p2),
+    )),
+    _ => None,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::net::{Ipv4Addr, SocketAddrV4};
+  use std::sync::Arc;
+  use std::time::Duration;
+
+  use rustls::pki_types::ServerName;
+  use tokio::io::{AsyncReadExt, AsyncWriteExt};
+  use tokio::net::TcpListener;
+  use tokio::sync::mpsc::channel;
+  use tokio::time::timeout;
+  use tokio_rustls::TlsConnector;
+
+  use crate::commands::command::Command;
+  use crate::commands::commands::Commands;
+  use crate::commands::r#impl::port::parse_port_address;
+  use crate::commands::reply_code::ReplyCode;
+  use crate::session::protection_mode::ProtMode;
+  use crate::utils::test_utils::*;
+
+  fn to_port_argument(addr: SocketAddrV4) -> String {
+    let octets = addr.ip().octets();
+    format!(
+      "{},{},{},{},{},{}",
+      octets[0],
+      octets[1],
+      octets[2],
+      octets[3],
+      addr.port() / 256,
+      addr.port() % 256
+    )
+  }
+
+  #[test]
+  fn parse_test() {
+    assert_eq!(
+      Some(SocketAddrV4::new(Ipv4Addr::LOCALHOST, 55692)),
+      parse_port_address("127,0,0,1,217,140")
+    );
+    assert_eq!(None, parse_port_address("127,0,0,1,217"));
+    assert_eq!(None, parse_port_address("127,0,0,1,217,140,1"));
+    assert_eq!(None, parse_port_address("127,0,0,256,217,140"));
+    assert_eq!(None, parse_port_address("127,0,0,a,217,140"));
+    assert_eq!(None, parse_port_address(""));
+  }
+
+  #[tokio::test]
+  async fn port_test() {
+    setup_tracing();
+    let (_, command_processor) = setup_test_command_processor();
+    let command_processor = Arc::new(command_processor);
+    let client_listener = TcpListener::bind(LOCALHOST).await.unwrap();
+    let client_addr = match client_listener.local_addr().unwrap() {
+      std::net::SocketAddr::V4(addr) => addr,
+      std::net::SocketAddr::V6(_) => panic!("Listener should be IPv4"),
+    };
+
+    let (tx, mut rx) = channel(1024);
+    let reply_sender = TestReplySender::new(tx);
+    let command = Command::new(Commands::Port, to_port_argument(client_addr));
+    timeout(
+      Duration::from_secs(2),
+      command.execute(command_processor.clone(), Arc::new(reply_sender)),
+    )
+    .await
+    .expect("Command timeout!");
+    receive_and_verify_reply(2, &mut rx, ReplyCode::CommandOkay, None).await;
+
+    let (mut client_dc, _) = timeout(Duration::from_secs(2), client_listener.accept())
+      .await
+      .expect("Server should connect in time")
+      .expect("Server should connect");
+    let (mut server_dc, _) =
+      timeout(Duration::from_secs(2), command_processor.data_wrapper.acquire())
+        .await
+        .expect("Data channel should be available in time")
+        .expect("Data channel should be available");
+
+    server_dc.write_all(b"active").await.unwrap();
+    server_dc.shutdown().await.unwrap();
+    let mut received = String::new();
+    client_dc.read_to_string(&mut received).await.unwrap();
+    assert_eq!("active", received);
+  }
+
+  #[tokio::test]
+  async fn port_tls_test() {
+    setup_tracing();
+    let (_, command_processor) = setup_test_command_processor();
+    command_processor.session_properties.write().await.prot_mode = ProtMode::Private;
+    let command_processor = Arc::new(command_processor);
+    let client_listener = TcpListener::bind(LOCALHOST).await.unwrap();
+    let client_addr = match client_listener.local_addr().unwrap() {
+      std::net::SocketAddr::V4(addr) => addr,
+      std::net::SocketAddr::V6(_) => panic!("Listener should be IPv4"),
+    };
+
+    let (tx, mut rx) = channel(1024);
+    let reply_sender = TestReplySender::new(tx);
+    let command = Command::new(Commands::Port, to_port_argument(client_addr));
+    timeout(
+      Duration::from_secs(2),
+      command.execute(command_processor.clone(), Arc::new(reply_sender)),
+    )
+    .await
+    .expect("Command timeout!");
+    receive_and_verify_reply(2, &mut rx, ReplyCode::CommandOkay, None).await;
+
+    let (client_dc, _) = timeout(Duration::from_secs(2), client_listener.accept())
+      .await
+      .expect("Server should connect in time")
+      .expect("Server should connect");
+    let connector = TlsConnector::from(Arc::new(create_tls_client_config("ftpoq-1")));
+    let mut client_dc = connector
+      .connect(ServerName::try_from("ftp.vanter.me").unwrap(), client_dc)
+      .await
+      .expect("TLS handshake should succeed");
+    let (mut server_dc, _) =
+      timeout(Duration::from_secs(2), command_processor.data_wrapper.acquire())
+        .await
+        .expect("Data channel should be available in time")
+        .expect("Data channel should be available");
+
+    server_dc.write_all(b"active").await.unwrap();
+    server_dc.shutdown().await.unwrap();
+    let mut received = String::new();
+    client_dc.read_to_string(&mut received).await.unwrap();
+    assert_eq!("active", received);
+  }
+
+  #[tokio::test]
+  async fn port_bounce_test() {
+    setup_tracing();
+    let (_, command_processor) = setup_test_command_processor();
+    let command_processor = Arc::new(command_processor);
+
+    let (tx, mut rx) = channel(1024);
+    let reply_sender = TestReplySender::new(tx);
+    let command = Command::new(Commands::Port, "10,0,0,1,0,21");
+    timeout(
+      Duration::from_secs(2),
+      command.execute(command_processor.clone(), Arc::new(reply_sender)),
+    )
+    .await
+    .expect("Command timeout!");
+
+    receive_and_verify_reply(2, &mut rx, ReplyCode::CommandNotImplementedForThatParameter, None)
+      .await;
+    assert!(command_processor.data_wrapper.try_acquire().is_err());
+  }
+
+  #[tokio::test]
+  async fn port_invalid_test() {
+    setup_tracing();
+    let (_, command_processor) = setup_test_command_processor();
+
+    let (tx, mut rx) = channel(1024);
+    let reply_sender = TestReplySender::new(tx);
+    let command = Command::new(Commands::Port, "127,0,0,1");
+    timeout(
+      Duration::from_secs(2),
+      command.execute(Arc::new(command_processor), Arc::new(reply_sender)),
+    )
+    .await
+    .expect("Command timeout!");
+
+    receive_and_verify_reply(2, &mut rx, ReplyCode::SyntaxErrorInParametersOrArguments, None).await;
+  }
+}
@@ -13,13 +13,31 @@ use std::sync::Arc;
 pub(crate) async fn prot(
   command: &Command,
   command_processor: Arc<CommandProcessor>,
-  reply_sender: Arc<impl ReplySend>,
+  reply_sender: Arc<dyn ReplySend>,
 ) {
   debug_assert_eq!(command.command, Commands::Prot);
 
   let mut properties = command_processor.session_properties.write().await;
 
   let reply = match ProtMode::from_str(&command.argument) {
+    // PROT is only allowed after the security data exchange, see RFC 4217 section 9
+    _ if !properties.connection_security.is_control_protected() => {
+      Reply::new(ReplyCode::BadSequenceOfCommands, "Secure the control connection with AUTH first!")
+    }
+    // PBSZ must always precede PROT, see RFC 4217 section 9
+    Ok(_) if properties.pbsz.is_none() => {
+      Reply::new(ReplyCode::BadSequenceOfCommands, "PBSZ must be sent first!")
+    }
+    Ok(ProtMode::Clear)
+      if !command_processor
+        .security_policy
+        .is_data_allowed(properties.connection_security, ProtMode::Clear) =>
+    {
+      Reply::new(
+        ReplyCode::RequestDeniedForPolicyReasons,
+        "Policy requires protected data channels, use PROT P!",
+      )
+    }
     Ok(ProtMode::Clear) => {
       properties.prot_mode = ProtMode::Clear;
       Reply::new(ReplyCode::CommandOkay, "Protection level set")
@@ -48,6 +66,7 @@ mod tests {
   use crate::commands::command::Command;
   use crate::commands::commands::Commands;
   use crate::commands::reply_code::ReplyCode;
+  use crate::session::connection_security::ConnectionSecurity;
   use crate::session::protection_mode::ProtMode;
   use crate::utils::test_utils::*;
   use std::sync::Arc;
@@ -61,6 +80,7 @@ mod tests {
     let command = Command::new(Commands::Prot, "");
 
     let settings = CommandProcessorSettingsBuilder::default()
+      .connection_security(ConnectionSecurity::Tls)
       .username(Some("testuser".to_string()))
       .build()
       .expect("Settings should be valid");
@@ -86,11 +106,13 @@ mod tests {
     let command = Command::new(Commands::Prot, "P");
 
     let settings = CommandProcessorSettingsBuilder::default()
+      .connection_security(ConnectionSecurity::Tls)
       .username(Some("testuser".to_string()))
       .build()
       .expect("Settings should be valid");
 
     let command_processor = Arc::new(setup_test_command_processor_custom(&settings));
+    command_processor.session_properties.write().await.pbsz = Some(0);
 
     let (tx, mut rx) = channel(1024);
     let reply_sender = TestReplySender::new(tx);
@@ -111,11 +133,13 @@ mod tests {
     let command = Command::new(Commands::Prot, "C");
 
     let settings = CommandProcessorSettingsBuilder::default()
+      .connection_security(ConnectionSecurity::Tls)
       .username(Some("testuser".to_string()))
       .build()
       .expect("Settings should be valid");
 
     let command_processor = Arc::new(setup_test_command_processor_custom(&settings));
+    command_processor.session_properties.write().await.pbsz = Some(0);
     command_processor.session_properties.write().await.prot_mode = ProtMode::Private;
 
     let (tx, mut rx) = channel(1024);
@@ -137,11 +161,13 @@ mod tests {
     let command = Command::new(Commands::Prot, "S");
 
     let settings = CommandProcessorSettingsBuilder::default()
+      .connection_security(ConnectionSecurity::Tls)
       .username(Some("testuser".to_string()))
       .build()
       .expect("Settings should be valid");
 
     let command_processor = Arc::new(setup_test_command_processor_custom(&settings));
+    command_processor.session_properties.write().await.pbsz = Some(0);
 
     let (tx, mut rx) = channel(1024);
     let reply_sender = TestReplySender::new(tx);
@@ -155,4 +181,45 @@ mod tests {
     receive_and_verify_reply(2, &mut rx, ReplyCode::ProtectionLevelNotSupported, None).await;
     assert_eq!(ProtMode::Clear, command_processor.session_properties.read().await.prot_mode);
   }
+
+  #[tokio::test]
+  async fn missing_pbsz_test() {
+    setup_tracing();
+    let command = Command::new(Commands::Prot, "P");
+
+    let settings = CommandProcessorSettingsBuilder::default()
+      .connection_security(ConnectionSecurity::Tls)
+      .username(Some("testuser".to_string()))
+      .build()
+      .expect("Settings should be valid");
+
+    let command_processor = Arc::new(setup_test_command_processor_custom(&settings));
+
+    let (tx, mut rx) = channel(1024);
+    let reply_sender = TestReplySender::new(tx);
+    timeout(
+      Duration::from_secs(3),
+      command.execute(command_processor.clone(), Arc::new(reply_sender)),
+    )
+    .await
+    .expect("Command timeout!");
+
+    receive_and_verify_reply(2, &mut rx, ReplyCode::BadSequenceOfCommands, None).await;
+    assert_eq!(ProtMode::Clear, command_processor.session_properties.read().await.prot_mode);
+  }
+
+  #[tokio::test]
+  async fn plain_connection_test() {
+    setup_tracing();
+    let settings = CommandProcessorSettingsBuilder::default()
+      .username(Some("testuser".to_string()))
+      .build()
+      .expect("Settings should be valid");
+    let command_processor = Arc::new(setup_test_command_processor_custom(&settings));
+    command_processor.session_properties.write().await.pbsz = Some(0);
+
+    let (code, _) = execute(Command::new(Commands::Prot, "P"), command_processor.clone()).await;
+    assert_eq!(ReplyCode::BadSequenceOfCommands, code);
+    assert_eq!(ProtMode::Clear, command_processor.session_properties.read().await.prot_mode);
+  }
 }
@@ -0,0 +1,95 @@
+use std::sync::Arc;
+
+use tracing::info;
+
+use crate::commands::command::Command;
+use crate::commands::commands::Commands;
+use crate::commands::reply::Reply;
+use crate::commands::reply_code::ReplyCode;
+use crate::handlers::reply_sender::ReplySend;
+use crate::session::command_processor::CommandProcessor;
+
+/// Ends the session, as specified by
+/// [RFC959](https://datatracker.ietf.org/doc/html/rfc959#section-4.1.1).
+///
+/// Commands are executed in order, so transfers started before QUIT are already finished. After
+/// the reply, the connection handler is signaled to close the session.
+#[tracing::instrument(skip(command_processor, reply_sender))]
+pub(crate) async fn quit(
+  command: &Command,
+  command_processor: Arc<CommandProcessor>,
+  reply_sender: Arc<dyn ReplySend>,
+) {
+  debug_assert_eq!(Commands::Quit, command.command);
+
+  if !command.argument.is_empty() {
+    return reply_sender
+      .send_control_message(Reply::new(
+        ReplyCode::SyntaxErrorInParametersOrArguments,
+        "QUIT must not have an argument!",
+      ))
+      .await;
+  }
+
+  if let Some(username) = command_processor.session_properties.read().await.username.as_ref() {
+    info!("User '{username}' logging out.");
+  }
+  reply_sender
+    .send_control_message(Reply::new(ReplyCode::ClosingControlConnection, "Goodbye."))
+    .await;
+  command_processor.close_token.cancel();
+}
+
+#[cfg(test)]
+mod tests {
+  use std::sync::Arc;
+  use std::time::Duration;
+
+  use tokio::sync::mpsc::channel;
+  use tokio::time::timeout;
+
+  use crate::commands::command::Command;
+  use crate::commands::commands::Commands;
+  use crate::commands::reply_code::ReplyCode;
+  use crate::utils::test_utils::*;
+
+  #[tokio::test]
+  async fn quit_test() {
+    setup_tracing();
+    let (_, command_processor) = setup_test_command_processor();
+    let command_processor = Arc::new(command_processor);
+    let command = Command::new(Commands::Quit, "");
+
+    let (tx, mut rx) = channel(1024);
+    let reply_sender = TestReplySender::new(tx);
+    timeout(
+      Duration::from_secs(3),
+      command.execute(command_processor.clone(), Arc::new(reply_sender)),
+    )
+    .await
+    .expect("Command timed out!");
+
+    receive_and_verify_reply(2, &mut rx, ReplyCode::ClosingControlConnection, None).await;
+    assert!(command_processor.close_token.is_cancelled());
+  }
+
+  #[tokio::test]
+  async fn with_argument_test() {
+    setup_tracing();
+    let (_, command_processor) = setup_test_command_processor();
+    let command_processor = Arc::new(command_processor);
+    let command = Command::new(Commands::Quit, "now");
+
+    let (tx, mut rx) = channel(1024);
+    let reply_sender = TestReplySender::new(tx);
+    timeout(
+      Duration::from_secs(3),
+      command.execute(command_processor.clone(), Arc::new(reply_sender)),
+    )
+    .await
+    .expect("Command timed out!");
+
+    receive_and_verify_reply(2, &mut rx, ReplyCode::SyntaxErrorInParametersOrArguments, None).await;
+    assert!(!command_processor.close_token.is_cancelled());
+  }
+}
@@ -0,0 +1,116 @@
+use std::sync::Arc;
+
+use tracing::{debug, info};
+
+use crate::commands::command::Command;
+use crate::commands::commands::Commands;
+use crate::commands::reply::Reply;
+use crate::commands::reply_code::ReplyCode;
+use crate::handlers::reply_sender::ReplySend;
+use crate::session::command_processor::CommandProcessor;
+
+/// Reinitializes the session, as specified by
+/// [RFC959](https://datatracker.ietf.org/doc/html/rfc959#section-4.1.1).
+///
+/// Commands are executed in order, so transfers started before REIN are already finished. The
+/// user is logged out, the data channel is closed and the session settings are reset, but the
+/// control connection stays open.
+#[tracing::instrument(skip(command_processor, reply_sender))]
+pub(crate) async fn rein(
+  command: &Command,
+  command_processor: Arc<CommandProcessor>,
+  reply_sender: Arc<dyn ReplySend>,
+) {
+  debug_assert_eq!(Commands::Rein, command.command);
+
+  if !command.argument.is_empty() {
+    return reply_sender
+      .send_control_message(Reply::new(
+        ReplyCode::SyntaxErrorInParametersOrArguments,
+        "REIN must not have an argument!",
+      ))
+      .await;
+  }
+
+  let mut session_properties = command_processor.session_properties.write().await;
+  if let Some(username) = session_properties.username.as_ref() {
+    info!("User '{username}' logging out.");
+  }
+  session_properties.reinitialize();
+  drop(session_properties);
+
+  debug!("Closing data channel");
+  command_processor.data_wrapper.close_data_stream().await;
+
+  reply_sender.send_control_message(Reply::new(ReplyCode::ServiceReady, "Session reset.")).await;
+}
+
+#[cfg(test)]
+mod tests {
+  use std::sync::Arc;
+  use std::sync::atomic::Ordering;
+  use std::time::Duration;
+
+  use tokio::sync::mpsc::channel;
+  use tokio::time::timeout;
+
+  use crate::commands::command::Command;
+  use crate::commands::commands::Commands;
+  use crate::commands::reply_code::ReplyCode;
+  use crate::session::data_type::DataType;
+  use crate::session::protection_mode::ProtMode;
+  use crate::utils::test_utils::*;
+
+  #[tokio::test]
+  async fn rein_test() {
+    setup_tracing();
+    let (_, command_processor) = setup_test_command_processor();
+    {
+      let mut session_properties = command_processor.session_properties.write().await;
+      session_properties.data_type = DataType::Binary;
+      session_properties.prot_mode = ProtMode::Private;
+      session_properties.offset.store(100, Ordering::SeqCst);
+      session_properties.idle_timeout = Some(Duration::from_secs(10));
+    }
+    let command_processor = Arc::new(command_processor);
+    let command = Command::new(Commands::Rein, "");
+
+    let (tx, mut rx) = channel(1024);
+    let reply_sender = TestReplySender::new(tx);
+    timeout(
+      Duration::from_secs(3),
+      command.execute(command_processor.clone(), Arc::new(reply_sender)),
+    )
+    .await
+    .expect("Command timed out!");
+
+    receive_and_verify_reply(2, &mut rx, ReplyCode::ServiceReady, None).await;
+    let session_properties = command_processor.session_properties.read().await;
+    assert!(!session_properties.is_logged_in());
+    assert_eq!(DataType::default(), session_properties.data_type);
+    assert_eq!(ProtMode::default(), session_properties.prot_mode);
+    assert_eq!(0, session_properties.offset.load(Ordering::SeqCst));
+    assert_eq!(Some(Duration::from_secs(10)), session_properties.idle_timeout);
+    assert!(!command_processor.close_token.is_cancelled());
+  }
+
+  #[tokio::test]
+  async fn with_argument_test() {
+    setup_tracing();
+    let (_, command_processor) = setup_test_command_processor();
+    let command_processor = Arc::new(command_processor);
+    let command = Command::new(Commands::Rein, "now");
+
+    let (tx, mut rx) = channel(1024);
+    let reply_sender = TestReplySender::new(tx);
+    timeout(
+      Duration::from_secs(3),
+      command.execute(command_processor.clone(), Arc::new(reply_sender)),
+    )
+    .await
+    .expect("Command timed out!");
+
+    receive_and_verify_reply(2, &mut rx, ReplyCode::SyntaxErrorInParametersOrArguments, None).await;
+    assert!(command_processor.session_properties.read().await.is_logged_in());
+  }
+}
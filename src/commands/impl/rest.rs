@@ -11,7 +11,7 @@ use std::sync::atomic::Ordering;
 pub(crate) async fn rest(
   command: &Command,
   command_processor: Arc<CommandProcessor>,
-  reply_sender: Arc<impl ReplySend>,
+  reply_sender: Arc<dyn ReplySend>,
 ) {
   debug_assert_eq!(command.command, Commands::Rest);
 
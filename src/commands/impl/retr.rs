@@ -1,7 +1,8 @@
 use std::io::{ErrorKind, SeekFrom};
 use std::sync::Arc;
 use std::sync::atomic::Ordering;
-use tokio::io::{AsyncSeekExt, AsyncWriteExt, BufReader};
+use tokio::io;
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt, BufReader};
 use tokio::select;
 use tracing::{debug, info, warn};
 
@@ -13,14 +14,16 @@ use crate::commands::r#impl::shared::{
 use crate::commands::reply::Reply;
 use crate::commands::reply_code::ReplyCode;
 use crate::handlers::reply_sender::ReplySend;
+use crate::io::ascii::{Conversion, create_transfer_reader};
 use crate::io::open_options_flags::OpenOptionsWrapperBuilder;
 use crate::session::command_processor::CommandProcessor;
+use crate::session::data_type::DataType;
 
 #[tracing::instrument(skip(command_processor, reply_sender))]
 pub(crate) async fn retr(
   command: &Command,
   command_processor: Arc<CommandProcessor>,
-  reply_sender: Arc<impl ReplySend>,
+  reply_sender: Arc<dyn ReplySend>,
 ) {
   debug_assert_eq!(command.command, Commands::Retr);
 
@@ -41,7 +44,7 @@ pub(crate) async fn retr(
       .await;
   }
 
-  let data_channel_pair = acquire_data_channel(command_processor.data_wrapper.clone()).await;
+  let data_channel_pair = acquire_data_channel(&command_processor, &session_properties).await;
   let (mut data_channel, token) = match data_channel_pair {
     Ok((dc, token)) => (dc, token),
     Err(e) => {
@@ -69,18 +72,30 @@ pub(crate) async fn retr(
     .send_control_message(Reply::new(ReplyCode::FileStatusOkay, "Starting file transfer!"))
     .await;
 
+  let data_type = session_properties.data_type;
   let offset = session_properties.offset.swap(0, Ordering::SeqCst);
-  if offset > 0 {
+  if offset > 0 && data_type == DataType::Binary {
     debug!("Setting cursor to offset: {}", offset);
     if let Err(e) = file.seek(SeekFrom::Start(offset)).await {
       warn!("Failed to seek file {} to offset {}. Error: {}", &command.argument, offset, e);
     };
   }
 
+  let mut reader = create_transfer_reader(&mut file, data_type, Conversion::ToCrlf);
+  // In ASCII mode the offset refers to the converted data, so it can't be seeked to
+  if offset > 0 && data_type != DataType::Binary {
+    debug!("Skipping converted data to offset: {}", offset);
+    if let Err(e) = io::copy(&mut (&mut reader).take(offset), &mut io::sink()).await {
+      warn!("Failed to skip file {} to offset {}. Error: {}", &command.argument, offset, e);
+    };
+  }
+
   debug!("Sending file data, offset: {}!", offset);
 
-  let mut buf = BufReader::with_capacity(TRANSFER_BUFFER_SIZE, &mut file);
-  let transfer = copy_data(&mut buf, &mut data_channel);
+  let mut buf = BufReader::with_capacity(TRANSFER_BUFFER_SIZE, reader);
+  let transfer_guard =
+    command_processor.transfer_status.start(format!("{} {}", command.command, command.argument));
+  let transfer = copy_data(&mut buf, &mut data_channel, transfer_guard.transferred());
 
   let success = select! {
     result = transfer => result,
@@ -105,6 +120,7 @@ mod tests {
   use std::env::{current_dir, temp_dir};
   use std::path::PathBuf;
   use std::sync::Arc;
+  use std::sync::atomic::Ordering;
   use std::time::Duration;
 
   use blake3::Hasher;
@@ -128,6 +144,7 @@ mod tests {
   use crate::listeners::quic_only_listener::QuicOnlyListener;
   use crate::listeners::quinn_listener::QuinnListener;
   use crate::session::command_processor::CommandProcessor;
+  use crate::session::data_type::DataType;
   use crate::session::protection_mode::ProtMode;
   use crate::tracing_print;
   use crate::utils::test_utils::*;
@@ -138,7 +155,7 @@ mod tests {
 
     let command = Command::new(Commands::Retr, argument);
 
-    let wrapper = StandardDataChannelWrapper::new(LOCALHOST);
+    let wrapper = StandardDataChannelWrapper::new(LOCALHOST, LOCALHOST.ip());
     let mut command_processor = setup_transfer_command_processor(wrapper, root);
     let client_dc = open_tcp_data_channel(&mut command_processor).await;
     transfer(file_path, command, command_processor, client_dc).await;
@@ -282,6 +299,8 @@ mod tests {
   ) {
     tracing_print!("Running transfer.");
     const TIMEOUT_SECS: u64 = 600;
+    // The files are compared byte by byte
+    command_processor.session_properties.write().await.data_type = DataType::Binary;
 
     let (tx, mut rx) = channel(1024);
     let reply_sender = TestReplySender::new(tx);
@@ -550,6 +569,74 @@ mod tests {
     common_quinn_quinn(temp_dir(), &file_name).await;
   }
 
+  #[tokio::test]
+  async fn default_ascii_test() {
+    setup_tracing();
+    let file_name = format!("{}.test", Uuid::new_v4().as_hyphenated());
+    let file_path = temp_dir().join(&file_name);
+    std::fs::write(&file_path, b"ab\ncd\r\nef\n").unwrap();
+    let _cleanup = FileCleanup::new(&file_path);
+
+    // Without TYPE, the session is in ASCII mode
+    let wrapper = StandardDataChannelWrapper::new(LOCALHOST, LOCALHOST.ip());
+    let mut command_processor = setup_transfer_command_processor(wrapper, temp_dir());
+    let mut client_dc = open_tcp_data_channel(&mut command_processor).await;
+
+    let (tx, mut rx) = channel(1024);
+    let reply_sender = TestReplySender::new(tx);
+    let command = Command::new(Commands::Retr, &file_name);
+    let command_fut = tokio::spawn(async move {
+      timeout(
+        Duration::from_secs(10),
+        command.execute(Arc::new(command_processor), Arc::new(reply_sender)),
+      )
+      .await
+      .expect("Command timeout!");
+    });
+
+    receive_and_verify_reply(2, &mut rx, ReplyCode::FileStatusOkay, None).await;
+    let mut received = Vec::new();
+    client_dc.read_to_end(&mut received).await.unwrap();
+    receive_and_verify_reply(2, &mut rx, ReplyCode::ClosingDataConnection, None).await;
+    command_fut.await.expect("Command should complete!");
+
+    assert_eq!(b"ab\r\ncd\r\nef\r\n".to_vec(), received);
+  }
+
+  #[tokio::test]
+  async fn ascii_restart_test() {
+    setup_tracing();
+    let file_name = format!("{}.test", Uuid::new_v4().as_hyphenated());
+    let file_path = temp_dir().join(&file_name);
+    std::fs::write(&file_path, b"ab\ncd\r\nef\n").unwrap();
+    let _cleanup = FileCleanup::new(&file_path);
+
+    let wrapper = StandardDataChannelWrapper::new(LOCALHOST, LOCALHOST.ip());
+    let mut command_processor = setup_transfer_command_processor(wrapper, temp_dir());
+    command_processor.session_properties.read().await.offset.store(4, Ordering::SeqCst);
+    let mut client_dc = open_tcp_data_channel(&mut command_processor).await;
+
+    let (tx, mut rx) = channel(1024);
+    let reply_sender = TestReplySender::new(tx);
+    let command = Command::new(Commands::Retr, &file_name);
+    let command_fut = tokio::spawn(async move {
+      timeout(
+        Duration::from_secs(10),
+        command.execute(Arc::new(command_processor), Arc::new(reply_sender)),
+      )
+      .await
+      .expect("Command timeout!");
+    });
+
+    receive_and_verify_reply(2, &mut rx, ReplyCode::FileStatusOkay, None).await;
+    let mut received = Vec::new();
+    client_dc.read_to_end(&mut received).await.unwrap();
+    receive_and_verify_reply(2, &mut rx, ReplyCode::ClosingDataConnection, None).await;
+    command_fut.await.expect("Command should complete!");
+
+    assert_eq!(b"cd\r\nef\r\n".to_vec(), received);
+  }
+
   #[tokio::test]
   async fn not_logged_in_test() {
     setup_tracing();
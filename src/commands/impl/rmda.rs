@@ -12,7 +12,7 @@ use tracing::info;
 pub(crate) async fn rmda(
   command: &Command,
   command_processor: Arc<CommandProcessor>,
-  reply_sender: Arc<impl ReplySend>,
+  reply_sender: Arc<dyn ReplySend>,
 ) {
   assert_eq!(Commands::Rmda, command.command);
 
@@ -0,0 +1,180 @@
+use std::sync::Arc;
+use tracing::{debug, trace};
+
+use crate::commands::command::Command;
+use crate::commands::commands::Commands;
+use crate::commands::r#impl::shared::get_rename_reply;
+use crate::commands::reply::Reply;
+use crate::commands::reply_code::ReplyCode;
+use crate::handlers::reply_sender::ReplySend;
+use crate::session::command_processor::CommandProcessor;
+
+#[tracing::instrument(skip(command_processor, reply_sender))]
+pub(crate) async fn rnfr(
+  command: &Command,
+  command_processor: Arc<CommandProcessor>,
+  reply_sender: Arc<dyn ReplySend>,
+) {
+  trace!("Executing RNFR command");
+  debug_assert_eq!(command.command, Commands::Rnfr);
+  let mut session_properties = command_processor.session_properties.write().await;
+
+  if !session_properties.is_logged_in() {
+    reply_sender
+      .send_control_message(Reply::new(ReplyCode::NotLoggedIn, "User not logged in!"))
+      .await;
+    return;
+  }
+
+  if command.argument.is_empty() {
+    reply_sender
+      .send_control_message(Reply::new(
+        ReplyCode::SyntaxErrorInParametersOrArguments,
+        "RNFR must have an argument!",
+      ))
+      .await;
+    return;
+  }
+
+  if let Err(e) = session_properties.file_system_view_root.metadata(&command.argument) {
+    debug!("Cannot rename '{}'! {e}", &command.argument);
+    return reply_sender.send_control_message(get_rename_reply(Err(e))).await;
+  }
+
+  debug!("Pending rename from '{}'", &command.argument);
+  session_properties.rename_from.replace(command.argument.clone());
+
+  reply_sender
+    .send_control_message(Reply::new(
+      ReplyCode::RequestedFileActionPendingFurtherInformation,
+      "Ready for destination name",
+    ))
+    .await;
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::commands::command::Command;
+  use crate::commands::commands::Commands;
+  use crate::commands::reply_code::ReplyCode;
+  use crate::utils::test_utils::*;
+  use std::env::temp_dir;
+  use std::sync::Arc;
+  use std::time::Duration;
+  use tokio::sync::mpsc;
+  use tokio::time::timeout;
+  use uuid::Uuid;
+
+  #[tokio::test]
+  async fn rnfr_test() {
+    setup_tracing();
+    let label = "test";
+    let file_name = format!("{}.test", Uuid::new_v4().as_hyphenated());
+    let file_path = temp_dir().join(&file_name);
+    touch(&file_path).expect("Test file must exist");
+    let _cleanup = FileCleanup::new(&file_path);
+    let command = Command::new(Commands::Rnfr, &file_name);
+
+    let settings = CommandProcessorSettingsBuilder::default()
+      .label(label.to_string())
+      .view_root(temp_dir())
+      .change_path(Some(label.to_string()))
+      .username(Some("test_user".to_string()))
+      .build()
+      .unwrap();
+    let command_processor = Arc::new(setup_test_command_processor_custom(&settings));
+
+    let (tx, mut rx) = mpsc::channel(1024);
+    let reply_sender = TestReplySender::new(tx);
+    timeout(
+      Duration::from_secs(3),
+      command.execute(command_processor.clone(), Arc::new(reply_sender)),
+    )
+    .await
+    .expect("Command timeout!");
+
+    receive_and_verify_reply(
+      2,
+      &mut rx,
+      ReplyCode::RequestedFileActionPendingFurtherInformation,
+      None,
+    )
+    .await;
+    assert_eq!(Some(file_name), command_processor.session_properties.read().await.rename_from);
+  }
+
+  #[tokio::test]
+  async fn rnfr_nonexistent_test() {
+    setup_tracing();
+    let label = "test";
+    let command = Command::new(Commands::Rnfr, format!("/{label}/{}", Uuid::new_v4()));
+
+    let settings = CommandProcessorSettingsBuilder::default()
+      .label(label.to_string())
+      .view_root(temp_dir())
+      .username(Some("test_user".to_string()))
+      .build()
+      .unwrap();
+    let command_processor = Arc::new(setup_test_command_processor_custom(&settings));
+
+    let (tx, mut rx) = mpsc::channel(1024);
+    let reply_sender = TestReplySender::new(tx);
+    timeout(
+      Duration::from_secs(3),
+      command.execute(command_processor.clone(), Arc::new(reply_sender)),
+    )
+    .await
+    .expect("Command timeout!");
+
+    receive_and_verify_reply(2, &mut rx, ReplyCode::FileUnavailable, None).await;
+    assert!(command_processor.session_properties.read().await.rename_from.is_none());
+  }
+
+  #[tokio::test]
+  async fn rnfr_no_argument_test() {
+    setup_tracing();
+    let label = "test";
+    let command = Command::new(Commands::Rnfr, "");
+
+    let settings = CommandProcessorSettingsBuilder::default()
+      .label(label.to_string())
+      .view_root(temp_dir())
+      .username(Some("test_user".to_string()))
+      .build()
+      .unwrap();
+    let command_processor = Arc::new(setup_test_command_processor_custom(&settings));
+
+    let (tx, mut rx) = mpsc::channel(1024);
+    let reply_sender = TestReplySender::new(tx);
+    timeout(
+      Duration::from_secs(3),
+      command.execute(command_processor.clone(), Arc::new(reply_sender)),
+    )
+    .await
+    .expect("Command timeout!");
+
+    receive_and_verify_reply(2, &mut rx, ReplyCode::SyntaxErrorInParametersOrArguments, None).await;
+    assert!(command_processor.session_properties.read().await.rename_from.is_none());
+  }
+
+  #[tokio::test]
+  async fn rnfr_not_logged_in_test() {
+    setup_tracing();
+    let command = Command::new(Commands::Rnfr, "file.test");
+
+    let settings = CommandProcessorSettingsBuilder::default().build().unwrap();
+    let command_processor = Arc::new(setup_test_command_processor_custom(&settings));
+
+    let (tx, mut rx) = mpsc::channel(1024);
+    let reply_sender = TestReplySender::new(tx);
+    timeout(
+      Duration::from_secs(3),
+      command.execute(command_processor.clone(), Arc::new(reply_sender)),
+    )
+    .await
+    .expect("Command timeout!");
+
+    receive_and_verify_reply(2, &mut rx, ReplyCode::NotLoggedIn, None).await;
+    assert!(command_processor.session_properties.read().await.rename_from.is_none());
+  }
+}
@@ -0,0 +1,315 @@
+use std::sync::Arc;
+use tracing::{info, trace};
+
+use crate::commands::command::Command;
+use crate::commands::commands::Commands;
+use crate::commands::r#impl::shared::get_rename_reply;
+use crate::commands::reply::Reply;
+use crate::commands::reply_code::ReplyCode;
+use crate::handlers::reply_sender::ReplySend;
+use crate::session::command_processor::CommandProcessor;
+
+#[tracing::instrument(skip(command_processor, reply_sender))]
+pub(crate) async fn rnto(
+  command: &Command,
+  command_processor: Arc<CommandProcessor>,
+  reply_sender: Arc<dyn ReplySend>,
+) {
+  trace!("Executing RNTO command");
+  debug_assert_eq!(command.command, Commands::Rnto);
+  let mut session_properties = command_processor.session_properties.write().await;
+
+  if !session_properties.is_logged_in() {
+    reply_sender
+      .send_control_message(Reply::new(ReplyCode::NotLoggedIn, "User not logged in!"))
+      .await;
+    return;
+  }
+
+  let Some(rename_from) = session_properties.rename_from.take() else {
+    reply_sender
+      .send_control_message(Reply::new(
+        ReplyCode::BadSequenceOfCommands,
+        "RNTO must be preceded by RNFR!",
+      ))
+      .await;
+    return;
+  };
+
+  if command.argument.is_empty() {
+    reply_sender
+      .send_control_message(Reply::new(
+        ReplyCode::SyntaxErrorInParametersOrArguments,
+        "RNTO must have an argument!",
+      ))
+      .await;
+    return;
+  }
+
+  info!(
+    "User '{}' renaming '{}' to '{}'.",
+    session_properties.username.as_ref().unwrap(),
+    &rename_from,
+    &command.argument
+  );
+
+  let result =
+    session_properties.file_system_view_root.rename(&rename_from, &command.argument).await;
+
+  reply_sender.send_control_message(get_rename_reply(result)).await;
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::commands::command::Command;
+  use crate::commands::commands::Commands;
+  use crate::commands::reply_code::ReplyCode;
+  use crate::utils::test_utils::*;
+  use std::env::temp_dir;
+  use std::sync::Arc;
+  use std::time::Duration;
+  use tokio::sync::mpsc;
+  use tokio::time::timeout;
+  use uuid::Uuid;
+
+  #[tokio::test]
+  async fn rnfr_rnto_relative_test() {
+    setup_tracing();
+    let label = "test";
+    let root = temp_dir();
+    let file_name = format!("{}.test", Uuid::new_v4().as_hyphenated());
+    let file_path = root.join(&file_name);
+    let new_file_name = format!("{}.test", Uuid::new_v4().as_hyphenated());
+    let new_file_path = root.join(&new_file_name);
+    touch(&file_path).expect("Test file must exist");
+    let _cleanup = FileCleanup::new(&file_path);
+    let _cleanup_new = FileCleanup::new(&new_file_path);
+
+    let settings = CommandProcessorSettingsBuilder::default()
+      .label(label.to_string())
+      .view_root(root)
+      .username(Some("test_user".to_string()))
+      .change_path(Some(label.to_string()))
+      .build()
+      .unwrap();
+    let command_processor = Arc::new(setup_test_command_processor_custom(&settings));
+
+    let (tx, mut rx) = mpsc::channel(1024);
+    let reply_sender = Arc::new(TestReplySender::new(tx));
+    let command = Command::new(Commands::Rnfr, &file_name);
+    timeout(
+      Duration::from_secs(3),
+      command.execute(command_processor.clone(), reply_sender.clone()),
+    )
+    .await
+    .expect("Command timeout!");
+    receive_and_verify_reply(
+      2,
+      &mut rx,
+      ReplyCode::RequestedFileActionPendingFurtherInformation,
+      None,
+    )
+    .await;
+
+    let command = Command::new(Commands::Rnto, &new_file_name);
+    timeout(Duration::from_secs(3), command.execute(command_processor.clone(), reply_sender))
+      .await
+      .expect("Command timeout!");
+    receive_and_verify_reply(2, &mut rx, ReplyCode::RequestedFileActionOkay, None).await;
+
+    assert!(!file_path.exists());
+    assert!(new_file_path.exists());
+    assert!(command_processor.session_properties.read().await.rename_from.is_none());
+  }
+
+  #[tokio::test]
+  async fn rnto_existing_target_test() {
+    setup_tracing();
+    let label = "test";
+    let root = temp_dir();
+    let file_name = format!("{}.test", Uuid::new_v4().as_hyphenated());
+    let file_path = root.join(&file_name);
+    let existing_name = format!("{}.test", Uuid::new_v4().as_hyphenated());
+    let existing_path = root.join(&existing_name);
+    std::fs::write(&file_path, "new").expect("Test file must exist");
+    std::fs::write(&existing_path, "existing").expect("Test file must exist");
+    let _cleanup = FileCleanup::new(&file_path);
+    let _cleanup_existing = FileCleanup::new(&existing_path);
+
+    let settings = CommandProcessorSettingsBuilder::default()
+      .label(label.to_string())
+      .view_root(root)
+      .username(Some("test_user".to_string()))
+      .change_path(Some(label.to_string()))
+      .build()
+      .unwrap();
+    let command_processor = Arc::new(setup_test_command_processor_custom(&settings));
+
+    let (code, _) =
+      execute(Command::new(Commands::Rnfr, &file_name), command_processor.clone()).await;
+    assert_eq!(ReplyCode::RequestedFileActionPendingFurtherInformation, code);
+    let (code, _) = execute(Command::new(Commands::Rnto, &existing_name), command_processor).await;
+    assert_eq!(ReplyCode::FileUnavailable, code);
+
+    assert_eq!("new", std::fs::read_to_string(&file_path).unwrap());
+    assert_eq!("existing", std::fs::read_to_string(&existing_path).unwrap());
+  }
+
+  #[tokio::test]
+  async fn rnto_absolute_test() {
+    setup_tracing();
+    let label = "test";
+    let root = temp_dir();
+    let file_name = format!("{}.test", Uuid::new_v4().as_hyphenated());
+    let file_path = root.join(&file_name);
+    let new_file_name = format!("{}.test", Uuid::new_v4().as_hyphenated());
+    let new_file_path = root.join(&new_file_name);
+    touch(&file_path).expect("Test file must exist");
+    let _cleanup = FileCleanup::new(&file_path);
+    let _cleanup_new = FileCleanup::new(&new_file_path);
+
+    let settings = CommandProcessorSettingsBuilder::default()
+      .label(label.to_string())
+      .view_root(root)
+      .username(Some("test_user".to_string()))
+      .build()
+      .unwrap();
+    let command_processor = Arc::new(setup_test_command_processor_custom(&settings));
+    command_processor
+      .session_properties
+      .write()
+      .await
+      .rename_from
+      .replace(format!("/{label}/{file_name}"));
+
+    let (tx, mut rx) = mpsc::channel(1024);
+    let reply_sender = TestReplySender::new(tx);
+    let command = Command::new(Commands::Rnto, format!("/{label}/{new_file_name}"));
+    timeout(Duration::from_secs(3), command.execute(command_processor, Arc::new(reply_sender)))
+      .await
+      .expect("Command timeout!");
+
+    receive_and_verify_reply(2, &mut rx, ReplyCode::RequestedFileActionOkay, None).await;
+    assert!(!file_path.exists());
+    assert!(new_file_path.exists());
+  }
+
+  #[tokio::test]
+  async fn rnfr_cancelled_test() {
+    setup_tracing();
+    let label = "test";
+    let root = temp_dir();
+    let file_name = format!("{}.test", Uuid::new_v4().as_hyphenated());
+    let file_path = root.join(&file_name);
+    touch(&file_path).expect("Test file must exist");
+    let _cleanup = FileCleanup::new(&file_path);
+
+    let settings = CommandProcessorSettingsBuilder::default()
+      .label(label.to_string())
+      .view_root(root)
+      .username(Some("test_user".to_string()))
+      .build()
+      .unwrap();
+    let command_processor = Arc::new(setup_test_command_processor_custom(&settings));
+
+    let (tx, mut rx) = mpsc::channel(1024);
+    let reply_sender = Arc::new(TestReplySender::new(tx));
+    for (message, expected) in [
+      (
+        format!("RNFR /{label}/{file_name}"),
+        ReplyCode::RequestedFileActionPendingFurtherInformation,
+      ),
+      (String::from("NOOP"), ReplyCode::CommandOkay),
+      (format!("RNTO /{label}/new.test"), ReplyCode::BadSequenceOfCommands),
+    ] {
+      timeout(
+        Duration::from_secs(3),
+        command_processor.clone().evaluate(message, reply_sender.clone()),
+      )
+      .await
+      .expect("Command timeout!");
+      receive_and_verify_reply(2, &mut rx, expected, None).await;
+    }
+    assert!(file_path.exists());
+  }
+
+  #[tokio::test]
+  async fn rnto_without_rnfr_test() {
+    setup_tracing();
+    let command = Command::new(Commands::Rnto, "new.test");
+
+    let settings = CommandProcessorSettingsBuilder::default()
+      .view_root(temp_dir())
+      .username(Some("test_user".to_string()))
+      .build()
+      .unwrap();
+    let command_processor = setup_test_command_processor_custom(&settings);
+
+    let (tx, mut rx) = mpsc::channel(1024);
+    let reply_sender = TestReplySender::new(tx);
+    timeout(
+      Duration::from_secs(3),
+      command.execute(Arc::new(command_processor), Arc::new(reply_sender)),
+    )
+    .await
+    .expect("Command timeout!");
+
+    receive_and_verify_reply(2, &mut rx, ReplyCode::BadSequenceOfCommands, None).await;
+  }
+
+  #[tokio::test]
+  async fn rnto_no_permission_test() {
+    setup_tracing();
+    let label = "test";
+    let root = temp_dir();
+    let file_name = format!("{}.test", Uuid::new_v4().as_hyphenated());
+    let file_path = root.join(&file_name);
+    touch(&file_path).expect("Test file must exist");
+    let _cleanup = FileCleanup::new(&file_path);
+
+    let settings = CommandProcessorSettingsBuilder::default()
+      .label(label.to_string())
+      .view_root(root)
+      .permissions(Default::default())
+      .username(Some("test_user".to_string()))
+      .build()
+      .unwrap();
+    let command_processor = Arc::new(setup_test_command_processor_custom(&settings));
+    command_processor
+      .session_properties
+      .write()
+      .await
+      .rename_from
+      .replace(format!("/{label}/{file_name}"));
+
+    let (tx, mut rx) = mpsc::channel(1024);
+    let reply_sender = TestReplySender::new(tx);
+    let command = Command::new(Commands::Rnto, format!("/{label}/new.test"));
+    timeout(Duration::from_secs(3), command.execute(command_processor, Arc::new(reply_sender)))
+      .await
+      .expect("Command timeout!");
+
+    receive_and_verify_reply(2, &mut rx, ReplyCode::FileUnavailable, None).await;
+    assert!(file_path.exists());
+  }
+
+  #[tokio::test]
+  async fn rnto_not_logged_in_test() {
+    setup_tracing();
+    let command = Command::new(Commands::Rnto, "new.test");
+
+    let settings = CommandProcessorSettingsBuilder::default().build().unwrap();
+    let command_processor = setup_test_command_processor_custom(&settings);
+
+    let (tx, mut rx) = mpsc::channel(1024);
+    let reply_sender = TestReplySender::new(tx);
+    timeout(
+      Duration::from_secs(3),
+      command.execute(Arc::new(command_processor), Arc::new(reply_sender)),
+    )
+    .await
+    .expect("Command timeout!");
+
+    receive_and_verify_reply(2, &mut rx, ReplyCode::NotLoggedIn, None).await;
+  }
+}
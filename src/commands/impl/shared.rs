@@ -1,19 +1,32 @@
-use chrono::{DateTime, Local};
+use chrono::{DateTime, Utc};
+use std::io::{ErrorKind, SeekFrom};
+use std::net::SocketAddr;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::time::Duration;
 use tokio::fs::File;
 use tokio::io;
-use tokio::io::{AsyncBufRead, AsyncWrite, AsyncWriteExt};
+use tokio::io::{
+  AsyncBufRead, AsyncBufReadExt, AsyncSeekExt, AsyncWrite, AsyncWriteExt, BufReader,
+};
+use tokio::select;
 use tokio::time::timeout;
 use tokio_util::sync::CancellationToken;
 use tracing::{debug, error, info, trace, warn};
 
+use crate::commands::command::Command;
 use crate::commands::reply::Reply;
 use crate::commands::reply_code::ReplyCode;
-use crate::data_channels::data_channel_wrapper::{DataChannel, DataChannelWrapper};
-use crate::io::entry_data::EntryData;
+use crate::data_channels::data_channel_wrapper::DataChannel;
+use crate::handlers::reply_sender::ReplySend;
+use crate::io::ascii::{Conversion, create_transfer_reader};
+use crate::io::entry_data::{EntryData, EntryType};
 use crate::io::error::IoError;
+use crate::io::open_options_flags::OpenOptionsWrapper;
 use crate::io::timeval::{format_timeval, parse_timeval};
+use crate::session::command_processor::CommandProcessor;
+use crate::session::data_type::DataType;
+use crate::session::session_properties::SessionProperties;
 
 #[cfg(not(test))]
 pub const ACQUIRE_TIMEOUT: u64 = 15;
@@ -22,9 +35,27 @@ pub const ACQUIRE_TIMEOUT: u64 = 3;
 
 pub const TRANSFER_BUFFER_SIZE: usize = 131072; // 2^17
 
+/// Waits for the data channel opened before, e.g.: by PASV.
+///
+/// If the security policy of the `command_processor` requires protected data and the data channel
+/// of the session isn't protected, the data channel is closed and refused.
 pub(crate) async fn acquire_data_channel(
-  data_wrapper: Arc<dyn DataChannelWrapper>,
+  command_processor: &CommandProcessor,
+  session_properties: &SessionProperties,
 ) -> Result<(DataChannel, CancellationToken), Reply> {
+  let data_wrapper = &command_processor.data_wrapper;
+  if !command_processor
+    .security_policy
+    .is_data_allowed(session_properties.connection_security, session_properties.prot_mode)
+  {
+    info!("Refusing unprotected data channel!");
+    data_wrapper.close_data_stream().await;
+    return Err(Reply::new(
+      ReplyCode::DataConnectionCannotBeOpenedWithThisProtSetting,
+      "Policy requires protected data channels, use PROT P!",
+    ));
+  }
+
   debug!("Acquiring data stream!");
   let error_reply =
     Reply::new(ReplyCode::BadSequenceOfCommands, "Data channel must be open first!");
@@ -43,12 +74,35 @@ pub(crate) async fn acquire_data_channel(
   }
 }
 
-pub(crate) fn parse_change_time(input: &str) -> Result<(DateTime<Local>, &str), Reply> {
+/// Opens a data channel in active mode, used by PORT and EPRT.
+///
+/// The connection to `addr` is created in the background, so the reply only states whether the
+/// address was accepted. After EPSV ALL, the active mode is refused.
+pub(crate) async fn connect_data_channel(
+  addr: SocketAddr,
+  command_processor: Arc<CommandProcessor>,
+) -> Reply {
+  let session_properties = command_processor.session_properties.read().await;
+  if session_properties.epsv_all {
+    return Reply::new(ReplyCode::BadSequenceOfCommands, "Only EPSV is allowed after EPSV ALL!");
+  }
+  let prot_mode = session_properties.prot_mode;
+  drop(session_properties);
+  match command_processor.data_wrapper.connect_data_stream(prot_mode, addr).await {
+    Ok(()) => Reply::new(ReplyCode::CommandOkay, "Active mode connection accepted."),
+    Err(e) => {
+      warn!("Failed to open active data channel: {e}");
+      Reply::new(ReplyCode::CommandNotImplementedForThatParameter, "Address not accepted!")
+    }
+  }
+}
+
+pub(crate) fn parse_change_time(input: &str) -> Result<(DateTime<Utc>, &str), Reply> {
   match input.split_once(' ') {
     Some((timeval, path)) => {
       let timeval = match parse_timeval(timeval) {
-        Ok(Some(t)) => t,
-        Err(_) | Ok(None) => {
+        Ok(t) => t,
+        Err(_) => {
           let reply = Reply::new(
             ReplyCode::SyntaxErrorInParametersOrArguments,
             "Failed to parse modification time.".to_string(),
@@ -90,6 +144,13 @@ pub(crate) fn get_listing_or_error_reply(
   listing.map_err(map_error_to_reply)
 }
 
+pub(crate) fn get_change_mode_reply(result: Result<(), IoError>) -> Reply {
+  match result {
+    Ok(_) => Reply::new(ReplyCode::CommandOkay, "Mode changed"),
+    Err(e) => map_error_to_reply(e),
+  }
+}
+
 fn map_error_to_reply(error: IoError) -> Reply {
   match error {
     IoError::UserError => Reply::new(ReplyCode::NotLoggedIn, IoError::UserError.to_string()),
@@ -136,9 +197,32 @@ pub(crate) fn get_delete_reply(dele_result: Result<(), IoError>, directory: bool
   }
 }
 
+pub(crate) fn get_entry_or_error_reply(
+  entry: Result<EntryData, IoError>,
+) -> Result<EntryData, Reply> {
+  entry.map_err(map_error_to_reply)
+}
+
+/// Maps the result of a metadata lookup, only plain files are accepted.
+pub(crate) fn get_file_entry_or_error_reply(
+  entry: Result<EntryData, IoError>,
+) -> Result<EntryData, Reply> {
+  match get_entry_or_error_reply(entry)? {
+    entry if entry.entry_type() == EntryType::File => Ok(entry),
+    _ => Err(Reply::new(ReplyCode::FileUnavailable, "Not a plain file!")),
+  }
+}
+
+pub(crate) fn get_rename_reply(rename_result: Result<(), IoError>) -> Reply {
+  match rename_result {
+    Ok(_) => Reply::new(ReplyCode::RequestedFileActionOkay, "Rename successful"),
+    Err(e) => map_error_to_reply(e),
+  }
+}
+
 pub(crate) fn get_modify_time_reply(
   modify_result: Result<(), IoError>,
-  timeval: &DateTime<Local>,
+  timeval: &DateTime<Utc>,
   path: &str,
 ) -> Reply {
   match modify_result {
@@ -149,13 +233,154 @@ pub(crate) fn get_modify_time_reply(
   }
 }
 
-pub(crate) async fn copy_data<F, T>(from: &mut F, to: &mut T) -> Result<(), io::Error>
+/// Receives a file from the client and stores it.
+///
+/// The file at the path in `command` is opened with `options` and everything received over the
+/// data channel is written into it. Used by both STOR and APPE, which only differ in how the file
+/// is opened.
+///
+/// If a restart offset was set by REST, the file is not truncated and writing starts at the
+/// offset. The offset is reset once the file is opened, the same as in RETR. Uploads can only be
+/// restarted in binary mode, in ASCII mode the offset refers to the converted data, and finding
+/// its position would need reading the file, which write-only users may not do.
+pub(crate) async fn store_file(
+  command: &Command,
+  command_processor: Arc<CommandProcessor>,
+  reply_sender: Arc<dyn ReplySend>,
+  mut options: OpenOptionsWrapper,
+) {
+  if command.argument.is_empty() {
+    reply_sender
+      .send_control_message(Reply::new(
+        ReplyCode::SyntaxErrorInParametersOrArguments,
+        "No file specified!",
+      ))
+      .await;
+    return;
+  }
+
+  let session_properties = command_processor.session_properties.read().await;
+
+  if !session_properties.is_logged_in() {
+    reply_sender
+      .send_control_message(Reply::new(ReplyCode::NotLoggedIn, "User not logged in!"))
+      .await;
+    return;
+  }
+
+  let data_type = session_properties.data_type;
+
+  if matches!(data_type, DataType::Ascii { .. })
+    && !options.append
+    && session_properties.offset.swap(0, Ordering::SeqCst) > 0
+  {
+    reply_sender
+      .send_control_message(Reply::new(
+        ReplyCode::CommandNotImplementedForThatParameter,
+        "Restarting uploads is not supported in ASCII mode, use TYPE I!",
+      ))
+      .await;
+    return;
+  }
+
+  let data_channel_pair = acquire_data_channel(&command_processor, &session_properties).await;
+  let (mut data_channel, token) = match data_channel_pair {
+    Ok((dc, token)) => (dc, token),
+    Err(e) => {
+      return reply_sender.send_control_message(e).await;
+    }
+  };
+
+  // Restarted upload must keep the data that was already received
+  if session_properties.offset.load(Ordering::SeqCst) > 0 {
+    options.truncate = false;
+  }
+
+  info!(
+    "User '{}' opening file '{}'.",
+    session_properties.username.as_ref().unwrap(),
+    &command.argument
+  );
+  let file = session_properties.file_system_view_root.open_file(&command.argument, options).await;
+
+  let mut file = match get_open_file_result(file) {
+    Ok(f) => f,
+    Err(reply) => {
+      reply_sender.send_control_message(reply).await;
+      return;
+    }
+  };
+
+  let offset = session_properties.offset.swap(0, Ordering::SeqCst);
+  if offset > 0 && !options.append {
+    debug!("Setting cursor to offset: {}", offset);
+    if let Err(e) = file.seek(SeekFrom::Start(offset)).await {
+      warn!("Failed to seek file {} to offset {}. Error: {}", &command.argument, offset, e);
+      reply_sender.send_control_message(map_error_to_reply(IoError::map_io_error(e))).await;
+      return;
+    }
+  }
+
+  reply_sender
+    .send_control_message(Reply::new(ReplyCode::FileStatusOkay, "Starting file transfer!"))
+    .await;
+
+  debug!("Receiving file data, offset: {}!", offset);
+
+  let reader = create_transfer_reader(&mut data_channel, data_type, Conversion::ToLf);
+  let mut buf = BufReader::with_capacity(TRANSFER_BUFFER_SIZE, reader);
+  let transfer_guard =
+    command_processor.transfer_status.start(format!("{} {}", command.command, command.argument));
+  let transfer = copy_data(&mut buf, &mut file, transfer_guard.transferred());
+
+  let success = select! {
+    result = transfer => result,
+    _ = token.cancelled() => {
+      debug!("Received transfer abort");
+      Err(std::io::Error::new(ErrorKind::ConnectionAborted, "Connection aborted!"))
+    }
+  };
+  // Releases the data channel borrowed by the reader
+  drop(buf);
+  if let Err(e) = file.sync_data().await {
+    warn!("Failed to sync file data! {e}");
+  };
+
+  reply_sender.send_control_message(get_transfer_reply(&success)).await;
+
+  if success.is_ok()
+    && let Err(e) = data_channel.shutdown().await
+  {
+    warn!("Failed to shutdown data channel after writing! {e}");
+  }
+}
+
+/// Copies all data from `from` to `to` and flushes `to`.
+///
+/// The number of bytes copied so far is added to `transferred`, so the progress can be reported.
+pub(crate) async fn copy_data<F, T>(
+  from: &mut F,
+  to: &mut T,
+  transferred: &AtomicU64,
+) -> Result<(), io::Error>
 where
   F: AsyncBufRead + Unpin,
   T: AsyncWrite + Unpin,
 {
-  match io::copy_buf(from, to).await {
-    Ok(_) => {
+  let result = async {
+    loop {
+      let buffer = from.fill_buf().await?;
+      if buffer.is_empty() {
+        return Ok(());
+      }
+      let len = buffer.len();
+      to.write_all(buffer).await?;
+      from.consume(len);
+      transferred.fetch_add(len as u64, Ordering::Relaxed);
+    }
+  };
+  match result.await {
+    Ok(()) => {
       debug!("Flushing data to target");
       if let Err(e) = to.flush().await {
         warn!("Failed to flush data to target! {e}");
@@ -0,0 +1,143 @@
+use std::sync::Arc;
+
+use crate::commands::command::Command;
+use crate::commands::r#impl::shared::get_change_mode_reply;
+use crate::commands::r#impl::site::parse_mode;
+use crate::commands::reply::Reply;
+use crate::commands::reply_code::ReplyCode;
+use crate::handlers::reply_sender::ReplySend;
+use crate::session::command_processor::CommandProcessor;
+
+/// Changes the mode of a file or directory, e.g.: 'SITE CHMOD 644 file.txt'.
+///
+/// The mode is octal and only the permission bits may be set. The user needs the change mode
+/// permission, and only objects in file system views can be changed.
+#[tracing::instrument(skip(command_processor, reply_sender))]
+pub(crate) async fn chmod(
+  command: &Command,
+  command_processor: Arc<CommandProcessor>,
+  reply_sender: Arc<dyn ReplySend>,
+) {
+  let parsed = command
+    .argument
+    .split_once(' ')
+    .and_then(|(mode, path)| parse_mode(mode).zip(Some(path.trim())))
+    .filter(|(_, path)| !path.is_empty());
+  let Some((mode, path)) = parsed else {
+    let reply =
+      Reply::new(ReplyCode::SyntaxErrorInParametersOrArguments, "Syntax: SITE CHMOD <mode> <path>");
+    return reply_sender.send_control_message(reply).await;
+  };
+
+  let session_properties = command_processor.session_properties.read().await;
+  let result = session_properties.file_system_view_root.change_mode(path, mode).await;
+  reply_sender.send_control_message(get_change_mode_reply(result)).await;
+}
+
+#[cfg(test)]
+mod tests {
+  use std::collections::HashSet;
+  use std::env::temp_dir;
+  use std::sync::Arc;
+
+  use uuid::Uuid;
+
+  use crate::auth::user_permission::UserPermission;
+  use crate::commands::command::Command;
+  use crate::commands::commands::Commands;
+  use crate::commands::reply_code::ReplyCode;
+  use crate::utils::test_utils::*;
+
+  fn settings(permissions: HashSet<UserPermission>) -> CommandProcessorSettings {
+    CommandProcessorSettingsBuilder::default()
+      .view_root(temp_dir())
+      .permissions(permissions)
+      .username(Some("testuser".to_string()))
+      .build()
+      .unwrap()
+  }
+
+  #[tokio::test]
+  async fn chmod_test() {
+    setup_tracing();
+    let file_name = format!("{}.test", Uuid::new_v4().as_hyphenated());
+    let file_path = temp_dir().join(&file_name);
+    touch(&file_path).expect("Test file must exist");
+    let _cleanup = FileCleanup::new(&file_path);
+    let settings = settings(HashSet::from([UserPermission::ChangeMode]));
+
+    let code = execute(
+      Command::new(Commands::Site, format!("CHMOD 400 /test/{file_name}")),
+      Arc::new(setup_test_command_processor_custom(&settings)),
+    )
+    .await
+    .0;
+    assert_eq!(ReplyCode::CommandOkay, code);
+    assert!(file_path.metadata().unwrap().permissions().readonly());
+    #[cfg(unix)]
+    {
+      use std::os::unix::fs::PermissionsExt;
+      assert_eq!(0o400, file_path.metadata().unwrap().permissions().mode() & 0o777);
+    }
+
+    let code = execute(
+      Command::new(Commands::Site, format!("CHMOD 644 test/{file_name}")),
+      Arc::new(setup_test_command_processor_custom(&settings)),
+    )
+    .await
+    .0;
+    assert_eq!(ReplyCode::CommandOkay, code);
+    assert!(!file_path.metadata().unwrap().permissions().readonly());
+  }
+
+  #[tokio::test]
+  async fn chmod_no_permission_test() {
+    setup_tracing();
+    let file_name = format!("{}.test", Uuid::new_v4().as_hyphenated());
+    let file_path = temp_dir().join(&file_name);
+    touch(&file_path).expect("Test file must exist");
+    let _cleanup = FileCleanup::new(&file_path);
+    let settings = settings(HashSet::from([UserPermission::Write]));
+
+    let code = execute(
+      Command::new(Commands::Site, format!("CHMOD 400 test/{file_name}")),
+      Arc::new(setup_test_command_processor_custom(&settings)),
+    )
+    .await
+    .0;
+    assert_eq!(ReplyCode::FileUnavailable, code);
+    assert!(!file_path.metadata().unwrap().permissions().readonly());
+  }
+
+  #[tokio::test]
+  async fn chmod_invalid_test() {
+    setup_tracing();
+    let settings = settings(HashSet::from([UserPermission::ChangeMode]));
+    for argument in ["CHMOD", "CHMOD 644", "CHMOD 999 file", "CHMOD 4755 file", "CHMOD rw file"] {
+      let code = execute(
+        Command::new(Commands::Site, argument),
+        Arc::new(setup_test_command_processor_custom(&settings)),
+      )
+      .await
+      .0;
+      assert_eq!(ReplyCode::SyntaxErrorInParametersOrArguments, code, "{argument}");
+    }
+  }
+
+  #[tokio::test]
+  async fn chmod_outside_view_test() {
+    setup_tracing();
+    let (settings, _) = setup_test_command_processor();
+    for argument in
+      ["CHMOD 755 /test", "CHMOD 755 ../", "CHMOD 755 /", "CHMOD 755 /test/NONEXISTENT"]
+    {
+      let code = execute(
+        Command::new(Commands::Site, argument),
+        Arc::new(setup_test_command_processor_custom(&settings)),
+      )
+      .await
+      .0;
+      assert_eq!(ReplyCode::FileUnavailable, code, "{argument}");
+    }
+  }
+}
@@ -0,0 +1,120 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+
+use crate::commands::command::Command;
+use crate::commands::command_registry::CommandHandler;
+use crate::commands::reply::Reply;
+use crate::commands::reply_code::ReplyCode;
+use crate::handlers::reply_sender::ReplySend;
+use crate::session::command_processor::CommandProcessor;
+
+/// Sets the idle timeout of this session in seconds, e.g.: 'SITE IDLE 1800'. Without an argument,
+/// the current timeout is sent.
+#[derive(Debug)]
+pub(crate) struct SiteIdle {
+  max: Option<Duration>,
+}
+
+impl SiteIdle {
+  /// Constructs the handler, allowing timeouts up to `max`. If `max` is not set, any timeout is
+  /// allowed.
+  pub(crate) fn new(max: Option<Duration>) -> Self {
+    SiteIdle { max }
+  }
+}
+
+#[async_trait]
+impl CommandHandler for SiteIdle {
+  #[tracing::instrument(skip(self, command_processor, reply_sender))]
+  async fn handle(
+    &self,
+    command: &Command,
+    command_processor: Arc<CommandProcessor>,
+    reply_sender: Arc<dyn ReplySend>,
+  ) {
+    let mut session_properties = command_processor.session_properties.write().await;
+
+    if command.argument.is_empty() {
+      let message = match session_properties.idle_timeout {
+        Some(timeout) => format!("Idle timeout is {} seconds", timeout.as_secs()),
+        None => "Idle timeout is disabled".to_string(),
+      };
+      return reply_sender.send_control_message(Reply::new(ReplyCode::CommandOkay, message)).await;
+    }
+
+    let timeout = command.argument.parse::<u64>().ok().map(Duration::from_secs);
+    let reply = match (timeout, self.max) {
+      (Some(timeout), max) if !timeout.is_zero() && max.is_none_or(|max| timeout <= max) => {
+        session_properties.idle_timeout.replace(timeout);
+        Reply::new(
+          ReplyCode::CommandOkay,
+          format!("Idle timeout set to {} seconds", timeout.as_secs()),
+        )
+      }
+      (_, Some(max)) => Reply::new(
+        ReplyCode::SyntaxErrorInParametersOrArguments,
+        format!("Idle timeout must be between 1 and {} seconds!", max.as_secs()),
+      ),
+      (_, None) => Reply::new(
+        ReplyCode::SyntaxErrorInParametersOrArguments,
+        "Idle timeout must be a positive number of seconds!",
+      ),
+    };
+    reply_sender.send_control_message(reply).await;
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::sync::Arc;
+  use std::time::Duration;
+
+  use crate::commands::command::Command;
+  use crate::commands::r#impl::site::idle::SiteIdle;
+  use crate::commands::reply_code::ReplyCode;
+  use crate::utils::test_utils::*;
+
+  #[tokio::test]
+  async fn idle_test() {
+    setup_tracing();
+    let (_, command_processor) = setup_test_command_processor();
+    let command_processor = Arc::new(command_processor);
+    let handler = SiteIdle::new(Some(Duration::from_secs(3600)));
+
+    let (_, reply) = handle(&handler, Command::other("IDLE", ""), command_processor.clone()).await;
+    assert!(reply.contains("disabled"), "{reply}");
+
+    let (code, _) =
+      handle(&handler, Command::other("IDLE", "1800"), command_processor.clone()).await;
+    assert_eq!(ReplyCode::CommandOkay, code);
+    assert_eq!(
+      Some(Duration::from_secs(1800)),
+      command_processor.session_properties.read().await.idle_timeout
+    );
+    let (_, reply) = handle(&handler, Command::other("IDLE", ""), command_processor.clone()).await;
+    assert!(reply.contains("1800 seconds"), "{reply}");
+  }
+
+  #[tokio::test]
+  async fn idle_invalid_test() {
+    setup_tracing();
+    let (_, command_processor) = setup_test_command_processor();
+    let command_processor = Arc::new(command_processor);
+    let handler = SiteIdle::new(Some(Duration::from_secs(3600)));
+
+    for argument in ["0", "3601", "-5", "invalid"] {
+      let (code, reply) =
+        handle(&handler, Command::other("IDLE", argument), command_processor.clone()).await;
+      assert_eq!(ReplyCode::SyntaxErrorInParametersOrArguments, code, "{argument}");
+      assert!(reply.contains("between 1 and 3600"), "{reply}");
+    }
+    assert_eq!(None, command_processor.session_properties.read().await.idle_timeout);
+
+    let handler = SiteIdle::new(None);
+    let (code, _) =
+      handle(&handler, Command::other("IDLE", "86400"), command_processor.clone()).await;
+    assert_eq!(ReplyCode::CommandOkay, code);
+  }
+}
@@ -0,0 +1,33 @@
+//! Contains the implementations of the SITE subcommands, see [`SiteRegistry`].
+//!
+//! [`SiteRegistry`]: crate::commands::site_registry::SiteRegistry
+
+use std::sync::Arc;
+
+use crate::commands::command::Command;
+use crate::global_context::site_registry;
+use crate::handlers::reply_sender::ReplySend;
+use crate::session::command_processor::CommandProcessor;
+
+pub(crate) mod chmod;
+pub(crate) mod idle;
+pub(crate) mod umask;
+pub(crate) mod who;
+
+/// Executes a site specific subcommand, as specified by
+/// [RFC959](https://datatracker.ietf.org/doc/html/rfc959#section-4.1.3).
+///
+/// The subcommand is looked up in the SITE registry, see [`site_registry`].
+#[tracing::instrument(skip(command_processor, reply_sender))]
+pub(crate) async fn site(
+  command: &Command,
+  command_processor: Arc<CommandProcessor>,
+  reply_sender: Arc<dyn ReplySend>,
+) {
+  site_registry().execute(command, command_processor, reply_sender).await
+}
+
+/// Parses an octal mode or mask, e.g.: '644'. Only the permission bits may be set.
+pub(crate) fn parse_mode(input: &str) -> Option<u32> {
+  u32::from_str_radix(input, 8).ok().filter(|mode| *mode <= 0o777)
+}
@@ -0,0 +1,103 @@
+use std::sync::Arc;
+
+use crate::commands::command::Command;
+use crate::commands::r#impl::site::parse_mode;
+use crate::commands::reply::Reply;
+use crate::commands::reply_code::ReplyCode;
+use crate::handlers::reply_sender::ReplySend;
+use crate::session::command_processor::CommandProcessor;
+
+/// Sets the mask applied to the mode of files and directories created in this session, e.g.:
+/// 'SITE UMASK 022'. Without an argument, the current mask is sent.
+#[tracing::instrument(skip(command_processor, reply_sender))]
+pub(crate) async fn umask(
+  command: &Command,
+  command_processor: Arc<CommandProcessor>,
+  reply_sender: Arc<dyn ReplySend>,
+) {
+  let mut session_properties = command_processor.session_properties.write().await;
+  let root = &mut session_properties.file_system_view_root;
+
+  let reply = if command.argument.is_empty() {
+    match root.umask {
+      Some(umask) => Reply::new(ReplyCode::CommandOkay, format!("Current UMASK is {umask:03o}")),
+      None => Reply::new(ReplyCode::CommandOkay, "UMASK is not set"),
+    }
+  } else {
+    match parse_mode(&command.argument) {
+      Some(umask) => {
+        root.umask.replace(umask);
+        Reply::new(ReplyCode::CommandOkay, format!("UMASK set to {umask:03o}"))
+      }
+      None => Reply::new(ReplyCode::SyntaxErrorInParametersOrArguments, "Invalid mask!"),
+    }
+  };
+  reply_sender.send_control_message(reply).await;
+}
+
+#[cfg(test)]
+mod tests {
+  use std::env::temp_dir;
+  use std::sync::Arc;
+
+  use uuid::Uuid;
+
+  use crate::commands::command::Command;
+  use crate::commands::commands::Commands;
+  use crate::io::open_options_flags::OpenOptionsWrapperBuilder;
+  use crate::utils::test_utils::*;
+
+  #[tokio::test]
+  async fn umask_test() {
+    setup_tracing();
+    let (_, command_processor) = setup_test_command_processor();
+    let command_processor = Arc::new(command_processor);
+
+    let reply = execute(Command::new(Commands::Site, "UMASK"), command_processor.clone()).await.1;
+    assert!(reply.contains("not set"), "{reply}");
+    let reply =
+      execute(Command::new(Commands::Site, "UMASK 027"), command_processor.clone()).await.1;
+    assert!(reply.starts_with("200 "), "{reply}");
+    assert_eq!(
+      Some(0o027),
+      command_processor.session_properties.read().await.file_system_view_root.umask
+    );
+    let reply = execute(Command::new(Commands::Site, "UMASK"), command_processor.clone()).await.1;
+    assert!(reply.contains("027"), "{reply}");
+
+    let reply =
+      execute(Command::new(Commands::Site, "UMASK 1000"), command_processor.clone()).await.1;
+    assert!(reply.starts_with("501 "), "{reply}");
+  }
+
+  #[cfg(unix)]
+  #[tokio::test]
+  async fn umask_applied_test() {
+    use std::os::unix::fs::PermissionsExt;
+
+    setup_tracing();
+    let settings = CommandProcessorSettingsBuilder::default()
+      .view_root(temp_dir())
+      .username(Some("testuser".to_string()))
+      .build()
+      .unwrap();
+    let command_processor = Arc::new(setup_test_command_processor_custom(&settings));
+    execute(Command::new(Commands::Site, "UMASK 077"), command_processor.clone()).await;
+
+    let file_name = format!("{}.test", Uuid::new_v4().as_hyphenated());
+    let file_path = temp_dir().join(&file_name);
+    let _file_cleanup = FileCleanup::new(&file_path);
+    let dir_name = Uuid::new_v4().as_hyphenated().to_string();
+    let dir_path = temp_dir().join(&dir_name);
+    let _dir_cleanup = DirCleanup::new(&dir_path);
+
+    let session_properties = command_processor.session_properties.read().await;
+    let root = &session_properties.file_system_view_root;
+    let options = OpenOptionsWrapperBuilder::default().write(true).create(true).build().unwrap();
+    root.open_file(&format!("/test/{file_name}"), options).await.expect("File should be created");
+    root.create_directory(&format!("/test/{dir_name}")).expect("Directory should be created");
+
+    assert_eq!(0o600, file_path.metadata().unwrap().permissions().mode() & 0o777);
+    assert_eq!(0o700, dir_path.metadata().unwrap().permissions().mode() & 0o777);
+  }
+}
@@ -0,0 +1,111 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use async_trait::async_trait;
+use tracing::info;
+
+use crate::commands::command::Command;
+use crate::commands::command_registry::CommandHandler;
+use crate::commands::reply::Reply;
+use crate::commands::reply_code::ReplyCode;
+use crate::global_context::CONNECTION_REGISTRY;
+use crate::handlers::reply_sender::ReplySend;
+use crate::session::command_processor::CommandProcessor;
+use crate::session::connection_registry::SessionInfo;
+
+/// Lists the active sessions with their users and addresses. Only administrators may do this.
+#[derive(Debug)]
+pub(crate) struct SiteWho {
+  admins: HashSet<String>,
+}
+
+impl SiteWho {
+  /// Constructs the handler, allowing only the users in `admins` to list the sessions.
+  pub(crate) fn new(admins: HashSet<String>) -> Self {
+    SiteWho { admins }
+  }
+}
+
+#[async_trait]
+impl CommandHandler for SiteWho {
+  #[tracing::instrument(skip(self, command_processor, reply_sender))]
+  async fn handle(
+    &self,
+    command: &Command,
+    command_processor: Arc<CommandProcessor>,
+    reply_sender: Arc<dyn ReplySend>,
+  ) {
+    let session_properties = command_processor.session_properties.read().await;
+    if !session_properties.username.as_ref().is_some_and(|u| self.admins.contains(u)) {
+      info!("User {:?} is not allowed to list sessions.", session_properties.username);
+      let reply = Reply::new(ReplyCode::FileUnavailable, "Permission denied!");
+      return reply_sender.send_control_message(reply).await;
+    }
+
+    let sessions = match session_properties.registration.as_ref() {
+      Some(registration) => registration.registry().sessions(),
+      None => CONNECTION_REGISTRY.sessions(),
+    };
+    let mut lines = vec![format!("Active sessions: {}", sessions.len())];
+    lines.extend(sessions.iter().map(session_line));
+    lines.push("End of list".to_string());
+    reply_sender.send_control_message(Reply::new_multiline(ReplyCode::SystemStatus, lines)).await;
+  }
+}
+
+fn session_line(session: &SessionInfo) -> String {
+  let connected_for = SystemTime::now().duration_since(session.connected).unwrap_or_default();
+  format!(
+    " {} from {}, connected for {} seconds",
+    session.username.as_deref().unwrap_or("(not logged in)"),
+    session.ip,
+    connected_for.as_secs()
+  )
+}
+
+#[cfg(test)]
+mod tests {
+  use std::collections::HashSet;
+  use std::net::{IpAddr, Ipv4Addr};
+  use std::sync::Arc;
+
+  use crate::commands::command::Command;
+  use crate::commands::r#impl::site::who::SiteWho;
+  use crate::commands::reply_code::ReplyCode;
+  use crate::session::connection_registry::{ConnectionLimits, ConnectionRegistry};
+  use crate::utils::test_utils::*;
+
+  const IP: IpAddr = IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1));
+
+  #[tokio::test]
+  async fn who_test() {
+    setup_tracing();
+    let registry = Arc::new(ConnectionRegistry::new(ConnectionLimits::default()));
+    let mut registration = registry.register(IP).unwrap();
+    registration.login("testuser").unwrap();
+    let _other = registry.register(IP).unwrap();
+    let (_, command_processor) = setup_test_command_processor();
+    command_processor.session_properties.write().await.registration.replace(registration);
+    let handler = SiteWho::new(HashSet::from(["testuser".to_string()]));
+
+    let (code, reply) =
+      handle(&handler, Command::other("WHO", ""), Arc::new(command_processor)).await;
+    assert_eq!(ReplyCode::SystemStatus, code);
+    assert!(reply.contains("Active sessions: 2"), "{reply}");
+    assert!(reply.contains(" testuser from 192.0.2.1, connected for "), "{reply}");
+    assert!(reply.contains(" (not logged in) from 192.0.2.1"), "{reply}");
+  }
+
+  #[tokio::test]
+  async fn who_not_admin_test() {
+    setup_tracing();
+    let (_, command_processor) = setup_test_command_processor();
+    let handler = SiteWho::new(HashSet::from(["admin".to_string()]));
+
+    let (code, reply) =
+      handle(&handler, Command::other("WHO", ""), Arc::new(command_processor)).await;
+    assert_eq!(ReplyCode::FileUnavailable, code);
+    assert!(!reply.contains("Active sessions"), "{reply}");
+  }
+}
@@ -0,0 +1,173 @@
+use std::sync::Arc;
+
+use tracing::warn;
+
+use crate::commands::command::Command;
+use crate::commands::commands::Commands;
+use crate::commands::r#impl::shared::{get_file_entry_or_error_reply, get_open_file_result};
+use crate::commands::reply::Reply;
+use crate::commands::reply_code::ReplyCode;
+use crate::handlers::reply_sender::ReplySend;
+use crate::io::ascii::ascii_size;
+use crate::io::open_options_flags::OpenOptionsWrapperBuilder;
+use crate::session::command_processor::CommandProcessor;
+use crate::session::data_type::DataType;
+
+/// Replies with the size of a file as specified by
+/// [RFC3659](https://datatracker.ietf.org/doc/html/rfc3659#section-4).
+///
+/// The size is the number of bytes that would be transferred by RETR in the current TYPE, so in
+/// ASCII mode the file has to be read to account for the line ending conversion.
+#[tracing::instrument(skip(command_processor, reply_sender))]
+pub(crate) async fn size(
+  command: &Command,
+  command_processor: Arc<CommandProcessor>,
+  reply_sender: Arc<dyn ReplySend>,
+) {
+  debug_assert_eq!(command.command, Commands::Size);
+
+  let session_properties = command_processor.session_properties.read().await;
+
+  if !session_properties.is_logged_in() {
+    reply_sender
+      .send_control_message(Reply::new(ReplyCode::NotLoggedIn, "User not logged in!"))
+      .await;
+    return;
+  }
+
+  if command.argument.is_empty() {
+    reply_sender
+      .send_control_message(Reply::new(
+        ReplyCode::SyntaxErrorInParametersOrArguments,
+        "No file specified!",
+      ))
+      .await;
+    return;
+  }
+
+  let root = &session_properties.file_system_view_root;
+  let entry = match get_file_entry_or_error_reply(root.metadata(&command.argument)) {
+    Ok(entry) => entry,
+    Err(reply) => return reply_sender.send_control_message(reply).await,
+  };
+
+  let size = match session_properties.data_type {
+    DataType::Binary => entry.size(),
+    DataType::Ascii { .. } => {
+      let options = OpenOptionsWrapperBuilder::default().read(true).build().unwrap();
+      let file = match get_open_file_result(root.open_file(&command.argument, options).await) {
+        Ok(file) => file,
+        Err(reply) => return reply_sender.send_control_message(reply).await,
+      };
+      match ascii_size(file).await {
+        Ok(size) => size,
+        Err(e) => {
+          warn!("Failed to compute ASCII size! {e}");
+          reply_sender
+            .send_control_message(Reply::new(
+              ReplyCode::RequestedActionAborted,
+              "Requested action aborted: local error in processing.",
+            ))
+            .await;
+          return;
+        }
+      }
+    }
+  };
+
+  reply_sender.send_control_message(Reply::new(ReplyCode::FileStatus, size.to_string())).await;
+}
+
+#[cfg(test)]
+mod tests {
+  use std::env::temp_dir;
+  use std::path::Path;
+  use std::sync::Arc;
+
+  use uuid::Uuid;
+
+  use crate::commands::command::Command;
+  use crate::commands::commands::Commands;
+  use crate::commands::reply_code::ReplyCode;
+  use crate::session::command_processor::CommandProcessor;
+  use crate::session::data_type::DataType;
+  use crate::utils::test_utils::*;
+
+  fn setup(file_path: &Path, data_type: DataType) -> CommandProcessor {
+    std::fs::write(file_path, b"line 1\nline 2\r\nline 3\n").unwrap();
+
+    let settings = CommandProcessorSettingsBuilder::default()
+      .label("test".to_string())
+      .view_root(temp_dir())
+      .change_path(Some("test".to_string()))
+      .username(Some("testuser".to_string()))
+      .build()
+      .unwrap();
+    let command_processor = setup_test_command_processor_custom(&settings);
+    command_processor.session_properties.try_write().unwrap().data_type = data_type;
+    command_processor
+  }
+
+  #[tokio::test]
+  async fn size_binary_test() {
+    setup_tracing();
+    let file_name = format!("{}.test", Uuid::new_v4().as_hyphenated());
+    let file_path = temp_dir().join(&file_name);
+    let _cleanup = FileCleanup::new(&file_path);
+    let command_processor = setup(&file_path, DataType::Binary);
+
+    let (code, message) =
+      execute(Command::new(Commands::Size, &file_name), Arc::new(command_processor)).await;
+    assert_eq!(ReplyCode::FileStatus, code);
+    assert_eq!("213 22\r\n", message);
+  }
+
+  #[tokio::test]
+  async fn size_ascii_test() {
+    setup_tracing();
+    let file_name = format!("{}.test", Uuid::new_v4().as_hyphenated());
+    let file_path = temp_dir().join(&file_name);
+    let _cleanup = FileCleanup::new(&file_path);
+    let command_processor = setup(&file_path, DataType::default());
+
+    let (code, message) =
+      execute(Command::new(Commands::Size, &file_name), Arc::new(command_processor)).await;
+    assert_eq!(ReplyCode::FileStatus, code);
+    assert_eq!("213 24\r\n", message);
+  }
+
+  #[tokio::test]
+  async fn size_directory_test() {
+    setup_tracing();
+    let file_path = temp_dir().join(format!("{}.test", Uuid::new_v4().as_hyphenated()));
+    let _cleanup = FileCleanup::new(&file_path);
+    let command_processor = setup(&file_path, DataType::Binary);
+
+    let (code, _) =
+      execute(Command::new(Commands::Size, "/test"), Arc::new(command_processor)).await;
+    assert_eq!(ReplyCode::FileUnavailable, code);
+  }
+
+  #[tokio::test]
+  async fn size_nonexistent_test() {
+    setup_tracing();
+    let file_path = temp_dir().join(format!("{}.test", Uuid::new_v4().as_hyphenated()));
+    let _cleanup = FileCleanup::new(&file_path);
+    let command_processor = setup(&file_path, DataType::Binary);
+
+    let (code, _) =
+      execute(Command::new(Commands::Size, "NONEXISTENT"), Arc::new(command_processor)).await;
+    assert_eq!(ReplyCode::FileUnavailable, code);
+  }
+
+  #[tokio::test]
+  async fn size_not_logged_in_test() {
+    setup_tracing();
+    let settings = CommandProcessorSettingsBuilder::default().build().unwrap();
+    let command_processor = setup_test_command_processor_custom(&settings);
+
+    let (code, _) =
+      execute(Command::new(Commands::Size, "file"), Arc::new(command_processor)).await;
+    assert_eq!(ReplyCode::NotLoggedIn, code);
+  }
+}
@@ -0,0 +1,228 @@
+use std::sync::Arc;
+use std::sync::atomic::Ordering;
+
+use tracing::debug;
+
+use crate::commands::command::Command;
+use crate::commands::commands::Commands;
+use crate::commands::r#impl::shared::{get_entry_or_error_reply, get_listing_or_error_reply};
+use crate::commands::reply::Reply;
+use crate::commands::reply_code::ReplyCode;
+use crate::handlers::reply_sender::ReplySend;
+use crate::io::entry_data::EntryType;
+use crate::session::command_processor::CommandProcessor;
+use crate::session::data_type::{DataType, SubType};
+use crate::session::protection_mode::ProtMode;
+use crate::session::session_properties::SessionProperties;
+
+/// Sends status information over the control connection, as specified by
+/// [RFC959](https://datatracker.ietf.org/doc/html/rfc959#section-4.1.3).
+///
+/// Without an argument, the progress of the running transfer is sent, or the status of the
+/// session if no transfer is running. With a path, the path is listed the same way as in LIST.
+#[tracing::instrument(skip(command_processor, reply_sender))]
+pub(crate) async fn stat(
+  command: &Command,
+  command_processor: Arc<CommandProcessor>,
+  reply_sender: Arc<dyn ReplySend>,
+) {
+  debug_assert_eq!(Commands::Stat, command.command);
+
+  if !command.argument.is_empty() {
+    let session_properties = command_processor.session_properties.read().await;
+    let reply = path_status(&session_properties, &command.argument);
+    return reply_sender.send_control_message(reply).await;
+  }
+
+  // STAT is executed out of order during transfers, so the transfer is checked first
+  if let Some((description, transferred)) = command_processor.transfer_status.current() {
+    debug!("Reporting transfer progress.");
+    let message = format!("Transferring '{description}', {transferred} bytes transferred.");
+    return reply_sender.send_control_message(Reply::new(ReplyCode::FileStatus, message)).await;
+  }
+
+  let session_properties = command_processor.session_properties.read().await;
+  let reply = Reply::new_multiline(ReplyCode::SystemStatus, session_status(&session_properties));
+  reply_sender.send_control_message(reply).await;
+}
+
+fn session_status(session_properties: &SessionProperties) -> Vec<String> {
+  let mut lines = vec!["Server status:".to_string()];
+  match session_properties.username.as_ref() {
+    Some(username) => {
+      lines.push(format!(" Logged in as {username}"));
+      lines.push(format!(
+        " Current directory: {}",
+        session_properties.file_system_view_root.get_current_working_directory()
+      ));
+    }
+    None => lines.push(" Not logged in".to_string()),
+  }
+  lines.push(format!(" TYPE: {}", data_type_name(session_properties.data_type)));
+  lines.push(format!(" PROT: {}", prot_mode_name(session_properties.prot_mode)));
+  lines.push(format!(" REST offset: {}", session_properties.offset.load(Ordering::SeqCst)));
+  lines.push("End of status".to_string());
+  lines
+}
+
+fn path_status(session_properties: &SessionProperties, path: &str) -> Reply {
+  if !session_properties.is_logged_in() {
+    return Reply::new(ReplyCode::NotLoggedIn, "User not logged in!");
+  }
+
+  let root = &session_properties.file_system_view_root;
+  let mut entry = match get_entry_or_error_reply(root.metadata(path)) {
+    Ok(entry) => entry,
+    Err(reply) => return reply,
+  };
+
+  let mut lines = vec![format!("Status of {path}:")];
+  let code = if entry.entry_type() == EntryType::File {
+    entry.change_name(path);
+    lines.push(format!(" {}", entry.to_list_string().trim_end()));
+    ReplyCode::FileStatus
+  } else {
+    let listing = match get_listing_or_error_reply(root.list_dir(path)) {
+      Ok(listing) => listing,
+      Err(reply) => return reply,
+    };
+    lines.extend(
+      listing
+        .iter()
+        .filter(|e| e.entry_type() != EntryType::Cdir)
+        .map(|e| format!(" {}", e.to_list_string().trim_end())),
+    );
+    ReplyCode::DirectoryStatus
+  };
+  lines.push("End of status".to_string());
+  Reply::new_multiline(code, lines)
+}
+
+fn data_type_name(data_type: DataType) -> &'static str {
+  match data_type {
+    DataType::Ascii {
+      sub_type: SubType::NonPrint,
+    } => "ASCII Non-print",
+    DataType::Ascii {
+      sub_type: SubType::TelnetFormatEffectors,
+    } => "ASCII Telnet format effectors",
+    DataType::Ascii {
+      sub_type: SubType::CarriageControl,
+    } => "ASCII Carriage control",
+    DataType::Binary => "Binary",
+  }
+}
+
+fn prot_mode_name(prot_mode: ProtMode) -> &'static str {
+  match prot_mode {
+    ProtMode::Clear => "Clear",
+    ProtMode::Safe => "Safe",
+    ProtMode::Confidential => "Confidential",
+    ProtMode::Private => "Private",
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::sync::Arc;
+  use std::sync::atomic::Ordering;
+
+  use crate::commands::command::Command;
+  use crate::commands::commands::Commands;
+  use crate::commands::reply_code::ReplyCode;
+  use crate::utils::test_utils::*;
+
+  #[tokio::test]
+  async fn session_status_test() {
+    setup_tracing();
+    let (_, command_processor) = setup_test_command_processor();
+    command_processor.session_properties.read().await.offset.store(42, Ordering::SeqCst);
+    let command_processor = Arc::new(command_processor);
+
+    let (code, reply) = execute(Command::new(Commands::Stat, ""), command_processor.clone()).await;
+    assert_eq!(ReplyCode::SystemStatus, code);
+    assert!(reply.contains("Logged in as testuser"), "{reply}");
+    assert!(reply.contains("Current directory: /"), "{reply}");
+    assert!(reply.contains("TYPE: ASCII Non-print"), "{reply}");
+    assert!(reply.contains("PROT: Clear"), "{reply}");
+    assert!(reply.contains("REST offset: 42"), "{reply}");
+  }
+
+  #[tokio::test]
+  async fn not_logged_in_test() {
+    setup_tracing();
+    let (_, command_processor) = setup_test_command_processor();
+    command_processor.session_properties.write().await.username.take();
+    let command_processor = Arc::new(command_processor);
+
+    let (code, reply) = execute(Command::new(Commands::Stat, ""), command_processor.clone()).await;
+    assert_eq!(ReplyCode::SystemStatus, code);
+    assert!(reply.contains("Not logged in"), "{reply}");
+
+    let (code, _) =
+      execute(Command::new(Commands::Stat, "test_files"), command_processor.clone()).await;
+    assert_eq!(ReplyCode::NotLoggedIn, code);
+  }
+
+  #[tokio::test]
+  async fn transfer_status_test() {
+    setup_tracing();
+    let (_, command_processor) = setup_test_command_processor();
+    let command_processor = Arc::new(command_processor);
+
+    let guard = command_processor.transfer_status.start("RETR file.txt");
+    guard.transferred().fetch_add(1234, Ordering::Relaxed);
+    let (code, reply) = execute(Command::new(Commands::Stat, ""), command_processor.clone()).await;
+    assert_eq!(ReplyCode::FileStatus, code);
+    assert!(reply.contains("RETR file.txt"), "{reply}");
+    assert!(reply.contains("1234 bytes"), "{reply}");
+
+    drop(guard);
+    let (code, _) = execute(Command::new(Commands::Stat, ""), command_processor.clone()).await;
+    assert_eq!(ReplyCode::SystemStatus, code);
+  }
+
+  #[tokio::test]
+  async fn directory_status_test() {
+    setup_tracing();
+    let (settings, command_processor) = setup_test_command_processor();
+    let command_processor = Arc::new(command_processor);
+
+    let (code, reply) = execute(
+      Command::new(Commands::Stat, format!("{}/test_files", settings.label)),
+      command_processor.clone(),
+    )
+    .await;
+    assert_eq!(ReplyCode::DirectoryStatus, code);
+    assert!(reply.contains("1MiB.txt"), "{reply}");
+    assert!(reply.lines().skip(1).all(|l| l.starts_with(' ') || l.starts_with("212 ")), "{reply}");
+  }
+
+  #[tokio::test]
+  async fn file_status_test() {
+    setup_tracing();
+    let (settings, command_processor) = setup_test_command_processor();
+    let command_processor = Arc::new(command_processor);
+
+    let path = format!("{}/test_files/1MiB.txt", settings.label);
+    let (code, reply) =
+      execute(Command::new(Commands::Stat, &path), command_processor.clone()).await;
+    assert_eq!(ReplyCode::FileStatus, code);
+    assert!(reply.contains(" 1048576 "), "{reply}");
+    assert!(reply.contains(&path), "{reply}");
+  }
+
+  #[tokio::test]
+  async fn nonexistent_path_test() {
+    setup_tracing();
+    let (settings, command_processor) = setup_test_command_processor();
+    let command_processor = Arc::new(command_processor);
+
+    let (code, _) = execute(
+      Command::new(Commands::Stat, format!("{}/NONEXISTENT", settings.label)),
+      command_processor.clone(),
+    )
+    .await;
+    assert_eq!(ReplyCode::FileUnavailable, code);
+  }
+}
@@ -1,17 +1,8 @@
-use std::io::ErrorKind;
 use std::sync::Arc;
 
-use tokio::io::{AsyncWriteExt, BufReader};
-use tokio::select;
-use tracing::{debug, info, warn};
-
 use crate::commands::command::Command;
 use crate::commands::commands::Commands;
-use crate::commands::r#impl::shared::{
-  TRANSFER_BUFFER_SIZE, acquire_data_channel, copy_data, get_open_file_result, get_transfer_reply,
-};
-use crate::commands::reply::Reply;
-use crate::commands::reply_code::ReplyCode;
+use crate::commands::r#impl::shared::store_file;
 use crate::handlers::reply_sender::ReplySend;
 use crate::io::open_options_flags::OpenOptionsWrapperBuilder;
 use crate::session::command_processor::CommandProcessor;
@@ -20,88 +11,22 @@ use crate::session::command_processor::CommandProcessor;
 pub(crate) async fn stor(
   command: &Command,
   command_processor: Arc<CommandProcessor>,
-  reply_sender: Arc<impl ReplySend>,
+  reply_sender: Arc<dyn ReplySend>,
 ) {
   debug_assert_eq!(command.command, Commands::Stor);
 
-  if command.argument.is_empty() {
-    reply_sender
-      .send_control_message(Reply::new(
-        ReplyCode::SyntaxErrorInParametersOrArguments,
-        "No file specified!",
-      ))
-      .await;
-    return;
-  }
-
-  let session_properties = command_processor.session_properties.read().await;
-
-  if !session_properties.is_logged_in() {
-    reply_sender
-      .send_control_message(Reply::new(ReplyCode::NotLoggedIn, "User not logged in!"))
-      .await;
-    return;
-  }
-
-  let data_channel_pair = acquire_data_channel(command_processor.data_wrapper.clone()).await;
-  let (mut data_channel, token) = match data_channel_pair {
-    Ok((dc, token)) => (dc, token),
-    Err(e) => {
-      return reply_sender.send_control_message(e).await;
-    }
-  };
-
   let options =
     OpenOptionsWrapperBuilder::default().write(true).truncate(true).create(true).build().unwrap();
-  info!(
-    "User '{}' opening file '{}'.",
-    session_properties.username.as_ref().unwrap(),
-    &command.argument
-  );
-  let file = session_properties.file_system_view_root.open_file(&command.argument, options).await;
-
-  let mut file = match get_open_file_result(file) {
-    Ok(f) => f,
-    Err(reply) => {
-      reply_sender.send_control_message(reply).await;
-      return;
-    }
-  };
-
-  reply_sender
-    .send_control_message(Reply::new(ReplyCode::FileStatusOkay, "Starting file transfer!"))
-    .await;
-
-  debug!("Receiving file data!");
-
-  let mut buf = BufReader::with_capacity(TRANSFER_BUFFER_SIZE, &mut data_channel);
-  let transfer = copy_data(&mut buf, &mut file);
-
-  let success = select! {
-    result = transfer => result,
-    _ = token.cancelled() => {
-      debug!("Received transfer abort");
-      Err(std::io::Error::new(ErrorKind::ConnectionAborted, "Connection aborted!"))
-    }
-  };
-  if let Err(e) = file.sync_data().await {
-    warn!("Failed to sync file data! {e}");
-  };
-
-  reply_sender.send_control_message(get_transfer_reply(&success)).await;
-
-  if success.is_ok()
-    && let Err(e) = data_channel.shutdown().await
-  {
-    warn!("Failed to shutdown data channel after writing! {e}");
-  }
+  store_file(command, command_processor, reply_sender, options).await;
 }
 
 #[cfg(test)]
 mod tests {
+  use std::collections::HashSet;
   use std::env::temp_dir;
   use std::path::Path;
   use std::sync::Arc;
+  use std::sync::atomic::Ordering;
   use std::time::Duration;
 
   use blake3::Hasher;
@@ -115,6 +40,7 @@ mod tests {
   use tokio_util::sync::CancellationToken;
   use uuid::Uuid;
 
+  use crate::auth::user_permission::UserPermission;
   use crate::commands::command::Command;
   use crate::commands::commands::Commands;
   use crate::commands::r#impl::shared::ACQUIRE_TIMEOUT;
@@ -125,6 +51,7 @@ mod tests {
   use crate::listeners::quic_only_listener::QuicOnlyListener;
   use crate::listeners::quinn_listener::QuinnListener;
   use crate::session::command_processor::CommandProcessor;
+  use crate::session::data_type::DataType;
   use crate::session::protection_mode::ProtMode;
   use crate::session::session_properties::SessionProperties;
   use crate::utils::test_utils::*;
@@ -317,6 +244,8 @@ mod tests {
     mut client_dc: T,
   ) {
     let (tx, mut rx) = channel(1024);
+    // The files are compared byte by byte
+    command_processor.session_properties.write().await.data_type = DataType::Binary;
     let reply_sender = TestReplySender::new(tx);
 
     let command_fut = tokio::spawn(async move {
@@ -602,10 +531,151 @@ mod tests {
     common_quinn_quinn(Path::new(LOCAL_FILE), &remote_file).await;
   }
 
+  #[tokio::test]
+  async fn restart_test() {
+    setup_tracing();
+    const LOCAL_FILE: &str = "test_files/2KiB.txt";
+    const OFFSET: usize = 1000;
+    let local_content = std::fs::read(LOCAL_FILE).expect("Test file must exist!");
+    let remote_file = format!("{}.test", Uuid::new_v4().as_hyphenated());
+    let remote_file_path = temp_dir().join(&remote_file);
+    std::fs::write(&remote_file_path, &local_content[..OFFSET]).unwrap();
+    let _cleanup = FileCleanup::new(&remote_file_path);
+
+    let settings = CommandProcessorSettingsBuilder::default()
+      .label("test_files".to_string())
+      .change_path(Some("test_files".to_string()))
+      .username(Some("testuser".to_string()))
+      .view_root(temp_dir())
+      .build()
+      .expect("Settings should be valid");
+    let mut command_processor = setup_test_command_processor_custom(&settings);
+    command_processor.session_properties.read().await.offset.store(OFFSET as u64, Ordering::SeqCst);
+    command_processor.session_properties.write().await.data_type = DataType::Binary;
+    let mut client_dc = open_tcp_data_channel(&mut command_processor).await;
+    let command_processor = Arc::new(command_processor);
+
+    let (tx, mut rx) = channel(1024);
+    let reply_sender = TestReplySender::new(tx);
+    let command = Command::new(Commands::Stor, &remote_file);
+    let processor = command_processor.clone();
+    let command_fut = tokio::spawn(async move {
+      timeout(Duration::from_secs(10), command.execute(processor, Arc::new(reply_sender)))
+        .await
+        .expect("Command timeout!");
+    });
+
+    receive_and_verify_reply(2, &mut rx, ReplyCode::FileStatusOkay, None).await;
+    client_dc.write_all(&local_content[OFFSET..]).await.unwrap();
+    client_dc.shutdown().await.unwrap();
+    receive_and_verify_reply(2, &mut rx, ReplyCode::ClosingDataConnection, None).await;
+    command_fut.await.expect("Command should complete!");
+
+    assert_eq!(local_content, std::fs::read(&remote_file_path).unwrap());
+    assert_eq!(0, command_processor.session_properties.read().await.offset.load(Ordering::SeqCst));
+  }
+
+  #[tokio::test]
+  async fn write_only_restart_test() {
+    setup_tracing();
+    const LOCAL_FILE: &str = "test_files/2KiB.txt";
+    const OFFSET: usize = 1000;
+    let local_content = std::fs::read(LOCAL_FILE).expect("Test file must exist!");
+    let remote_file = format!("{}.test", Uuid::new_v4().as_hyphenated());
+    let remote_file_path = temp_dir().join(&remote_file);
+    std::fs::write(&remote_file_path, &local_content[..OFFSET]).unwrap();
+    let _cleanup = FileCleanup::new(&remote_file_path);
+
+    let settings = CommandProcessorSettingsBuilder::default()
+      .change_path(Some("test".to_string()))
+      .username(Some("testuser".to_string()))
+      .view_root(temp_dir())
+      .permissions(HashSet::from([UserPermission::Write]))
+      .build()
+      .expect("Settings should be valid");
+    let mut command_processor = setup_test_command_processor_custom(&settings);
+    command_processor.session_properties.read().await.offset.store(OFFSET as u64, Ordering::SeqCst);
+    command_processor.session_properties.write().await.data_type = DataType::Binary;
+    let mut client_dc = open_tcp_data_channel(&mut command_processor).await;
+
+    let (tx, mut rx) = channel(1024);
+    let reply_sender = TestReplySender::new(tx);
+    let command = Command::new(Commands::Stor, &remote_file);
+    let command_fut = tokio::spawn(async move {
+      timeout(
+        Duration::from_secs(10),
+        command.execute(Arc::new(command_processor), Arc::new(reply_sender)),
+      )
+      .await
+      .expect("Command timeout!");
+    });
+
+    receive_and_verify_reply(2, &mut rx, ReplyCode::FileStatusOkay, None).await;
+    client_dc.write_all(&local_content[OFFSET..]).await.unwrap();
+    client_dc.shutdown().await.unwrap();
+    receive_and_verify_reply(2, &mut rx, ReplyCode::ClosingDataConnection, None).await;
+    command_fut.await.expect("Command should complete!");
+
+    assert_eq!(local_content, std::fs::read(&remote_file_path).unwrap());
+  }
+
+  #[tokio::test]
+  async fn default_ascii_test() {
+    setup_tracing();
+    let remote_file = format!("{}.test", Uuid::new_v4().as_hyphenated());
+    let remote_file_path = temp_dir().join(&remote_file);
+    let _cleanup = FileCleanup::new(&remote_file_path);
+
+    // Without TYPE, the session is in ASCII mode
+    let wrapper = StandardDataChannelWrapper::new(LOCALHOST, LOCALHOST.ip());
+    let mut command_processor = setup_transfer_command_processor(wrapper, temp_dir());
+    let mut client_dc = open_tcp_data_channel(&mut command_processor).await;
+
+    let (tx, mut rx) = channel(1024);
+    let reply_sender = TestReplySender::new(tx);
+    let command = Command::new(Commands::Stor, &remote_file);
+    let command_fut = tokio::spawn(async move {
+      timeout(
+        Duration::from_secs(10),
+        command.execute(Arc::new(command_processor), Arc::new(reply_sender)),
+      )
+      .await
+      .expect("Command timeout!");
+    });
+
+    receive_and_verify_reply(2, &mut rx, ReplyCode::FileStatusOkay, None).await;
+    client_dc.write_all(b"ab\r\ncd\r\nef\n").await.unwrap();
+    client_dc.shutdown().await.unwrap();
+    receive_and_verify_reply(2, &mut rx, ReplyCode::ClosingDataConnection, None).await;
+    command_fut.await.expect("Command should complete!");
+
+    assert_eq!(b"ab\ncd\nef\n".to_vec(), std::fs::read(&remote_file_path).unwrap());
+  }
+
+  #[tokio::test]
+  async fn ascii_restart_refused_test() {
+    setup_tracing();
+    let remote_file = format!("{}.test", Uuid::new_v4().as_hyphenated());
+    let remote_file_path = temp_dir().join(&remote_file);
+    std::fs::write(&remote_file_path, b"ab\ncd\nxx").unwrap();
+    let _cleanup = FileCleanup::new(&remote_file_path);
+
+    let wrapper = StandardDataChannelWrapper::new(LOCALHOST, LOCALHOST.ip());
+    let command_processor = setup_transfer_command_processor(wrapper, temp_dir());
+    command_processor.session_properties.read().await.offset.store(8, Ordering::SeqCst);
+    let command_processor = Arc::new(command_processor);
+
+    let (code, _) =
+      execute(Command::new(Commands::Stor, &remote_file), command_processor.clone()).await;
+    assert_eq!(ReplyCode::CommandNotImplementedForThatParameter, code);
+    assert_eq!(b"ab\ncd\nxx".to_vec(), std::fs::read(&remote_file_path).unwrap());
+    assert_eq!(0, command_processor.session_properties.read().await.offset.load(Ordering::SeqCst));
+  }
+
   #[tokio::test]
   async fn not_logged_in_test() {
     setup_tracing();
-    let wrapper = Arc::new(StandardDataChannelWrapper::new(LOCALHOST));
+    let wrapper = Arc::new(StandardDataChannelWrapper::new(LOCALHOST, LOCALHOST.ip()));
     let session_properties = Arc::new(RwLock::new(SessionProperties::new()));
     let mut command_processor = CommandProcessor::new(session_properties, wrapper);
 
@@ -6,7 +6,7 @@ use crate::handlers::reply_sender::ReplySend;
 use std::sync::Arc;
 
 #[tracing::instrument(skip(reply_sender))]
-pub(crate) async fn syst(command: &Command, reply_sender: Arc<impl ReplySend>) {
+pub(crate) async fn syst(command: &Command, reply_sender: Arc<dyn ReplySend>) {
   debug_assert_eq!(command.command, Commands::Syst);
   reply_sender.send_control_message(Reply::new(ReplyCode::NameSystemType, "UNIX Type: L8")).await;
 }
@@ -11,7 +11,7 @@ use std::sync::Arc;
 pub(crate) async fn r#type(
   command: &Command,
   command_processor: Arc<CommandProcessor>,
-  reply_sender: Arc<impl ReplySend>,
+  reply_sender: Arc<dyn ReplySend>,
 ) {
   debug_assert_eq!(command.command, Commands::Type);
 
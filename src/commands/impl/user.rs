@@ -1,16 +1,22 @@
+use crate::auth::auth_error::AuthError;
+use crate::auth::auth_provider::AuthProvider;
+use crate::auth::login_form::LoginForm;
 use crate::commands::command::Command;
 use crate::commands::commands::Commands;
 use crate::commands::reply::Reply;
 use crate::commands::reply_code::ReplyCode;
+use crate::global_context::{AUTH_PROVIDER, LOGIN_FAILURES};
 use crate::handlers::reply_sender::ReplySend;
 use crate::session::command_processor::CommandProcessor;
+use crate::session::session_properties::SessionProperties;
 use std::sync::Arc;
+use tracing::info;
 
 #[tracing::instrument(skip(command_processor, reply_sender))]
 pub(crate) async fn user(
   command: &Command,
   command_processor: Arc<CommandProcessor>,
-  reply_sender: Arc<impl ReplySend>,
+  reply_sender: Arc<dyn ReplySend>,
 ) {
   debug_assert_eq!(command.command, Commands::User);
 
@@ -25,13 +31,81 @@ pub(crate) async fn user(
   }
 
   let mut session_properties = command_processor.session_properties.write().await;
+  // Refused before the password is sent, so it never crosses an unprotected connection
+  if !command_processor
+    .security_policy
+    .is_login_allowed(Some(&command.argument), session_properties.connection_security)
+  {
+    info!("Refusing login of '{}' over an unprotected connection!", command.argument);
+    return reply_sender.send_control_message(tls_required_reply()).await;
+  }
   session_properties.login_form.username.replace(command.argument.clone());
+  // A password waiting for its one-time password belongs to the previous user
+  session_properties.login_form.password.take();
+
+  if let Some(provider) = AUTH_PROVIDER.get() {
+    let reply = certificate_login(&mut session_properties, provider, &command.argument).await;
+    if let Some(reply) = reply {
+      return reply_sender.send_control_message(reply).await;
+    }
+  }
 
   reply_sender
     .send_control_message(Reply::new(ReplyCode::UserNameOkay, "User name okay, need password."))
     .await;
 }
 
+/// Attempts to log in the user with the client certificate of the session, as an implicit PASS.
+///
+/// # Returns
+///
+/// The reply if the user was logged in or refused, [`None`] if the client has no certificate or
+/// the certificate isn't mapped to the user, so a password is needed.
+///
+async fn certificate_login(
+  session_properties: &mut SessionProperties,
+  provider: &AuthProvider,
+  username: &str,
+) -> Option<Reply> {
+  let certificate = session_properties.client_certificate.clone()?;
+  let ip = session_properties.registration.as_ref().map(|r| r.ip());
+  if LOGIN_FAILURES.is_client_banned(ip) {
+    return None;
+  }
+  let form = LoginForm {
+    username: Some(username.to_string()),
+    password: None,
+    certificate: Some(certificate),
+    one_time_password: None,
+  };
+  match session_properties.login(provider, form).await {
+    Ok(()) => {
+      info!("User '{username}' logged in with a client certificate.");
+      LOGIN_FAILURES.record_success(ip, username);
+      // The login is complete, so a following PASS is out of sequence
+      session_properties.login_form.username.take();
+      Some(Reply::new(
+        ReplyCode::UserLoggedInAuthorizedBySecurityData,
+        "Logged in with client certificate.",
+      ))
+    }
+    Err(AuthError::LimitReached(e)) => {
+      info!("User '{username}' failed to login! {e}");
+      Some(Reply::new(ReplyCode::NotLoggedIn, e.to_string()))
+    }
+    Err(_) => None,
+  }
+}
+
+/// The reply to logins refused by the security policy, as specified by
+/// [RFC4217](https://datatracker.ietf.org/doc/html/rfc4217#section-11).
+pub(crate) fn tls_required_reply() -> Reply {
+  Reply::new(
+    ReplyCode::RequestDeniedForPolicyReasons,
+    "Policy requires a protected connection, use AUTH TLS!",
+  )
+}
+
 #[cfg(test)]
 mod tests {
   use std::sync::Arc;
@@ -40,9 +114,15 @@ mod tests {
   use tokio::sync::mpsc::channel;
   use tokio::time::timeout;
 
+  use crate::auth::auth_provider::AuthProvider;
+  use crate::auth::certificate_data_source::{CertificateDataSource, CertificateUser};
+  use crate::auth::client_certificate::ClientCertificate;
   use crate::commands::command::Command;
   use crate::commands::commands::Commands;
+  use crate::commands::r#impl::user::certificate_login;
   use crate::commands::reply_code::ReplyCode;
+  use crate::session::security_policy::SecurityPolicy;
+  use crate::session::session_properties::SessionProperties;
   use crate::utils::test_utils::*;
 
   #[tokio::test]
@@ -68,6 +148,33 @@ mod tests {
     assert_eq!(command_processor.session_properties.read().await.login_form.username, Some(name));
   }
 
+  #[tokio::test]
+  async fn tls_required_test() {
+    setup_tracing();
+    let command = Command::new(Commands::User, "test");
+
+    let settings =
+      CommandProcessorSettingsBuilder::default().build().expect("Settings should be valid");
+    let mut command_processor = setup_test_command_processor_custom(&settings);
+    command_processor.security_policy = Arc::new(SecurityPolicy {
+      tls_login: true,
+      ..Default::default()
+    });
+    let command_processor = Arc::new(command_processor);
+
+    let (tx, mut rx) = channel(1024);
+    let reply_sender = TestReplySender::new(tx);
+    timeout(
+      Duration::from_secs(3),
+      command.execute(command_processor.clone(), Arc::new(reply_sender)),
+    )
+    .await
+    .expect("Command timeout!");
+
+    receive_and_verify_reply(2, &mut rx, ReplyCode::RequestDeniedForPolicyReasons, None).await;
+    assert_eq!(command_processor.session_properties.read().await.login_form.username, None);
+  }
+
   #[tokio::test]
   async fn empty_username_test() {
     setup_tracing();
@@ -88,4 +195,46 @@ mod tests {
 
     receive_and_verify_reply(2, &mut rx, ReplyCode::SyntaxErrorInParametersOrArguments, None).await;
   }
+
+  fn certificate_provider() -> AuthProvider {
+    let mut provider = AuthProvider::new();
+    provider.add_data_source(Box::new(CertificateDataSource::new(vec![CertificateUser {
+      identity: "client.example.com".to_string(),
+      username: "backup".to_string(),
+      views: Vec::new(),
+    }])));
+    provider
+  }
+
+  #[tokio::test]
+  async fn certificate_login_test() {
+    setup_tracing();
+    let provider = certificate_provider();
+    let mut session_properties = SessionProperties::new();
+    session_properties.login_form.username.replace("backup".to_string());
+    session_properties.client_certificate = Some(ClientCertificate {
+      subject: "CN=client".to_string(),
+      alt_names: vec!["client.example.com".to_string()],
+    });
+
+    let reply = certificate_login(&mut session_properties, &provider, "backup").await;
+    assert_eq!(ReplyCode::UserLoggedInAuthorizedBySecurityData, reply.unwrap().code);
+    assert_eq!(Some("backup".to_string()), session_properties.username);
+    assert_eq!(None, session_properties.login_form.username);
+  }
+
+  #[tokio::test]
+  async fn certificate_login_needs_password_test() {
+    setup_tracing();
+    let provider = certificate_provider();
+    let mut session_properties = SessionProperties::new();
+    assert!(certificate_login(&mut session_properties, &provider, "backup").await.is_none());
+
+    session_properties.client_certificate = Some(ClientCertificate {
+      subject: "CN=other".to_string(),
+      alt_names: Vec::new(),
+    });
+    assert!(certificate_login(&mut session_properties, &provider, "backup").await.is_none());
+    assert!(!session_properties.is_logged_in());
+  }
 }
@@ -1,8 +1,11 @@
 //! Contains implementation of methods relevant to all commands.
 
 pub(crate) mod command;
+pub(crate) mod command_registry;
 #[allow(clippy::module_inception)]
 pub(crate) mod commands;
 pub(crate) mod r#impl;
 pub(crate) mod reply;
 pub(crate) mod reply_code;
+pub(crate) mod site_registry;
+pub(crate) mod site_settings;
@@ -10,20 +10,20 @@ use crate::commands::reply_code::ReplyCode;
 
 /// Struct containing the reply code and a message for the client.
 #[derive(PartialEq, Clone, Debug)]
-pub(crate) struct Reply {
-  pub(crate) code: ReplyCode,
+pub struct Reply {
+  pub code: ReplyCode,
   lines: Vec<String>,
 }
 
 impl Reply {
-  pub(crate) fn new(code: ReplyCode, message: impl Into<String>) -> Self {
+  pub fn new(code: ReplyCode, message: impl Into<String>) -> Self {
     Reply {
       code,
       lines: vec![message.into()],
     }
   }
 
-  pub(crate) fn new_multiline(code: ReplyCode, lines: Vec<impl Into<String>>) -> Self {
+  pub fn new_multiline(code: ReplyCode, lines: Vec<impl Into<String>>) -> Self {
     let lines = lines.into_iter().map(|l| l.into()).collect();
     Reply { code, lines }
   }
@@ -4,7 +4,7 @@ use strum_macros::FromRepr;
 
 #[derive(Ord, PartialOrd, Eq, PartialEq, Debug, Copy, Clone, FromRepr)]
 #[repr(u16)]
-pub(crate) enum ReplyCode {
+pub enum ReplyCode {
   RestartMarkerReply = 110,
   ServiceReadyInNNNMinutes = 120,
   DataConnectionAlreadyOpen = 125,
@@ -21,7 +21,10 @@ pub(crate) enum ReplyCode {
   DataConnectionOpen = 225,
   ClosingDataConnection = 226,
   EnteringPassiveMode = 227,
+  EnteringExtendedPassiveMode = 229,
   UserLoggedIn = 230,
+  UserLoggedInAuthorizedBySecurityData = 232,
+  SecurityDataExchangeComplete = 234,
   RequestedFileActionOkay = 250,
   PathnameCreated = 257,
   UserNameOkay = 331,
@@ -39,6 +42,8 @@ pub(crate) enum ReplyCode {
   CommandNotImplemented = 502,
   BadSequenceOfCommands = 503,
   CommandNotImplementedForThatParameter = 504,
+  DataConnectionCannotBeOpenedWithThisProtSetting = 521,
+  NetworkProtocolNotSupported = 522,
   NotLoggedIn = 530,
   NeedAccountForStoringFiles = 532,
   RequestDeniedForPolicyReasons = 534,
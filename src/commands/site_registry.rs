@@ -0,0 +1,219 @@
+//! The registry of SITE subcommands.
+//!
+//! SITE is dispatched to the subcommand named by the first word of its argument, the rest of the
+//! argument is passed to the subcommand. Subcommands are registered by name the same way as the
+//! commands in [`CommandRegistry`], and executed as [`Commands::Other`]. The registry used by the
+//! server is set up at startup, see [`SITE_REGISTRY`].
+//!
+//! [`CommandRegistry`]: crate::commands::command_registry::CommandRegistry
+//! [`SITE_REGISTRY`]: crate::global_context::SITE_REGISTRY
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use tracing::info;
+
+use crate::commands::command::Command;
+use crate::commands::command_registry::CommandEntry;
+use crate::commands::commands::Commands;
+use crate::commands::r#impl::site::chmod::chmod;
+use crate::commands::r#impl::site::idle::SiteIdle;
+use crate::commands::r#impl::site::umask::umask;
+use crate::commands::r#impl::site::who::SiteWho;
+use crate::commands::reply::Reply;
+use crate::commands::reply_code::ReplyCode;
+use crate::commands::site_settings::SiteSettings;
+use crate::handlers::reply_sender::ReplySend;
+use crate::session::command_processor::CommandProcessor;
+use crate::session::timeout_settings::TimeoutSettings;
+
+/// The implemented SITE subcommands, ordered by name.
+#[derive(Debug, Default)]
+pub struct SiteRegistry {
+  commands: BTreeMap<String, CommandEntry>,
+}
+
+impl SiteRegistry {
+  /// Constructs a registry containing all the implemented subcommands, restricted by the settings.
+  pub(crate) fn with_builtin_commands(
+    site_settings: &SiteSettings,
+    timeout_settings: &TimeoutSettings,
+  ) -> Self {
+    let mut registry = SiteRegistry::default();
+    registry.register(
+      "CHMOD",
+      CommandEntry::new("CHMOD <mode> <path>", "Changes the mode of a file.", |c, p, r| {
+        Box::pin(chmod(c, p, r))
+      }),
+    );
+    registry.register(
+      "IDLE",
+      CommandEntry::with_handler(
+        "IDLE [<seconds>]",
+        "Shows or sets the idle timeout.",
+        SiteIdle::new(timeout_settings.max_idle),
+      ),
+    );
+    registry.register(
+      "UMASK",
+      CommandEntry::new("UMASK [<mask>]", "Shows or sets the mask of created files.", |c, p, r| {
+        Box::pin(umask(c, p, r))
+      }),
+    );
+    registry.register(
+      "WHO",
+      CommandEntry::with_handler(
+        "WHO",
+        "Lists the active sessions.",
+        SiteWho::new(site_settings.admins.clone()),
+      ),
+    );
+    registry
+  }
+
+  /// Registers the subcommand with the name, replacing the previous entry.
+  pub fn register(&mut self, name: &str, entry: CommandEntry) {
+    self.commands.insert(name.to_uppercase(), entry);
+  }
+
+  /// Returns the entry of the subcommand with the name, case-insensitive.
+  pub(crate) fn get(&self, name: &str) -> Option<&CommandEntry> {
+    self.commands.get(&name.to_uppercase())
+  }
+
+  /// Executes the subcommand named in the argument of the SITE `command`.
+  ///
+  /// SITE HELP is always available and lists the subcommands. The other subcommands are only
+  /// available to logged in users.
+  pub(crate) async fn execute(
+    &self,
+    command: &Command,
+    command_processor: Arc<CommandProcessor>,
+    reply_sender: Arc<dyn ReplySend>,
+  ) {
+    debug_assert_eq!(Commands::Site, command.command);
+
+    let argument = command.argument.trim();
+    let (name, argument) = argument.split_once(' ').unwrap_or((argument, ""));
+    if name.is_empty() {
+      let reply =
+        Reply::new(ReplyCode::SyntaxErrorInParametersOrArguments, "SITE command expected!");
+      return reply_sender.send_control_message(reply).await;
+    }
+
+    if name.eq_ignore_ascii_case("HELP") {
+      return reply_sender.send_control_message(self.help(argument.trim())).await;
+    }
+
+    if !command_processor.session_properties.read().await.is_logged_in() {
+      let reply = Reply::new(ReplyCode::NotLoggedIn, "User not logged in!");
+      return reply_sender.send_control_message(reply).await;
+    }
+
+    match self.get(name) {
+      Some(entry) => {
+        let subcommand = Command::other(name, argument.trim_start());
+        entry.execute(&subcommand, command_processor, reply_sender).await
+      }
+      None => {
+        info!("Unknown SITE command: {name}");
+        let reply = Reply::new(
+          ReplyCode::CommandNotImplementedForThatParameter,
+          format!("Unknown SITE command {name}."),
+        );
+        reply_sender.send_control_message(reply).await
+      }
+    }
+  }
+
+  fn help(&self, name: &str) -> Reply {
+    if name.is_empty() {
+      let mut lines = vec!["The following SITE commands are recognized:".to_string()];
+      lines.extend(self.commands.values().map(|e| format!(" {} - {}", e.syntax, e.description)));
+      lines.push("Help OK.".to_string());
+      return Reply::new_multiline(ReplyCode::Help, lines);
+    }
+    match self.get(name) {
+      Some(entry) => Reply::new(
+        ReplyCode::Help,
+        format!("Syntax: SITE {} - {}", entry.syntax, entry.description),
+      ),
+      None => Reply::new(
+        ReplyCode::SyntaxErrorInParametersOrArguments,
+        format!("Unknown SITE command {name}."),
+      ),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::sync::Arc;
+
+  use crate::commands::command::Command;
+  use crate::commands::commands::Commands;
+  use crate::commands::reply_code::ReplyCode;
+  use crate::commands::site_registry::SiteRegistry;
+  use crate::commands::site_settings::SiteSettings;
+  use crate::session::command_processor::CommandProcessor;
+  use crate::session::timeout_settings::TimeoutSettings;
+  use crate::utils::test_utils::*;
+
+  async fn execute_site(
+    command_processor: CommandProcessor,
+    argument: &str,
+  ) -> (ReplyCode, String) {
+    let registry =
+      SiteRegistry::with_builtin_commands(&SiteSettings::default(), &TimeoutSettings::default());
+    let command = Command::new(Commands::Site, argument);
+    let command_processor = Arc::new(command_processor);
+    receive_reply(|reply_sender| registry.execute(&command, command_processor, reply_sender)).await
+  }
+
+  #[test]
+  fn builtin_commands_test() {
+    let registry =
+      SiteRegistry::with_builtin_commands(&SiteSettings::default(), &TimeoutSettings::default());
+    assert_eq!(vec!["CHMOD", "IDLE", "UMASK", "WHO"], registry.commands.keys().collect::<Vec<_>>());
+    for (name, entry) in &registry.commands {
+      assert!(entry.syntax.starts_with(name), "Syntax of {name} doesn't match: {}", entry.syntax);
+    }
+    assert!(registry.get("chmod").is_some());
+  }
+
+  #[tokio::test]
+  async fn help_test() {
+    setup_tracing();
+    let (_, command_processor) = setup_test_command_processor();
+    let (code, reply) = execute_site(command_processor, "HELP").await;
+    assert_eq!(ReplyCode::Help, code);
+    assert!(reply.contains(" CHMOD <mode> <path> - "), "{reply}");
+    assert!(reply.contains(" WHO - "), "{reply}");
+
+    let (_, command_processor) = setup_test_command_processor();
+    let (code, reply) = execute_site(command_processor, "help umask").await;
+    assert_eq!(ReplyCode::Help, code);
+    assert!(reply.contains("Syntax: SITE UMASK [<mask>]"), "{reply}");
+  }
+
+  #[tokio::test]
+  async fn unknown_test() {
+    setup_tracing();
+    let (_, command_processor) = setup_test_command_processor();
+    let (code, _) = execute_site(command_processor, "NONEXISTENT arg").await;
+    assert_eq!(ReplyCode::CommandNotImplementedForThatParameter, code);
+
+    let (_, command_processor) = setup_test_command_processor();
+    let (code, _) = execute_site(command_processor, "").await;
+    assert_eq!(ReplyCode::SyntaxErrorInParametersOrArguments, code);
+  }
+
+  #[tokio::test]
+  async fn not_logged_in_test() {
+    setup_tracing();
+    let (_, command_processor) = setup_test_command_processor();
+    command_processor.session_properties.write().await.username.take();
+    let (code, _) = execute_site(command_processor, "IDLE").await;
+    assert_eq!(ReplyCode::NotLoggedIn, code);
+  }
+}
@@ -0,0 +1,59 @@
+//! Settings of the SITE subcommands.
+
+use std::collections::HashSet;
+
+use config::Config;
+use tracing::warn;
+
+/// Controls who may use the restricted SITE subcommands.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct SiteSettings {
+  /// The users allowed to use the administrative subcommands, such as SITE WHO.
+  pub(crate) admins: HashSet<String>,
+}
+
+impl SiteSettings {
+  /// Loads the settings from config.
+  ///
+  /// The administrators are read from 'site_admins', as a list of usernames. If the list is
+  /// invalid, it's reported and no user is an administrator.
+  ///
+  pub(crate) fn from_config(config: &Config) -> Self {
+    let admins = match config.get_array("site_admins") {
+      Ok(admins) => admins.into_iter().filter_map(|a| a.into_string().ok()).collect(),
+      Err(config::ConfigError::NotFound(_)) => HashSet::new(),
+      Err(e) => {
+        warn!("Invalid site_admins, ignoring! {e}");
+        HashSet::new()
+      }
+    };
+    SiteSettings { admins }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::collections::HashSet;
+
+  use config::Config;
+
+  use crate::commands::site_settings::SiteSettings;
+
+  #[test]
+  fn from_config_test() {
+    let config = Config::builder()
+      .set_override("site_admins", vec!["admin", "root"])
+      .unwrap()
+      .build()
+      .unwrap();
+
+    let settings = SiteSettings::from_config(&config);
+    assert_eq!(HashSet::from(["admin".to_string(), "root".to_string()]), settings.admins);
+  }
+
+  #[test]
+  fn from_config_invalid_test() {
+    let config = Config::builder().set_override("site_admins", "admin").unwrap().build().unwrap();
+    assert_eq!(SiteSettings::default(), SiteSettings::from_config(&config));
+  }
+}
@@ -1,5 +1,6 @@
 use std::net::SocketAddr;
 
+use anyhow::bail;
 use async_trait::async_trait;
 use tokio_util::sync::CancellationToken;
 
@@ -11,7 +12,19 @@ pub(crate) type DataChannel = Box<dyn AsyncReadWrite>;
 /// This trait specifies operations that can be used on a data channel.
 #[async_trait]
 pub(crate) trait DataChannelWrapper: Sync + Send {
+  /// Returns the local address passive data channels are opened on, without the port.
+  fn local_addr(&self) -> SocketAddr;
   async fn open_data_stream(&self, prot_mode: ProtMode) -> Result<SocketAddr, anyhow::Error>;
+  /// Opens a data channel in active mode by connecting to the client at `addr`.
+  ///
+  /// Wrappers that can't connect to the client, e.g.: QUIC, return an error by default.
+  async fn connect_data_stream(
+    &self,
+    _prot_mode: ProtMode,
+    addr: SocketAddr,
+  ) -> Result<(), anyhow::Error> {
+    bail!("Active mode is not supported on this connection! Address: {addr}")
+  }
   fn try_acquire(&self) -> Result<(DataChannel, CancellationToken), anyhow::Error>;
   async fn acquire(&self) -> Result<(DataChannel, CancellationToken), anyhow::Error>;
   async fn close_data_stream(&self);
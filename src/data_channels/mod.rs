@@ -1,5 +1,6 @@
 //! Contains the implementation of data channel wrappers, which are use to send data to clients.
 pub(crate) mod data_channel_wrapper;
+pub(crate) mod passive_settings;
 pub(crate) mod quic_data_channel;
 pub(crate) mod quic_only_data_channel_wrapper;
 pub(crate) mod quic_quinn_data_channel_wrapper;
@@ -0,0 +1,110 @@
+//! Settings of passive data channels for TCP connections.
+
+use std::net::IpAddr;
+use std::ops::RangeInclusive;
+
+use config::Config;
+use tracing::warn;
+
+/// Controls how passive listeners are created and advertised.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct PassiveSettings {
+  /// The ports passive listeners may bind to. If not set, the OS chooses a port.
+  pub(crate) port_range: Option<RangeInclusive<u16>>,
+  /// The address sent to clients in PASV replies instead of the local address, e.g.: when the
+  /// server is behind NAT.
+  pub(crate) public_address: Option<IpAddr>,
+}
+
+impl PassiveSettings {
+  /// Loads the settings from config.
+  ///
+  /// The port range is read from 'passive_port_min' and 'passive_port_max', both must be set and
+  /// 'passive_port_min' must not be greater than 'passive_port_max'. The public address is read
+  /// from 'passive_address'. Invalid values are reported and ignored.
+  ///
+  pub(crate) fn from_config(config: &Config) -> Self {
+    let port_range = match (config.get_int("passive_port_min"), config.get_int("passive_port_max"))
+    {
+      (Ok(min), Ok(max)) => match (u16::try_from(min), u16::try_from(max)) {
+        (Ok(min), Ok(max)) if 0 < min && min <= max => Some(min..=max),
+        _ => {
+          warn!("Invalid passive port range {min}-{max}, ignoring!");
+          None
+        }
+      },
+      (Ok(_), Err(_)) | (Err(_), Ok(_)) => {
+        warn!("Both passive_port_min and passive_port_max must be set, ignoring!");
+        None
+      }
+      (Err(_), Err(_)) => None,
+    };
+
+    let public_address = match config.get_string("passive_address") {
+      Ok(address) => match address.parse() {
+        Ok(address) => Some(address),
+        Err(e) => {
+          warn!("Invalid passive address '{address}', ignoring! {e}");
+          None
+        }
+      },
+      Err(_) => None,
+    };
+
+    PassiveSettings {
+      port_range,
+      public_address,
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::net::{IpAddr, Ipv4Addr};
+
+  use config::Config;
+
+  use crate::data_channels::passive_settings::PassiveSettings;
+
+  #[test]
+  fn from_config_test() {
+    let config = Config::builder()
+      .set_override("passive_port_min", 50000)
+      .unwrap()
+      .set_override("passive_port_max", 50100)
+      .unwrap()
+      .set_override("passive_address", "203.0.113.1")
+      .unwrap()
+      .build()
+      .unwrap();
+
+    let settings = PassiveSettings::from_config(&config);
+    assert_eq!(Some(50000..=50100), settings.port_range);
+    assert_eq!(Some(IpAddr::V4(Ipv4Addr::new(203, 0, 113, 1))), settings.public_address);
+  }
+
+  #[test]
+  fn from_config_empty_test() {
+    let config = Config::builder().build().unwrap();
+    assert_eq!(PassiveSettings::default(), PassiveSettings::from_config(&config));
+  }
+
+  #[test]
+  fn from_config_invalid_test() {
+    let configs = [
+      vec![("passive_port_min", "50100"), ("passive_port_max", "50000")],
+      vec![("passive_port_min", "0"), ("passive_port_max", "50000")],
+      vec![("passive_port_min", "50000"), ("passive_port_max", "70000")],
+      vec![("passive_port_min", "50000")],
+      vec![("passive_address", "invalid")],
+    ];
+    for overrides in configs {
+      let mut builder = Config::builder();
+      for (key, value) in overrides {
+        builder = builder.set_override(key, value).unwrap();
+      }
+      let settings = PassiveSettings::from_config(&builder.build().unwrap());
+      assert_eq!(PassiveSettings::default(), settings);
+    }
+  }
+}
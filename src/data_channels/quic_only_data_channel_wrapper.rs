@@ -99,6 +99,10 @@ impl QuicOnlyDataChannelWrapper {
 #[async_trait]
 impl DataChannelWrapper for QuicOnlyDataChannelWrapper {
   /// Opens a data channel using [`QuicOnlyDataChannelWrapper::create_stream`].
+  fn local_addr(&self) -> SocketAddr {
+    self.addr
+  }
+
   async fn open_data_stream(&self, _prot_mode: ProtMode) -> Result<SocketAddr, anyhow::Error> {
     self.create_stream().await
   }
@@ -96,6 +96,10 @@ impl QuicQuinnDataChannelWrapper {
 #[async_trait]
 impl DataChannelWrapper for QuicQuinnDataChannelWrapper {
   /// Opens a data channel using [`QuicQuinnDataChannelWrapper::create_stream`].
+  fn local_addr(&self) -> SocketAddr {
+    self.addr
+  }
+
   async fn open_data_stream(&self, _prot_mode: ProtMode) -> Result<SocketAddr, anyhow::Error> {
     self.create_stream().await
   }
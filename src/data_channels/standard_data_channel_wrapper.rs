@@ -1,6 +1,8 @@
 use anyhow::bail;
 use async_channel::{Receiver, Sender, unbounded};
-use std::net::SocketAddr;
+use std::io::ErrorKind;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::time::Duration;
 
 use async_trait::async_trait;
@@ -12,13 +14,20 @@ use tokio_util::sync::CancellationToken;
 use tracing::{Instrument, Span, debug, error, info, trace, warn};
 
 use crate::data_channels::data_channel_wrapper::{DataChannel, DataChannelWrapper};
+use crate::data_channels::passive_settings::PassiveSettings;
 use crate::data_channels::tcp_data_channel::TcpDataChannel;
 use crate::data_channels::tls_data_channel::TlsDataChannel;
 use crate::global_context::TLS_CONFIG;
+use crate::session::connection_mode::ConnectionMode;
 use crate::session::protection_mode::ProtMode;
 
+/// Used to spread passive listeners across the configured port range.
+static NEXT_PASSIVE_PORT: AtomicUsize = AtomicUsize::new(0);
+
 pub(crate) struct StandardDataChannelWrapper {
   addr: SocketAddr,
+  peer: IpAddr,
+  passive_settings: PassiveSettings,
   channel_sender: Sender<DataChannel>,
   channel_receiver: Receiver<DataChannel>,
   abort_token: CancellationToken,
@@ -27,31 +36,43 @@ pub(crate) struct StandardDataChannelWrapper {
 impl StandardDataChannelWrapper {
   /// Creates a new instance of a [`StandardDataChannelWrapper`].
   ///
-  /// This function takes in a [`SocketAddr`] and returns a new [`StandardDataChannelWrapper`]
-  /// instance.
+  /// This function takes in a [`SocketAddr`] and the clients [`IpAddr`] and returns a new
+  /// [`StandardDataChannelWrapper`] instance.
   /// The [`StandardDataChannelWrapper`] represents a wrapper for a TCP (unencrypted) data channel
   /// that is used for sending data.
   ///
   /// # Arguments
   ///
   /// - `addr`: A [`SocketAddr`] representing the address for the data channel.
-  ///   The port is set to 0.
+  ///   The port is set to 0. IPv4-mapped IPv6 addresses, as seen on dual-stack listeners, are
+  ///   converted to IPv4.
+  /// - `peer`: An [`IpAddr`] of the client's control connection. In active mode, the server will
+  ///   only connect to this address.
   ///
   /// # Returns
   ///
   /// A new instance of [`StandardDataChannelWrapper`].
   ///
-  pub(crate) fn new(mut addr: SocketAddr) -> Self {
+  pub(crate) fn new(mut addr: SocketAddr, peer: IpAddr) -> Self {
     addr.set_port(0);
+    addr.set_ip(addr.ip().to_canonical());
     let (sender, receiver) = unbounded();
     StandardDataChannelWrapper {
       addr,
+      peer: peer.to_canonical(),
+      passive_settings: PassiveSettings::default(),
       channel_sender: sender,
       channel_receiver: receiver,
       abort_token: CancellationToken::new(),
     }
   }
 
+  /// Sets the port range and public address used for passive listeners.
+  pub(crate) fn with_passive_settings(mut self, passive_settings: PassiveSettings) -> Self {
+    self.passive_settings = passive_settings;
+    self
+  }
+
   /// Creates a new stream for the data channel.
   ///
   /// Creates a new [`tokio::task`] that creates a new TCP listener which waits for up to 20
@@ -59,18 +80,20 @@ impl StandardDataChannelWrapper {
   /// is set and the data channel can be used. If the client does not connect in time or some
   /// other error occurs it will be logged.
   ///
-  /// # Panics
+  /// # Errors
   ///
-  /// This function will panic if all ports are in use.
+  /// Returns an error if the listener can't be created, e.g.: all ports in the passive port range
+  /// are in use.
   ///
   /// # Returns
   ///
-  /// A [`SocketAddr`] the server listens on.
+  /// A [`SocketAddr`] the server listens on. If a public address is configured, it's used instead
+  /// of the local one.
   ///
   #[tracing::instrument(skip(self))]
   async fn create_stream(&self, prot_mode: ProtMode) -> Result<SocketAddr, anyhow::Error> {
     debug!("Creating passive listener");
-    let listener = TcpListener::bind(self.addr).await.expect("Implement passive port search!");
+    let listener = self.bind_passive_listener().await?;
     let port = listener.local_addr()?.port();
     let sender = self.channel_sender.clone();
     let span = Span::current();
@@ -83,7 +106,7 @@ impl StandardDataChannelWrapper {
         .await;
         match conn {
           Ok(Ok((stream, _))) => {
-            Self::establish_connection(stream, prot_mode, sender).await;
+            Self::establish_connection(stream, prot_mode, ConnectionMode::Passive, sender).await;
           }
           Ok(Err(e)) => {
             warn!("Passive listener connection failed! {e}");
@@ -98,16 +121,99 @@ impl StandardDataChannelWrapper {
 
     let mut addr = self.addr;
     addr.set_port(port);
+    if let Some(public_address) = self.passive_settings.public_address
+      && public_address.is_ipv4() == addr.is_ipv4()
+    {
+      addr.set_ip(public_address);
+    }
     Ok(addr)
   }
 
+  /// Binds a TCP listener for the passive mode.
+  ///
+  /// If a port range is configured, the ports are tried one after another, starting after the
+  /// previously used one, until a free port is found. Otherwise the OS chooses the port.
+  ///
+  async fn bind_passive_listener(&self) -> Result<TcpListener, anyhow::Error> {
+    let Some(port_range) = &self.passive_settings.port_range else {
+      return Ok(TcpListener::bind(self.addr).await?);
+    };
+
+    let start = NEXT_PASSIVE_PORT.fetch_add(1, Ordering::Relaxed);
+    let ports = port_range.clone().cycle().skip(start % port_range.len()).take(port_range.len());
+    for port in ports {
+      let mut addr = self.addr;
+      addr.set_port(port);
+      match TcpListener::bind(addr).await {
+        Ok(listener) => return Ok(listener),
+        Err(e) if e.kind() == ErrorKind::AddrInUse => {
+          trace!("Passive port {port} is in use, trying next one.");
+        }
+        Err(e) => return Err(e.into()),
+      }
+    }
+    bail!("All ports in passive port range {port_range:?} are in use!")
+  }
+
+  /// Creates a new stream for the data channel in active mode.
+  ///
+  /// Creates a new [`tokio::task`] that connects to the client at `addr`, waiting up to 20
+  /// seconds for the connection to be established. If the connection succeeds, then the data
+  /// channel can be used. If the connection fails, or it doesn't complete in time, it will be
+  /// logged. Even in active mode the server acts as the TLS server, as per
+  /// [RFC4217](https://datatracker.ietf.org/doc/html/rfc4217#section-7).
+  ///
+  /// # Errors
+  ///
+  /// To prevent bounce attacks
+  /// ([RFC2577](https://datatracker.ietf.org/doc/html/rfc2577#section-3)), an error is returned
+  /// if the IP of `addr` differs from the IP of the control connection. An error is also
+  /// returned if the port of `addr` is 0.
+  ///
+  #[tracing::instrument(skip(self))]
+  async fn create_active_stream(
+    &self,
+    prot_mode: ProtMode,
+    addr: SocketAddr,
+  ) -> Result<(), anyhow::Error> {
+    if addr.ip().to_canonical() != self.peer {
+      warn!(peer_addr = ?self.peer, "Refusing to connect to foreign address {addr}!");
+      bail!("Address {} does not match the peer address {}!", addr.ip(), self.peer);
+    }
+    if addr.port() == 0 {
+      bail!("Invalid port in address {addr}!");
+    }
+
+    debug!("Creating active connection");
+    let sender = self.channel_sender.clone();
+    let span = Span::current();
+    tokio::spawn(
+      async move {
+        match timeout(Duration::from_secs(20), TcpStream::connect(addr)).await {
+          Ok(Ok(stream)) => {
+            Self::establish_connection(stream, prot_mode, ConnectionMode::Active, sender).await;
+          }
+          Ok(Err(e)) => {
+            warn!("Active connection to {addr} failed! {e}");
+          }
+          Err(e) => {
+            info!("Active connection to {addr} failed to connect before timeout! {e}");
+          }
+        };
+      }
+      .instrument(span),
+    );
+    Ok(())
+  }
+
   async fn establish_connection(
     mut stream: TcpStream,
     prot_mode: ProtMode,
+    mode: ConnectionMode,
     sender: Sender<DataChannel>,
   ) {
     let peer = stream.peer_addr().expect("Data channel should have peer address");
-    info!("Passive connection created! Remote address: {:?}", peer);
+    info!("{:?} connection created! Remote address: {:?}", mode, peer);
     let tls = TLS_CONFIG.clone().map(TlsAcceptor::from);
     let data_channel = match prot_mode {
       ProtMode::Private => {
@@ -155,11 +261,24 @@ impl StandardDataChannelWrapper {
 
 #[async_trait]
 impl DataChannelWrapper for StandardDataChannelWrapper {
+  fn local_addr(&self) -> SocketAddr {
+    self.addr
+  }
+
   /// Opens a data channel using [`StandardDataChannelWrapper::create_stream`].
   async fn open_data_stream(&self, prot_mode: ProtMode) -> Result<SocketAddr, anyhow::Error> {
     self.create_stream(prot_mode).await
   }
 
+  /// Connects to the client using [`StandardDataChannelWrapper::create_active_stream`].
+  async fn connect_data_stream(
+    &self,
+    prot_mode: ProtMode,
+    addr: SocketAddr,
+  ) -> Result<(), anyhow::Error> {
+    self.create_active_stream(prot_mode, addr).await
+  }
+
   fn try_acquire(&self) -> Result<(DataChannel, CancellationToken), anyhow::Error> {
     match self.channel_receiver.try_recv() {
       Ok(stream) => Ok((stream, self.abort_token.clone())),
@@ -1,7 +1,7 @@
 //! Contains global statics
 
 use std::io::{Error, ErrorKind};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
 use config::Config;
@@ -16,7 +16,15 @@ use tokio::sync::OnceCell;
 use tracing::{info, warn};
 
 use crate::auth::auth_provider::AuthProvider;
-use crate::utils::tls_utils::{load_certs, load_keys};
+use crate::auth::login_failures::{LoginFailureSettings, LoginFailureTracker};
+use crate::commands::command_registry::CommandRegistry;
+use crate::commands::site_registry::SiteRegistry;
+use crate::commands::site_settings::SiteSettings;
+use crate::data_channels::passive_settings::PassiveSettings;
+use crate::session::connection_registry::{ConnectionLimits, ConnectionRegistry};
+use crate::session::security_policy::SecurityPolicy;
+use crate::session::timeout_settings::TimeoutSettings;
+use crate::utils::tls_utils::{ClientAuth, load_certs, load_keys};
 
 /// The configuration loaded from config file
 pub(crate) static CONFIG: Lazy<Config> = Lazy::new(|| {
@@ -46,6 +54,50 @@ pub(crate) static KEY: Lazy<PrivateKeyDer> = Lazy::new(|| {
     .clone_key()
 });
 
+/// The passive data channel settings loaded from config
+pub(crate) static PASSIVE_SETTINGS: Lazy<PassiveSettings> =
+  Lazy::new(|| PassiveSettings::from_config(&CONFIG));
+
+/// The control connection timeouts loaded from config
+pub(crate) static TIMEOUT_SETTINGS: Lazy<TimeoutSettings> =
+  Lazy::new(|| TimeoutSettings::from_config(&CONFIG));
+
+/// The security required from clients, loaded from config. Each session gets it through its
+/// [`CommandProcessor`].
+///
+/// [`CommandProcessor`]: crate::session::command_processor::CommandProcessor
+pub(crate) static SECURITY_POLICY: Lazy<Arc<SecurityPolicy>> =
+  Lazy::new(|| Arc::new(SecurityPolicy::from_config(&CONFIG)));
+
+/// The registry of all sessions, enforcing the connection limits loaded from config
+pub(crate) static CONNECTION_REGISTRY: Lazy<Arc<ConnectionRegistry>> =
+  Lazy::new(|| Arc::new(ConnectionRegistry::new(ConnectionLimits::from_config(&CONFIG))));
+
+/// The tracker of failed logins, punishing them as configured
+pub(crate) static LOGIN_FAILURES: Lazy<LoginFailureTracker> =
+  Lazy::new(|| LoginFailureTracker::new(LoginFailureSettings::from_config(&CONFIG)));
+
+/// The registry of implemented commands, set up at startup. Site-specific commands are added to
+/// the registry before it's set.
+pub(crate) static COMMAND_REGISTRY: once_cell::sync::OnceCell<CommandRegistry> =
+  once_cell::sync::OnceCell::new();
+
+/// Returns the [`COMMAND_REGISTRY`], containing only the built-in commands if it wasn't set up.
+pub(crate) fn command_registry() -> &'static CommandRegistry {
+  COMMAND_REGISTRY.get_or_init(CommandRegistry::with_builtin_commands)
+}
+
+/// The registry of SITE subcommands, set up at startup like the [`COMMAND_REGISTRY`].
+pub(crate) static SITE_REGISTRY: once_cell::sync::OnceCell<SiteRegistry> =
+  once_cell::sync::OnceCell::new();
+
+/// Returns the [`SITE_REGISTRY`], containing only the built-in subcommands if it wasn't set up.
+pub(crate) fn site_registry() -> &'static SiteRegistry {
+  SITE_REGISTRY.get_or_init(|| {
+    SiteRegistry::with_builtin_commands(&SiteSettings::from_config(&CONFIG), &TIMEOUT_SETTINGS)
+  })
+}
+
 /// The SQLite connection
 pub(crate) static DB_LAZY: Lazy<SqlitePool> = Lazy::new(|| {
   let db_url = CONFIG.get_string("DATABASE_URL").expect("DATABASE_URL must be set!");
@@ -59,8 +111,16 @@ pub(crate) static TLS_CONFIG: Lazy<Option<Arc<ServerConfig>>> = Lazy::new(|| {
       .install_default()
       .expect("CryptoProvider should install successfuly");
   }
-  let mut config = match ServerConfig::builder()
-    .with_no_client_auth()
+  let ca_chain_file = CONFIG.get_string("ca_chain_file").ok().map(PathBuf::from);
+  let builder = match ClientAuth::from_config(&CONFIG).verifier(ca_chain_file.as_deref()) {
+    Ok(Some(verifier)) => ServerConfig::builder().with_client_cert_verifier(verifier),
+    Ok(None) => ServerConfig::builder().with_no_client_auth(),
+    Err(e) => {
+      warn!("Failed to configure client certificates! {e}");
+      return None;
+    }
+  };
+  let mut config = match builder
     .with_single_cert(CERTS.clone(), KEY.clone_key())
     .map_err(|err| Error::new(ErrorKind::InvalidInput, err))
   {
@@ -2,6 +2,9 @@ use async_trait::async_trait;
 use tokio::io::{AsyncRead, AsyncWrite};
 use tokio_util::sync::CancellationToken;
 
+use crate::commands::reply::Reply;
+use crate::session::connection_registry::SessionRegistration;
+
 /// Handles clients connection.
 #[async_trait]
 pub(crate) trait ConnectionHandler {
@@ -10,6 +13,15 @@ pub(crate) trait ConnectionHandler {
   /// Starts by sending the client a server hello message. Then enters the command loop, where
   /// it listens for incoming requests and evaluates them.
   async fn handle(&mut self, token: CancellationToken) -> Result<(), anyhow::Error>;
+
+  /// Attaches the registration of the session. It's released once the handler is dropped.
+  async fn register(&mut self, registration: SessionRegistration);
+
+  /// Rejects the connection, e.g.: when a connection limit is reached.
+  ///
+  /// The reply is sent instead of the server hello, then the connection is closed without
+  /// processing any commands.
+  async fn reject(&mut self, reply: Reply) -> Result<(), anyhow::Error>;
 }
 
 /// Types that implement this trait allow for asynchronous thread-safe reading and writing.
@@ -2,23 +2,27 @@ use std::sync::Arc;
 use std::time::Duration;
 
 use async_trait::async_trait;
-use futures::future::join_all;
 use s2n_quic::Connection;
 use s2n_quic::stream::{ReceiveStream, SendStream};
 use tokio::io::{AsyncBufReadExt, BufReader};
 use tokio::sync::{Mutex, RwLock};
-use tokio::task::JoinHandle;
-use tokio::time::timeout;
+use tokio::time::{Instant, timeout};
 use tokio_util::sync::CancellationToken;
 use tracing::{debug, info, warn};
 
+use crate::auth::client_certificate::ClientCertificate;
 use crate::commands::reply::Reply;
 use crate::commands::reply_code::ReplyCode;
 use crate::data_channels::data_channel_wrapper::DataChannelWrapper;
 use crate::data_channels::quic_only_data_channel_wrapper::QuicOnlyDataChannelWrapper;
+use crate::global_context::TIMEOUT_SETTINGS;
 use crate::handlers::connection_handler::ConnectionHandler;
 use crate::handlers::reply_sender::{ReplySend, ReplySender};
+use crate::listeners::quic_only_listener::PeerCertificates;
 use crate::session::command_processor::CommandProcessor;
+use crate::session::command_scheduler::CommandScheduler;
+use crate::session::connection_registry::SessionRegistration;
+use crate::session::connection_security::ConnectionSecurity;
 use crate::session::session_properties::SessionProperties;
 
 /// Represents the networking part of clients session for QUIC.
@@ -31,7 +35,8 @@ pub(crate) struct QuicOnlyConnectionHandler {
   control_channel: Option<BufReader<ReceiveStream>>,
   reply_sender: Option<Arc<ReplySender<SendStream>>>,
   session_properties: Arc<RwLock<SessionProperties>>,
-  running_commands: Vec<JoinHandle<()>>,
+  command_scheduler: CommandScheduler<ReplySender<SendStream>>,
+  login_deadline: Option<Instant>,
 }
 
 impl QuicOnlyConnectionHandler {
@@ -42,13 +47,22 @@ impl QuicOnlyConnectionHandler {
   ///
   pub(crate) fn new(connection: Connection) -> Self {
     let addr = connection.local_addr().unwrap();
+    let client_certificate = connection
+      .query_event_context(|certificates: &PeerCertificates| certificates.0.clone())
+      .ok()
+      .flatten()
+      .and_then(|chain| ClientCertificate::from_chain(&chain));
     let connection = Arc::new(Mutex::new(connection));
     let wrapper = Arc::new(QuicOnlyDataChannelWrapper::new(addr, connection.clone()));
 
-    let session_properties = Arc::new(RwLock::new(SessionProperties::new()));
+    let mut session_properties = SessionProperties::new();
+    session_properties.idle_timeout = TIMEOUT_SETTINGS.idle;
+    session_properties.connection_security = ConnectionSecurity::Quic;
+    session_properties.client_certificate = client_certificate;
+    let session_properties = Arc::new(RwLock::new(session_properties));
     let command_processor =
       Arc::new(CommandProcessor::new(session_properties.clone(), wrapper.clone()));
-    let running_commands = Vec::with_capacity(10);
+    let command_scheduler = CommandScheduler::new(command_processor.clone());
     QuicOnlyConnectionHandler {
       connection,
       data_channel_wrapper: wrapper,
@@ -56,7 +70,8 @@ impl QuicOnlyConnectionHandler {
       control_channel: None,
       reply_sender: None,
       session_properties,
-      running_commands,
+      command_scheduler,
+      login_deadline: TIMEOUT_SETTINGS.login.map(|t| Instant::now() + t),
     }
   }
 
@@ -65,7 +80,7 @@ impl QuicOnlyConnectionHandler {
   /// Reads data from the client until newline. If the connection closes, this returns an [`error`].
   /// Otherwise, it will return [`Ok(())`].
   ///
-  /// After reading clients message, it sent for evaluation to [`CommandProcessor`].
+  /// After reading clients message, it is scheduled for evaluation by [`CommandScheduler`].
   ///
   /// [`error`]: anyhow::Error
   ///
@@ -87,11 +102,7 @@ impl QuicOnlyConnectionHandler {
       }
     };
 
-    let command_processor = self.command_processor.clone();
-    let task = command_processor.evaluate(buf, self.reply_sender.clone().unwrap());
-    self.running_commands.push(tokio::spawn(task));
-    self.running_commands.retain(|c| !c.is_finished());
-    debug!("Running commands: {}", self.running_commands.len());
+    self.command_scheduler.schedule(buf, self.reply_sender.clone().unwrap());
     Ok(true)
   }
 
@@ -131,13 +142,25 @@ impl ConnectionHandler for QuicOnlyConnectionHandler {
     debug!("[QUIC] Sending hello to client.");
     let _ = &mut self.reply_sender.as_mut().unwrap().send_control_message(hello).await;
 
+    let closing = self.command_processor.close_token.clone();
     loop {
+      let expiry = self.command_scheduler.expiry(self.login_deadline);
       tokio::select! {
         biased;
         _ = token.cancelled() => {
           info!("[QUIC] Shutdown received!");
           break;
         }
+        _ = closing.cancelled() => {
+          debug!("[QUIC] Client quit.");
+          break;
+        }
+        reason = expiry => {
+          info!("[QUIC] {reason}");
+          let reply = Reply::new(ReplyCode::ServiceNotAvailableClosingControlConnection, reason);
+          self.reply_sender.as_ref().unwrap().send_control_message(reply).await;
+          break;
+        }
         result = self.await_command() => {
           match result {
             Ok(true) => {},
@@ -153,13 +176,24 @@ impl ConnectionHandler for QuicOnlyConnectionHandler {
     self.cleanup().await;
     Ok(())
   }
+
+  async fn register(&mut self, registration: SessionRegistration) {
+    self.session_properties.write().await.registration.replace(registration);
+  }
+
+  async fn reject(&mut self, reply: Reply) -> Result<(), anyhow::Error> {
+    self.create_control_channel().await?;
+    debug!("[QUIC] Rejecting connection.");
+    self.reply_sender.as_ref().unwrap().send_control_message(reply).await;
+    self.cleanup().await;
+    Ok(())
+  }
 }
 
 impl QuicOnlyConnectionHandler {
   async fn cleanup(&mut self) {
     info!("[QUIC] Shutdown received!");
-    let commands_to_finish = join_all(std::mem::take(&mut self.running_commands));
-    if timeout(Duration::from_secs(5), commands_to_finish).await.is_err() {
+    if timeout(Duration::from_secs(5), self.command_scheduler.finish()).await.is_err() {
       warn!("[QUIC] Failed to finish processing running commands in time!");
     }
     if timeout(Duration::from_secs(2), self.reply_sender.as_mut().unwrap().close()).await.is_err() {
@@ -178,13 +212,13 @@ impl QuicOnlyConnectionHandler {
 
 impl Drop for QuicOnlyConnectionHandler {
   fn drop(&mut self) {
-    debug!("[QUIC] aborting {} commands", self.running_commands.len());
-    self.running_commands.iter().for_each(|c| c.abort());
+    self.command_scheduler.abort();
   }
 }
 
 #[cfg(test)]
 mod tests {
+  use std::sync::Arc;
   use std::time::Duration;
 
   use s2n_quic::Client;
@@ -195,7 +229,10 @@ mod tests {
   use tokio_util::sync::CancellationToken;
 
   use crate::commands::reply_code::ReplyCode;
+  use crate::handlers::quic_only_connection_handler::QuicOnlyConnectionHandler;
+  use crate::listeners::quic_only_listener::QuicOnlyListener;
   use crate::utils::test_utils::*;
+  use crate::utils::tls_utils::ClientAuth;
 
   #[tokio::test]
   async fn server_hello_test() {
@@ -284,4 +321,38 @@ mod tests {
       panic!("Handler future failed to finish!");
     };
   }
+
+  #[tokio::test]
+  async fn client_certificate_test() {
+    setup_tracing();
+    let server_config = Arc::new(create_client_auth_server_config(ClientAuth::Optional));
+    let mut listener = QuicOnlyListener::with_tls_config(LOCALHOST, server_config).unwrap();
+    let addr = listener.server.local_addr().unwrap();
+    let handler_fut = tokio::spawn(async move {
+      let connection = listener.accept(CancellationToken::new()).await.unwrap();
+      let handler = QuicOnlyConnectionHandler::new(connection);
+      handler.session_properties.read().await.client_certificate.clone()
+    });
+
+    let tls_client = TlsClient::from(create_client_cert_client_config("ftpoq-1"));
+    let client = Client::builder()
+      .with_tls(tls_client)
+      .expect("Client requires valid TLS settings!")
+      .with_io(LOCALHOST)
+      .expect("Client requires valid I/O settings!")
+      .start()
+      .expect("Client must be able to start");
+    let connect = Connect::new(addr).with_server_name("localhost");
+    let _connection = timeout(Duration::from_secs(2), client.connect(connect))
+      .await
+      .unwrap()
+      .expect("Client failed to connect to the server!");
+
+    let certificate = timeout(Duration::from_secs(3), handler_fut)
+      .await
+      .expect("Handler future failed to finish!")
+      .unwrap()
+      .expect("Client certificate should be recorded");
+    assert_eq!("O=foq, CN=test-client", certificate.subject);
+  }
 }
@@ -3,22 +3,26 @@ use std::sync::Arc;
 use std::time::Duration;
 
 use async_trait::async_trait;
-use futures::future::join_all;
 use quinn::{Connection, RecvStream, SendStream};
+use rustls::pki_types::CertificateDer;
 use tokio::io::{AsyncBufReadExt, BufReader};
 use tokio::sync::{Mutex, RwLock};
-use tokio::task::JoinHandle;
-use tokio::time::timeout;
+use tokio::time::{Instant, timeout};
 use tokio_util::sync::CancellationToken;
 use tracing::{debug, info, warn};
 
+use crate::auth::client_certificate::ClientCertificate;
 use crate::commands::reply::Reply;
 use crate::commands::reply_code::ReplyCode;
 use crate::data_channels::data_channel_wrapper::DataChannelWrapper;
 use crate::data_channels::quic_quinn_data_channel_wrapper::QuicQuinnDataChannelWrapper;
+use crate::global_context::TIMEOUT_SETTINGS;
 use crate::handlers::connection_handler::ConnectionHandler;
 use crate::handlers::reply_sender::{ReplySend, ReplySender};
 use crate::session::command_processor::CommandProcessor;
+use crate::session::command_scheduler::CommandScheduler;
+use crate::session::connection_registry::SessionRegistration;
+use crate::session::connection_security::ConnectionSecurity;
 use crate::session::session_properties::SessionProperties;
 
 /// Represents the networking part of clients session for QUIC.
@@ -31,7 +35,8 @@ pub(crate) struct QuicQuinnConnectionHandler {
   control_channel: Option<BufReader<RecvStream>>,
   reply_sender: Option<Arc<ReplySender<SendStream>>>,
   session_properties: Arc<RwLock<SessionProperties>>,
-  running_commands: Vec<JoinHandle<()>>,
+  command_scheduler: CommandScheduler<ReplySender<SendStream>>,
+  login_deadline: Option<Instant>,
 }
 
 impl QuicQuinnConnectionHandler {
@@ -42,13 +47,21 @@ impl QuicQuinnConnectionHandler {
   ///
   pub(crate) fn new(connection: Connection) -> Self {
     let addr = SocketAddr::new(connection.local_ip().unwrap(), 0);
+    let client_certificate = connection
+      .peer_identity()
+      .and_then(|identity| identity.downcast::<Vec<CertificateDer<'static>>>().ok())
+      .and_then(|chain| ClientCertificate::from_chain(&chain));
     let connection = Arc::new(Mutex::new(connection));
     let wrapper = Arc::new(QuicQuinnDataChannelWrapper::new(addr, connection.clone()));
 
-    let session_properties = Arc::new(RwLock::new(SessionProperties::new()));
+    let mut session_properties = SessionProperties::new();
+    session_properties.idle_timeout = TIMEOUT_SETTINGS.idle;
+    session_properties.connection_security = ConnectionSecurity::Quic;
+    session_properties.client_certificate = client_certificate;
+    let session_properties = Arc::new(RwLock::new(session_properties));
     let command_processor =
       Arc::new(CommandProcessor::new(session_properties.clone(), wrapper.clone()));
-    let running_commands = Vec::with_capacity(10);
+    let command_scheduler = CommandScheduler::new(command_processor.clone());
     QuicQuinnConnectionHandler {
       connection,
       data_channel_wrapper: wrapper,
@@ -56,7 +69,8 @@ impl QuicQuinnConnectionHandler {
       control_channel: None,
       reply_sender: None,
       session_properties,
-      running_commands,
+      command_scheduler,
+      login_deadline: TIMEOUT_SETTINGS.login.map(|t| Instant::now() + t),
     }
   }
 
@@ -65,7 +79,7 @@ impl QuicQuinnConnectionHandler {
   /// Reads data from the client until newline. If the connection closes, this returns an [`error`].
   /// Otherwise, it will return [`Ok(())`].
   ///
-  /// After reading clients message, it sent for evaluation to [`CommandProcessor`].
+  /// After reading clients message, it is scheduled for evaluation by [`CommandScheduler`].
   ///
   /// [`error`]: anyhow::Error
   ///
@@ -87,11 +101,7 @@ impl QuicQuinnConnectionHandler {
       }
     };
 
-    let command_processor = self.command_processor.clone();
-    let task = command_processor.evaluate(buf, self.reply_sender.clone().unwrap());
-    self.running_commands.push(tokio::spawn(task));
-    self.running_commands.retain(|c| !c.is_finished());
-    debug!("Running commands: {}", self.running_commands.len());
+    self.command_scheduler.schedule(buf, self.reply_sender.clone().unwrap());
     Ok(true)
   }
 
@@ -130,13 +140,25 @@ impl ConnectionHandler for QuicQuinnConnectionHandler {
     debug!("[QUINN] Sending hello to client.");
     let _ = &mut self.reply_sender.as_mut().unwrap().send_control_message(hello).await;
 
+    let closing = self.command_processor.close_token.clone();
     loop {
+      let expiry = self.command_scheduler.expiry(self.login_deadline);
       tokio::select! {
         biased;
         _ = token.cancelled() => {
           info!("[QUINN] Shutdown received!");
           break;
         }
+        _ = closing.cancelled() => {
+          debug!("[QUINN] Client quit.");
+          break;
+        }
+        reason = expiry => {
+          info!("[QUINN] {reason}");
+          let reply = Reply::new(ReplyCode::ServiceNotAvailableClosingControlConnection, reason);
+          self.reply_sender.as_ref().unwrap().send_control_message(reply).await;
+          break;
+        }
         result = self.await_command() => {
           match result {
             Ok(true) => {},
@@ -152,13 +174,24 @@ impl ConnectionHandler for QuicQuinnConnectionHandler {
     self.cleanup().await;
     Ok(())
   }
+
+  async fn register(&mut self, registration: SessionRegistration) {
+    self.session_properties.write().await.registration.replace(registration);
+  }
+
+  async fn reject(&mut self, reply: Reply) -> Result<(), anyhow::Error> {
+    self.create_control_channel().await?;
+    debug!("[QUINN] Rejecting connection.");
+    self.reply_sender.as_ref().unwrap().send_control_message(reply).await;
+    self.cleanup().await;
+    Ok(())
+  }
 }
 
 impl QuicQuinnConnectionHandler {
   async fn cleanup(&mut self) {
     info!("[QUINN] Shutdown received!");
-    let commands_to_finish = join_all(std::mem::take(&mut self.running_commands));
-    if timeout(Duration::from_secs(5), commands_to_finish).await.is_err() {
+    if timeout(Duration::from_secs(5), self.command_scheduler.finish()).await.is_err() {
       warn!("[QUINN] Failed to finish processing running commands in time!");
     }
     if timeout(Duration::from_secs(2), self.reply_sender.as_mut().unwrap().close()).await.is_err() {
@@ -177,15 +210,16 @@ impl QuicQuinnConnectionHandler {
 
 impl Drop for QuicQuinnConnectionHandler {
   fn drop(&mut self) {
-    debug!("[QUINN] aborting {} commands", self.running_commands.len());
-    self.running_commands.iter().for_each(|c| c.abort());
+    self.command_scheduler.abort();
   }
 }
 
 #[cfg(test)]
 mod tests {
   use super::*;
+  use crate::listeners::quinn_listener::QuinnListener;
   use crate::utils::test_utils::*;
+  use crate::utils::tls_utils::ClientAuth;
   use s2n_quic::client::Connect;
 
   #[tokio::test]
@@ -267,4 +301,31 @@ mod tests {
       panic!("Handler future failed to finish!");
     };
   }
+
+  #[tokio::test]
+  async fn client_certificate_test() {
+    setup_tracing();
+    let server_config = Arc::new(create_client_auth_server_config(ClientAuth::Optional));
+    let listener = QuinnListener::with_tls_config(LOCALHOST, server_config).unwrap();
+    let addr = listener.listener.local_addr().unwrap();
+    let handler_fut = tokio::spawn(async move {
+      let incoming = listener.accept(CancellationToken::new()).await.unwrap();
+      let handler = QuicQuinnConnectionHandler::new(incoming.await.unwrap());
+      handler.session_properties.read().await.client_certificate.clone()
+    });
+
+    let quinn_client = setup_quinn_client(create_client_cert_client_config("ftpoq-1"));
+    let _connection =
+      timeout(Duration::from_secs(2), quinn_client.connect(addr, "localhost").unwrap())
+        .await
+        .unwrap()
+        .expect("Client failed to connect to the server!");
+
+    let certificate = timeout(Duration::from_secs(3), handler_fut)
+      .await
+      .expect("Handler future failed to finish!")
+      .unwrap()
+      .expect("Client certificate should be recorded");
+    assert_eq!("O=foq, CN=test-client", certificate.subject);
+  }
 }
@@ -25,6 +25,19 @@ impl<T: AsyncWrite + Sync + Send + Unpin> ReplySender<T> {
       writer: Arc::new(Mutex::new(BufWriter::new(writer))),
     }
   }
+
+  /// Replaces the underlying writer and returns the previous one.
+  ///
+  /// Pending data is flushed to the previous writer first. This is used to upgrade the control
+  /// channel, e.g.: after AUTH TLS.
+  ///
+  pub(crate) async fn replace_writer(&self, writer: T) -> T {
+    let mut current = self.writer.lock().await;
+    if let Err(e) = current.flush().await {
+      warn!("Failed to flush reply! Error: {}", e);
+    }
+    std::mem::replace(&mut *current, BufWriter::new(writer)).into_inner()
+  }
 }
 
 #[async_trait]
@@ -57,7 +70,7 @@ impl<T: AsyncWrite + Sync + Send + Unpin> ReplySend for ReplySender<T> {
 ///
 /// Implementors must be thread-safe.
 #[async_trait]
-pub(crate) trait ReplySend: Sync + Send {
+pub trait ReplySend: Sync + Send {
   async fn send_control_message(&self, reply: Reply);
   async fn close(&self) -> Result<(), Error>;
 }
@@ -3,35 +3,50 @@ use std::sync::Arc;
 use std::time::Duration;
 
 use async_trait::async_trait;
-use futures::future::join_all;
 
 use tokio::io::{AsyncBufReadExt, BufReader, ReadHalf, WriteHalf};
 use tokio::net::TcpStream;
 use tokio::sync::RwLock;
-use tokio::task::JoinHandle;
-use tokio::time::timeout;
+use tokio::time::{Instant, timeout};
+use tokio_rustls::TlsAcceptor;
 use tokio_util::sync::CancellationToken;
 use tracing::{debug, error, info, warn};
+use zeroize::Zeroize;
 
+use crate::auth::client_certificate::ClientCertificate;
+use crate::commands::command::Command;
+use crate::commands::commands::Commands;
+use crate::commands::r#impl::auth::validate_mechanism;
 use crate::commands::reply::Reply;
 use crate::commands::reply_code::ReplyCode;
 use crate::data_channels::data_channel_wrapper::DataChannelWrapper;
 use crate::data_channels::standard_data_channel_wrapper::StandardDataChannelWrapper;
-use crate::handlers::connection_handler::ConnectionHandler;
+use crate::global_context::{PASSIVE_SETTINGS, TIMEOUT_SETTINGS, TLS_CONFIG};
+use crate::handlers::connection_handler::{AsyncReadWrite, ConnectionHandler};
 use crate::handlers::reply_sender::{ReplySend, ReplySender};
 use crate::session::command_processor::CommandProcessor;
+use crate::session::command_scheduler::CommandScheduler;
+use crate::session::connection_registry::SessionRegistration;
+use crate::session::connection_security::ConnectionSecurity;
 use crate::session::session_properties::SessionProperties;
 
+/// The control connection, which is either cleartext TCP or TLS after AUTH TLS.
+type ControlStream = Box<dyn AsyncReadWrite>;
+
 /// Represents the networking part of clients session for TCP.
 ///
+/// The control channel can be upgraded to TLS using AUTH TLS (explicit FTPS).
+///
 #[allow(unused)]
 pub(crate) struct StandardConnectionHandler {
   data_channel_wrapper: Arc<StandardDataChannelWrapper>,
   command_processor: Arc<CommandProcessor>,
-  control_channel: BufReader<ReadHalf<TcpStream>>,
-  reply_sender: Arc<ReplySender<WriteHalf<TcpStream>>>,
+  control_channel: BufReader<ReadHalf<ControlStream>>,
+  reply_sender: Arc<ReplySender<WriteHalf<ControlStream>>>,
   session_properties: Arc<RwLock<SessionProperties>>,
-  running_commands: Vec<JoinHandle<()>>,
+  command_scheduler: CommandScheduler<ReplySender<WriteHalf<ControlStream>>>,
+  login_deadline: Option<Instant>,
+  secured: bool,
 }
 
 impl StandardConnectionHandler {
@@ -43,21 +58,31 @@ impl StandardConnectionHandler {
   /// construct [`ReplySender`], the reader will be used to read messages from client.
   ///
   pub(crate) fn new(stream: TcpStream) -> Self {
-    let wrapper = Arc::new(StandardDataChannelWrapper::new(stream.local_addr().unwrap()));
-    let stream_halves = tokio::io::split(stream);
+    let wrapper = Arc::new(
+      StandardDataChannelWrapper::new(
+        stream.local_addr().unwrap(),
+        stream.peer_addr().unwrap().ip(),
+      )
+      .with_passive_settings(PASSIVE_SETTINGS.clone()),
+    );
+    let stream_halves = tokio::io::split(Box::new(stream) as ControlStream);
     let control_channel = BufReader::new(stream_halves.0);
     let reply_sender = Arc::new(ReplySender::new(stream_halves.1));
-    let session_properties = Arc::new(RwLock::new(SessionProperties::new()));
+    let mut session_properties = SessionProperties::new();
+    session_properties.idle_timeout = TIMEOUT_SETTINGS.idle;
+    let session_properties = Arc::new(RwLock::new(session_properties));
     let command_processor =
       Arc::new(CommandProcessor::new(session_properties.clone(), wrapper.clone()));
-    let running_commands = Vec::with_capacity(10);
+    let command_scheduler = CommandScheduler::new(command_processor.clone());
     StandardConnectionHandler {
       data_channel_wrapper: wrapper,
       command_processor,
       control_channel,
       reply_sender,
       session_properties,
-      running_commands,
+      command_scheduler,
+      login_deadline: TIMEOUT_SETTINGS.login.map(|t| Instant::now() + t),
+      secured: false,
     }
   }
 
@@ -66,7 +91,7 @@ impl StandardConnectionHandler {
   /// Reads data from the client until newline. If the connection closes, this returns an [`error`].
   /// Otherwise it will return [`Ok(())`].
   ///
-  /// After reading clients message, it sent for evaluation to [`CommandProcessor`].
+  /// After reading clients message, it is scheduled for evaluation by [`CommandScheduler`].
   ///
   /// [`error`]: anyhow::Error
   ///
@@ -91,11 +116,12 @@ impl StandardConnectionHandler {
       }
     };
 
-    let command_processor = self.command_processor.clone();
-    let task = command_processor.evaluate(buf, self.reply_sender.clone());
-    self.running_commands.push(tokio::spawn(task));
-    self.running_commands.retain(|t| !t.is_finished());
-    debug!("Running commands: {}", self.running_commands.len());
+    if !self.secured && Self::is_auth(&buf) {
+      self.upgrade_to_tls(buf).await?;
+      return Ok(true);
+    }
+
+    self.command_scheduler.schedule(buf, self.reply_sender.clone());
     Ok(true)
   }
 }
@@ -109,12 +135,24 @@ impl ConnectionHandler for StandardConnectionHandler {
     let hello = Reply::new(ReplyCode::ServiceReady, "Hello");
     debug!("[TCP] Sending hello to client.");
     self.reply_sender.send_control_message(hello).await;
+    let closing = self.command_processor.close_token.clone();
     loop {
+      let expiry = self.command_scheduler.expiry(self.login_deadline);
       tokio::select! {
         biased;
         _ = token.cancelled() => {
           break;
         }
+        _ = closing.cancelled() => {
+          debug!("[TCP] Client quit.");
+          break;
+        }
+        reason = expiry => {
+          info!("[TCP] {reason}");
+          let reply = Reply::new(ReplyCode::ServiceNotAvailableClosingControlConnection, reason);
+          self.reply_sender.send_control_message(reply).await;
+          break;
+        }
         result = self.await_command() => {
           match result {
             Ok(true) => {},
@@ -130,15 +168,116 @@ impl ConnectionHandler for StandardConnectionHandler {
     self.cleanup().await;
     Ok(())
   }
+
+  async fn register(&mut self, registration: SessionRegistration) {
+    self.session_properties.write().await.registration.replace(registration);
+  }
+
+  async fn reject(&mut self, reply: Reply) -> Result<(), anyhow::Error> {
+    debug!("[TCP] Rejecting connection.");
+    self.reply_sender.send_control_message(reply).await;
+    self.cleanup().await;
+    Ok(())
+  }
 }
 
 impl StandardConnectionHandler {
+  fn is_auth(message: &str) -> bool {
+    message.trim().parse::<Command>().is_ok_and(|c| c.command == Commands::Auth)
+  }
+
+  /// Upgrades the control channel to TLS, as specified by
+  /// [RFC4217](https://datatracker.ietf.org/doc/html/rfc4217#section-4).
+  ///
+  /// Waits for the running commands to finish, replies 234 and performs the TLS handshake using
+  /// [`TLS_CONFIG`]. The session (login, views, etc.) continues on the upgraded connection. If the
+  /// commands don't finish in time, e.g.: during a long transfer, the upgrade is refused, as their
+  /// replies would be lost.
+  ///
+  /// Commands the client sent after AUTH, before the handshake, are not allowed, since they
+  /// could have been injected into the cleartext connection. In that case the upgrade is refused.
+  ///
+  /// # Errors
+  ///
+  /// Returns an error if the TLS handshake fails, in which case the connection is unusable.
+  ///
+  #[tracing::instrument(skip_all)]
+  async fn upgrade_to_tls(&mut self, mut message: String) -> Result<(), anyhow::Error> {
+    let command = message.trim().parse::<Command>();
+    message.zeroize();
+    let Ok(command) = command else {
+      return Ok(());
+    };
+
+    if let Err(reply) = validate_mechanism(&command.argument) {
+      self.reply_sender.send_control_message(reply).await;
+      return Ok(());
+    }
+
+    let Some(acceptor) = TLS_CONFIG.clone().map(TlsAcceptor::from) else {
+      self
+        .reply_sender
+        .send_control_message(Reply::new(ReplyCode::AuthNotAvailable, "TLS not available"))
+        .await;
+      return Ok(());
+    };
+
+    if timeout(Duration::from_secs(5), self.command_scheduler.finish()).await.is_err() {
+      warn!("[TCP] Commands still running, refusing AUTH!");
+      self
+        .reply_sender
+        .send_control_message(Reply::new(
+          ReplyCode::BadSequenceOfCommands,
+          "Commands or transfers are still running, send AUTH after they finish!",
+        ))
+        .await;
+      return Ok(());
+    }
+
+    if !self.control_channel.buffer().is_empty() {
+      warn!("[TCP] Client sent commands before TLS handshake, refusing AUTH!");
+      self
+        .reply_sender
+        .send_control_message(Reply::new(
+          ReplyCode::BadSequenceOfCommands,
+          "No commands may follow AUTH before the TLS handshake!",
+        ))
+        .await;
+      return Ok(());
+    }
+
+    self
+      .reply_sender
+      .send_control_message(Reply::new(
+        ReplyCode::SecurityDataExchangeComplete,
+        "AUTH TLS successful",
+      ))
+      .await;
+
+    let placeholder = tokio::io::split(Box::new(tokio::io::empty()) as ControlStream);
+    let reader =
+      std::mem::replace(&mut self.control_channel, BufReader::new(placeholder.0)).into_inner();
+    let writer = self.reply_sender.replace_writer(placeholder.1).await;
+    let stream = reader.unsplit(writer);
+
+    debug!("[TCP] Performing TLS handshake.");
+    let tls_stream = timeout(Duration::from_secs(10), acceptor.accept(stream)).await??;
+    let client_certificate =
+      tls_stream.get_ref().1.peer_certificates().and_then(ClientCertificate::from_chain);
+    let stream_halves = tokio::io::split(Box::new(tls_stream) as ControlStream);
+    self.control_channel = BufReader::new(stream_halves.0);
+    self.reply_sender.replace_writer(stream_halves.1).await;
+    self.secured = true;
+    let mut session_properties = self.session_properties.write().await;
+    session_properties.connection_security = ConnectionSecurity::Tls;
+    session_properties.client_certificate = client_certificate;
+    info!("[TCP] Control channel upgraded to TLS.");
+    Ok(())
+  }
+
   async fn cleanup(&mut self) {
     info!("[TCP] Shutdown received!");
-    let mut commands_to_finish = std::mem::take(&mut self.running_commands);
-    commands_to_finish.retain(|t| !t.is_finished());
-    debug!("[TCP] Commands to finish: {:?}", commands_to_finish);
-    if timeout(Duration::from_secs(5), join_all(commands_to_finish)).await.is_err() {
+    if timeout(Duration::from_secs(5), self.command_scheduler.finish()).await.is_err() {
       warn!("[TCP] Failed to finish processing running commands in time!");
     } else {
       debug!("[TCP] Finished processing running tasks");
@@ -164,8 +303,7 @@ impl StandardConnectionHandler {
 
 impl Drop for StandardConnectionHandler {
   fn drop(&mut self) {
-    debug!("[TCP] aborting {} commands", self.running_commands.len());
-    self.running_commands.iter().for_each(|t| t.abort());
+    self.command_scheduler.abort();
   }
 }
 
@@ -173,11 +311,18 @@ impl Drop for StandardConnectionHandler {
 mod tests {
   use std::time::Duration;
 
-  use tokio::io::{AsyncBufReadExt, BufReader};
+  use std::net::SocketAddr;
+  use std::sync::Arc;
+
+  use rustls::pki_types::ServerName;
+  use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
   use tokio::net::TcpStream;
+  use tokio::task::JoinHandle;
   use tokio::time::timeout;
+  use tokio_rustls::TlsConnector;
   use tokio_util::sync::CancellationToken;
 
+  use crate::commands::reply::Reply;
   use crate::commands::reply_code::ReplyCode;
   use crate::handlers::connection_handler::ConnectionHandler;
   use crate::handlers::standard_connection_handler::StandardConnectionHandler;
@@ -222,4 +367,167 @@ mod tests {
       panic!("Handler future failed to finish!");
     };
   }
+
+  #[tokio::test]
+  async fn reject_test() {
+    setup_tracing();
+    let mut listener = StandardListener::new(LOCALHOST).await.unwrap();
+    let addr = listener.listener.local_addr().unwrap();
+    let handler_fut = tokio::spawn(async move {
+      let (server_cc, _) = listener.accept(CancellationToken::new()).await.unwrap();
+      let mut handler = StandardConnectionHandler::new(server_cc);
+      let reply = Reply::new(ReplyCode::ServiceNotAvailableClosingControlConnection, "Rejected");
+      handler.reject(reply).await.expect("Handler should exit gracefully");
+    });
+
+    let client_cc =
+      timeout(Duration::from_secs(2), TcpStream::connect(addr)).await.unwrap().unwrap();
+    let mut client_cc = BufReader::new(client_cc);
+    read_reply(&mut client_cc, ReplyCode::ServiceNotAvailableClosingControlConnection).await;
+    let mut buffer = String::new();
+    let len = timeout(Duration::from_secs(3), client_cc.read_line(&mut buffer)).await.unwrap();
+    assert_eq!(0, len.unwrap(), "Connection should be closed");
+
+    timeout(Duration::from_secs(10), handler_fut)
+      .await
+      .expect("Handler future should finish in time")
+      .unwrap();
+  }
+
+  async fn run_handler(token: CancellationToken) -> (JoinHandle<()>, SocketAddr) {
+    let mut listener = StandardListener::new(LOCALHOST).await.unwrap();
+    let addr = listener.listener.local_addr().unwrap();
+    let handler_fut = tokio::spawn(async move {
+      let (server_cc, _) = listener.accept(token.clone()).await.unwrap();
+      let mut handler = StandardConnectionHandler::new(server_cc);
+
+      handler.handle(token).await.expect("Handler should exit gracefully");
+    });
+    (handler_fut, addr)
+  }
+
+  async fn read_reply<T: AsyncRead + Unpin>(reader: &mut BufReader<T>, code: ReplyCode) {
+    let mut buffer = String::new();
+    timeout(Duration::from_secs(3), reader.read_line(&mut buffer))
+      .await
+      .expect("Reply should arrive in time")
+      .expect("Reply should be readable");
+    tracing_print!("Received reply from server!: {}", buffer.trim());
+    assert!(buffer.starts_with(&(code as u32).to_string()), "Unexpected reply: {buffer}");
+  }
+
+  async fn send<T: AsyncWrite + Unpin>(stream: &mut T, message: &str) {
+    stream.write_all(message.as_bytes()).await.unwrap();
+    stream.flush().await.unwrap();
+  }
+
+  #[tokio::test]
+  async fn auth_tls_test() {
+    setup_tracing();
+    let token = CancellationToken::new();
+    let (handler_fut, addr) = run_handler(token.clone()).await;
+
+    let client_cc =
+      timeout(Duration::from_secs(2), TcpStream::connect(addr)).await.unwrap().unwrap();
+    let mut client_cc = BufReader::new(client_cc);
+    read_reply(&mut client_cc, ReplyCode::ServiceReady).await;
+
+    send(client_cc.get_mut(), "AUTH TLS\r\n").await;
+    read_reply(&mut client_cc, ReplyCode::SecurityDataExchangeComplete).await;
+
+    let connector = TlsConnector::from(Arc::new(create_tls_client_config("ftpoq-1")));
+    let client_cc = timeout(
+      Duration::from_secs(3),
+      connector.connect(ServerName::try_from("ftp.vanter.me").unwrap(), client_cc.into_inner()),
+    )
+    .await
+    .expect("TLS handshake should finish in time")
+    .expect("TLS handshake should succeed");
+    let mut client_cc = BufReader::new(client_cc);
+
+    send(client_cc.get_mut(), "NOOP\r\n").await;
+    read_reply(&mut client_cc, ReplyCode::CommandOkay).await;
+
+    send(client_cc.get_mut(), "PROT P\r\n").await;
+    read_reply(&mut client_cc, ReplyCode::BadSequenceOfCommands).await;
+    send(client_cc.get_mut(), "PBSZ 0\r\nPROT P\r\n").await;
+    read_reply(&mut client_cc, ReplyCode::CommandOkay).await;
+    read_reply(&mut client_cc, ReplyCode::CommandOkay).await;
+
+    send(client_cc.get_mut(), "AUTH TLS\r\n").await;
+    read_reply(&mut client_cc, ReplyCode::BadSequenceOfCommands).await;
+
+    token.cancel();
+    timeout(Duration::from_secs(10), handler_fut)
+      .await
+      .expect("Handler future should finish in time")
+      .unwrap();
+  }
+
+  #[tokio::test]
+  async fn auth_pipelined_commands_test() {
+    setup_tracing();
+    let token = CancellationToken::new();
+    let (handler_fut, addr) = run_handler(token.clone()).await;
+
+    let client_cc =
+      timeout(Duration::from_secs(2), TcpStream::connect(addr)).await.unwrap().unwrap();
+    let mut client_cc = BufReader::new(client_cc);
+    read_reply(&mut client_cc, ReplyCode::ServiceReady).await;
+
+    send(client_cc.get_mut(), "AUTH TLS\r\nNOOP\r\n").await;
+    read_reply(&mut client_cc, ReplyCode::BadSequenceOfCommands).await;
+    read_reply(&mut client_cc, ReplyCode::CommandOkay).await;
+
+    token.cancel();
+    timeout(Duration::from_secs(10), handler_fut)
+      .await
+      .expect("Handler future should finish in time")
+      .unwrap();
+  }
+
+  #[tokio::test]
+  async fn quit_test() {
+    setup_tracing();
+    let (handler_fut, addr) = run_handler(CancellationToken::new()).await;
+
+    let client_cc =
+      timeout(Duration::from_secs(2), TcpStream::connect(addr)).await.unwrap().unwrap();
+    let mut client_cc = BufReader::new(client_cc);
+    read_reply(&mut client_cc, ReplyCode::ServiceReady).await;
+
+    // Commands after QUIT are not executed
+    send(client_cc.get_mut(), "NOOP\r\nQUIT\r\nNOOP\r\n").await;
+    read_reply(&mut client_cc, ReplyCode::CommandOkay).await;
+    read_reply(&mut client_cc, ReplyCode::ClosingControlConnection).await;
+    let mut buffer = String::new();
+    let len = timeout(Duration::from_secs(3), client_cc.read_line(&mut buffer)).await.unwrap();
+    assert_eq!(0, len.unwrap(), "Connection should be closed");
+
+    timeout(Duration::from_secs(10), handler_fut)
+      .await
+      .expect("Handler future should finish in time")
+      .unwrap();
+  }
+
+  #[tokio::test]
+  async fn auth_unsupported_mechanism_test() {
+    setup_tracing();
+    let token = CancellationToken::new();
+    let (handler_fut, addr) = run_handler(token.clone()).await;
+
+    let client_cc =
+      timeout(Duration::from_secs(2), TcpStream::connect(addr)).await.unwrap().unwrap();
+    let mut client_cc = BufReader::new(client_cc);
+    read_reply(&mut client_cc, ReplyCode::ServiceReady).await;
+
+    send(client_cc.get_mut(), "AUTH GSSAPI\r\n").await;
+    read_reply(&mut client_cc, ReplyCode::CommandNotImplementedForThatParameter).await;
+
+    token.cancel();
+    timeout(Duration::from_secs(10), handler_fut)
+      .await
+      .expect("Handler future should finish in time")
+      .unwrap();
+  }
 }
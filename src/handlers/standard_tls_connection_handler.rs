@@ -3,23 +3,26 @@ use std::sync::Arc;
 use std::time::Duration;
 
 use async_trait::async_trait;
-use futures::future::join_all;
 use tokio::io::{AsyncBufReadExt, BufReader, ReadHalf, WriteHalf};
 use tokio::net::TcpStream;
 use tokio::sync::RwLock;
-use tokio::task::JoinHandle;
-use tokio::time::timeout;
+use tokio::time::{Instant, timeout};
 use tokio_rustls::server::TlsStream;
 use tokio_util::sync::CancellationToken;
 use tracing::{debug, error, info, warn};
 
+use crate::auth::client_certificate::ClientCertificate;
 use crate::commands::reply::Reply;
 use crate::commands::reply_code::ReplyCode;
 use crate::data_channels::data_channel_wrapper::DataChannelWrapper;
 use crate::data_channels::standard_data_channel_wrapper::StandardDataChannelWrapper;
+use crate::global_context::{PASSIVE_SETTINGS, TIMEOUT_SETTINGS};
 use crate::handlers::connection_handler::ConnectionHandler;
 use crate::handlers::reply_sender::{ReplySend, ReplySender};
 use crate::session::command_processor::CommandProcessor;
+use crate::session::command_scheduler::CommandScheduler;
+use crate::session::connection_registry::SessionRegistration;
+use crate::session::connection_security::ConnectionSecurity;
 use crate::session::session_properties::SessionProperties;
 
 /// Represents the networking part of clients session for TCP+TLS.
@@ -31,7 +34,8 @@ pub(crate) struct StandardTlsConnectionHandler {
   control_channel: BufReader<ReadHalf<TlsStream<TcpStream>>>,
   reply_sender: Arc<ReplySender<WriteHalf<TlsStream<TcpStream>>>>,
   session_properties: Arc<RwLock<SessionProperties>>,
-  running_commands: Vec<JoinHandle<()>>,
+  command_scheduler: CommandScheduler<ReplySender<WriteHalf<TlsStream<TcpStream>>>>,
+  login_deadline: Option<Instant>,
 }
 
 impl StandardTlsConnectionHandler {
@@ -43,22 +47,34 @@ impl StandardTlsConnectionHandler {
   /// construct [`ReplySender`], the reader will be used to read messages from client.
   ///
   pub(crate) fn new(stream: TlsStream<TcpStream>) -> Self {
-    let wrapper =
-      Arc::new(StandardDataChannelWrapper::new(stream.get_ref().0.local_addr().unwrap()));
+    let client_certificate =
+      stream.get_ref().1.peer_certificates().and_then(ClientCertificate::from_chain);
+    let wrapper = Arc::new(
+      StandardDataChannelWrapper::new(
+        stream.get_ref().0.local_addr().unwrap(),
+        stream.get_ref().0.peer_addr().unwrap().ip(),
+      )
+      .with_passive_settings(PASSIVE_SETTINGS.clone()),
+    );
     let stream_halves = tokio::io::split(stream);
     let control_channel = BufReader::new(stream_halves.0);
     let reply_sender = Arc::new(ReplySender::new(stream_halves.1));
-    let session_properties = Arc::new(RwLock::new(SessionProperties::new()));
+    let mut session_properties = SessionProperties::new();
+    session_properties.idle_timeout = TIMEOUT_SETTINGS.idle;
+    session_properties.connection_security = ConnectionSecurity::Tls;
+    session_properties.client_certificate = client_certificate;
+    let session_properties = Arc::new(RwLock::new(session_properties));
     let command_processor =
       Arc::new(CommandProcessor::new(session_properties.clone(), wrapper.clone()));
-    let running_commands = Vec::with_capacity(10);
+    let command_scheduler = CommandScheduler::new(command_processor.clone());
     StandardTlsConnectionHandler {
       data_channel_wrapper: wrapper,
       command_processor,
       control_channel,
       reply_sender,
       session_properties,
-      running_commands,
+      command_scheduler,
+      login_deadline: TIMEOUT_SETTINGS.login.map(|t| Instant::now() + t),
     }
   }
 
@@ -67,7 +83,7 @@ impl StandardTlsConnectionHandler {
   /// Reads data from the client until newline. If the connection closes, this returns an [`error`].
   /// Otherwise it will return [`Ok(())`].
   ///
-  /// After reading clients message, it sent for evaluation to [`CommandProcessor`].
+  /// After reading clients message, it is scheduled for evaluation by [`CommandScheduler`].
   ///
   /// [`error`]: anyhow::Error
   ///
@@ -92,11 +108,7 @@ impl StandardTlsConnectionHandler {
       }
     };
 
-    let command_processor = self.command_processor.clone();
-    let task = command_processor.evaluate(buf, self.reply_sender.clone());
-    self.running_commands.push(tokio::spawn(task));
-    self.running_commands.retain(|c| !c.is_finished());
-    debug!("Running commands: {}", self.running_commands.len());
+    self.command_scheduler.schedule(buf, self.reply_sender.clone());
     Ok(true)
   }
 }
@@ -111,13 +123,25 @@ impl ConnectionHandler for StandardTlsConnectionHandler {
     debug!("[TCP+TLS] Sending hello to client.");
     self.reply_sender.send_control_message(hello).await;
 
+    let closing = self.command_processor.close_token.clone();
     loop {
+      let expiry = self.command_scheduler.expiry(self.login_deadline);
       tokio::select! {
         biased;
         _ = token.cancelled() => {
           info!("[TCP+TLS] Shutdown received!");
           break;
         }
+        _ = closing.cancelled() => {
+          debug!("[TCP+TLS] Client quit.");
+          break;
+        }
+        reason = expiry => {
+          info!("[TCP+TLS] {reason}");
+          let reply = Reply::new(ReplyCode::ServiceNotAvailableClosingControlConnection, reason);
+          self.reply_sender.send_control_message(reply).await;
+          break;
+        }
         result = self.await_command() => {
           match result {
             Ok(true) => {},
@@ -133,14 +157,23 @@ impl ConnectionHandler for StandardTlsConnectionHandler {
     self.cleanup().await;
     Ok(())
   }
+
+  async fn register(&mut self, registration: SessionRegistration) {
+    self.session_properties.write().await.registration.replace(registration);
+  }
+
+  async fn reject(&mut self, reply: Reply) -> Result<(), anyhow::Error> {
+    debug!("[TCP+TLS] Rejecting connection.");
+    self.reply_sender.send_control_message(reply).await;
+    self.cleanup().await;
+    Ok(())
+  }
 }
 
 impl StandardTlsConnectionHandler {
   async fn cleanup(&mut self) {
     info!("[TCP+TLS] Shutdown received!");
-    let mut commands_to_finish = std::mem::take(&mut self.running_commands);
-    commands_to_finish.retain(|t| !t.is_finished());
-    if timeout(Duration::from_secs(5), join_all(commands_to_finish)).await.is_err() {
+    if timeout(Duration::from_secs(5), self.command_scheduler.finish()).await.is_err() {
       warn!("[TCP+TLS] Failed to finish processing running commands in time!");
     }
     if timeout(Duration::from_secs(2), self.reply_sender.close()).await.is_err() {
@@ -159,8 +192,7 @@ impl StandardTlsConnectionHandler {
 
 impl Drop for StandardTlsConnectionHandler {
   fn drop(&mut self) {
-    debug!("[TCP+TLS] aborting {} commands", self.running_commands.len());
-    self.running_commands.iter().for_each(|c| c.abort());
+    self.command_scheduler.abort();
   }
 }
 
@@ -182,6 +214,7 @@ mod tests {
   use crate::listeners::standard_listener::StandardListener;
   use crate::tracing_print;
   use crate::utils::test_utils::*;
+  use crate::utils::tls_utils::ClientAuth;
 
   #[tokio::test]
   async fn server_hello_test() {
@@ -230,4 +263,56 @@ mod tests {
       panic!("Handler future failed to finish!");
     };
   }
+
+  #[tokio::test]
+  async fn client_certificate_test() {
+    setup_tracing();
+    let mut listener = StandardListener::new(LOCALHOST).await.unwrap();
+    let addr = listener.listener.local_addr().unwrap();
+    let server_config = create_client_auth_server_config(ClientAuth::Required);
+    let server_acceptor = TlsAcceptor::from(Arc::new(server_config));
+    let handler_fut = tokio::spawn(async move {
+      let (server_stream, _) = listener.accept(CancellationToken::new()).await.unwrap();
+      let server_cc = server_acceptor.accept(server_stream).await.unwrap();
+      let handler = StandardTlsConnectionHandler::new(server_cc);
+      handler.session_properties.read().await.client_certificate.clone()
+    });
+
+    let client_stream = TcpStream::connect(addr).await.unwrap();
+    let connector = TlsConnector::from(Arc::new(create_client_cert_client_config("ftpoq-1")));
+    let domain = ServerName::try_from("localhost").unwrap();
+    let _client_cc = timeout(Duration::from_secs(2), connector.connect(domain, client_stream))
+      .await
+      .unwrap()
+      .unwrap();
+
+    let certificate = timeout(Duration::from_secs(3), handler_fut)
+      .await
+      .expect("Handler future failed to finish!")
+      .unwrap()
+      .expect("Client certificate should be recorded");
+    assert_eq!("O=foq, CN=test-client", certificate.subject);
+  }
+
+  #[tokio::test]
+  async fn client_certificate_required_test() {
+    setup_tracing();
+    let mut listener = StandardListener::new(LOCALHOST).await.unwrap();
+    let addr = listener.listener.local_addr().unwrap();
+    let server_config = create_client_auth_server_config(ClientAuth::Required);
+    let server_acceptor = TlsAcceptor::from(Arc::new(server_config));
+    let handler_fut = tokio::spawn(async move {
+      let (server_stream, _) = listener.accept(CancellationToken::new()).await.unwrap();
+      server_acceptor.accept(server_stream).await.is_ok()
+    });
+
+    let client_stream = TcpStream::connect(addr).await.unwrap();
+    let connector = TlsConnector::from(Arc::new(create_test_client_config()));
+    let domain = ServerName::try_from("localhost").unwrap();
+    let _client_cc =
+      timeout(Duration::from_secs(2), connector.connect(domain, client_stream)).await;
+
+    let accepted = timeout(Duration::from_secs(3), handler_fut).await.unwrap().unwrap();
+    assert!(!accepted, "Clients without a certificate should be refused");
+  }
 }
@@ -0,0 +1,222 @@
+//! Line ending handling for the ASCII data type.
+//!
+//! In ASCII mode, files are transferred with CRLF line endings as specified by
+//! [RFC959](https://datatracker.ietf.org/doc/html/rfc959#section-3.1.1.1). Every LF that is not
+//! already preceded by CR is sent as CRLF. Received files are stored with LF line endings.
+//!
+//! Offsets set by REST refer to the data as it was transferred, so they are translated to the
+//! position in the stored file.
+
+use std::pin::Pin;
+use std::task::{Context, Poll, ready};
+
+use tokio::io;
+use tokio::io::{AsyncRead, AsyncReadExt, ReadBuf};
+
+use crate::session::data_type::DataType;
+
+const CR: u8 = b'\r';
+const LF: u8 = b'\n';
+const BUFFER_SIZE: usize = 8192;
+
+/// Direction of the line ending conversion.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub(crate) enum Conversion {
+  /// Used when sending data, every LF that is not preceded by CR is converted to CRLF.
+  ToCrlf,
+  /// Used when receiving data, every CRLF is converted to LF.
+  ToLf,
+}
+
+/// Reader that converts line endings of the data read from the inner reader.
+pub(crate) struct AsciiReader<R> {
+  inner: R,
+  conversion: Conversion,
+  input: Box<[u8]>,
+  output: Vec<u8>,
+  position: usize,
+  previous: u8,
+  pending_cr: bool,
+}
+
+impl<R: AsyncRead + Unpin> AsciiReader<R> {
+  pub(crate) fn new(inner: R, conversion: Conversion) -> Self {
+    AsciiReader {
+      inner,
+      conversion,
+      input: vec![0u8; BUFFER_SIZE].into_boxed_slice(),
+      output: Vec::with_capacity(BUFFER_SIZE * 2),
+      position: 0,
+      previous: 0,
+      pending_cr: false,
+    }
+  }
+}
+
+impl<R: AsyncRead + Unpin> AsyncRead for AsciiReader<R> {
+  fn poll_read(
+    self: Pin<&mut Self>,
+    cx: &mut Context<'_>,
+    buf: &mut ReadBuf<'_>,
+  ) -> Poll<io::Result<()>> {
+    let this = self.get_mut();
+    loop {
+      if this.position < this.output.len() {
+        let len = buf.remaining().min(this.output.len() - this.position);
+        buf.put_slice(&this.output[this.position..this.position + len]);
+        this.position += len;
+        return Poll::Ready(Ok(()));
+      }
+      this.output.clear();
+      this.position = 0;
+
+      let mut input = ReadBuf::new(&mut this.input);
+      ready!(Pin::new(&mut this.inner).poll_read(cx, &mut input))?;
+      let input = input.filled();
+      if input.is_empty() {
+        // A CR at the very end of the data was not followed by LF
+        if this.pending_cr {
+          this.pending_cr = false;
+          this.output.push(CR);
+          continue;
+        }
+        return Poll::Ready(Ok(()));
+      }
+
+      match this.conversion {
+        Conversion::ToCrlf => {
+          for &byte in input {
+            if byte == LF && this.previous != CR {
+              this.output.push(CR);
+            }
+            this.output.push(byte);
+            this.previous = byte;
+          }
+        }
+        Conversion::ToLf => {
+          for &byte in input {
+            if this.pending_cr {
+              this.pending_cr = false;
+              if byte != LF {
+                this.output.push(CR);
+              }
+            }
+            if byte == CR {
+              this.pending_cr = true;
+            } else {
+              this.output.push(byte);
+            }
+          }
+        }
+      }
+    }
+  }
+}
+
+/// Wraps the reader in an [`AsciiReader`] if the data type is ASCII, otherwise the data is read
+/// unchanged.
+pub(crate) fn create_transfer_reader<'a, R>(
+  reader: R,
+  data_type: DataType,
+  conversion: Conversion,
+) -> Box<dyn AsyncRead + Unpin + Send + 'a>
+where
+  R: AsyncRead + Unpin + Send + 'a,
+{
+  match data_type {
+    DataType::Ascii { .. } => Box::new(AsciiReader::new(reader, conversion)),
+    DataType::Binary => Box::new(reader),
+  }
+}
+
+/// Computes the size of the data as it would be sent in ASCII mode.
+///
+/// The size is the number of bytes read plus one for each LF that is not preceded by CR.
+pub(crate) async fn ascii_size<R: AsyncRead + Unpin>(mut reader: R) -> Result<u64, io::Error> {
+  let mut buffer = vec![0u8; BUFFER_SIZE];
+  let mut size = 0u64;
+  let mut previous = 0u8;
+  loop {
+    let len = reader.read(&mut buffer).await?;
+    if len == 0 {
+      return Ok(size);
+    }
+    for &byte in &buffer[..len] {
+      if byte == LF && previous != CR {
+        size += 1;
+      }
+      size += 1;
+      previous = byte;
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use tokio::io::AsyncReadExt;
+
+  use crate::io::ascii::{AsciiReader, Conversion, ascii_size};
+
+  async fn convert(data: &[u8], conversion: Conversion) -> Vec<u8> {
+    let mut output = Vec::new();
+    AsciiReader::new(data, conversion).read_to_end(&mut output).await.unwrap();
+    output
+  }
+
+  #[tokio::test]
+  async fn ascii_size_test() {
+    assert_eq!(0, ascii_size(&b""[..]).await.unwrap());
+    assert_eq!(5, ascii_size(&b"hello"[..]).await.unwrap());
+    assert_eq!(8, ascii_size(&b"ab\ncd\n"[..]).await.unwrap());
+    assert_eq!(8, ascii_size(&b"ab\r\ncd\n"[..]).await.unwrap());
+    assert_eq!(4, ascii_size(&b"\n\n"[..]).await.unwrap());
+    assert_eq!(3, ascii_size(&b"\r\r\n"[..]).await.unwrap());
+  }
+
+  #[tokio::test]
+  async fn ascii_size_buffer_boundary_test() {
+    let mut data = vec![b'a'; 8191];
+    data.extend_from_slice(b"\r\n\n");
+    assert_eq!(data.len() as u64 + 1, ascii_size(&data[..]).await.unwrap());
+  }
+
+  #[tokio::test]
+  async fn to_crlf_test() {
+    assert_eq!(b"".to_vec(), convert(b"", Conversion::ToCrlf).await);
+    assert_eq!(b"ab\r\ncd\r\n".to_vec(), convert(b"ab\ncd\n", Conversion::ToCrlf).await);
+    assert_eq!(b"ab\r\ncd\r\n".to_vec(), convert(b"ab\r\ncd\n", Conversion::ToCrlf).await);
+    assert_eq!(b"\r\r\n\r".to_vec(), convert(b"\r\r\n\r", Conversion::ToCrlf).await);
+  }
+
+  #[tokio::test]
+  async fn to_lf_test() {
+    assert_eq!(b"".to_vec(), convert(b"", Conversion::ToLf).await);
+    assert_eq!(b"ab\ncd\n".to_vec(), convert(b"ab\r\ncd\r\n", Conversion::ToLf).await);
+    assert_eq!(b"ab\ncd".to_vec(), convert(b"ab\ncd", Conversion::ToLf).await);
+    assert_eq!(b"\r\n\r".to_vec(), convert(b"\r\r\n\r", Conversion::ToLf).await);
+  }
+
+  #[tokio::test]
+  async fn conversion_buffer_boundary_test() {
+    let mut data = vec![b'a'; 8191];
+    data.extend_from_slice(b"\r\n\n");
+    let converted = convert(&data, Conversion::ToCrlf).await;
+    assert_eq!(ascii_size(&data[..]).await.unwrap(), converted.len() as u64);
+    assert!(converted.ends_with(b"a\r\n\r\n"));
+
+    let restored = convert(&converted, Conversion::ToLf).await;
+    assert_eq!(8191 + 2, restored.len());
+    assert!(restored.ends_with(b"a\n\n"));
+  }
+
+  #[tokio::test]
+  async fn small_read_buffer_test() {
+    let mut reader = AsciiReader::new(&b"a\nb\n"[..], Conversion::ToCrlf);
+    let mut output = Vec::new();
+    let mut buffer = [0u8; 1];
+    while reader.read(&mut buffer).await.unwrap() > 0 {
+      output.push(buffer[0]);
+    }
+    assert_eq!(b"a\r\nb\r\n".to_vec(), output);
+  }
+}
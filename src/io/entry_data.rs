@@ -3,12 +3,13 @@
 use std::collections::HashSet;
 use std::fmt::{Debug, Display};
 use std::fs::Metadata;
+use std::str::FromStr;
 use std::time::SystemTime;
 
 use chrono::format::{DelayedFormat, StrftimeItems};
 use chrono::{DateTime, Local};
 use strum::EnumMessage;
-use strum_macros::Display;
+use strum_macros::{Display, EnumString};
 
 use crate::auth::user_permission::UserPermission;
 
@@ -25,6 +26,67 @@ pub(crate) enum EntryType {
   Link,
 }
 
+/// Facts that can be sent about an entry in MLST and MLSD replies as specified by
+/// [RFC3659](https://datatracker.ietf.org/doc/html/rfc3659#section-7.5).
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Display, EnumString, Hash)]
+#[strum(serialize_all = "lowercase", ascii_case_insensitive)]
+pub(crate) enum Fact {
+  Size,
+  Type,
+  Modify,
+  Perm,
+}
+
+impl Fact {
+  /// All supported facts, in the order they are sent.
+  pub(crate) const ALL: [Fact; 4] = [Fact::Size, Fact::Type, Fact::Modify, Fact::Perm];
+}
+
+/// The set of facts that are sent to the client, selected by 'OPTS MLST'. All facts are enabled
+/// by default.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub(crate) struct MlstFacts(HashSet<Fact>);
+
+impl MlstFacts {
+  pub(crate) fn contains(&self, fact: &Fact) -> bool {
+    self.0.contains(fact)
+  }
+
+  /// Creates the MLST feature line as specified by
+  /// [RFC3659](https://datatracker.ietf.org/doc/html/rfc3659#section-7.8), enabled facts are
+  /// marked with an asterisk.
+  pub(crate) fn to_feature_string(&self) -> String {
+    let facts = Fact::ALL
+      .iter()
+      .map(|f| format!("{f}{};", if self.contains(f) { "*" } else { "" }))
+      .collect::<String>();
+    format!("MLST {facts}")
+  }
+}
+
+impl Default for MlstFacts {
+  fn default() -> Self {
+    MlstFacts(HashSet::from(Fact::ALL))
+  }
+}
+
+/// Parses the facts from the 'OPTS MLST' argument, e.g.: 'type;size;'. Unsupported facts are
+/// ignored as required by the RFC.
+impl FromStr for MlstFacts {
+  type Err = std::convert::Infallible;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    Ok(MlstFacts(s.split(';').filter_map(|f| Fact::from_str(f.trim()).ok()).collect()))
+  }
+}
+
+/// Formats the enabled facts as a semicolon separated list, e.g.: 'size;type;'.
+impl Display for MlstFacts {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    Fact::ALL.iter().filter(|fact| self.contains(fact)).try_for_each(|fact| write!(f, "{fact};"))
+  }
+}
+
 const MLSD_DATETIME_FORMAT: &str = "%Y%m%d%H%M%S";
 const LIST_DATETIME_FORMAT_TIME: &str = "%b %d %H:%M";
 const LIST_DATETIME_FORMAT_YEAR: &str = "%b %d %Y";
@@ -65,6 +127,16 @@ impl EntryData {
     self.entry_type = new_type;
   }
 
+  pub(crate) fn change_name(&mut self, new_name: impl Into<String>) {
+    self.name = new_name.into();
+  }
+
+  /// Returns a line with only the selected facts and the name of this entry, without the line
+  /// ending. Used for MLST and MLSD replies.
+  pub(crate) fn facts<'a>(&'a self, facts: &'a MlstFacts) -> EntryFacts<'a> {
+    EntryFacts { entry: self, facts }
+  }
+
   /// Constructs a new entry from the metadata of an object.
   ///
   /// Users permissions are filtered to permissions that are relevant for an object.
@@ -159,25 +231,78 @@ impl EntryData {
   }
 }
 
+/// Displays the facts of an entry selected by [`MlstFacts`], see [`EntryData::facts`].
+pub(crate) struct EntryFacts<'a> {
+  entry: &'a EntryData,
+  facts: &'a MlstFacts,
+}
+
+impl Display for EntryFacts<'_> {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    let entry = self.entry;
+    for fact in Fact::ALL.iter().filter(|fact| self.facts.contains(fact)) {
+      match fact {
+        Fact::Size => write!(f, "size={};", entry.size)?,
+        Fact::Type => write!(f, "type={};", entry.entry_type)?,
+        Fact::Modify => {
+          let modify_dt: DateTime<Local> = entry.modify.into();
+          let modify_formatted: DelayedFormat<StrftimeItems> =
+            modify_dt.format(MLSD_DATETIME_FORMAT);
+          write!(f, "modify={};", modify_formatted)?
+        }
+        Fact::Perm => write!(
+          f,
+          "perm={};",
+          entry
+            .perm
+            .iter()
+            .map(|p| p.get_serializations().first().copied().unwrap_or(""))
+            .collect::<String>()
+        )?,
+      }
+    }
+    write!(f, " {}", entry.name)
+  }
+}
+
+/// Formats the entry with all facts as a line of MLSD listing.
 impl Display for EntryData {
   fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-    let mut buffer = String::new();
-    let modify_dt: DateTime<Local> = self.modify.into();
-    let modify_formatted: DelayedFormat<StrftimeItems> = modify_dt.format(MLSD_DATETIME_FORMAT);
-    buffer.push_str(&format!("size={};", self.size));
-    buffer.push_str(&format!("type={};", self.entry_type));
-    buffer.push_str(&format!("modify={};", modify_formatted));
-    buffer.push_str(&format!(
-      "perm={};",
-      self
-        .perm
-        .iter()
-        .map(|p| p.get_serializations().first().copied().unwrap_or(""))
-        .collect::<String>()
-    ));
-    buffer.push_str(&format!(" {}", self.name));
-    buffer.push('\r');
-    buffer.push('\n');
-    write!(f, "{}", buffer)
+    write!(f, "{}\r\n", self.facts(&MlstFacts::default()))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::str::FromStr;
+  use std::time::SystemTime;
+
+  use crate::auth::user_permission::UserPermission;
+  use crate::io::entry_data::{EntryData, EntryType, MlstFacts};
+
+  #[test]
+  fn mlst_facts_parse_test() {
+    let facts = MlstFacts::from_str("type;SIZE;unknown;").unwrap();
+    assert_eq!("size;type;", facts.to_string());
+    assert_eq!("MLST size*;type*;modify;perm;", facts.to_feature_string());
+    assert_eq!("", MlstFacts::from_str("").unwrap().to_string());
+    assert_eq!("size;type;modify;perm;", MlstFacts::default().to_string());
+  }
+
+  #[test]
+  fn facts_display_test() {
+    let entry = EntryData::new(
+      42,
+      EntryType::File,
+      vec![UserPermission::Read],
+      SystemTime::now(),
+      "file.txt",
+    );
+    let facts = MlstFacts::from_str("type;size;").unwrap();
+    assert_eq!("size=42;type=file; file.txt", entry.facts(&facts).to_string());
+    let facts = MlstFacts::from_str("").unwrap();
+    assert_eq!(" file.txt", entry.facts(&facts).to_string());
+    assert!(entry.to_string().starts_with("size=42;type=file;modify="));
+    assert!(entry.to_string().ends_with("perm=r; file.txt\r\n"));
   }
 }
@@ -5,11 +5,12 @@
 use async_trait::async_trait;
 use path_clean::PathClean;
 use std::collections::HashSet;
-use std::fs::{ReadDir, create_dir_all};
+use std::fs::{Permissions, ReadDir, create_dir_all};
 use std::io::ErrorKind;
 use std::path::{Path, PathBuf};
 use tracing::debug;
 use unicode_segmentation::UnicodeSegmentation;
+use walkdir::WalkDir;
 
 use crate::auth::user_permission::UserPermission;
 use crate::io::entry_data::{EntryData, EntryType};
@@ -144,6 +145,187 @@ impl FileSystemView {
     listing.append(&mut entries);
     listing
   }
+
+  /// Resolves a path that is the source or target of a rename, or whose mode is changed.
+  ///
+  /// The path is resolved relative to this view and must be inside it, but must not be the root
+  /// of the view itself.
+  fn resolve_entry_path(&self, path: &str) -> Result<PathBuf, IoError> {
+    let path = self.process_path(path).clean();
+    if !path.starts_with(&self.root) || path == self.root {
+      return Err(IoError::InvalidPathError(String::from("Invalid path!")));
+    }
+    Ok(path)
+  }
+
+  /// Moves a file or directory from this view into another one.
+  ///
+  /// The user needs the rename permission in both views. If the views are on the same file
+  /// system, this is a plain rename. Otherwise, the object is copied into the `target` view and
+  /// then deleted from this one, so the user also needs the create permission in the `target` view
+  /// and the delete permission in this one. An existing object is never overwritten.
+  ///
+  /// # Arguments
+  ///
+  /// - `from`: Path of the object to move, relative to this view.
+  /// - `target`: The [`FileSystemView`] to move the object into.
+  /// - `to`: New path of the object, relative to the `target` view.
+  ///
+  /// # Errors
+  ///
+  /// Same as [`View::rename`], and [`IoError::InvalidPathError`] if `to` already exists, or if
+  /// symbolic links would have to be copied.
+  ///
+  pub(crate) async fn move_to(
+    &self,
+    from: &str,
+    target: &FileSystemView,
+    to: &str,
+  ) -> Result<(), IoError> {
+    if !self.permissions.contains(&UserPermission::Rename)
+      || !target.permissions.contains(&UserPermission::Rename)
+    {
+      return Err(IoError::PermissionError);
+    }
+
+    let from = self.resolve_moved_path(from)?;
+    let to = target.resolve_moved_path(to)?;
+
+    if tokio::fs::symlink_metadata(&from).await.is_err() {
+      return Err(IoError::NotFoundError(String::from("File or directory not found")));
+    }
+    if tokio::fs::symlink_metadata(&to).await.is_ok() {
+      return Err(IoError::InvalidPathError(String::from("File or directory already exists!")));
+    }
+
+    debug!("Moving: {:?} to {:?}", &from, &to);
+
+    match tokio::fs::rename(&from, &to).await {
+      Ok(()) => Ok(()),
+      Err(e) if e.kind() == ErrorKind::CrossesDevices => {
+        debug!("Views are on different file systems, falling back to copy");
+        self.copy_and_delete(&from, target, &to).await
+      }
+      Err(e) => Err(IoError::map_io_error(e)),
+    }
+  }
+
+  /// Resolves a path that is renamed or moved between views, see
+  /// [`FileSystemView::resolve_entry_path`].
+  ///
+  /// The directory containing the object is canonicalized, so the object can't be reached through
+  /// a symbolic link pointing outside the view. The object itself may be a symbolic link, which is
+  /// moved as a link.
+  fn resolve_moved_path(&self, path: &str) -> Result<PathBuf, IoError> {
+    let path = self.resolve_entry_path(path)?;
+    let (Some(parent), Some(name)) = (path.parent(), path.file_name()) else {
+      return Err(IoError::InvalidPathError(String::from("Invalid path!")));
+    };
+    let path = parent.canonicalize().map_err(IoError::map_io_error)?.join(name);
+    if !path.starts_with(&self.root) || path == self.root {
+      return Err(IoError::InvalidPathError(String::from("Invalid path!")));
+    }
+    Ok(path)
+  }
+
+  /// Moves the object at the resolved path `from` to `to` in the `target` view, by copying it and
+  /// deleting the original.
+  async fn copy_and_delete(
+    &self,
+    from: &Path,
+    target: &FileSystemView,
+    to: &Path,
+  ) -> Result<(), IoError> {
+    if !self.permissions.contains(&UserPermission::Delete)
+      || !target.permissions.contains(&UserPermission::Create)
+    {
+      return Err(IoError::PermissionError);
+    }
+
+    copy_recursive(from, to).await?;
+    if tokio::fs::symlink_metadata(from).await.map_err(IoError::map_io_error)?.is_dir() {
+      tokio::fs::remove_dir_all(from).await.map_err(IoError::map_io_error)
+    } else {
+      tokio::fs::remove_file(from).await.map_err(IoError::map_io_error)
+    }
+  }
+
+  /// Changes the mode of a file or directory, e.g.: to 0o644.
+  ///
+  /// The `path` is resolved the same way as in [`FileSystemView::move_to`]. Symbolic links are
+  /// followed, but their target must be inside the view too.
+  ///
+  /// # Errors
+  ///
+  /// This function can return the following [`IoError`] variants:
+  ///
+  /// - [`IoError::PermissionError`]: If the user does not have the change mode permission.
+  /// - [`IoError::InvalidPathError`]: If the `path` or its target is outside the view or is its
+  ///   root.
+  /// - [`IoError::NotFoundError`]: If the `path` does not exist.
+  /// - [`IoError::OsError`]: If the OS reports any other error.
+  ///
+  pub(crate) async fn change_mode(&self, path: &str, mode: u32) -> Result<(), IoError> {
+    if !self.permissions.contains(&UserPermission::ChangeMode) {
+      return Err(IoError::PermissionError);
+    }
+
+    let path = self.resolve_entry_path(path)?.canonicalize().map_err(IoError::map_io_error)?;
+    if !path.starts_with(&self.root) || path == self.root {
+      return Err(IoError::InvalidPathError(String::from("Invalid path!")));
+    }
+
+    debug!("Changing mode of {:?} to {:o}", &path, mode);
+    let permissions =
+      tokio::fs::metadata(&path).await.map_err(IoError::map_io_error)?.permissions();
+    tokio::fs::set_permissions(&path, permissions_with_mode(permissions, mode))
+      .await
+      .map_err(IoError::map_io_error)
+  }
+}
+
+/// Returns the `permissions` with their mode set to `mode`.
+///
+/// Without Unix modes, only the read-only flag is set, if `mode` doesn't allow anyone to write.
+pub(crate) fn permissions_with_mode(mut permissions: Permissions, mode: u32) -> Permissions {
+  #[cfg(unix)]
+  std::os::unix::fs::PermissionsExt::set_mode(&mut permissions, mode);
+  #[cfg(not(unix))]
+  permissions.set_readonly(mode & 0o222 == 0);
+  permissions
+}
+
+/// Copies a file, or a directory with all its contents, from `from` to `to`.
+///
+/// Symbolic links are refused before anything is copied, as following them could copy objects
+/// from outside the view.
+async fn copy_recursive(from: &Path, to: &Path) -> Result<(), IoError> {
+  let metadata = tokio::fs::symlink_metadata(from).await.map_err(IoError::map_io_error)?;
+  if metadata.is_file() {
+    return tokio::fs::copy(from, to).await.map(|_| ()).map_err(IoError::map_io_error);
+  }
+
+  let entries = WalkDir::new(from)
+    .follow_links(false)
+    .into_iter()
+    .collect::<Result<Vec<_>, _>>()
+    .map_err(|e| IoError::map_io_error(e.into()))?;
+  if entries.iter().any(|e| e.path_is_symlink()) {
+    return Err(IoError::InvalidPathError(String::from("Cannot copy symbolic links!")));
+  }
+
+  for entry in entries {
+    let destination = match entry.path().strip_prefix(from) {
+      Ok(relative) => to.join(relative),
+      Err(_) => return Err(IoError::SystemError),
+    };
+    if entry.file_type().is_dir() {
+      tokio::fs::create_dir_all(&destination).await.map_err(IoError::map_io_error)?;
+    } else {
+      tokio::fs::copy(entry.path(), &destination).await.map_err(IoError::map_io_error)?;
+    }
+  }
+  Ok(())
 }
 
 #[async_trait]
@@ -337,6 +519,29 @@ impl View for FileSystemView {
     }
   }
 
+  async fn rename(&self, from: &str, to: &str) -> Result<(), IoError> {
+    if !self.permissions.contains(&UserPermission::Rename) {
+      return Err(IoError::PermissionError);
+    }
+
+    let from = self.resolve_moved_path(from)?;
+    let to = self.resolve_moved_path(to)?;
+
+    if tokio::fs::symlink_metadata(&from).await.is_err() {
+      return Err(IoError::NotFoundError(String::from("File or directory not found")));
+    }
+    if tokio::fs::symlink_metadata(&to).await.is_ok() {
+      return Err(IoError::InvalidPathError(String::from("File or directory already exists!")));
+    }
+
+    debug!("Renaming: {:?} to {:?}", &from, &to);
+
+    match tokio::fs::rename(from, to).await {
+      Ok(()) => Ok(()),
+      Err(e) => Err(IoError::map_io_error(e)),
+    }
+  }
+
   fn list_dir(&self, path: &str) -> Result<Vec<EntryData>, IoError> {
     if !self.permissions.contains(&UserPermission::List) {
       return Err(IoError::PermissionError);
@@ -1011,6 +1216,314 @@ pub(crate) mod tests {
     assert!(dir_path.exists());
   }
 
+  #[tokio::test]
+  async fn rename_file_relative_test() {
+    setup_tracing();
+    let permissions = HashSet::from([UserPermission::Rename]);
+    let root = temp_dir();
+    let label = "test";
+    let view = FileSystemView::new(root.clone(), label, permissions);
+    let file_name = format!("{}.test", Uuid::new_v4().as_hyphenated());
+    let file_path = root.join(&file_name);
+    let new_file_name = format!("{}.test", Uuid::new_v4().as_hyphenated());
+    let new_file_path = root.join(&new_file_name);
+    touch(&file_path).expect("Test file must exist");
+
+    let _cleanup = FileCleanup::new(&file_path);
+    let _cleanup_new = FileCleanup::new(&new_file_path);
+
+    let result = view.rename(&file_name, &new_file_name).await;
+    let Ok(()) = result else {
+      panic!("Expected OK, got: {:?}", result);
+    };
+    assert!(!file_path.exists());
+    assert!(new_file_path.exists());
+  }
+
+  #[tokio::test]
+  async fn rename_folder_absolute_test() {
+    setup_tracing();
+    let permissions = HashSet::from([UserPermission::Rename]);
+    let root = temp_dir();
+    let label = "test";
+    let view = FileSystemView::new(root.clone(), label, permissions);
+    let dir_name = Uuid::new_v4().as_hyphenated().to_string();
+    let dir_path = root.join(&dir_name);
+    let new_dir_name = Uuid::new_v4().as_hyphenated().to_string();
+    let new_dir_path = root.join(&new_dir_name);
+    create_dir(&dir_path).expect("Test directory should exist");
+
+    let _cleanup = DirCleanup::new(&dir_path);
+    let _cleanup_new = DirCleanup::new(&new_dir_path);
+
+    let result = view.rename(&format!("/{label}/{dir_name}"), &format!("/{new_dir_name}")).await;
+    let Ok(()) = result else {
+      panic!("Expected OK, got: {:?}", result);
+    };
+    assert!(!dir_path.exists());
+    assert!(new_dir_path.is_dir());
+  }
+
+  #[tokio::test]
+  async fn rename_nonexistent_test() {
+    setup_tracing();
+    let permissions = HashSet::from([UserPermission::Rename]);
+    let root = temp_dir();
+    let label = "test";
+    let view = FileSystemView::new(root.clone(), label, permissions);
+
+    let result = view.rename(&Uuid::new_v4().as_hyphenated().to_string(), "new").await;
+    let Err(IoError::NotFoundError(_)) = result else {
+      panic!("Expected NotFound Error, got: {:?}", result);
+    };
+  }
+
+  #[tokio::test]
+  async fn rename_outside_root_test() {
+    setup_tracing();
+    let permissions = HashSet::from([UserPermission::Rename]);
+    let root = temp_dir();
+    let label = "test";
+    let view = FileSystemView::new(root.clone(), label, permissions);
+    let file_name = format!("{}.test", Uuid::new_v4().as_hyphenated());
+    let file_path = root.join(&file_name);
+    touch(&file_path).expect("Test file must exist");
+
+    let _cleanup = FileCleanup::new(&file_path);
+
+    let result = view.rename(&file_name, &format!("../{file_name}")).await;
+    let Err(IoError::InvalidPathError(_)) = result else {
+      panic!("Expected InvalidPath Error, got: {:?}", result);
+    };
+    assert!(file_path.exists());
+  }
+
+  #[tokio::test]
+  async fn rename_existing_target_test() {
+    setup_tracing();
+    let permissions = HashSet::from([UserPermission::Rename]);
+    let root = temp_dir();
+    let view = FileSystemView::new(root.clone(), "test", permissions);
+    let file_name = format!("{}.test", Uuid::new_v4().as_hyphenated());
+    let file_path = root.join(&file_name);
+    let existing_name = format!("{}.test", Uuid::new_v4().as_hyphenated());
+    let existing_path = root.join(&existing_name);
+    touch(&file_path).expect("Test file must exist");
+    touch(&existing_path).expect("Test file must exist");
+
+    let _cleanup = FileCleanup::new(&file_path);
+    let _cleanup_existing = FileCleanup::new(&existing_path);
+
+    let result = view.rename(&file_name, &existing_name).await;
+    let Err(IoError::InvalidPathError(_)) = result else {
+      panic!("Expected Invalid Path Error, got: {:?}", result);
+    };
+    assert!(file_path.exists());
+    assert!(existing_path.exists());
+  }
+
+  #[tokio::test]
+  async fn rename_no_permissions_test() {
+    setup_tracing();
+    let permissions = HashSet::from([]);
+    let root = temp_dir();
+    let label = "test";
+    let view = FileSystemView::new(root.clone(), label, permissions);
+    let file_name = format!("{}.test", Uuid::new_v4().as_hyphenated());
+    let file_path = root.join(&file_name);
+    touch(&file_path).expect("Test file must exist");
+
+    let _cleanup = FileCleanup::new(&file_path);
+
+    let result = view.rename(&file_name, "new").await;
+    let Err(IoError::PermissionError) = result else {
+      panic!("Expected Permission Error, got: {:?}", result);
+    };
+    assert!(file_path.exists());
+  }
+
+  #[tokio::test]
+  async fn move_to_test() {
+    setup_tracing();
+    let permissions = HashSet::from([UserPermission::Rename]);
+    let root = temp_dir();
+    let source_name = Uuid::new_v4().as_hyphenated().to_string();
+    let source_path = root.join(&source_name);
+    let target_name = Uuid::new_v4().as_hyphenated().to_string();
+    let target_path = root.join(&target_name);
+    create_dir(&source_path).expect("Test directory should exist");
+    create_dir(&target_path).expect("Test directory should exist");
+
+    let _cleanup_source = DirCleanup::new(&source_path);
+    let _cleanup_target = DirCleanup::new(&target_path);
+
+    let source = FileSystemView::new(source_path.clone(), "source", permissions.clone());
+    let target = FileSystemView::new(target_path.clone(), "target", permissions);
+    let file_name = format!("{}.test", Uuid::new_v4().as_hyphenated());
+    touch(&source_path.join(&file_name)).expect("Test file must exist");
+
+    let result = source.move_to(&file_name, &target, &format!("/target/{file_name}")).await;
+    let Ok(()) = result else {
+      panic!("Expected OK, got: {:?}", result);
+    };
+    assert!(!source_path.join(&file_name).exists());
+    assert!(target_path.join(&file_name).exists());
+  }
+
+  #[tokio::test]
+  async fn move_to_no_target_permissions_test() {
+    setup_tracing();
+    let root = temp_dir();
+    let file_name = format!("{}.test", Uuid::new_v4().as_hyphenated());
+    let file_path = root.join(&file_name);
+    touch(&file_path).expect("Test file must exist");
+
+    let _cleanup = FileCleanup::new(&file_path);
+
+    let source =
+      FileSystemView::new(root.clone(), "source", HashSet::from([UserPermission::Rename]));
+    let target = FileSystemView::new(root.clone(), "target", HashSet::from([UserPermission::Read]));
+
+    let result = source.move_to(&file_name, &target, "new").await;
+    let Err(IoError::PermissionError) = result else {
+      panic!("Expected Permission Error, got: {:?}", result);
+    };
+    assert!(file_path.exists());
+  }
+
+  #[tokio::test]
+  async fn copy_recursive_test() {
+    setup_tracing();
+    let root = temp_dir();
+    let dir_name = Uuid::new_v4().as_hyphenated().to_string();
+    let dir_path = root.join(&dir_name);
+    let sub_path = dir_path.join("sub");
+    let copy_path = root.join(Uuid::new_v4().as_hyphenated().to_string());
+    create_dir(&sub_path).expect("Test directory should exist");
+    touch(&dir_path.join("a.test")).expect("Test file must exist");
+    touch(&sub_path.join("b.test")).expect("Test file must exist");
+
+    let _cleanup = DirCleanup::new(&dir_path);
+    let _cleanup_copy = DirCleanup::new(&copy_path);
+
+    super::copy_recursive(&dir_path, &copy_path).await.expect("Copy should succeed");
+    assert!(copy_path.join("a.test").is_file());
+    assert!(copy_path.join("sub").join("b.test").is_file());
+    assert!(dir_path.join("sub").join("b.test").is_file());
+  }
+
+  #[cfg(unix)]
+  #[tokio::test]
+  async fn copy_recursive_symlink_test() {
+    setup_tracing();
+    let root = temp_dir();
+    let dir_path = root.join(Uuid::new_v4().as_hyphenated().to_string());
+    let copy_path = root.join(Uuid::new_v4().as_hyphenated().to_string());
+    create_dir(&dir_path).expect("Test directory should exist");
+    std::os::unix::fs::symlink("/etc", dir_path.join("link")).expect("Test link should exist");
+
+    let _cleanup = DirCleanup::new(&dir_path);
+
+    let result = super::copy_recursive(&dir_path, &copy_path).await;
+    let Err(IoError::InvalidPathError(_)) = result else {
+      panic!("Expected Invalid Path Error, got: {:?}", result);
+    };
+    assert!(!copy_path.exists());
+  }
+
+  #[tokio::test]
+  async fn move_to_existing_target_test() {
+    setup_tracing();
+    let permissions = HashSet::from([UserPermission::Rename]);
+    let root = temp_dir();
+    let file_name = format!("{}.test", Uuid::new_v4().as_hyphenated());
+    let file_path = root.join(&file_name);
+    let existing_name = format!("{}.test", Uuid::new_v4().as_hyphenated());
+    let existing_path = root.join(&existing_name);
+    touch(&file_path).expect("Test file must exist");
+    touch(&existing_path).expect("Test file must exist");
+
+    let _cleanup = FileCleanup::new(&file_path);
+    let _cleanup_existing = FileCleanup::new(&existing_path);
+
+    let source = FileSystemView::new(root.clone(), "source", permissions.clone());
+    let target = FileSystemView::new(root.clone(), "target", permissions);
+
+    let result = source.move_to(&file_name, &target, &existing_name).await;
+    let Err(IoError::InvalidPathError(_)) = result else {
+      panic!("Expected Invalid Path Error, got: {:?}", result);
+    };
+    assert!(file_path.exists());
+  }
+
+  #[cfg(unix)]
+  #[tokio::test]
+  async fn move_to_through_symlink_test() {
+    setup_tracing();
+    let permissions = HashSet::from([UserPermission::Rename]);
+    let root = temp_dir();
+    let outside_path = root.join(Uuid::new_v4().as_hyphenated().to_string());
+    let source_path = root.join(Uuid::new_v4().as_hyphenated().to_string());
+    let target_path = root.join(Uuid::new_v4().as_hyphenated().to_string());
+    create_dir(&outside_path).expect("Test directory should exist");
+    create_dir(&source_path).expect("Test directory should exist");
+    create_dir(&target_path).expect("Test directory should exist");
+    touch(&outside_path.join("secret.test")).expect("Test file must exist");
+    std::os::unix::fs::symlink(&outside_path, source_path.join("link"))
+      .expect("Test link should exist");
+
+    let _cleanup_outside = DirCleanup::new(&outside_path);
+    let _cleanup_source = DirCleanup::new(&source_path);
+    let _cleanup_target = DirCleanup::new(&target_path);
+
+    let source = FileSystemView::new(source_path.clone(), "source", permissions.clone());
+    let target = FileSystemView::new(target_path.clone(), "target", permissions);
+
+    let result = source.move_to("link/secret.test", &target, "secret.test").await;
+    let Err(IoError::InvalidPathError(_)) = result else {
+      panic!("Expected Invalid Path Error, got: {:?}", result);
+    };
+    assert!(outside_path.join("secret.test").exists());
+    assert!(!target_path.join("secret.test").exists());
+  }
+
+  #[tokio::test]
+  async fn copy_and_delete_permissions_test() {
+    setup_tracing();
+    let root = temp_dir();
+    let file_name = format!("{}.test", Uuid::new_v4().as_hyphenated());
+    let file_path = root.join(&file_name);
+    let new_path = root.join(format!("{}.test", Uuid::new_v4().as_hyphenated()));
+    touch(&file_path).expect("Test file must exist");
+
+    let _cleanup = FileCleanup::new(&file_path);
+    let _cleanup_new = FileCleanup::new(&new_path);
+
+    let rename = HashSet::from([UserPermission::Rename]);
+    let source = FileSystemView::new(root.clone(), "source", rename.clone());
+    let target = FileSystemView::new(root.clone(), "target", rename);
+    let result = source.copy_and_delete(&file_path, &target, &new_path).await;
+    let Err(IoError::PermissionError) = result else {
+      panic!("Expected Permission Error, got: {:?}", result);
+    };
+    assert!(file_path.exists());
+    assert!(!new_path.exists());
+
+    let source = FileSystemView::new(
+      root.clone(),
+      "source",
+      HashSet::from([UserPermission::Rename, UserPermission::Delete]),
+    );
+    let target = FileSystemView::new(
+      root.clone(),
+      "target",
+      HashSet::from([UserPermission::Rename, UserPermission::Create]),
+    );
+    source.copy_and_delete(&file_path, &target, &new_path).await.expect("Move should succeed");
+    assert!(!file_path.exists());
+    assert!(new_path.exists());
+  }
+
   #[tokio::test]
   async fn change_file_times_absolute_test() {
     setup_tracing();
@@ -6,9 +6,11 @@
 use crate::auth::user_permission::UserPermission;
 use crate::io::entry_data::{EntryData, EntryType};
 use crate::io::error::IoError;
+use crate::io::file_system_view::permissions_with_mode;
 use crate::io::open_options_flags::OpenOptionsWrapper;
 use crate::io::view::View;
 use crate::io::view_dispatch::ViewDispatch;
+use path_clean::PathClean;
 use std::collections::HashMap;
 use std::fs::FileTimes;
 use std::time::SystemTime;
@@ -21,6 +23,9 @@ use tracing_attributes::instrument;
 pub(crate) struct FileSystemViewRoot {
   pub(crate) file_system_views: Option<HashMap<String, ViewDispatch>>,
   current_view: Option<String>,
+  /// The mask applied to the mode of created files and directories, set by SITE UMASK. If not
+  /// set, the mode is left to the OS.
+  pub(crate) umask: Option<u32>,
 }
 
 enum ViewType<'a> {
@@ -38,6 +43,7 @@ impl FileSystemViewRoot {
     FileSystemViewRoot {
       file_system_views: views,
       current_view: None,
+      umask: None,
     }
   }
 
@@ -113,7 +119,17 @@ impl FileSystemViewRoot {
 
     match self.find_view(path) {
       Some((ViewType::Virtual(_), _)) => Err(IoError::SystemError),
-      Some((ViewType::Real(v), sub_path)) => v.create_directory(&sub_path),
+      Some((ViewType::Real(v), sub_path)) => {
+        let path = v.process_path(&sub_path.replace('\\', "/")).clean();
+        let created = !path.exists();
+        let new_path = v.create_directory(&sub_path)?;
+        if let Some(umask) = self.umask.filter(|_| created) {
+          let permissions = path.metadata().map_err(IoError::map_io_error)?.permissions();
+          std::fs::set_permissions(&path, permissions_with_mode(permissions, 0o777 & !umask))
+            .map_err(IoError::map_io_error)?;
+        }
+        Ok(new_path)
+      }
       None => Err(IoError::InvalidPathError(String::from("Directory path is invalid!"))),
     }
   }
@@ -212,8 +228,27 @@ impl FileSystemViewRoot {
     Ok(entries)
   }
 
+  /// Returns the facts about a single file or directory.
+  ///
+  /// The virtual root is reported as a directory. See: [`View::metadata`].
+  #[instrument(skip(self, path))]
+  pub(crate) fn metadata(&self, path: &str) -> Result<EntryData, IoError> {
+    if self.file_system_views.is_none() {
+      return Err(IoError::UserError);
+    }
+
+    match self.find_view(path) {
+      Some((ViewType::Virtual(_), _)) => {
+        Ok(EntryData::new(0, EntryType::Dir, ROOT_PERMISSIONS.to_vec(), SystemTime::now(), "/"))
+      }
+      Some((ViewType::Real(v), subpath)) => v.metadata(&subpath),
+      None => Err(IoError::NotFoundError(String::from("Path doesn't exist!"))),
+    }
+  }
+
   /// Opens a file with the specified path and options.
   ///
+  /// If the file is created and the umask is set, the mode of the file is set accordingly.
   /// See: [`FileSystemView::open_file`].
   #[instrument(skip(self, path, options))]
   pub(crate) async fn open_file(
@@ -229,7 +264,18 @@ impl FileSystemViewRoot {
       Some((ViewType::Virtual(_), _)) => {
         Err(IoError::InvalidPathError(String::from("Path references a directory, not a file!")))
       }
-      Some((ViewType::Real(v), subpath)) => v.open_file(&subpath, options).await,
+      Some((ViewType::Real(v), subpath)) => {
+        let created = options.create && !v.process_path(&subpath).clean().exists();
+        let file = v.open_file(&subpath, options).await?;
+        if let Some(umask) = self.umask.filter(|_| created) {
+          let permissions = file.metadata().await.map_err(IoError::map_io_error)?.permissions();
+          file
+            .set_permissions(permissions_with_mode(permissions, 0o666 & !umask))
+            .await
+            .map_err(IoError::map_io_error)?;
+        }
+        Ok(file)
+      }
       None => Err(IoError::UserError),
     }
   }
@@ -291,6 +337,57 @@ impl FileSystemViewRoot {
     }
   }
 
+  /// Renames or moves a file or directory.
+  ///
+  /// If both `from` and `to` are in the same view, then this is delegated to [`View::rename`].
+  /// Otherwise, the object is moved between the views, see [`FileSystemView::move_to`].
+  ///
+  /// # Errors
+  ///
+  /// This function can return the following [`IoError`] variants:
+  ///
+  /// - [`IoError::UserError`]: If the user is not logged in.
+  /// - [`IoError::InvalidPathError`]: If any of the paths refers to the root or a view itself.
+  /// - [`IoError::NotFoundError`]: If any of the paths refers to a nonexistent view.
+  /// - [`IoError::SystemError`]: If the object should be moved from or into a view that does not
+  ///   support it.
+  /// - Other [`IoError`] returned by [`View::rename`] or [`FileSystemView::move_to`].
+  ///
+  /// [`FileSystemView::move_to`]: crate::io::file_system_view::FileSystemView::move_to
+  #[instrument(skip(self, from, to))]
+  pub(crate) async fn rename(&self, from: &str, to: &str) -> Result<(), IoError> {
+    if self.file_system_views.is_none() {
+      return Err(IoError::UserError);
+    }
+
+    let (source, source_path) = match self.find_view(from) {
+      Some((ViewType::Real(v), sub_path)) => (v, sub_path),
+      Some((ViewType::Virtual(_), _)) => {
+        return Err(IoError::InvalidPathError(String::from("Cannot rename root directory!")));
+      }
+      None => return Err(IoError::NotFoundError(String::from("Path doesn't exist!"))),
+    };
+
+    let (target, target_path) = match self.find_view(to) {
+      Some((ViewType::Real(v), sub_path)) => (v, sub_path),
+      Some((ViewType::Virtual(_), _)) => {
+        return Err(IoError::InvalidPathError(String::from("Cannot rename to root directory!")));
+      }
+      None => return Err(IoError::NotFoundError(String::from("Path doesn't exist!"))),
+    };
+
+    if source.get_label() == target.get_label() {
+      return source.rename(&source_path, &target_path).await;
+    }
+
+    match (source, target) {
+      (ViewDispatch::FileSystemView(s), ViewDispatch::FileSystemView(t)) => {
+        s.move_to(&source_path, t, &target_path).await
+      }
+      _ => Err(IoError::SystemError),
+    }
+  }
+
   pub(crate) async fn change_file_times(
     &self,
     new_time: FileTimes,
@@ -315,6 +412,30 @@ impl FileSystemViewRoot {
     }
   }
 
+  /// Changes the mode of a file or directory, as done by SITE CHMOD.
+  ///
+  /// Only supported in [`FileSystemView`]s. See: [`FileSystemView::change_mode`].
+  ///
+  /// [`FileSystemView`]: crate::io::file_system_view::FileSystemView
+  /// [`FileSystemView::change_mode`]: crate::io::file_system_view::FileSystemView::change_mode
+  #[instrument(skip(self, path))]
+  pub(crate) async fn change_mode(&self, path: &str, mode: u32) -> Result<(), IoError> {
+    if self.file_system_views.is_none() {
+      return Err(IoError::UserError);
+    }
+
+    match self.find_view(path) {
+      Some((ViewType::Real(ViewDispatch::FileSystemView(v)), sub_path)) => {
+        v.change_mode(&sub_path, mode).await
+      }
+      Some((ViewType::Real(_), _)) => Err(IoError::SystemError),
+      Some((ViewType::Virtual(_), _)) => {
+        Err(IoError::InvalidPathError(String::from("Cannot change mode of root directory!")))
+      }
+      None => Err(IoError::NotFoundError(String::from("Path doesn't exist!"))),
+    }
+  }
+
   fn find_view(&self, path: &str) -> Option<(ViewType<'_>, String)> {
     let mut parts = path.split('/');
     if path == "/" || path == "~" {
@@ -371,7 +492,7 @@ mod tests {
   use uuid::Uuid;
 
   use crate::auth::user_permission::UserPermission;
-  use crate::io::entry_data::EntryData;
+  use crate::io::entry_data::{EntryData, EntryType};
   use crate::io::error::IoError;
   use crate::io::file_system_view::FileSystemView;
   use crate::io::file_system_view::tests::validate_listing;
@@ -379,7 +500,7 @@ mod tests {
   use crate::io::open_options_flags::OpenOptionsWrapperBuilder;
   use crate::io::view::View;
   use crate::io::view_dispatch::ViewDispatch;
-  use crate::{tracing_error, tracing_print};
+  use crate::tracing_print;
   use crate::utils::test_utils::*;
 
   #[tokio::test]
@@ -481,6 +602,33 @@ mod tests {
     };
   }
 
+  #[test]
+  fn metadata_test() {
+    setup_tracing();
+    let permissions = HashSet::from([UserPermission::Read]);
+    let root1 = std::env::current_dir().unwrap().join("test_files");
+    let label = "test_files";
+    let view1 = FileSystemView::new(root1, label, permissions);
+    let root = FileSystemViewRoot::new(Some(create_root(vec![view1])));
+
+    let entry = root.metadata(&format!("/{label}/2KiB.txt")).unwrap();
+    assert_eq!(EntryType::File, entry.entry_type());
+    assert_eq!(2048, entry.size());
+    assert_eq!("2KiB.txt", entry.name());
+
+    let entry = root.metadata(&format!("/{label}")).unwrap();
+    assert_eq!(EntryType::Dir, entry.entry_type());
+    assert_eq!(EntryType::Dir, root.metadata("/").unwrap().entry_type());
+
+    let result = root.metadata(&format!("/{label}/NONEXISTENT"));
+    let Err(IoError::NotFoundError(_)) = result else {
+      panic!("Expected NotFound error, got: {:?}", result);
+    };
+    let Err(IoError::UserError) = FileSystemViewRoot::new(None).metadata("/") else {
+      panic!("Expected User error");
+    };
+  }
+
   #[test]
   fn get_cwd_not_logged_in() {
     setup_tracing();
@@ -1423,6 +1571,102 @@ mod tests {
     assert!(dir_path.exists());
   }
 
+  #[tokio::test]
+  async fn rename_relative_test() {
+    setup_tracing();
+    let permissions = HashSet::from([UserPermission::Rename]);
+
+    let root1 = temp_dir();
+    let label1 = "test_files";
+    let view1 = FileSystemView::new(root1.clone(), label1, permissions.clone());
+    let views = create_root(vec![view1]);
+
+    let mut root = FileSystemViewRoot::new(Some(views));
+    root.change_working_directory(&format!("/{}", label1)).unwrap();
+
+    let file_name = format!("{}.test", Uuid::new_v4().as_hyphenated());
+    let file_path = root1.join(&file_name);
+    let new_file_name = format!("{}.test", Uuid::new_v4().as_hyphenated());
+    let new_file_path = root1.join(&new_file_name);
+    touch(&file_path).expect("Test file must exist");
+
+    let _cleanup = FileCleanup::new(&file_path);
+    let _cleanup_new = FileCleanup::new(&new_file_path);
+
+    let result = root.rename(&file_name, &new_file_name).await;
+    let Ok(()) = result else {
+      panic!("Expected OK, got: {:?}", result);
+    };
+    assert!(!file_path.exists());
+    assert!(new_file_path.exists());
+  }
+
+  #[tokio::test]
+  async fn rename_across_views_test() {
+    setup_tracing();
+    let permissions = HashSet::from([UserPermission::Rename]);
+
+    let root1 = temp_dir().join(Uuid::new_v4().as_hyphenated().to_string());
+    let root2 = temp_dir().join(Uuid::new_v4().as_hyphenated().to_string());
+    create_dir(&root1).expect("Test directory should exist");
+    create_dir(&root2).expect("Test directory should exist");
+    let _cleanup1 = DirCleanup::new(&root1);
+    let _cleanup2 = DirCleanup::new(&root2);
+
+    let label1 = "source";
+    let label2 = "target";
+    let view1 = FileSystemView::new(root1.clone(), label1, permissions.clone());
+    let view2 = FileSystemView::new(root2.clone(), label2, permissions.clone());
+    let views = create_root(vec![view1, view2]);
+
+    let root = FileSystemViewRoot::new(Some(views));
+
+    let dir_name = Uuid::new_v4().as_hyphenated().to_string();
+    create_dir(&root1.join(&dir_name)).expect("Test directory should exist");
+    touch(&root1.join(&dir_name).join("file.test")).expect("Test file must exist");
+
+    let result =
+      root.rename(&format!("/{label1}/{dir_name}"), &format!("/{label2}/{dir_name}")).await;
+    let Ok(()) = result else {
+      panic!("Expected OK, got: {:?}", result);
+    };
+    assert!(!root1.join(&dir_name).exists());
+    assert!(root2.join(&dir_name).join("file.test").is_file());
+  }
+
+  #[tokio::test]
+  async fn rename_view_test() {
+    setup_tracing();
+    let permissions = HashSet::from([UserPermission::Rename]);
+
+    let root1 = temp_dir();
+    let label1 = "test_files";
+    let view1 = FileSystemView::new(root1.clone(), label1, permissions.clone());
+    let views = create_root(vec![view1]);
+
+    let root = FileSystemViewRoot::new(Some(views));
+
+    let result = root.rename(&format!("/{label1}"), &format!("/{label1}/new")).await;
+    let Err(IoError::InvalidPathError(_)) = result else {
+      panic!("Expected InvalidPath error, got: {:?}", result);
+    };
+    let result = root.rename("/", &format!("/{label1}/new")).await;
+    let Err(IoError::InvalidPathError(_)) = result else {
+      panic!("Expected InvalidPath error, got: {:?}", result);
+    };
+  }
+
+  #[tokio::test]
+  async fn rename_not_logged_in_test() {
+    setup_tracing();
+    let root = FileSystemViewRoot::new(None);
+
+    let result = root.rename("a", "b").await;
+    let Err(IoError::UserError) = result else {
+      panic!("Expected User error, got: {:?}", result);
+    };
+  }
+
   #[tokio::test]
   async fn change_file_times_relative_test() {
     setup_tracing();
@@ -1,4 +1,5 @@
 //! Contains implementation of functions that access the filesystem.
+pub(crate) mod ascii;
 pub(crate) mod entry_data;
 pub(crate) mod error;
 pub(crate) mod file_system_view;
@@ -130,6 +130,12 @@ impl View for RecursiveView {
     Err(IoError::SystemError)
   }
 
+  async fn rename(&self, from: &str, to: &str) -> Result<(), IoError> {
+    // Entries are flattened, so their names do not map back to a path that could be renamed
+    warn!("{:?} -> {:?}", from, to);
+    Err(IoError::SystemError)
+  }
+
   fn list_dir(&self, _path: &str) -> Result<Vec<EntryData>, IoError> {
     if !self.permissions.contains(&UserPermission::List) {
       return Err(IoError::PermissionError);
@@ -1,19 +1,21 @@
-use chrono::{DateTime, Local, NaiveDateTime, ParseError, TimeZone};
+//! The time-val of MDTM, MFMT and MFCT. It is always in UTC, as specified by
+//! [RFC3659](https://datatracker.ietf.org/doc/html/rfc3659#section-2.3).
+
+use chrono::{DateTime, NaiveDateTime, ParseError, Utc};
 
 const TIMEVAL_FORMAT: &str = "%Y%m%d%H%M%S";
 
-pub(crate) fn parse_timeval(input: &str) -> Result<Option<DateTime<Local>>, ParseError> {
-  NaiveDateTime::parse_from_str(input, TIMEVAL_FORMAT)
-    .map(|t| Local.from_local_datetime(&t).latest())
+pub(crate) fn parse_timeval(input: &str) -> Result<DateTime<Utc>, ParseError> {
+  NaiveDateTime::parse_from_str(input, TIMEVAL_FORMAT).map(|t| t.and_utc())
 }
 
-pub(crate) fn format_timeval(timeval: &DateTime<Local>) -> String {
+pub(crate) fn format_timeval(timeval: &DateTime<Utc>) -> String {
   timeval.format(TIMEVAL_FORMAT).to_string()
 }
 
 #[cfg(test)]
 mod tests {
-  use chrono::NaiveDate;
+  use chrono::TimeZone;
 
   use crate::utils::test_utils::*;
 
@@ -23,15 +25,10 @@ mod tests {
   fn valid_test() {
     setup_tracing();
     let timeval = "20020717210715";
-    let correct = Local
-      .from_local_datetime(
-        &NaiveDate::from_ymd_opt(2002, 7, 17).unwrap().and_hms_opt(21, 7, 15).unwrap(),
-      )
-      .unwrap();
+    let correct = Utc.with_ymd_and_hms(2002, 7, 17, 21, 7, 15).unwrap();
     let parsed = parse_timeval(timeval);
-    assert!(parsed.is_ok());
-    assert!(parsed.unwrap().is_some());
-    assert_eq!(correct, parsed.unwrap().unwrap());
+    assert_eq!(Ok(correct), parsed);
+    assert_eq!(timeval, format_timeval(&parsed.unwrap()));
   }
 
   #[test]
@@ -46,14 +43,7 @@ mod tests {
   fn leap_year_test() {
     setup_tracing();
     let timeval = "20240214010203";
-    let correct = Local
-      .from_local_datetime(
-        &NaiveDate::from_ymd_opt(2024, 2, 14).unwrap().and_hms_opt(1, 2, 3).unwrap(),
-      )
-      .unwrap();
-    let parsed = parse_timeval(timeval);
-    assert!(parsed.is_ok());
-    assert!(parsed.unwrap().is_some());
-    assert_eq!(correct, parsed.unwrap().unwrap());
+    let correct = Utc.with_ymd_and_hms(2024, 2, 14, 1, 2, 3).unwrap();
+    assert_eq!(Ok(correct), parse_timeval(timeval));
   }
 }
@@ -70,7 +70,6 @@ pub(crate) trait View {
   async fn open_file(&self, path: &str, options: OpenOptionsWrapper) -> Result<File, IoError> {
     if options.read && !self.get_permissions().contains(&UserPermission::Read)
       || (options.write && !self.get_permissions().contains(&UserPermission::Write))
-      || (options.create && !self.get_permissions().contains(&UserPermission::Create))
       || (options.append && !self.get_permissions().contains(&UserPermission::Append))
       || (options.truncate && !self.get_permissions().contains(&UserPermission::Write))
     {
@@ -84,6 +83,13 @@ pub(crate) trait View {
       return Err(IoError::InvalidPathError(String::from("Invalid path!")));
     }
 
+    // Creating is only checked if the file would actually be created, so that appending to an
+    // existing file only requires the append permission
+    if options.create && !path.exists() && !self.get_permissions().contains(&UserPermission::Create)
+    {
+      return Err(IoError::PermissionError);
+    }
+
     if path.is_dir() {
       return Err(IoError::NotAFileError);
     }
@@ -98,6 +104,23 @@ pub(crate) trait View {
   async fn delete_file(&self, path: &str) -> Result<(), IoError>;
   async fn delete_folder(&self, path: &str) -> Result<(), IoError>;
   async fn delete_folder_recursive(&self, path: &str) -> Result<(), IoError>;
+  /// Renames a file or directory inside this view.
+  ///
+  /// Both `from` and `to` are resolved relative to the view, the same way as in
+  /// [`View::open_file`]. Neither of them may point outside the view or to the root of the view.
+  /// An existing object at `to` is never overwritten.
+  ///
+  /// # Errors
+  ///
+  /// This function can return the following [`IoError`] variants:
+  ///
+  /// - [`IoError::PermissionError`]: If the user does not have the rename permission.
+  /// - [`IoError::InvalidPathError`]: If any of the paths is outside the view or is its root, or
+  ///   if `to` already exists.
+  /// - [`IoError::NotFoundError`]: If `from` does not exist.
+  /// - [`IoError::OsError`]: If the OS reports any other error.
+  ///
+  async fn rename(&self, from: &str, to: &str) -> Result<(), IoError>;
 
   async fn change_file_times(&self, new_time: FileTimes, path: &str) -> Result<(), IoError> {
     if !self.get_permissions().contains(&UserPermission::Execute)
@@ -131,6 +154,42 @@ pub(crate) trait View {
   /// an error occurs.
   ///
   fn list_dir(&self, path: &str) -> Result<Vec<EntryData>, IoError>;
+
+  /// Returns the facts about a single file or directory.
+  ///
+  /// The `path` is resolved the same way as in [`View::open_file`]. If the `path` refers to the
+  /// root of the view, then the entry is named after the view's label.
+  ///
+  /// # Errors
+  ///
+  /// This function can return the following [`IoError`] variants:
+  ///
+  /// - [`IoError::PermissionError`]: If the user has neither read nor list permission.
+  /// - [`IoError::InvalidPathError`]: If the `path` is outside the view.
+  /// - [`IoError::NotFoundError`]: If the `path` does not exist.
+  /// - [`IoError::OsError`]: If the OS reports any other error.
+  ///
+  fn metadata(&self, path: &str) -> Result<EntryData, IoError> {
+    if !self.get_permissions().contains(&UserPermission::Read)
+      && !self.get_permissions().contains(&UserPermission::List)
+    {
+      return Err(IoError::PermissionError);
+    }
+
+    let path = self.process_path(path).clean();
+    trace!("Found path: {:?}", path);
+
+    if !path.starts_with(self.get_root_path()) {
+      return Err(IoError::InvalidPathError(String::from("Invalid path!")));
+    }
+
+    let metadata = path.metadata().map_err(IoError::map_io_error)?;
+    let name = match path.file_name() {
+      Some(name) if path != self.get_root_path() => name.to_string_lossy().to_string(),
+      _ => self.get_label().to_string(),
+    };
+    Ok(EntryData::create_from_metadata(metadata, name, self.get_permissions()))
+  }
   fn get_label(&self) -> &str;
   fn get_display_path(&self) -> &str;
   fn get_permissions(&self) -> &HashSet<UserPermission>;
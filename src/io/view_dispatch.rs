@@ -73,6 +73,13 @@ impl View for ViewDispatch {
     }
   }
 
+  async fn rename(&self, from: &str, to: &str) -> Result<(), IoError> {
+    match self {
+      ViewDispatch::FileSystemView(v) => v.rename(from, to).await,
+      ViewDispatch::RecursiveView(v) => v.rename(from, to).await,
+    }
+  }
+
   async fn change_file_times(&self, new_time: FileTimes, path: &str) -> Result<(), IoError> {
     match self {
       ViewDispatch::FileSystemView(v) => v.change_file_times(new_time, path).await,
@@ -87,6 +94,13 @@ impl View for ViewDispatch {
     }
   }
 
+  fn metadata(&self, path: &str) -> Result<EntryData, IoError> {
+    match self {
+      ViewDispatch::FileSystemView(v) => v.metadata(path),
+      ViewDispatch::RecursiveView(v) => v.metadata(path),
+    }
+  }
+
   fn get_label(&self) -> &str {
     match self {
       ViewDispatch::FileSystemView(v) => v.get_label(),
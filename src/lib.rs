@@ -0,0 +1,117 @@
+//! An FTP server over TCP, TCP+TLS and QUIC.
+//!
+//! The server is run by the foq binary, or by [`run`] from a binary of your own, which can add
+//! site-specific commands. A command is added by implementing [`CommandHandler`] and registering
+//! it in the [`CommandRegistry`], or in the [`SiteRegistry`] as a SITE subcommand, e.g.:
+//!
+//! ```no_run
+//! use std::sync::Arc;
+//!
+//! use foq::{
+//!   Command, CommandEntry, CommandHandler, CommandProcessor, Reply, ReplyCode, ReplySend,
+//! };
+//!
+//! struct Ping;
+//!
+//! #[async_trait::async_trait]
+//! impl CommandHandler for Ping {
+//!   async fn handle(
+//!     &self,
+//!     _command: &Command,
+//!     _command_processor: Arc<CommandProcessor>,
+//!     reply_sender: Arc<dyn ReplySend>,
+//!   ) {
+//!     reply_sender.send_control_message(Reply::new(ReplyCode::CommandOkay, "Pong")).await
+//!   }
+//! }
+//!
+//! foq::run(|commands, _site_commands| {
+//!   commands.register("XPNG", CommandEntry::with_handler("XPNG", "Replies pong.", Ping));
+//! });
+//! ```
+
+use chrono::Local;
+use std::fs::OpenOptions;
+use std::str::FromStr;
+use tracing::{Level, debug};
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{EnvFilter, Layer, Registry};
+
+use crate::global_context::CONFIG;
+
+mod auth;
+mod commands;
+mod data_channels;
+mod global_context;
+mod handlers;
+mod io;
+mod listeners;
+mod runner;
+mod session;
+mod utils;
+
+pub use crate::commands::command::Command;
+pub use crate::commands::command_registry::{
+  CommandEntry, CommandFn, CommandHandler, CommandRegistry,
+};
+pub use crate::commands::reply::Reply;
+pub use crate::commands::reply_code::ReplyCode;
+pub use crate::commands::site_registry::SiteRegistry;
+pub use crate::handlers::reply_sender::ReplySend;
+pub use crate::session::command_processor::CommandProcessor;
+
+/// Runs the server until it's shut down. Runs on tokio.
+///
+/// # Tracing  setup
+/// Attempts to load desired log level from configuration. If config does not specify this setting
+/// then [`INFO`] is assumed. Then sets the log file as the tracing output.
+///
+/// # Runner
+/// After the tracing is set up, the runner is executed. The registries of the built-in commands
+/// and SITE subcommands are passed to `register`, which can add commands to them or replace the
+/// built-in ones.
+///
+/// [`INFO`]: Level::INFO
+///
+pub fn run(register: impl FnOnce(&mut CommandRegistry, &mut SiteRegistry)) {
+  let log_file_name = format_log_file_name();
+  let mut log_file_options = OpenOptions::new();
+  log_file_options.write(true).truncate(true).create(true);
+  let log_file = log_file_options.open(log_file_name).expect("Log file should be accessible");
+  let (non_blocking, _guard) = tracing_appender::non_blocking(log_file);
+  let log_filter = CONFIG.get_string("log_filter").unwrap_or_else(|_| {
+    let log_level =
+      Level::from_str(&CONFIG.get_string("log_level").unwrap_or_default()).unwrap_or(Level::INFO);
+    format!("foq={}", log_level)
+  });
+  let fmt_layer = tracing_subscriber::fmt::Layer::default()
+    .with_writer(non_blocking)
+    .with_file(false)
+    .with_ansi(false)
+    .with_line_number(false)
+    .with_thread_ids(true)
+    .with_target(false)
+    .with_filter(EnvFilter::new(log_filter));
+  Registry::default().with(fmt_layer).init();
+
+  let threads: i64 = CONFIG.get_int("threads").unwrap_or_else(|_| {
+    std::thread::available_parallelism().map(|p| p.get()).unwrap_or(1usize) as i64
+  });
+  debug!("Using {} threads", threads);
+
+  tokio::runtime::Builder::new_multi_thread()
+    .worker_threads(threads as usize)
+    .enable_all()
+    .build()
+    .unwrap()
+    .block_on(async {
+      runner::run(register).await;
+    });
+}
+
+fn format_log_file_name() -> String {
+  let name = CONFIG.get_string("logfile").unwrap_or(String::from("foq-%Y%m%d%H%M.log"));
+  let current_time = Local::now();
+  current_time.format(&name).to_string()
+}
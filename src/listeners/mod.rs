@@ -2,3 +2,23 @@
 pub(crate) mod quic_only_listener;
 pub(crate) mod quinn_listener;
 pub(crate) mod standard_listener;
+
+use std::io::Error;
+use std::net::SocketAddr;
+
+use socket2::{Domain, Protocol, Socket, Type};
+
+/// Creates a new socket bound to `addr`.
+///
+/// IPv6 sockets are created as dual-stack, so that a listener bound to an unspecified IPv6
+/// address (`[::]`) also accepts IPv4 clients, regardless of the OS defaults.
+///
+pub(crate) fn bind_socket(addr: SocketAddr, ty: Type, protocol: Protocol) -> Result<Socket, Error> {
+  let socket = Socket::new(Domain::for_address(addr), ty, Some(protocol))?;
+  if addr.is_ipv6() {
+    socket.set_only_v6(false)?;
+  }
+  socket.set_nonblocking(true)?;
+  socket.bind(&addr.into())?;
+  Ok(socket)
+}
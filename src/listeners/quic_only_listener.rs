@@ -1,8 +1,11 @@
-use std::io::{Error, ErrorKind};
+use std::io::Error;
 use std::net::SocketAddr;
+use std::sync::Arc;
 use std::time::Duration;
 
+use rustls::ServerConfig;
 use s2n_quic::provider::congestion_controller::Bbr;
+use s2n_quic::provider::event::{ConnectionInfo, ConnectionMeta, Subscriber, events};
 use s2n_quic::provider::limits::{Limits, Provider};
 use s2n_quic::{
   Connection, Server, provider::io::tokio::Builder as IoBuilder,
@@ -13,6 +16,36 @@ use tracing::info;
 
 use crate::global_context::TLS_CONFIG;
 
+/// The DER encoded certificate chain presented by the client, available through
+/// [`Connection::query_event_context`].
+#[derive(Debug, Default)]
+pub(crate) struct PeerCertificates(pub(crate) Option<Vec<Vec<u8>>>);
+
+/// Records the certificate chain of each client once the handshake completes, s2n-quic doesn't
+/// expose it on the [`Connection`] otherwise.
+struct PeerCertificateSubscriber;
+
+impl Subscriber for PeerCertificateSubscriber {
+  type ConnectionContext = PeerCertificates;
+
+  fn create_connection_context(
+    &mut self,
+    _meta: &ConnectionMeta,
+    _info: &ConnectionInfo,
+  ) -> Self::ConnectionContext {
+    PeerCertificates::default()
+  }
+
+  fn on_tls_exporter_ready(
+    &mut self,
+    context: &mut Self::ConnectionContext,
+    _meta: &ConnectionMeta,
+    event: &events::TlsExporterReady,
+  ) {
+    context.0 = event.session.peer_cert_chain_der().ok().filter(|chain| !chain.is_empty());
+  }
+}
+
 pub(crate) struct QuicOnlyListener {
   pub(crate) server: Server,
 }
@@ -23,22 +56,33 @@ impl QuicOnlyListener {
   /// # TLS config
   /// TLS is backed by [`rustls`]. Configuration uses default settings, and the ALPN is set to
   /// 'ftpoq-1'. If the 'SSLKEYLOGFILE' environment variable is set, then the session secrets are
-  /// exported.
+  /// exported. If client certificates are requested, the chain presented by the client is
+  /// recorded as [`PeerCertificates`].
+  ///
+  /// Both IPv4 and IPv6 addresses are supported. If an unspecified IPv6 address (`[::]`) is
+  /// specified, then the listener accepts IPv4 connections as well.
   ///
   /// # Failure points
   /// Constructing the listener will fail under these circumstances:
-  /// - IPv6 address is specified
   /// - The certificate and key are not set or invalid
   /// - Binding to the IP address fails (e.g.: the port is in use)
   ///
   ///
   pub(crate) fn new(addr: SocketAddr) -> Result<Self, Error> {
-    if addr.is_ipv6() {
-      return Err(Error::new(ErrorKind::Unsupported, "IPv6 is not supported!"));
+    match TLS_CONFIG.clone() {
+      Some(tls_config) => QuicOnlyListener::with_tls_config(addr, tls_config),
+      None => Err(Error::other("TLS config is not available")),
     }
+  }
 
+  /// Constructs a new QUIC listener with the `tls_config` instead of the one from global context.
+  pub(crate) fn with_tls_config(
+    addr: SocketAddr,
+    tls_config: Arc<ServerConfig>,
+  ) -> Result<Self, Error> {
     let io = IoBuilder::default()
       .with_receive_address(addr)?
+      .with_only_v6(false)?
       .with_send_buffer_size(50 * 2usize.pow(20))?
       .with_recv_buffer_size(50 * 2usize.pow(20))?
       .with_internal_send_buffer_size(50 * 2usize.pow(20))?
@@ -52,11 +96,6 @@ impl QuicOnlyListener {
 
     let congestion_controller = Bbr::default();
 
-    let tls_config = match TLS_CONFIG.clone() {
-      Some(tls) => tls,
-      None => return Err(Error::other("TLS config is not available")),
-    };
-
     let tls_server = TlsServer::from(tls_config);
 
     let server = Server::builder()
@@ -68,6 +107,11 @@ impl QuicOnlyListener {
       .unwrap()
       .with_limits(limits)
       .unwrap()
+      .with_event((
+        s2n_quic::provider::event::tracing::Subscriber::default(),
+        PeerCertificateSubscriber,
+      ))
+      .unwrap()
       .start()
       .map_err(|e| Error::other(e.to_string()))?;
 
@@ -1,7 +1,9 @@
 use crate::global_context::TLS_CONFIG;
+use crate::listeners::bind_socket;
 use anyhow::{Error, bail};
 use quinn::crypto::rustls::QuicServerConfig;
-use quinn::{Endpoint, Incoming, ServerConfig, TransportConfig};
+use quinn::{Endpoint, EndpointConfig, Incoming, ServerConfig, TransportConfig, default_runtime};
+use socket2::{Protocol, Type};
 use std::net::SocketAddr;
 use std::sync::Arc;
 use std::time::Duration;
@@ -18,30 +20,42 @@ impl QuinnListener {
   /// # TLS config
   /// Uses TLS settings from the global context
   ///
+  /// Both IPv4 and IPv6 addresses are supported. If an unspecified IPv6 address (`[::]`) is
+  /// specified, then the listener accepts IPv4 connections as well.
+  ///
   /// # Failure points
   /// Constructing the listener will fail in the following cases:
-  /// - IPv6 address is specified
   /// - The TLS config is not available
   /// - Quinn server config cannot be created from the TLS config
   /// - The listener fails to bind to the address
   ///
   pub(crate) fn new(addr: SocketAddr) -> Result<Self, Error> {
-    if addr.is_ipv6() {
-      bail!("IPv6 is not supported!");
-    }
     let tls_config = match TLS_CONFIG.clone() {
       Some(tls) => tls,
       None => {
         bail!("TLS not available, unable to create Quinn listener!");
       }
     };
+    QuinnListener::with_tls_config(addr, tls_config)
+  }
+
+  /// Constructs a new QUIC listener with the `tls_config` instead of the one from global context.
+  pub(crate) fn with_tls_config(
+    addr: SocketAddr,
+    tls_config: Arc<rustls::ServerConfig>,
+  ) -> Result<Self, Error> {
     let mut config = ServerConfig::with_crypto(Arc::new(QuicServerConfig::try_from(tls_config)?));
     let mut transport_config = TransportConfig::default();
     transport_config.keep_alive_interval(Some(Duration::from_secs(5)));
     config.transport = Arc::new(transport_config);
 
+    let Some(runtime) = default_runtime() else {
+      bail!("No async runtime available, unable to create Quinn listener!");
+    };
+    let socket = bind_socket(addr, Type::DGRAM, Protocol::UDP)?;
+
     Ok(QuinnListener {
-      listener: Endpoint::server(config, addr)?,
+      listener: Endpoint::new(EndpointConfig::default(), Some(config), socket.into(), runtime)?,
     })
   }
 
@@ -1,10 +1,13 @@
-use std::io::{Error, ErrorKind};
+use std::io::Error;
 use std::net::SocketAddr;
 
-use tokio::net::{TcpListener, TcpSocket, TcpStream};
+use socket2::{Protocol, Type};
+use tokio::net::{TcpListener, TcpStream};
 use tokio_util::sync::CancellationToken;
 use tracing::info;
 
+use crate::listeners::bind_socket;
+
 pub(crate) struct StandardListener {
   pub(crate) listener: TcpListener,
 }
@@ -12,20 +15,19 @@ pub(crate) struct StandardListener {
 impl StandardListener {
   /// Construct a new TCP listener, listening on specified [`SocketAddr`].
   ///
+  /// Both IPv4 and IPv6 addresses are supported. If an unspecified IPv6 address (`[::]`) is
+  /// specified, then the listener accepts IPv4 connections as well.
+  ///
   /// # Failure points
   /// Constructing the listener will fail under these circumstances:
-  /// - IPv6 address is specified
   /// - Binding to the IP address fails (e.g.: the port is in use)
   ///
   pub(crate) async fn new(addr: SocketAddr) -> Result<Self, Error> {
-    if addr.is_ipv6() {
-      return Err(Error::new(ErrorKind::Unsupported, "IPv6 is not supported!"));
-    }
-    let socket = TcpSocket::new_v4()?;
-    socket.bind(addr)?;
+    let socket = bind_socket(addr, Type::STREAM, Protocol::TCP)?;
+    socket.listen(16392)?;
 
     Ok(StandardListener {
-      listener: socket.listen(16392)?,
+      listener: TcpListener::from_std(socket.into())?,
     })
   }
 
@@ -49,3 +51,32 @@ impl StandardListener {
     value
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+  use std::time::Duration;
+
+  use tokio::net::TcpStream;
+  use tokio::time::timeout;
+  use tokio_util::sync::CancellationToken;
+
+  use crate::listeners::standard_listener::StandardListener;
+
+  #[tokio::test]
+  async fn dual_stack_test() {
+    let addr = SocketAddr::new(IpAddr::V6(Ipv6Addr::UNSPECIFIED), 0);
+    let mut listener = StandardListener::new(addr).await.expect("Listener should bind to [::]");
+    let port = listener.listener.local_addr().unwrap().port();
+
+    for ip in [IpAddr::V4(Ipv4Addr::LOCALHOST), IpAddr::V6(Ipv6Addr::LOCALHOST)] {
+      let client =
+        TcpStream::connect(SocketAddr::new(ip, port)).await.expect("Client should connect");
+      let (_, peer) = timeout(Duration::from_secs(2), listener.accept(CancellationToken::new()))
+        .await
+        .expect("Accept should not time out")
+        .expect("Connection should be accepted");
+      assert_eq!(client.local_addr().unwrap().ip(), peer.ip().to_canonical());
+    }
+  }
+}
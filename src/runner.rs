@@ -1,15 +1,27 @@
 //! Execution point for all listeners.
 
+use crate::auth::anonymous_data_source::AnonymousDataSource;
 use crate::auth::auth_provider::AuthProvider;
+use crate::auth::certificate_data_source::CertificateDataSource;
+use crate::auth::file_data_source::FileDataSource;
+use crate::auth::ldap_data_source::LdapDataSource;
 use crate::auth::sqlite_data_source::SqliteDataSource;
-use std::net::SocketAddr;
+use std::net::{IpAddr, SocketAddr};
 use tokio::net::TcpStream;
 use tokio_rustls::TlsAcceptor;
 use tokio_rustls::server::TlsStream;
 use tokio_util::sync::CancellationToken;
 use tracing::{debug, error, info, warn};
 
-use crate::global_context::{AUTH_PROVIDER, CONFIG, DB_LAZY, TLS_CONFIG};
+use crate::commands::command_registry::CommandRegistry;
+use crate::commands::reply::Reply;
+use crate::commands::reply_code::ReplyCode;
+use crate::commands::site_registry::SiteRegistry;
+use crate::commands::site_settings::SiteSettings;
+use crate::global_context::{
+  AUTH_PROVIDER, COMMAND_REGISTRY, CONFIG, CONNECTION_REGISTRY, DB_LAZY, LOGIN_FAILURES,
+  SITE_REGISTRY, TIMEOUT_SETTINGS, TLS_CONFIG,
+};
 use crate::handlers::connection_handler::ConnectionHandler;
 use crate::handlers::quic_only_connection_handler::QuicOnlyConnectionHandler;
 use crate::handlers::quic_quinn_connection_handler::QuicQuinnConnectionHandler;
@@ -18,26 +30,71 @@ use crate::handlers::standard_tls_connection_handler::StandardTlsConnectionHandl
 use crate::listeners::quic_only_listener::QuicOnlyListener;
 use crate::listeners::quinn_listener::QuinnListener;
 use crate::listeners::standard_listener::StandardListener;
+use crate::session::connection_registry::SessionRegistration;
 
 /// Starts all available listeners.
 ///
 /// # Auth setup
-/// The authentication backend ([`AUTH_PROVIDER`]) is initialized with [`SqliteDataSource`]
-/// as the only source. If the SQLite connection is invalid, this will panic.
+/// The authentication backend ([`AUTH_PROVIDER`]) is initialized with the data sources set in
+/// config, in order: [`CertificateDataSource`] if client certificates are mapped to users,
+/// [`AnonymousDataSource`] if anonymous login is enabled, [`FileDataSource`]
+/// if 'users_file' is set, [`LdapDataSource`] if 'ldap_url' is set and [`SqliteDataSource`] if
+/// 'DATABASE_URL' is set. If the SQLite connection is invalid, this will panic.
+///
+/// # Command setup
+/// The [`COMMAND_REGISTRY`] is initialized with the built-in commands and the [`SITE_REGISTRY`]
+/// with the built-in SITE subcommands. Both are passed to `register` first, which can register
+/// site-specific commands implementing [`CommandHandler`].
+///
+/// [`CommandHandler`]: crate::commands::command_registry::CommandHandler
 ///
 /// # Listener setup
 /// The TCP, TCP+TLS and QUIC listeners are setup. If the IP address of a listener is not set in
 /// config, then that listener is skipped. Each listener runs in it's own [`tokio::task`].
 ///
+/// Each accepted connection is registered in [`CONNECTION_REGISTRY`], which enforces the
+/// connection limits. Connections from addresses banned by [`LOGIN_FAILURES`] are refused.
+///
 /// After the listeners are setup, the runner awaits for SIGINT which trigger a graceful shutdown.
 ///
 ///
-pub(crate) async fn run() {
+pub(crate) async fn run(register: impl FnOnce(&mut CommandRegistry, &mut SiteRegistry)) {
+  debug!("Setting up command registries.");
+  let mut commands = CommandRegistry::with_builtin_commands();
+  let mut site_commands =
+    SiteRegistry::with_builtin_commands(&SiteSettings::from_config(&CONFIG), &TIMEOUT_SETTINGS);
+  register(&mut commands, &mut site_commands);
+  COMMAND_REGISTRY.get_or_init(|| commands);
+  SITE_REGISTRY.get_or_init(|| site_commands);
+
   AUTH_PROVIDER
     .get_or_init(|| async {
       debug!("Setting up auth provider.");
       let mut provider = AuthProvider::new();
-      provider.add_data_source(Box::new(SqliteDataSource::new(DB_LAZY.clone())));
+      if let Some(certificate) = CertificateDataSource::from_config(&CONFIG) {
+        debug!("Client certificates are mapped to users.");
+        provider.add_data_source(Box::new(certificate));
+      }
+      if let Some(anonymous) = AnonymousDataSource::from_config(&CONFIG) {
+        debug!("Anonymous login enabled.");
+        provider.add_data_source(Box::new(anonymous));
+      }
+      if let Some(file) = FileDataSource::from_config(&CONFIG) {
+        debug!("Loading users from file.");
+        provider.add_data_source(Box::new(file));
+      }
+      if let Some(ldap) = LdapDataSource::from_config(&CONFIG) {
+        debug!("Authenticating users with LDAP.");
+        provider.add_data_source(Box::new(ldap));
+      }
+      if CONFIG.get_string("DATABASE_URL").is_ok() {
+        match SqliteDataSource::migrate(&DB_LAZY).await {
+          Ok(()) => provider.add_data_source(Box::new(SqliteDataSource::new(DB_LAZY.clone()))),
+          Err(e) => error!("Failed to migrate the database, SQLite users are disabled! {e}"),
+        }
+      } else {
+        warn!("No DATABASE_URL in config, SQLite users are disabled!");
+      }
       provider
     })
     .await;
@@ -107,6 +164,46 @@ pub(crate) async fn run() {
   }
 }
 
+/// Registers a new connection from the address.
+///
+/// Connections from banned addresses are refused, otherwise the connection is registered in
+/// [`CONNECTION_REGISTRY`]. If the connection is refused, the reply to reject it is returned.
+///
+fn register_connection(ip: IpAddr) -> Result<SessionRegistration, Reply> {
+  if LOGIN_FAILURES.is_banned(ip) {
+    return Err(Reply::new(
+      ReplyCode::ServiceNotAvailableClosingControlConnection,
+      "Too many failed logins, try again later.",
+    ));
+  }
+  CONNECTION_REGISTRY.register(ip).map_err(|e| {
+    info!("Connection from {ip} over limit! {e}");
+    Reply::new(ReplyCode::ServiceNotAvailableClosingControlConnection, e.to_string())
+  })
+}
+
+/// Runs the handler of a new connection.
+///
+/// If the connection was registered, the registration is attached to the session and the handler
+/// starts processing commands. Otherwise, the client is rejected with the reply.
+///
+async fn handle_connection(
+  mut handler: impl ConnectionHandler,
+  registration: Result<SessionRegistration, Reply>,
+  token: CancellationToken,
+) {
+  let result = match registration {
+    Ok(registration) => {
+      handler.register(registration).await;
+      handler.handle(token).await
+    }
+    Err(reply) => handler.reject(reply).await,
+  };
+  if let Err(e) = result {
+    error!("{:?}", e);
+  };
+}
+
 /// Executes a TCP listener.
 ///
 /// # Listener loop
@@ -133,11 +230,10 @@ async fn run_tcp(addr: SocketAddr, token: CancellationToken) {
       Some((stream, addr)) => {
         info!("[TCP] Received connection from: {:?}", addr);
         debug!("[TCP] Creating handler for connection from {:?}", addr);
+        let registration = register_connection(addr.ip());
         tokio::spawn(async move {
-          let mut handler = StandardConnectionHandler::new(stream);
-          if let Err(e) = handler.handle(cancel).await {
-            error!("{:?}", e);
-          };
+          let handler = StandardConnectionHandler::new(stream);
+          handle_connection(handler, registration, cancel).await;
         });
       }
       None => {
@@ -185,6 +281,7 @@ async fn run_tcp_tls(addr: SocketAddr, token: CancellationToken) {
       Some((stream, addr)) => {
         info!("[TCP+TLS] Received connection from: {:?}", addr);
         let acceptor = tls_acceptor.clone();
+        let registration = register_connection(addr.ip());
         tokio::spawn(async move {
           debug!("[TCP+TLS] Creating handler for connection from {:?}", addr);
           let tls_stream: TlsStream<TcpStream> = match acceptor.accept(stream).await {
@@ -194,10 +291,8 @@ async fn run_tcp_tls(addr: SocketAddr, token: CancellationToken) {
               return;
             }
           };
-          let mut handler = StandardTlsConnectionHandler::new(tls_stream);
-          if let Err(e) = handler.handle(cancel).await {
-            error!("{:?}", e);
-          };
+          let handler = StandardTlsConnectionHandler::new(tls_stream);
+          handle_connection(handler, registration, cancel).await;
         });
       }
       None => {
@@ -234,12 +329,11 @@ async fn run_quic(addr: SocketAddr, token: CancellationToken) {
         let peer = conn.remote_addr().unwrap();
         conn.keep_alive(false).unwrap();
         info!("[QUIC] Received connection from: {:?}", peer);
+        let registration = register_connection(peer.ip());
         tokio::spawn(async move {
           debug!("[QUIC] Creating handler for connection from {:?}", peer);
-          let mut handler = QuicOnlyConnectionHandler::new(conn);
-          if let Err(e) = handler.handle(cancel).await {
-            error!("{:?}", e);
-          };
+          let handler = QuicOnlyConnectionHandler::new(conn);
+          handle_connection(handler, registration, cancel).await;
         });
       }
       None => {
@@ -272,12 +366,11 @@ async fn run_quinn(addr: SocketAddr, token: CancellationToken) {
         };
         let peer = conn.remote_address();
         info!("[QUINN] Received connection from: {:?}", peer);
+        let registration = register_connection(peer.ip());
         tokio::spawn(async move {
           debug!("[QUINN] Creating handler for connection from {:?}", peer);
-          let mut handler = QuicQuinnConnectionHandler::new(conn);
-          if let Err(e) = handler.handle(cancel).await {
-            error!("{:?}", e);
-          };
+          let handler = QuicQuinnConnectionHandler::new(conn);
+          handle_connection(handler, registration, cancel).await;
         });
       }
       None => {
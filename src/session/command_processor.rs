@@ -3,26 +3,39 @@
 use std::sync::Arc;
 
 use tokio::sync::RwLock;
+use tokio_util::sync::CancellationToken;
 use tracing::{debug, trace};
 use zeroize::Zeroize;
 
 use crate::commands::command::Command;
+use crate::commands::commands::Commands;
 use crate::commands::reply::Reply;
 use crate::commands::reply_code::ReplyCode;
 use crate::data_channels::data_channel_wrapper::DataChannelWrapper;
+use crate::global_context::SECURITY_POLICY;
 use crate::handlers::reply_sender::ReplySend;
+use crate::session::security_policy::SecurityPolicy;
 use crate::session::session_properties::SessionProperties;
+use crate::session::transfer_status::TransferStatus;
 
 #[derive(Clone)]
-pub(crate) struct CommandProcessor {
+pub struct CommandProcessor {
   pub(crate) session_properties: Arc<RwLock<SessionProperties>>,
   pub(crate) data_wrapper: Arc<dyn DataChannelWrapper>,
+  /// Cancelled by commands to make the connection handler close the session, e.g.: QUIT.
+  pub(crate) close_token: CancellationToken,
+  /// The transfer currently running, reported by STAT.
+  pub(crate) transfer_status: Arc<TransferStatus>,
+  /// The security required from the client.
+  pub(crate) security_policy: Arc<SecurityPolicy>,
 }
 
 impl CommandProcessor {
   /// Constructs new processor.
   ///
-  /// Holds session properties and data wrapper which can be used in commands.
+  /// Holds session properties and data wrapper which can be used in commands, a token commands
+  /// can use to close the session and the status of the running transfer. The security policy is
+  /// the [`SECURITY_POLICY`] loaded from config.
   pub(crate) fn new(
     session_properties: Arc<RwLock<SessionProperties>>,
     data_wrapper: Arc<dyn DataChannelWrapper>,
@@ -30,6 +43,9 @@ impl CommandProcessor {
     CommandProcessor {
       session_properties,
       data_wrapper,
+      close_token: CancellationToken::new(),
+      transfer_status: Arc::new(TransferStatus::default()),
+      security_policy: SECURITY_POLICY.clone(),
     }
   }
 
@@ -37,13 +53,13 @@ impl CommandProcessor {
   ///
   /// The commands are first parsed. If parsing fails a reply is sent and this returns. If parsing
   /// succeeds and the command is implemented, then it is executed. If it's not implemented then
-  /// a reply is sent stating such.
+  /// a reply is sent stating such. Any command except RNTO cancels a rename pending after RNFR.
   ///
   #[tracing::instrument(skip_all)]
-  pub(crate) async fn evaluate<T: ReplySend + Send>(
+  pub(crate) async fn evaluate(
     self: Arc<Self>,
     mut message: String,
-    reply_sender: Arc<T>,
+    reply_sender: Arc<dyn ReplySend>,
   ) {
     trace!("Evaluating command");
     let command = message.trim().parse::<Command>();
@@ -51,6 +67,13 @@ impl CommandProcessor {
     match command {
       Ok(command) => {
         trace!("Parsed command: {:#?}", command);
+        // RNTO must immediately follow RNFR, any other command cancels the pending rename. Only
+        // locked for writing when there is one, out-of-band commands run during transfers.
+        if command.command != Commands::Rnto
+          && self.session_properties.read().await.rename_from.is_some()
+        {
+          self.session_properties.write().await.rename_from.take();
+        }
         command.execute(self, reply_sender).await;
       }
       Err(e) => {
@@ -0,0 +1,369 @@
+//! Schedules the execution of commands received in a session.
+//!
+//! Commands are executed one at a time, in the order they were received, so pipelined commands
+//! can rely on the session state set by the commands before them. ABOR, STAT and NOOP are the
+//! exception, while a transfer is queued or running they are executed immediately, as specified
+//! by [RFC959](https://datatracker.ietf.org/doc/html/rfc959#section-4.1.3).
+//!
+//! The scheduler also tracks the activity of the session, so idle sessions can be closed.
+
+use std::future::Future;
+use std::panic::AssertUnwindSafe;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use futures::FutureExt;
+use futures::future::join_all;
+use tokio::sync::mpsc::{UnboundedSender, unbounded_channel};
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+use tokio::time::{Instant, sleep_until};
+use tracing::{debug, error};
+use zeroize::Zeroize;
+
+use crate::commands::commands::Commands;
+use crate::handlers::reply_sender::ReplySend;
+use crate::session::command_processor::CommandProcessor;
+
+/// Commands that may be executed while a transfer is running.
+const OUT_OF_BAND_COMMANDS: [Commands; 3] = [Commands::Abor, Commands::Stat, Commands::Noop];
+
+/// Commands that use the data channel and may take a long time to finish.
+const TRANSFER_COMMANDS: [Commands; 6] =
+  [Commands::Appe, Commands::List, Commands::Mlsd, Commands::Nlst, Commands::Retr, Commands::Stor];
+
+/// An entry in the queue of the worker.
+enum Queued<T> {
+  /// A message from the client, and whether it's a transfer.
+  Command(String, Arc<T>, bool),
+  /// Notifies [`CommandScheduler::finish`] once the commands queued before it finished.
+  Barrier(oneshot::Sender<()>),
+}
+
+pub(crate) struct CommandScheduler<T: ReplySend + 'static> {
+  command_processor: Arc<CommandProcessor>,
+  queue: Option<UnboundedSender<Queued<T>>>,
+  worker: Option<JoinHandle<()>>,
+  out_of_band: Vec<JoinHandle<()>>,
+  pending_transfers: Arc<AtomicUsize>,
+  last_activity: Arc<Mutex<Instant>>,
+}
+
+impl<T: ReplySend + 'static> CommandScheduler<T> {
+  /// Constructs a new scheduler executing commands with the [`CommandProcessor`].
+  pub(crate) fn new(command_processor: Arc<CommandProcessor>) -> Self {
+    CommandScheduler {
+      command_processor,
+      queue: None,
+      worker: None,
+      out_of_band: Vec::with_capacity(4),
+      pending_transfers: Arc::new(AtomicUsize::new(0)),
+      last_activity: Arc::new(Mutex::new(Instant::now())),
+    }
+  }
+
+  /// Schedules the message from client for evaluation.
+  ///
+  /// The message is queued behind the previously scheduled ones, unless it's an out-of-band
+  /// command sent during a transfer, in which case it's evaluated right away.
+  pub(crate) fn schedule(&mut self, message: String, reply_sender: Arc<T>) {
+    self.out_of_band.retain(|t| !t.is_finished());
+    touch(&self.last_activity);
+    let command = parse_command_name(&message);
+
+    if command.is_some_and(|c| OUT_OF_BAND_COMMANDS.contains(&c))
+      && self.pending_transfers.load(Ordering::SeqCst) > 0
+    {
+      debug!("Evaluating out-of-band command: {:?}", command);
+      let task = self.command_processor.clone().evaluate(message, reply_sender);
+      let last_activity = self.last_activity.clone();
+      self.out_of_band.push(tokio::spawn(async move {
+        task.await;
+        touch(&last_activity);
+      }));
+      return;
+    }
+
+    let transfer = command.is_some_and(|c| TRANSFER_COMMANDS.contains(&c));
+    if transfer {
+      self.pending_transfers.fetch_add(1, Ordering::SeqCst);
+    }
+    let queue = match &self.queue {
+      Some(queue) => queue,
+      None => self.start_worker(),
+    };
+    if queue.send(Queued::Command(message, reply_sender, transfer)).is_err() {
+      error!("Command queue is closed!");
+    }
+  }
+
+  /// Waits until all the scheduled commands finish.
+  ///
+  /// The scheduler can still be used afterward. If this is cancelled, the unfinished commands
+  /// keep running until [`CommandScheduler::abort`] is called, and commands scheduled later still
+  /// run after them.
+  pub(crate) async fn finish(&mut self) {
+    if let Some(queue) = &self.queue {
+      let (sender, receiver) = oneshot::channel();
+      if queue.send(Queued::Barrier(sender)).is_ok() {
+        // Fails only if the worker was aborted
+        let _ = receiver.await;
+      }
+    }
+    join_all(self.out_of_band.iter_mut()).await;
+    self.out_of_band.clear();
+  }
+
+  /// Aborts all the scheduled commands.
+  pub(crate) fn abort(&mut self) {
+    debug!("Aborting {} out-of-band commands", self.out_of_band.len());
+    self.queue.take();
+    self.worker.iter().chain(self.out_of_band.iter()).for_each(|t| t.abort());
+  }
+
+  /// Creates a future that resolves once the session expires, with the reason of expiry.
+  ///
+  /// The session expires when it's inactive for longer than the idle timeout in
+  /// [`SessionProperties`], or when the user isn't logged in by the `login_deadline`. Scheduled
+  /// commands and running transfers count as activity. The timeouts are checked again whenever
+  /// they pass, so changes of the idle timeout are applied.
+  ///
+  /// [`SessionProperties`]: crate::session::session_properties::SessionProperties
+  pub(crate) fn expiry(
+    &self,
+    login_deadline: Option<Instant>,
+  ) -> impl Future<Output = &'static str> + Send + 'static {
+    let session_properties = self.command_processor.session_properties.clone();
+    let pending_transfers = self.pending_transfers.clone();
+    let last_activity = self.last_activity.clone();
+    async move {
+      loop {
+        let (idle_timeout, logged_in) = {
+          let session_properties = session_properties.read().await;
+          (session_properties.idle_timeout, session_properties.is_logged_in())
+        };
+        let login_deadline = login_deadline.filter(|_| !logged_in);
+        // Timeouts too long to represent never pass
+        let idle_deadline = idle_timeout.and_then(|t| last_activity.lock().unwrap().checked_add(t));
+        let deadline = match (idle_deadline, login_deadline) {
+          (Some(idle), Some(login)) => idle.min(login),
+          (Some(deadline), None) | (None, Some(deadline)) => deadline,
+          (None, None) => return futures::future::pending().await,
+        };
+        sleep_until(deadline).await;
+
+        if pending_transfers.load(Ordering::SeqCst) > 0 {
+          touch(&last_activity);
+          continue;
+        }
+        let now = Instant::now();
+        if login_deadline.is_some_and(|d| d <= now)
+          && !session_properties.read().await.is_logged_in()
+        {
+          return "Login timeout, closing control connection.";
+        }
+        let idle_timeout = session_properties.read().await.idle_timeout;
+        let idle_deadline = idle_timeout.and_then(|t| last_activity.lock().unwrap().checked_add(t));
+        if idle_deadline.is_some_and(|d| d <= now) {
+          return "Idle timeout, closing control connection.";
+        }
+      }
+    }
+  }
+
+  fn start_worker(&mut self) -> &UnboundedSender<Queued<T>> {
+    let (sender, mut receiver) = unbounded_channel::<Queued<T>>();
+    let command_processor = self.command_processor.clone();
+    let pending_transfers = self.pending_transfers.clone();
+    let last_activity = self.last_activity.clone();
+    self.worker.replace(tokio::spawn(async move {
+      while let Some(queued) = receiver.recv().await {
+        let (mut message, reply_sender, transfer) = match queued {
+          Queued::Command(message, reply_sender, transfer) => (message, reply_sender, transfer),
+          Queued::Barrier(sender) => {
+            let _ = sender.send(());
+            continue;
+          }
+        };
+        // Commands received after the session started closing are dropped
+        if command_processor.close_token.is_cancelled() {
+          debug!("Session closing, dropping command.");
+          message.zeroize();
+        } else {
+          let task = command_processor.clone().evaluate(message, reply_sender);
+          if AssertUnwindSafe(task).catch_unwind().await.is_err() {
+            error!("Command evaluation panicked!");
+          }
+        }
+        if transfer {
+          pending_transfers.fetch_sub(1, Ordering::SeqCst);
+        }
+        touch(&last_activity);
+      }
+      debug!("Command queue closed.");
+    }));
+    self.queue.insert(sender)
+  }
+}
+
+fn touch(last_activity: &Mutex<Instant>) {
+  *last_activity.lock().unwrap() = Instant::now();
+}
+
+/// Parses only the command name from the message, so the argument isn't copied.
+fn parse_command_name(message: &str) -> Option<Commands> {
+  let message = message.trim_start();
+  let name = message.split([' ', '\r', '\n']).next().unwrap_or(message);
+  name.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+  use std::sync::Arc;
+  use std::time::Duration;
+
+  use tokio::sync::mpsc::channel;
+  use tokio::time::{Instant, timeout};
+
+  use crate::commands::commands::Commands;
+  use crate::commands::reply_code::ReplyCode;
+  use crate::session::command_scheduler::{CommandScheduler, parse_command_name};
+  use crate::session::data_type::DataType;
+  use crate::utils::test_utils::*;
+
+  #[test]
+  fn parse_command_name_test() {
+    assert_eq!(Some(Commands::Noop), parse_command_name("NOOP\r\n"));
+    assert_eq!(Some(Commands::Retr), parse_command_name("retr file.txt\r\n"));
+    assert_eq!(None, parse_command_name("NONEXISTENT\r\n"));
+  }
+
+  #[tokio::test]
+  async fn in_order_test() {
+    setup_tracing();
+    let (_, command_processor) = setup_test_command_processor();
+    let command_processor = Arc::new(command_processor);
+    let mut scheduler = CommandScheduler::new(command_processor.clone());
+
+    let (tx, mut rx) = channel(1024);
+    let reply_sender = Arc::new(TestReplySender::new(tx));
+    scheduler.schedule("TYPE A\r\n".to_string(), reply_sender.clone());
+    scheduler.schedule("TYPE I\r\n".to_string(), reply_sender.clone());
+    scheduler.schedule("REST 100\r\n".to_string(), reply_sender.clone());
+    scheduler.schedule("NOOP\r\n".to_string(), reply_sender.clone());
+    timeout(Duration::from_secs(5), scheduler.finish()).await.expect("Commands should finish");
+
+    receive_and_verify_reply(2, &mut rx, ReplyCode::CommandOkay, None).await;
+    receive_and_verify_reply(2, &mut rx, ReplyCode::CommandOkay, None).await;
+    receive_and_verify_reply(
+      2,
+      &mut rx,
+      ReplyCode::RequestedFileActionPendingFurtherInformation,
+      None,
+    )
+    .await;
+    receive_and_verify_reply(2, &mut rx, ReplyCode::CommandOkay, None).await;
+    let session_properties = command_processor.session_properties.read().await;
+    assert_eq!(DataType::Binary, session_properties.data_type);
+  }
+
+  #[tokio::test]
+  async fn out_of_band_during_transfer_test() {
+    setup_tracing();
+    let (settings, command_processor) = setup_test_command_processor();
+    let mut scheduler = CommandScheduler::new(Arc::new(command_processor));
+
+    let (tx, mut rx) = channel(1024);
+    let reply_sender = Arc::new(TestReplySender::new(tx));
+    // The data channel is not open, so RETR waits for it until it times out
+    let retr = format!("RETR {}/test_files/2KiB.txt\r\n", settings.label);
+    scheduler.schedule(retr, reply_sender.clone());
+    scheduler.schedule("NOOP\r\n".to_string(), reply_sender.clone());
+    receive_and_verify_reply(1, &mut rx, ReplyCode::CommandOkay, None).await;
+
+    scheduler.schedule("PWD\r\n".to_string(), reply_sender.clone());
+    timeout(Duration::from_secs(10), scheduler.finish()).await.expect("Commands should finish");
+    receive_and_verify_reply(2, &mut rx, ReplyCode::BadSequenceOfCommands, None).await;
+    receive_and_verify_reply(2, &mut rx, ReplyCode::PathnameCreated, None).await;
+  }
+
+  #[tokio::test]
+  async fn cancelled_finish_test() {
+    setup_tracing();
+    let (settings, command_processor) = setup_test_command_processor();
+    let mut scheduler = CommandScheduler::new(Arc::new(command_processor));
+
+    let (tx, mut rx) = channel(1024);
+    let reply_sender = Arc::new(TestReplySender::new(tx));
+    // The data channel is not open, so RETR waits for it until it times out
+    let retr = format!("RETR {}/test_files/2KiB.txt\r\n", settings.label);
+    scheduler.schedule(retr, reply_sender.clone());
+    assert!(timeout(Duration::from_millis(100), scheduler.finish()).await.is_err());
+
+    // Commands scheduled after the cancelled finish still wait for the running ones
+    scheduler.schedule("PWD\r\n".to_string(), reply_sender.clone());
+    timeout(Duration::from_secs(10), scheduler.finish()).await.expect("Commands should finish");
+    receive_and_verify_reply(2, &mut rx, ReplyCode::BadSequenceOfCommands, None).await;
+    receive_and_verify_reply(2, &mut rx, ReplyCode::PathnameCreated, None).await;
+  }
+
+  #[tokio::test]
+  async fn idle_expiry_test() {
+    setup_tracing();
+    let (_, command_processor) = setup_test_command_processor();
+    command_processor.session_properties.write().await.idle_timeout =
+      Some(Duration::from_millis(200));
+    let scheduler = CommandScheduler::<TestReplySender>::new(Arc::new(command_processor));
+
+    let reason =
+      timeout(Duration::from_secs(2), scheduler.expiry(None)).await.expect("Session should expire");
+    assert!(reason.contains("Idle"));
+  }
+
+  #[tokio::test]
+  async fn idle_timeout_overflow_test() {
+    setup_tracing();
+    let settings = CommandProcessorSettingsBuilder::default().build().unwrap();
+    let command_processor = setup_test_command_processor_custom(&settings);
+    command_processor.session_properties.write().await.idle_timeout =
+      Some(Duration::from_secs(u64::MAX));
+    let scheduler = CommandScheduler::<TestReplySender>::new(Arc::new(command_processor));
+
+    let login_deadline = Instant::now() + Duration::from_millis(200);
+    let reason = timeout(Duration::from_secs(2), scheduler.expiry(Some(login_deadline)))
+      .await
+      .expect("Session should expire");
+    assert!(reason.contains("Login"));
+  }
+
+  #[tokio::test]
+  async fn login_expiry_test() {
+    setup_tracing();
+    let settings = CommandProcessorSettingsBuilder::default().build().unwrap();
+    let command_processor = setup_test_command_processor_custom(&settings);
+    let scheduler = CommandScheduler::<TestReplySender>::new(Arc::new(command_processor));
+
+    let login_deadline = Instant::now() + Duration::from_millis(200);
+    let reason = timeout(Duration::from_secs(2), scheduler.expiry(Some(login_deadline)))
+      .await
+      .expect("Session should expire");
+    assert!(reason.contains("Login"));
+  }
+
+  #[tokio::test]
+  async fn transfer_is_activity_test() {
+    setup_tracing();
+    let (settings, command_processor) = setup_test_command_processor();
+    command_processor.session_properties.write().await.idle_timeout =
+      Some(Duration::from_millis(200));
+    let mut scheduler = CommandScheduler::new(Arc::new(command_processor));
+
+    let (tx, _rx) = channel(1024);
+    let reply_sender = Arc::new(TestReplySender::new(tx));
+    // The data channel is not open, so RETR waits for it until it times out
+    let retr = format!("RETR {}/test_files/2KiB.txt\r\n", settings.label);
+    scheduler.schedule(retr, reply_sender);
+
+    assert!(timeout(Duration::from_secs(1), scheduler.expiry(None)).await.is_err());
+  }
+}
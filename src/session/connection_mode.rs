@@ -1,9 +1,8 @@
 //! Possible modes of creating a data channel.
 //!
-//! Only the passive mode is implemented, although the active mode is required by
-//! [RFC959](https://datatracker.ietf.org/doc/html/rfc959).
+//! The active mode is only available for TCP connections, QUIC connections always use the
+//! passive mode.
 
-#[allow(unused)]
 #[derive(Copy, Clone, Debug, Default)]
 pub(crate) enum ConnectionMode {
   /// In active mode the server initiates a connection to address specified by client.
@@ -0,0 +1,403 @@
+//! Keeps track of the active sessions and enforces the connection limits.
+//!
+//! Each accepted connection is registered before any command is processed. The registration is
+//! released when the session ends, once it's dropped. The registered sessions can be listed, see
+//! [`ConnectionRegistry::sessions`].
+
+use std::borrow::Borrow;
+use std::collections::{BTreeMap, HashMap};
+use std::hash::Hash;
+use std::net::IpAddr;
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+use config::Config;
+use thiserror::Error;
+use tracing::{debug, warn};
+
+/// The maximum counts of sessions. Limits that are not set are not enforced.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct ConnectionLimits {
+  /// The maximum number of sessions in total.
+  pub(crate) max_sessions: Option<usize>,
+  /// The maximum number of sessions from a single IP address.
+  pub(crate) max_sessions_per_ip: Option<usize>,
+  /// The maximum number of sessions a single user may be logged into.
+  pub(crate) max_logins_per_user: Option<usize>,
+  /// The maximum number of sessions logged in anonymously, replacing the limit per user.
+  pub(crate) max_anonymous_logins: Option<usize>,
+}
+
+impl ConnectionLimits {
+  /// Loads the limits from config.
+  ///
+  /// The limits are read from 'max_sessions', 'max_sessions_per_ip', 'max_logins_per_user' and
+  /// 'max_anonymous_logins'. A limit of 0 or invalid value means the limit is not enforced.
+  ///
+  pub(crate) fn from_config(config: &Config) -> Self {
+    ConnectionLimits {
+      max_sessions: read_limit(config, "max_sessions"),
+      max_sessions_per_ip: read_limit(config, "max_sessions_per_ip"),
+      max_logins_per_user: read_limit(config, "max_logins_per_user"),
+      max_anonymous_logins: read_limit(config, "max_anonymous_logins"),
+    }
+  }
+}
+
+fn read_limit(config: &Config, key: &str) -> Option<usize> {
+  match config.get_int(key) {
+    Ok(0) => None,
+    Ok(limit) => match usize::try_from(limit) {
+      Ok(limit) => Some(limit),
+      Err(_) => {
+        warn!("Invalid {key} {limit}, ignoring!");
+        None
+      }
+    },
+    Err(config::ConfigError::NotFound(_)) => None,
+    Err(e) => {
+      warn!("Invalid {key}, ignoring! {e}");
+      None
+    }
+  }
+}
+
+#[derive(Debug, Eq, PartialEq, Error)]
+pub(crate) enum ConnectionLimitError {
+  #[error("Too many sessions, try again later.")]
+  Total,
+  #[error("Too many sessions from your address, try again later.")]
+  PerAddress,
+  #[error("Too many sessions of this user, try again later.")]
+  PerUser,
+  #[error("Too many anonymous sessions, try again later.")]
+  Anonymous,
+}
+
+/// A registered session, as listed by SITE WHO.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct SessionInfo {
+  /// The address the session is from.
+  pub(crate) ip: IpAddr,
+  /// The user logged into the session, if any.
+  pub(crate) username: Option<String>,
+  /// When the session was registered.
+  pub(crate) connected: SystemTime,
+}
+
+#[derive(Debug, Default)]
+struct Counts {
+  total: usize,
+  per_ip: HashMap<IpAddr, usize>,
+  per_user: HashMap<String, usize>,
+  next_id: u64,
+  sessions: BTreeMap<u64, SessionInfo>,
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct ConnectionRegistry {
+  limits: ConnectionLimits,
+  counts: Mutex<Counts>,
+}
+
+impl ConnectionRegistry {
+  /// Constructs a new registry enforcing the limits.
+  pub(crate) fn new(limits: ConnectionLimits) -> Self {
+    ConnectionRegistry {
+      limits,
+      counts: Mutex::new(Counts::default()),
+    }
+  }
+
+  /// Registers a new session from the address.
+  ///
+  /// # Errors
+  ///
+  /// Returns an error if the total or per IP limit would be exceeded.
+  ///
+  pub(crate) fn register(
+    self: &Arc<Self>,
+    ip: IpAddr,
+  ) -> Result<SessionRegistration, ConnectionLimitError> {
+    let mut counts = self.counts.lock().unwrap();
+    if self.limits.max_sessions.is_some_and(|max| counts.total >= max) {
+      return Err(ConnectionLimitError::Total);
+    }
+    let ip_count = counts.per_ip.get(&ip).copied().unwrap_or(0);
+    if self.limits.max_sessions_per_ip.is_some_and(|max| ip_count >= max) {
+      return Err(ConnectionLimitError::PerAddress);
+    }
+    counts.total += 1;
+    counts.per_ip.insert(ip, ip_count + 1);
+    let id = counts.next_id;
+    counts.next_id += 1;
+    counts.sessions.insert(
+      id,
+      SessionInfo {
+        ip,
+        username: None,
+        connected: SystemTime::now(),
+      },
+    );
+    debug!("Registered session from {ip}, sessions: {}", counts.total);
+    Ok(SessionRegistration {
+      registry: self.clone(),
+      id,
+      ip,
+      username: None,
+    })
+  }
+
+  /// Returns all the registered sessions, in the order they were registered.
+  pub(crate) fn sessions(&self) -> Vec<SessionInfo> {
+    self.counts.lock().unwrap().sessions.values().cloned().collect()
+  }
+
+  fn login(&self, id: u64, username: &str, anonymous: bool) -> Result<(), ConnectionLimitError> {
+    let mut counts = self.counts.lock().unwrap();
+    let user_count = counts.per_user.get(username).copied().unwrap_or(0);
+    let (limit, error) = if anonymous {
+      (self.limits.max_anonymous_logins, ConnectionLimitError::Anonymous)
+    } else {
+      (self.limits.max_logins_per_user, ConnectionLimitError::PerUser)
+    };
+    if limit.is_some_and(|max| user_count >= max) {
+      return Err(error);
+    }
+    counts.per_user.insert(username.to_string(), user_count + 1);
+    if let Some(session) = counts.sessions.get_mut(&id) {
+      session.username = Some(username.to_string());
+    }
+    Ok(())
+  }
+
+  fn logout(&self, id: u64, username: &str) {
+    let mut counts = self.counts.lock().unwrap();
+    decrement(&mut counts.per_user, username);
+    // The session may already be logged in as another user, which is kept
+    if let Some(session) = counts.sessions.get_mut(&id) {
+      session.username.take_if(|u| u == username);
+    }
+  }
+
+  fn unregister(&self, id: u64, ip: &IpAddr) {
+    let mut counts = self.counts.lock().unwrap();
+    counts.total -= 1;
+    decrement(&mut counts.per_ip, ip);
+    counts.sessions.remove(&id);
+  }
+}
+
+fn decrement<K, Q>(map: &mut HashMap<K, usize>, key: &Q)
+where
+  K: Borrow<Q> + Hash + Eq,
+  Q: Hash + Eq + ?Sized,
+{
+  if let Some(count) = map.get_mut(key) {
+    *count -= 1;
+    if *count == 0 {
+      map.remove(key);
+    }
+  }
+}
+
+/// A registered session. The session is unregistered once this is dropped.
+#[derive(Debug)]
+pub(crate) struct SessionRegistration {
+  registry: Arc<ConnectionRegistry>,
+  id: u64,
+  ip: IpAddr,
+  username: Option<String>,
+}
+
+impl SessionRegistration {
+  /// Returns the address the session is from.
+  pub(crate) fn ip(&self) -> IpAddr {
+    self.ip
+  }
+
+  /// Returns the registry the session is registered in.
+  pub(crate) fn registry(&self) -> &ConnectionRegistry {
+    &self.registry
+  }
+
+  /// Registers the login of the user in this session, replacing the previous login.
+  ///
+  /// # Errors
+  ///
+  /// Returns an error if the user is logged into too many sessions. The previous login is kept.
+  ///
+  pub(crate) fn login(&mut self, username: &str) -> Result<(), ConnectionLimitError> {
+    self.register_login(username, false)
+  }
+
+  /// Registers an anonymous login in this session, replacing the previous login.
+  ///
+  /// Anonymous users all share the `username`, so they are limited separately from the other
+  /// users.
+  ///
+  /// # Errors
+  ///
+  /// Returns an error if too many sessions are logged in anonymously. The previous login is kept.
+  ///
+  pub(crate) fn login_anonymous(&mut self, username: &str) -> Result<(), ConnectionLimitError> {
+    self.register_login(username, true)
+  }
+
+  fn register_login(
+    &mut self,
+    username: &str,
+    anonymous: bool,
+  ) -> Result<(), ConnectionLimitError> {
+    if self.username.as_deref() == Some(username) {
+      return Ok(());
+    }
+    self.registry.login(self.id, username, anonymous)?;
+    if let Some(previous) = self.username.replace(username.to_string()) {
+      self.registry.logout(self.id, &previous);
+    }
+    Ok(())
+  }
+
+  /// Releases the login of the user in this session.
+  pub(crate) fn logout(&mut self) {
+    if let Some(username) = self.username.take() {
+      self.registry.logout(self.id, &username);
+    }
+  }
+}
+
+impl Drop for SessionRegistration {
+  fn drop(&mut self) {
+    self.logout();
+    self.registry.unregister(self.id, &self.ip);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::net::{IpAddr, Ipv4Addr};
+  use std::sync::Arc;
+
+  use config::Config;
+
+  use crate::session::connection_registry::{
+    ConnectionLimitError, ConnectionLimits, ConnectionRegistry,
+  };
+
+  const IP_1: IpAddr = IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1));
+  const IP_2: IpAddr = IpAddr::V4(Ipv4Addr::new(192, 0, 2, 2));
+
+  fn registry(
+    max_sessions: Option<usize>,
+    max_sessions_per_ip: Option<usize>,
+    max_logins_per_user: Option<usize>,
+  ) -> Arc<ConnectionRegistry> {
+    Arc::new(ConnectionRegistry::new(ConnectionLimits {
+      max_sessions,
+      max_sessions_per_ip,
+      max_logins_per_user,
+      max_anonymous_logins: None,
+    }))
+  }
+
+  #[test]
+  fn from_config_test() {
+    let config = Config::builder()
+      .set_override("max_sessions", 100)
+      .unwrap()
+      .set_override("max_sessions_per_ip", 0)
+      .unwrap()
+      .set_override("max_logins_per_user", "invalid")
+      .unwrap()
+      .set_override("max_anonymous_logins", 20)
+      .unwrap()
+      .build()
+      .unwrap();
+
+    let limits = ConnectionLimits::from_config(&config);
+    assert_eq!(Some(100), limits.max_sessions);
+    assert_eq!(None, limits.max_sessions_per_ip);
+    assert_eq!(None, limits.max_logins_per_user);
+    assert_eq!(Some(20), limits.max_anonymous_logins);
+  }
+
+  #[test]
+  fn max_sessions_test() {
+    let registry = registry(Some(2), None, None);
+    let first = registry.register(IP_1).unwrap();
+    let _second = registry.register(IP_2).unwrap();
+    assert_eq!(ConnectionLimitError::Total, registry.register(IP_1).unwrap_err());
+
+    drop(first);
+    assert!(registry.register(IP_1).is_ok());
+  }
+
+  #[test]
+  fn max_sessions_per_ip_test() {
+    let registry = registry(None, Some(1), None);
+    let first = registry.register(IP_1).unwrap();
+    assert_eq!(ConnectionLimitError::PerAddress, registry.register(IP_1).unwrap_err());
+    assert!(registry.register(IP_2).is_ok());
+
+    drop(first);
+    assert!(registry.register(IP_1).is_ok());
+    assert_eq!(0, registry.counts.lock().unwrap().total);
+  }
+
+  #[test]
+  fn max_logins_per_user_test() {
+    let registry = registry(None, None, Some(1));
+    let mut first = registry.register(IP_1).unwrap();
+    let mut second = registry.register(IP_2).unwrap();
+    first.login("test").unwrap();
+    // Logging in again in the same session doesn't count twice
+    first.login("test").unwrap();
+    assert_eq!(ConnectionLimitError::PerUser, second.login("test").unwrap_err());
+    second.login("other").unwrap();
+
+    first.login("another").unwrap();
+    second.login("test").unwrap();
+    drop(second);
+    first.login("test").unwrap();
+  }
+
+  #[test]
+  fn max_anonymous_logins_test() {
+    let registry = Arc::new(ConnectionRegistry::new(ConnectionLimits {
+      max_logins_per_user: Some(1),
+      max_anonymous_logins: Some(2),
+      ..ConnectionLimits::default()
+    }));
+    let mut first = registry.register(IP_1).unwrap();
+    let mut second = registry.register(IP_1).unwrap();
+    let mut third = registry.register(IP_2).unwrap();
+    first.login_anonymous("anonymous").unwrap();
+    second.login_anonymous("anonymous").unwrap();
+    assert_eq!(ConnectionLimitError::Anonymous, third.login_anonymous("anonymous").unwrap_err());
+    third.login("test").unwrap();
+
+    drop(first);
+    third.login_anonymous("anonymous").unwrap();
+  }
+
+  #[test]
+  fn sessions_test() {
+    let registry = registry(None, None, None);
+    let mut first = registry.register(IP_1).unwrap();
+    let second = registry.register(IP_2).unwrap();
+    first.login("test").unwrap();
+    first.login("other").unwrap();
+
+    let sessions = registry.sessions();
+    assert_eq!(2, sessions.len());
+    assert_eq!((IP_1, Some("other")), (sessions[0].ip, sessions[0].username.as_deref()));
+    assert_eq!((IP_2, None), (sessions[1].ip, sessions[1].username.as_deref()));
+
+    first.logout();
+    drop(second);
+    let sessions = registry.sessions();
+    assert_eq!(1, sessions.len());
+    assert_eq!(None, sessions[0].username);
+  }
+}
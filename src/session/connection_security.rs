@@ -0,0 +1,34 @@
+//! How the connection of a session is protected.
+
+use crate::session::protection_mode::ProtMode;
+
+/// The protection of the control connection, which determines how data channels are protected.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub(crate) enum ConnectionSecurity {
+  /// Plain TCP, until the control channel is secured with AUTH TLS.
+  #[default]
+  Plain,
+  /// TCP secured with TLS, either implicitly or with AUTH TLS. Data channels are only protected
+  /// after PROT P.
+  Tls,
+  /// QUIC, all channels are always protected.
+  Quic,
+}
+
+impl ConnectionSecurity {
+  /// Returns true if the control channel is protected, so credentials can be sent.
+  pub(crate) fn is_control_protected(self) -> bool {
+    self != ConnectionSecurity::Plain
+  }
+
+  /// Returns true if the data channels are protected with the `prot_mode`.
+  ///
+  /// PROT P is refused on plain connections, so data channels are never protected on them.
+  pub(crate) fn is_data_protected(self, prot_mode: ProtMode) -> bool {
+    match self {
+      ConnectionSecurity::Plain => false,
+      ConnectionSecurity::Tls => prot_mode == ProtMode::Private,
+      ConnectionSecurity::Quic => true,
+    }
+  }
+}
@@ -1,7 +1,8 @@
 //! Possible representations of data.
 //!
-//! Only the binary type is implemented although the ASCII type is required by
-//! [RFC959](https://datatracker.ietf.org/doc/html/rfc959).
+//! The ASCII type converts line endings of transferred files as required by
+//! [RFC959](https://datatracker.ietf.org/doc/html/rfc959), the binary type transfers files
+//! unchanged. Format controls of the ASCII type don't change the transferred data.
 
 #[derive(Copy, Clone, Debug, Ord, PartialOrd, Eq, PartialEq)]
 pub(crate) enum DataType {
@@ -1,7 +1,13 @@
 //! Contains implementation of session management and processing of commands.
 pub(crate) mod command_processor;
+pub(crate) mod command_scheduler;
 pub(crate) mod connection_mode;
+pub(crate) mod connection_registry;
+pub(crate) mod connection_security;
 pub(crate) mod data_type;
 pub(crate) mod protection_mode;
+pub(crate) mod security_policy;
 pub(crate) mod session_properties;
+pub(crate) mod timeout_settings;
 pub(crate) mod transfer_mode;
+pub(crate) mod transfer_status;
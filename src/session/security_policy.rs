@@ -0,0 +1,144 @@
+//! Settings of the security required from clients, see
+//! [RFC4217](https://datatracker.ietf.org/doc/html/rfc4217).
+
+use std::collections::HashSet;
+
+use config::Config;
+use tracing::warn;
+
+use crate::session::connection_security::ConnectionSecurity;
+use crate::session::protection_mode::ProtMode;
+
+/// Controls which protection clients must use before logging in and transferring data.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct SecurityPolicy {
+  /// Whether all users must secure the control channel before logging in.
+  pub(crate) tls_login: bool,
+  /// The users that must secure the control channel before logging in, even if it's not required
+  /// for all users.
+  pub(crate) tls_login_users: HashSet<String>,
+  /// Whether data channels must be protected with PROT P.
+  pub(crate) protected_data: bool,
+}
+
+impl SecurityPolicy {
+  /// Loads the policy from config.
+  ///
+  /// The policy is read from 'require_tls_login', 'require_tls_login_users' (a list of usernames)
+  /// and 'require_protected_data'. Invalid values are reported and nothing is required instead.
+  ///
+  pub(crate) fn from_config(config: &Config) -> Self {
+    let tls_login_users = match config.get_array("require_tls_login_users") {
+      Ok(users) => users.into_iter().filter_map(|u| u.into_string().ok()).collect(),
+      Err(config::ConfigError::NotFound(_)) => HashSet::new(),
+      Err(e) => {
+        warn!("Invalid require_tls_login_users, ignoring! {e}");
+        HashSet::new()
+      }
+    };
+    SecurityPolicy {
+      tls_login: read_flag(config, "require_tls_login"),
+      tls_login_users,
+      protected_data: read_flag(config, "require_protected_data"),
+    }
+  }
+
+  /// Returns true if the user may log in over a connection with the `security`.
+  ///
+  /// If the username isn't known yet, only the global policy is checked.
+  pub(crate) fn is_login_allowed(
+    &self,
+    username: Option<&str>,
+    security: ConnectionSecurity,
+  ) -> bool {
+    security.is_control_protected()
+      || !self.tls_login && !username.is_some_and(|u| self.tls_login_users.contains(u))
+  }
+
+  /// Returns true if data may be transferred over a connection with the `security` and the
+  /// `prot_mode`.
+  pub(crate) fn is_data_allowed(&self, security: ConnectionSecurity, prot_mode: ProtMode) -> bool {
+    !self.protected_data || security.is_data_protected(prot_mode)
+  }
+}
+
+fn read_flag(config: &Config, key: &str) -> bool {
+  match config.get_bool(key) {
+    Ok(flag) => flag,
+    Err(config::ConfigError::NotFound(_)) => false,
+    Err(e) => {
+      warn!("Invalid {key}, ignoring! {e}");
+      false
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::collections::HashSet;
+
+  use config::Config;
+
+  use crate::session::connection_security::ConnectionSecurity;
+  use crate::session::protection_mode::ProtMode;
+  use crate::session::security_policy::SecurityPolicy;
+
+  #[test]
+  fn from_config_test() {
+    let config = Config::builder()
+      .set_override("require_tls_login", true)
+      .unwrap()
+      .set_override("require_tls_login_users", vec!["admin"])
+      .unwrap()
+      .set_override("require_protected_data", "invalid")
+      .unwrap()
+      .build()
+      .unwrap();
+
+    let policy = SecurityPolicy::from_config(&config);
+    assert!(policy.tls_login);
+    assert_eq!(HashSet::from(["admin".to_string()]), policy.tls_login_users);
+    assert!(!policy.protected_data);
+  }
+
+  #[test]
+  fn from_config_empty_test() {
+    let config = Config::builder().build().unwrap();
+    assert_eq!(SecurityPolicy::default(), SecurityPolicy::from_config(&config));
+  }
+
+  #[test]
+  fn login_test() {
+    let policy = SecurityPolicy {
+      tls_login_users: HashSet::from(["admin".to_string()]),
+      ..SecurityPolicy::default()
+    };
+    assert!(policy.is_login_allowed(Some("user"), ConnectionSecurity::Plain));
+    assert!(policy.is_login_allowed(None, ConnectionSecurity::Plain));
+    assert!(!policy.is_login_allowed(Some("admin"), ConnectionSecurity::Plain));
+    assert!(policy.is_login_allowed(Some("admin"), ConnectionSecurity::Tls));
+
+    let policy = SecurityPolicy {
+      tls_login: true,
+      ..SecurityPolicy::default()
+    };
+    assert!(!policy.is_login_allowed(None, ConnectionSecurity::Plain));
+    assert!(!policy.is_login_allowed(Some("user"), ConnectionSecurity::Plain));
+    assert!(policy.is_login_allowed(Some("user"), ConnectionSecurity::Quic));
+  }
+
+  #[test]
+  fn data_test() {
+    assert!(SecurityPolicy::default().is_data_allowed(ConnectionSecurity::Tls, ProtMode::Clear));
+
+    let policy = SecurityPolicy {
+      protected_data: true,
+      ..SecurityPolicy::default()
+    };
+    assert!(!policy.is_data_allowed(ConnectionSecurity::Tls, ProtMode::Clear));
+    assert!(!policy.is_data_allowed(ConnectionSecurity::Plain, ProtMode::Clear));
+    assert!(policy.is_data_allowed(ConnectionSecurity::Tls, ProtMode::Private));
+    assert!(!policy.is_data_allowed(ConnectionSecurity::Plain, ProtMode::Private));
+    assert!(policy.is_data_allowed(ConnectionSecurity::Quic, ProtMode::Clear));
+  }
+}
@@ -1,15 +1,21 @@
 //! Contains properties used throughout a session, such as username, datatype file system views
 //! and other.
 
+use crate::auth::auth_error::AuthError;
 use crate::auth::auth_provider::AuthProvider;
+use crate::auth::client_certificate::ClientCertificate;
 use crate::auth::login_form::LoginForm;
+use crate::io::entry_data::MlstFacts;
 use crate::io::file_system_view_root::FileSystemViewRoot;
+use crate::session::connection_registry::SessionRegistration;
+use crate::session::connection_security::ConnectionSecurity;
 use crate::session::data_type::DataType;
 use crate::session::protection_mode::ProtMode;
 use crate::session::transfer_mode::TransferMode;
 use std::default::Default;
 use std::fmt::Debug;
 use std::sync::atomic::AtomicU64;
+use std::time::Duration;
 
 /// Currently implemented properties.
 #[allow(unused)]
@@ -24,6 +30,14 @@ pub(crate) struct SessionProperties {
   pub(crate) utf8: bool,
   pub(crate) prot_mode: ProtMode,
   pub(crate) pbsz: Option<u32>,
+  pub(crate) rename_from: Option<String>,
+  pub(crate) epsv_all: bool,
+  pub(crate) mlst_facts: MlstFacts,
+  pub(crate) idle_timeout: Option<Duration>,
+  pub(crate) connection_security: ConnectionSecurity,
+  /// The verified certificate the client presented during the TLS handshake.
+  pub(crate) client_certificate: Option<ClientCertificate>,
+  pub(crate) registration: Option<SessionRegistration>,
 }
 
 impl SessionProperties {
@@ -41,19 +55,49 @@ impl SessionProperties {
   ///
   /// Passes the credentials from client to [`AuthProvider`]. If an authenticated user entity is
   /// returned, then the entity is used to set the username and [`FileSystemViewRoot`] is set up.
+  /// If the session is registered, the login is registered too, so the limit of sessions per user,
+  /// or of anonymous sessions, is enforced.
   ///
-  /// Returns **true** if authentication succeeds, **false** otherwise.
+  /// # Errors
+  ///
+  /// Returns [`AuthError::InvalidCredentials`] if authentication fails,
+  /// [`AuthError::SecondFactorRequired`] if the password is correct but the one-time password is
+  /// missing, or [`AuthError::LimitReached`] if the user is logged into too many sessions.
   pub(crate) async fn login(
     &mut self,
     auth_provider: &AuthProvider,
     login_form: LoginForm,
-  ) -> bool {
-    let user_data = match auth_provider.authenticate(login_form).await {
-      Some(data) => data,
-      None => return false,
-    };
+  ) -> Result<(), AuthError> {
+    let user_data = auth_provider.authenticate(login_form).await?;
+    if let Some(registration) = self.registration.as_mut() {
+      let result = if user_data.anonymous {
+        registration.login_anonymous(&user_data.username)
+      } else {
+        registration.login(&user_data.username)
+      };
+      result.map_err(AuthError::LimitReached)?;
+    }
     self.username.replace(user_data.username);
     self.file_system_view_root.set_views(user_data.file_system_views);
-    true
+    Ok(())
+  }
+
+  /// Resets the session to the state before login, as done by REIN.
+  ///
+  /// The user is logged out and all the settings are reset to defaults. The idle timeout, the
+  /// connection security, the client certificate and the registration belong to the connection,
+  /// so they are kept.
+  pub(crate) fn reinitialize(&mut self) {
+    let mut registration = self.registration.take();
+    if let Some(registration) = registration.as_mut() {
+      registration.logout();
+    }
+    *self = SessionProperties {
+      idle_timeout: self.idle_timeout,
+      connection_security: self.connection_security,
+      client_certificate: self.client_certificate.take(),
+      registration,
+      ..SessionProperties::default()
+    };
   }
 }
@@ -0,0 +1,110 @@
+//! Settings of control connection timeouts.
+
+use std::time::Duration;
+
+use config::Config;
+use tracing::warn;
+
+const DEFAULT_IDLE_TIMEOUT: u64 = 600;
+const DEFAULT_LOGIN_TIMEOUT: u64 = 60;
+const DEFAULT_MAX_IDLE_TIMEOUT: u64 = 7200;
+
+/// Controls when silent control connections are closed.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct TimeoutSettings {
+  /// How long a session may stay without any activity. Running transfers count as activity.
+  pub(crate) idle: Option<Duration>,
+  /// How long after connecting the client has to log in.
+  pub(crate) login: Option<Duration>,
+  /// The longest idle timeout a client may set with SITE IDLE.
+  pub(crate) max_idle: Option<Duration>,
+}
+
+impl Default for TimeoutSettings {
+  fn default() -> Self {
+    TimeoutSettings {
+      idle: Some(Duration::from_secs(DEFAULT_IDLE_TIMEOUT)),
+      login: Some(Duration::from_secs(DEFAULT_LOGIN_TIMEOUT)),
+      max_idle: Some(Duration::from_secs(DEFAULT_MAX_IDLE_TIMEOUT)),
+    }
+  }
+}
+
+impl TimeoutSettings {
+  /// Loads the settings from config.
+  ///
+  /// The timeouts are read in seconds from 'idle_timeout', 'login_timeout' and
+  /// 'max_idle_timeout'. A timeout of 0 disables it. Invalid values are reported and the default is used instead.
+  ///
+  pub(crate) fn from_config(config: &Config) -> Self {
+    let defaults = TimeoutSettings::default();
+    TimeoutSettings {
+      idle: read_timeout(config, "idle_timeout", defaults.idle),
+      login: read_timeout(config, "login_timeout", defaults.login),
+      max_idle: read_timeout(config, "max_idle_timeout", defaults.max_idle),
+    }
+  }
+}
+
+fn read_timeout(config: &Config, key: &str, default: Option<Duration>) -> Option<Duration> {
+  match config.get_int(key) {
+    Ok(0) => None,
+    Ok(secs) => match u64::try_from(secs) {
+      Ok(secs) => Some(Duration::from_secs(secs)),
+      Err(_) => {
+        warn!("Invalid {key} {secs}, using default!");
+        default
+      }
+    },
+    Err(config::ConfigError::NotFound(_)) => default,
+    Err(e) => {
+      warn!("Invalid {key}, using default! {e}");
+      default
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::time::Duration;
+
+  use config::Config;
+
+  use crate::session::timeout_settings::TimeoutSettings;
+
+  #[test]
+  fn from_config_test() {
+    let config = Config::builder()
+      .set_override("idle_timeout", 30)
+      .unwrap()
+      .set_override("login_timeout", 0)
+      .unwrap()
+      .set_override("max_idle_timeout", 3600)
+      .unwrap()
+      .build()
+      .unwrap();
+
+    let settings = TimeoutSettings::from_config(&config);
+    assert_eq!(Some(Duration::from_secs(30)), settings.idle);
+    assert_eq!(None, settings.login);
+    assert_eq!(Some(Duration::from_secs(3600)), settings.max_idle);
+  }
+
+  #[test]
+  fn from_config_empty_test() {
+    let config = Config::builder().build().unwrap();
+    assert_eq!(TimeoutSettings::default(), TimeoutSettings::from_config(&config));
+  }
+
+  #[test]
+  fn from_config_invalid_test() {
+    let config = Config::builder()
+      .set_override("idle_timeout", -5)
+      .unwrap()
+      .set_override("login_timeout", "invalid")
+      .unwrap()
+      .build()
+      .unwrap();
+    assert_eq!(TimeoutSettings::default(), TimeoutSettings::from_config(&config));
+  }
+}
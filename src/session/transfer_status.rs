@@ -0,0 +1,86 @@
+//! Tracks the transfer running in a session, so its progress can be reported by STAT.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+#[derive(Debug)]
+struct RunningTransfer {
+  description: String,
+  transferred: Arc<AtomicU64>,
+}
+
+/// The transfer currently running in a session, if any.
+#[derive(Debug, Default)]
+pub(crate) struct TransferStatus {
+  running: Mutex<Option<RunningTransfer>>,
+}
+
+impl TransferStatus {
+  /// Marks the start of a transfer described by `description`, e.g.: 'RETR file.txt'.
+  ///
+  /// The transfer is considered running until the returned guard is dropped. The guard holds the
+  /// counter of transferred bytes.
+  pub(crate) fn start(self: &Arc<Self>, description: impl Into<String>) -> TransferGuard {
+    let transferred = Arc::new(AtomicU64::new(0));
+    self.running.lock().unwrap().replace(RunningTransfer {
+      description: description.into(),
+      transferred: transferred.clone(),
+    });
+    TransferGuard {
+      status: self.clone(),
+      transferred,
+    }
+  }
+
+  /// Returns the description of the running transfer and the number of bytes transferred so far.
+  pub(crate) fn current(&self) -> Option<(String, u64)> {
+    self
+      .running
+      .lock()
+      .unwrap()
+      .as_ref()
+      .map(|t| (t.description.clone(), t.transferred.load(Ordering::Relaxed)))
+  }
+}
+
+/// A running transfer. The transfer is finished once this is dropped.
+#[derive(Debug)]
+pub(crate) struct TransferGuard {
+  status: Arc<TransferStatus>,
+  transferred: Arc<AtomicU64>,
+}
+
+impl TransferGuard {
+  /// The counter of bytes transferred, to be passed to
+  /// [`copy_data`](crate::commands::r#impl::shared::copy_data).
+  pub(crate) fn transferred(&self) -> &AtomicU64 {
+    &self.transferred
+  }
+}
+
+impl Drop for TransferGuard {
+  fn drop(&mut self) {
+    self.status.running.lock().unwrap().take();
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::sync::Arc;
+  use std::sync::atomic::Ordering;
+
+  use crate::session::transfer_status::TransferStatus;
+
+  #[test]
+  fn transfer_status_test() {
+    let status = Arc::new(TransferStatus::default());
+    assert_eq!(None, status.current());
+
+    let guard = status.start("RETR file");
+    guard.transferred().fetch_add(100, Ordering::Relaxed);
+    assert_eq!(Some(("RETR file".to_string(), 100)), status.current());
+
+    drop(guard);
+    assert_eq!(None, status.current());
+  }
+}
@@ -1,5 +1,6 @@
 use std::collections::HashSet;
 use std::fs::{OpenOptions as OpenOptionsStd, remove_dir_all, remove_file};
+use std::future::Future;
 use std::io;
 use std::io::Error;
 use std::iter::Iterator;
@@ -27,6 +28,7 @@ use tokio::net::TcpStream;
 use tokio::sync::RwLock;
 use tokio::sync::mpsc::Receiver;
 use tokio::sync::mpsc::Sender;
+use tokio::sync::mpsc::channel;
 use tokio::task::JoinHandle;
 use tokio::time::timeout;
 use tokio_rustls::TlsConnector;
@@ -40,6 +42,8 @@ use crate::auth::data_source::DataSource;
 use crate::auth::login_form::LoginForm;
 use crate::auth::user_data::UserData;
 use crate::auth::user_permission::UserPermission;
+use crate::commands::command::Command;
+use crate::commands::command_registry::CommandHandler;
 use crate::commands::reply::Reply;
 use crate::commands::reply_code::ReplyCode;
 use crate::data_channels::data_channel_wrapper::DataChannelWrapper;
@@ -53,8 +57,11 @@ use crate::io::file_system_view::FileSystemView;
 use crate::listeners::quic_only_listener::QuicOnlyListener;
 use crate::listeners::quinn_listener::QuinnListener;
 use crate::session::command_processor::CommandProcessor;
+use crate::session::connection_security::ConnectionSecurity;
+use crate::session::data_type::DataType;
 use crate::session::protection_mode::ProtMode;
 use crate::session::session_properties::SessionProperties;
+use crate::utils::tls_utils::{ClientAuth, load_certs, load_keys};
 use crate::{tracing_error, tracing_print};
 
 pub(crate) struct TestReplySender {
@@ -165,8 +172,8 @@ pub(crate) async fn receive_and_verify_reply(
   match timeout(Duration::from_secs(time), rx.recv()).await {
     Ok(Some(result)) => {
       assert_eq!(expected, result.code);
-      if substring.is_some() {
-        assert!(result.to_string().contains(substring.unwrap()));
+      if let Some(substring) = substring {
+        assert!(result.to_string().contains(substring));
       }
     }
     Err(_) | Ok(None) => {
@@ -175,6 +182,42 @@ pub(crate) async fn receive_and_verify_reply(
   };
 }
 
+/// Executes the command and returns the code and the full text of its reply.
+pub(crate) async fn execute(
+  command: Command,
+  command_processor: Arc<CommandProcessor>,
+) -> (ReplyCode, String) {
+  receive_reply(
+    |reply_sender| async move { command.execute(command_processor, reply_sender).await },
+  )
+  .await
+}
+
+/// Handles the command with the `handler` and returns the code and the full text of its reply.
+pub(crate) async fn handle(
+  handler: &impl CommandHandler,
+  command: Command,
+  command_processor: Arc<CommandProcessor>,
+) -> (ReplyCode, String) {
+  receive_reply(|reply_sender| handler.handle(&command, command_processor, reply_sender)).await
+}
+
+/// Runs `execute` with a new [`TestReplySender`] and returns the code and the full text of the
+/// reply it sends, e.g.: for handlers that aren't in the command registry.
+pub(crate) async fn receive_reply<F: Future<Output = ()>>(
+  execute: impl FnOnce(Arc<dyn ReplySend>) -> F,
+) -> (ReplyCode, String) {
+  let (tx, mut rx) = channel(1024);
+  timeout(Duration::from_secs(2), execute(Arc::new(TestReplySender::new(tx))))
+    .await
+    .expect("Command timeout!");
+  let reply = timeout(Duration::from_secs(2), rx.recv())
+    .await
+    .expect("Reply timeout!")
+    .expect("Reply should be received");
+  (reply.code, reply.to_string())
+}
+
 pub(crate) async fn receive_and_verify_reply_from_buf<T: AsyncRead + Unpin>(
   time: u64,
   client_reader: &mut BufReader<T>,
@@ -257,6 +300,39 @@ pub(crate) fn create_test_server_config() -> ServerConfig {
     .unwrap()
 }
 
+/// Creates a server config requesting client certificates issued by the test CA.
+pub(crate) fn create_client_auth_server_config(client_auth: ClientAuth) -> ServerConfig {
+  if CryptoProvider::get_default().is_none() {
+    let _ = rustls::crypto::aws_lc_rs::default_provider().install_default();
+  }
+  let verifier = client_auth
+    .verifier(Some(Path::new("certs/test-ca-chain.pem")))
+    .unwrap()
+    .expect("Client certificates should be requested");
+  let mut config = ServerConfig::builder()
+    .with_client_cert_verifier(verifier)
+    .with_single_cert(CERTS.clone(), KEY.clone_key())
+    .unwrap();
+  config.alpn_protocols = vec!["ftpoq-1".as_bytes().to_vec()];
+  config
+}
+
+/// Creates a client config presenting the test client certificate.
+pub(crate) fn create_client_cert_client_config(alpn: &str) -> ClientConfig {
+  if CryptoProvider::get_default().is_none() {
+    let _ = rustls::crypto::aws_lc_rs::default_provider().install_default();
+  }
+  let certs = load_certs(Path::new("certs/test-client-cert.pem")).unwrap();
+  let key = load_keys(Path::new("certs/test-client-key.pem")).unwrap().remove(0);
+  let mut client_config = ClientConfig::builder()
+    .dangerous()
+    .with_custom_certificate_verifier(Arc::new(NoCertificateVerification::new()))
+    .with_client_auth_cert(certs, key)
+    .unwrap();
+  client_config.alpn_protocols = vec![alpn.as_bytes().to_vec()];
+  client_config
+}
+
 pub(crate) const LOCALHOST: SocketAddr = SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 0);
 
 pub(crate) async fn open_tcp_data_channel(command_processor: &mut CommandProcessor) -> TcpStream {
@@ -288,7 +364,7 @@ pub(crate) async fn open_tls_data_channel(
   tracing_print!("Connecting to passive listener");
   let client_dc = match TcpStream::connect(addr).await {
     Ok(c) => {
-      let connector = TlsConnector::from(Arc::new(create_tls_client_config("ftp")));
+      let connector = TlsConnector::from(Arc::new(create_tls_client_config("ftpoq-1")));
       connector
         .connect(ServerName::try_from("ftp.vanter.me").unwrap(), c)
         .await
@@ -333,8 +409,10 @@ pub(crate) fn setup_test_command_processor_custom(
       .expect("change_path should be valid");
   }
 
+  session_properties.connection_security = settings.connection_security;
+
   let session_properties = Arc::new(RwLock::new(session_properties));
-  let wrapper = Arc::new(StandardDataChannelWrapper::new(LOCALHOST));
+  let wrapper = Arc::new(StandardDataChannelWrapper::new(LOCALHOST, LOCALHOST.ip()));
   CommandProcessor::new(session_properties, wrapper)
 }
 
@@ -351,6 +429,8 @@ pub(crate) struct CommandProcessorSettings {
   pub(crate) username: Option<String>,
   #[builder(default)]
   pub(crate) change_path: Option<String>,
+  #[builder(default)]
+  pub(crate) connection_security: ConnectionSecurity,
 }
 
 pub(crate) async fn run_quic_listener(
@@ -381,6 +461,9 @@ pub fn run_quinn_listener(token: CancellationToken) -> (JoinHandle<()>, SocketAd
 }
 
 pub(crate) fn create_tls_client_config(alpn: &str) -> ClientConfig {
+  if CryptoProvider::get_default().is_none() {
+    let _ = rustls::crypto::aws_lc_rs::default_provider().install_default();
+  }
   let mut client_config = ClientConfig::builder()
     .dangerous()
     .with_custom_certificate_verifier(Arc::new(NoCertificateVerification::new()))
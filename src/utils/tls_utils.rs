@@ -1,9 +1,78 @@
 use std::fs::File;
 use std::io::BufReader;
 use std::path::Path;
+use std::sync::Arc;
 
+use anyhow::Context;
+use config::Config;
+use rustls::RootCertStore;
 use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use rustls::server::WebPkiClientVerifier;
+use rustls::server::danger::ClientCertVerifier;
 use rustls_pemfile::{certs, ec_private_keys, pkcs8_private_keys, rsa_private_keys};
+use tracing::warn;
+
+/// Whether TLS clients are asked for a certificate, used to log in without a password.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub(crate) enum ClientAuth {
+  /// Client certificates aren't requested.
+  #[default]
+  None,
+  /// Clients may present a certificate, it's verified if they do.
+  Optional,
+  /// Clients must present a valid certificate, the handshake fails otherwise.
+  Required,
+}
+
+impl ClientAuth {
+  /// Loads the mode from 'tls_client_auth', which is one of "none", "optional" or "required".
+  /// Invalid values are reported and client certificates aren't requested.
+  pub(crate) fn from_config(config: &Config) -> Self {
+    match config.get_string("tls_client_auth").map(|m| m.to_lowercase()).as_deref() {
+      Ok("optional") => ClientAuth::Optional,
+      Ok("required") => ClientAuth::Required,
+      Ok("none") | Err(config::ConfigError::NotFound(_)) => ClientAuth::None,
+      Ok(mode) => {
+        warn!("Invalid tls_client_auth '{mode}', client certificates are disabled!");
+        ClientAuth::None
+      }
+      Err(e) => {
+        warn!("Invalid tls_client_auth, client certificates are disabled! {e}");
+        ClientAuth::None
+      }
+    }
+  }
+
+  /// Creates the verifier of client certificates issued by the CAs in the `ca_chain_file`.
+  ///
+  /// # Returns
+  ///
+  /// The verifier, or [`None`] if client certificates aren't requested.
+  ///
+  /// # Errors
+  ///
+  /// Fails if client certificates are requested, but the CA chain is missing or invalid.
+  ///
+  pub(crate) fn verifier(
+    self,
+    ca_chain_file: Option<&Path>,
+  ) -> Result<Option<Arc<dyn ClientCertVerifier>>, anyhow::Error> {
+    if self == ClientAuth::None {
+      return Ok(None);
+    }
+    let ca_chain_file = ca_chain_file.context("Client certificates require a ca_chain_file")?;
+    let mut roots = RootCertStore::empty();
+    for certificate in load_certs(ca_chain_file)? {
+      roots.add(certificate)?;
+    }
+    let builder = WebPkiClientVerifier::builder(Arc::new(roots));
+    let builder = match self {
+      ClientAuth::Optional => builder.allow_unauthenticated(),
+      _ => builder,
+    };
+    Ok(Some(builder.build()?))
+  }
+}
 
 pub(crate) fn load_keys(path: &Path) -> Result<Vec<PrivateKeyDer<'_>>, anyhow::Error> {
   load_ec_keys(path).or_else(|_| load_rsa_pkcks1_keys(path)).or_else(|_| load_pkcs8_keys(path))
@@ -45,3 +114,44 @@ pub(crate) fn load_pkcs8_keys(path: &Path) -> Result<Vec<PrivateKeyDer<'static>>
     })
     .collect()
 }
+
+#[cfg(test)]
+mod tests {
+  use std::path::Path;
+
+  use config::Config;
+
+  use crate::utils::test_utils::*;
+  use crate::utils::tls_utils::ClientAuth;
+
+  #[test]
+  fn client_auth_from_config_test() {
+    setup_tracing();
+    for (value, expected) in [
+      ("Optional", ClientAuth::Optional),
+      ("required", ClientAuth::Required),
+      ("none", ClientAuth::None),
+      ("INVALID", ClientAuth::None),
+    ] {
+      let config =
+        Config::builder().set_override("tls_client_auth", value).unwrap().build().unwrap();
+      assert_eq!(expected, ClientAuth::from_config(&config));
+    }
+    assert_eq!(ClientAuth::None, ClientAuth::from_config(&Config::builder().build().unwrap()));
+  }
+
+  #[test]
+  fn verifier_test() {
+    setup_tracing();
+    let ca_chain_file = Path::new("certs/test-ca-chain.pem");
+    assert!(ClientAuth::None.verifier(None).unwrap().is_none());
+
+    let verifier = ClientAuth::Optional.verifier(Some(ca_chain_file)).unwrap().unwrap();
+    assert!(!verifier.client_auth_mandatory());
+    let verifier = ClientAuth::Required.verifier(Some(ca_chain_file)).unwrap().unwrap();
+    assert!(verifier.client_auth_mandatory());
+
+    assert!(ClientAuth::Required.verifier(None).is_err());
+    assert!(ClientAuth::Required.verifier(Some(Path::new("NONEXISTENT"))).is_err());
+  }
+}